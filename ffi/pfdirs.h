@@ -0,0 +1,39 @@
+/*
+ * Declarations for pfdirs_ffi.dll, which finds program files directories the way the pfdirs
+ * crate's resolve_program_files() does. See ffi/src/lib.rs for details.
+ */
+
+#ifndef PFDIRS_H
+#define PFDIRS_H
+
+#include <stdint.h>
+#include <wchar.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The 64-bit program files directory, used by both x86-64 and ARM64 programs. */
+#define PFDIRS_ARCH_X64 0u
+
+/* The 32-bit x86 program files directory. */
+#define PFDIRS_ARCH_X86 1u
+
+/* The 32-bit ARM program files directory, present only on ARM64 systems. */
+#define PFDIRS_ARCH_ARM 2u
+
+/*
+ * Copies the path of the program files directory for arch into buffer, as a null-terminated
+ * UTF-16 string. len is the size of buffer in UTF-16 code units, including the null.
+ *
+ * Returns the length of the path, not counting the null, if it fits; otherwise the size needed,
+ * counting the null, leaving buffer unchanged. Returns 0 on failure, with GetLastError() giving
+ * ERROR_INVALID_PARAMETER or ERROR_PATH_NOT_FOUND.
+ */
+uint32_t pfdirs_get_program_files(uint32_t arch, wchar_t *buffer, uint32_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PFDIRS_H */
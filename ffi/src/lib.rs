@@ -0,0 +1,94 @@
+//! A C ABI for `pfdirs::resolve_program_files()`, built as a DLL, so installers and tools not
+//! written in Rust can use the same fallback logic rather than reimplementing it.
+//!
+//! NSIS plugins, Inno Setup `[Code]` sections, MSI custom actions, and C++ programs can all call
+//! functions exported from a DLL. This exports just one, `pfdirs_get_program_files()`, which
+//! follows the conventions of Win32 functions such as [`GetEnvironmentVariableW`][gevw] that fill
+//! in a caller's buffer: it takes and gives UTF-16, reports how big a buffer is needed if the one
+//! given is too small, and reports errors through `GetLastError`. The declarations for C and C++
+//! are in `pfdirs.h`.
+//!
+//! This depends on `pfdirs` without its default features, so the DLL does not include the
+//! command-line interface, serialization, or COM lookups.
+//!
+//! The DLL is only built on Windows. Elsewhere this library is empty.
+//!
+//! [gevw]: https://learn.microsoft.com/en-us/windows/win32/api/processenv/nf-processenv-getenvironmentvariablew
+
+#![cfg(windows)]
+
+use std::os::windows::ffi::OsStrExt;
+
+use pfdirs::{resolve_program_files, Arch};
+use windows::Win32::Foundation::{
+    SetLastError, ERROR_INVALID_PARAMETER, ERROR_PATH_NOT_FOUND, WIN32_ERROR,
+};
+
+/// Selects the 64-bit program files directory, as `Arch::X64` does.
+pub const PFDIRS_ARCH_X64: u32 = 0;
+
+/// Selects the 32-bit x86 program files directory, as `Arch::X86` does.
+pub const PFDIRS_ARCH_X86: u32 = 1;
+
+/// Selects the 32-bit ARM program files directory, as `Arch::Arm` does.
+pub const PFDIRS_ARCH_ARM: u32 = 2;
+
+/// Gives the `Arch` for one of the `PFDIRS_ARCH_*` constants, or `None` for any other value.
+fn arch_of(arch: u32) -> Option<Arch> {
+    match arch {
+        PFDIRS_ARCH_X64 => Some(Arch::X64),
+        PFDIRS_ARCH_X86 => Some(Arch::X86),
+        PFDIRS_ARCH_ARM => Some(Arch::Arm),
+        _ => None,
+    }
+}
+
+/// Sets the calling thread's last-error code and returns 0, for reporting a failure.
+fn fail(error: WIN32_ERROR) -> u32 {
+    unsafe { SetLastError(error) };
+    0
+}
+
+/// Finds the program files directory for an architecture, as `resolve_program_files()` does, and
+/// copies its path into a buffer as a null-terminated UTF-16 string.
+///
+/// `arch` is one of `PFDIRS_ARCH_X64`, `PFDIRS_ARCH_X86`, and `PFDIRS_ARCH_ARM`. `len` is the size
+/// of `buffer` in UTF-16 code units, including room for the terminating null.
+///
+/// If the path fits, it is copied, and the return value is its length, not counting the null. If
+/// it does not fit, `buffer` is left unchanged, and the return value is the size needed, counting
+/// the null, so the caller can allocate a big enough buffer and call this again. Passing a null
+/// `buffer` and a `len` of 0 finds the size needed without copying anything.
+///
+/// On failure, the return value is 0, and `GetLastError` gives `ERROR_INVALID_PARAMETER` if `arch`
+/// is not one of the constants or `buffer` is null but `len` is not 0, or `ERROR_PATH_NOT_FOUND`
+/// if no source gave the directory. That is expected for the 64-bit directory on a 32-bit system,
+/// and for the 32-bit ARM directory on a system other than ARM64.
+///
+/// # Safety
+///
+/// Unless `len` is 0, `buffer` must be null or point to at least `len` writable UTF-16 code units.
+#[no_mangle]
+pub unsafe extern "C" fn pfdirs_get_program_files(arch: u32, buffer: *mut u16, len: u32) -> u32 {
+    let Some(arch) = arch_of(arch) else {
+        return fail(ERROR_INVALID_PARAMETER);
+    };
+    if buffer.is_null() && len != 0 {
+        return fail(ERROR_INVALID_PARAMETER);
+    }
+    let Ok(path) = resolve_program_files(arch) else {
+        return fail(ERROR_PATH_NOT_FOUND);
+    };
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let Ok(needed) = u32::try_from(wide.len()) else {
+        return fail(ERROR_PATH_NOT_FOUND);
+    };
+    if needed > len {
+        return needed;
+    }
+
+    // SAFETY: The caller guarantees `buffer` has room for `len` code units, and `needed <= len`.
+    unsafe { std::ptr::copy_nonoverlapping(wide.as_ptr(), buffer, wide.len()) };
+    needed - 1
+}
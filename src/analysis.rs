@@ -0,0 +1,2688 @@
+//! Comparing and explaining the program files folder locations found by different sources.
+
+use std::cmp::Reverse;
+use std::env::VarError;
+use std::ffi::{OsStr, OsString};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::apps::InstalledApp;
+use crate::arm::{ARM_REGISTRY_VALUE, ARM_VARIABLE_REFERENCE};
+use crate::csidl::PROGRAM_FILES_CSIDLS;
+use crate::environment::PROGRAM_FILES_ENVIRONMENT_VARIABLES;
+use crate::error::EntryError;
+use crate::filesystem::FileId;
+#[cfg(windows)]
+use crate::filesystem::{file_id, verify_path};
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+use crate::known_folder::KNOWN_FOLDER_ENUM_EQUIVALENTS;
+use crate::known_folder::PROGRAM_FILES_KNOWN_FOLDERS;
+use crate::msi::MSI_FOLDER_PROPERTIES;
+use crate::package::{
+    IN_APP_CONTAINER, PACKAGE_FULL_NAME, PACKAGE_PATH, PACKAGE_PROGRAM_FILES,
+    TOKEN_IS_APP_CONTAINER,
+};
+use crate::per_user::{
+    PACKAGE_ROOT_VALUE, USER_PROGRAM_FILES_COMMON, USER_PROGRAM_FILES_COMMON_SHELL_FOLDER,
+    USER_WINDOWS_APPS,
+};
+use crate::platform::{Machine, Platform};
+use crate::policy::{
+    DENIED_BY_POLICY, NO_VALUES, POLICY_KEYS, REDIRECTABLE, REDIRECTED_BY_POLICY,
+    REDIRECTION_CAPABILITIES,
+};
+use crate::registry::{LITERAL_PATH_VIEW, LITERAL_WOW6432NODE_VIEW, PROGRAM_FILES_REGISTRY_VALUES};
+#[cfg(feature = "serde")]
+use crate::render::{JSON_SCHEMA, SCHEMA_VERSION};
+use crate::report::{Entry, Lookup, RegistryEntry, Report, Row, Section};
+use crate::service::{ServiceAccount, ServiceContext};
+use crate::shell_folders::PROGRAM_FILES_SHELL_FOLDER_VALUES;
+use crate::shell_moniker::{known_folder_of_moniker, PROGRAM_FILES_SHELL_MONIKERS};
+use crate::shells::{CMD_REFERENCES, POWERSHELL_EXPRESSIONS};
+use crate::system_directories::{
+    SYSNATIVE, SYSTEM_DIRECTORY, SYSTEM_WOW64_ARM_DIRECTORY, SYSTEM_WOW64_DIRECTORY,
+    WINDOWS_DIRECTORY,
+};
+use crate::virtualization::{
+    ALLOWED, ENABLED, TOKEN_VIRTUALIZATION_ALLOWED, TOKEN_VIRTUALIZATION_ENABLED, VIRTUAL_STORES,
+};
+use crate::wmi::OS_ARCHITECTURE;
+
+/// A program files directory, identified by what it is for rather than by how it is looked up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogicalDirectory {
+    /// The 64-bit program files directory, used by both x86-64 and ARM64 programs.
+    ProgramFiles64,
+
+    /// The 32-bit x86 program files directory.
+    ProgramFiles32,
+
+    /// The 32-bit ARM program files directory, present only on ARM64 systems.
+    ProgramFilesArm,
+
+    /// The per-user program files directory.
+    UserProgramFiles,
+}
+
+impl LogicalDirectory {
+    /// Whether a system of the given architecture has this directory at all.
+    ///
+    /// Only 64-bit systems have a separate 64-bit program files directory, and only ARM64 systems
+    /// have one for 32-bit ARM programs. If the architecture is unknown, it is assumed to have it.
+    pub fn exists_on(self, native: Machine) -> bool {
+        match (self, native) {
+            (_, Machine::Unknown) => true,
+            (Self::ProgramFiles64, _) => native.is_64_bit(),
+            (Self::ProgramFilesArm, _) => native == Machine::Arm64,
+            (Self::ProgramFiles32 | Self::UserProgramFiles, _) => true,
+        }
+    }
+
+    pub const ALL: [Self; 4] = [
+        Self::ProgramFiles64,
+        Self::ProgramFiles32,
+        Self::ProgramFilesArm,
+        Self::UserProgramFiles,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::ProgramFiles64 => "64-bit program files",
+            Self::ProgramFiles32 => "32-bit program files",
+            Self::ProgramFilesArm => "32-bit ARM program files",
+            Self::UserProgramFiles => "user program files",
+        }
+    }
+
+    /// A short identifier for the directory, used in CSV and TSV output, which does not change
+    /// between versions.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ProgramFiles64 => "program-files-64",
+            Self::ProgramFiles32 => "program-files-32",
+            Self::ProgramFilesArm => "program-files-arm",
+            Self::UserProgramFiles => "user-program-files",
+        }
+    }
+}
+
+/// A source of information, corresponding to one section of the report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceKind {
+    EnvironmentVariable,
+
+    /// Environment variables as stored in the registry key with the given caption in
+    /// `ENVIRONMENT_REGISTRY_KEYS`, rather than as inherited.
+    EnvironmentRegistry(&'static str),
+
+    /// Environment variables in a freshly created environment block, rather than as inherited.
+    FreshEnvironment,
+
+    /// System environment variables and the OS architecture, as WMI gives them.
+    Wmi,
+
+    /// Environment variables and folder paths, as `cmd` and PowerShell give them.
+    Shells,
+
+    /// Folder properties, as Windows Installer resolves them in a session for an MSI package.
+    Msi,
+
+    /// The Windows and system directories, showing the context of WOW64 file system redirection.
+    SystemDirectories,
+
+    KnownFolder,
+
+    /// Known folders, looked up through `IKnownFolder` along with their definitions.
+    KnownFolderDefinition,
+
+    /// `shell:` monikers, parsed in the Shell namespace.
+    ShellMoniker,
+
+    Csidl,
+
+    /// CSIDLs, looked up through `SHGetFolderLocation` and checked against `SHGetFolderPathW`.
+    CsidlLocation,
+
+    /// The 32-bit ARM program files directory, found in the ways that do not need a known folder
+    /// or CSIDL, since it has neither.
+    ArmProgramFiles,
+
+    /// The other places per-user installs go, and the registry values that define them.
+    PerUserInstalls,
+
+    /// Whether UAC virtualization may redirect writes to the program files directories, and the
+    /// virtual store directories they would go to.
+    Virtualization,
+
+    /// Whether this process runs in an AppContainer or with package identity, and the package's
+    /// virtual file system directories for the program files directories.
+    Package,
+
+    /// The policy keys that configure Explorer, and whether the program files known folders can
+    /// be redirected.
+    Policy,
+
+    /// The registry, through the view with the given caption in `REGISTRY_VIEWS` or
+    /// `LITERAL_PATH_VIEWS`.
+    Registry(&'static str),
+
+    /// A legacy shell folders registry key, with the given caption in `SHELL_FOLDERS_KEYS`,
+    /// through the view with the given caption in `REGISTRY_VIEWS`.
+    ShellFolders {
+        key: &'static str,
+        view: &'static str,
+    },
+}
+
+impl SourceKind {
+    /// The names of the entries this source looks up, without looking any of them up.
+    pub fn names(self) -> Vec<&'static str> {
+        match self {
+            Self::EnvironmentVariable | Self::EnvironmentRegistry(_) | Self::FreshEnvironment => {
+                PROGRAM_FILES_ENVIRONMENT_VARIABLES.to_vec()
+            }
+            Self::KnownFolder | Self::KnownFolderDefinition => {
+                PROGRAM_FILES_KNOWN_FOLDERS.map(|(name, _)| name).to_vec()
+            }
+            Self::ShellMoniker => PROGRAM_FILES_SHELL_MONIKERS
+                .map(|(name, _, _)| name)
+                .to_vec(),
+            Self::Csidl | Self::CsidlLocation => {
+                PROGRAM_FILES_CSIDLS.map(|(name, _, _)| name).to_vec()
+            }
+            Self::Wmi => {
+                let mut names = PROGRAM_FILES_ENVIRONMENT_VARIABLES.to_vec();
+                names.push(OS_ARCHITECTURE);
+                names
+            }
+            Self::Shells => CMD_REFERENCES
+                .into_iter()
+                .chain(POWERSHELL_EXPRESSIONS)
+                .collect(),
+            Self::Msi => MSI_FOLDER_PROPERTIES.map(|(name, _, _)| name).to_vec(),
+            Self::SystemDirectories => vec![
+                WINDOWS_DIRECTORY,
+                SYSTEM_DIRECTORY,
+                SYSTEM_WOW64_DIRECTORY,
+                SYSTEM_WOW64_ARM_DIRECTORY,
+                SYSNATIVE,
+            ],
+            Self::ArmProgramFiles => vec![ARM_VARIABLE_REFERENCE, ARM_REGISTRY_VALUE],
+            Self::PerUserInstalls => vec![
+                USER_PROGRAM_FILES_COMMON,
+                USER_PROGRAM_FILES_COMMON_SHELL_FOLDER,
+                USER_WINDOWS_APPS,
+                PACKAGE_ROOT_VALUE,
+            ],
+            Self::Virtualization => {
+                let mut names = vec![TOKEN_VIRTUALIZATION_ALLOWED, TOKEN_VIRTUALIZATION_ENABLED];
+                names.extend(VIRTUAL_STORES);
+                names
+            }
+            Self::Package => {
+                let mut names = vec![TOKEN_IS_APP_CONTAINER, PACKAGE_FULL_NAME, PACKAGE_PATH];
+                names.extend(PACKAGE_PROGRAM_FILES);
+                names
+            }
+            Self::Policy => POLICY_KEYS
+                .map(|(caption, _)| caption)
+                .into_iter()
+                .chain(REDIRECTION_CAPABILITIES.map(|(name, _)| name))
+                .collect(),
+            Self::Registry(_) => PROGRAM_FILES_REGISTRY_VALUES.to_vec(),
+            Self::ShellFolders { .. } => PROGRAM_FILES_SHELL_FOLDER_VALUES
+                .map(|(name, _)| name)
+                .to_vec(),
+        }
+    }
+
+    /// A short, stable identifier for the kind of source, for machine-readable output.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::EnvironmentVariable => "environment",
+            Self::EnvironmentRegistry(_) => "environment-registry",
+            Self::FreshEnvironment => "fresh-environment",
+            Self::Wmi => "wmi",
+            Self::Shells => "shells",
+            Self::Msi => "msi",
+            Self::SystemDirectories => "system-directories",
+            Self::KnownFolder => "known-folder",
+            Self::KnownFolderDefinition => "known-folder-definition",
+            Self::ShellMoniker => "shell-moniker",
+            Self::Csidl => "csidl",
+            Self::CsidlLocation => "csidl-location",
+            Self::ArmProgramFiles => "arm-program-files",
+            Self::PerUserInstalls => "per-user-installs",
+            Self::Virtualization => "virtualization",
+            Self::Package => "package",
+            Self::Policy => "policy",
+            Self::Registry(_) => "registry",
+            Self::ShellFolders { .. } => "shell-folders",
+        }
+    }
+
+    /// Which of several sections of the same kind this is, such as the registry view, or an empty
+    /// string for kinds of source that have only one section.
+    pub fn view(self) -> String {
+        match self {
+            Self::EnvironmentRegistry(key) => key.to_owned(),
+            Self::Registry(view) => view.to_owned(),
+            Self::ShellFolders { key, view } => format!("{key} - {view}"),
+            Self::EnvironmentVariable
+            | Self::FreshEnvironment
+            | Self::Wmi
+            | Self::Shells
+            | Self::Msi
+            | Self::SystemDirectories
+            | Self::KnownFolder
+            | Self::KnownFolderDefinition
+            | Self::ShellMoniker
+            | Self::Csidl
+            | Self::CsidlLocation
+            | Self::ArmProgramFiles
+            | Self::PerUserInstalls
+            | Self::Virtualization
+            | Self::Package
+            | Self::Policy => String::new(),
+        }
+    }
+}
+
+/// Columns of the summary table, with their headers, in the order they are shown.
+pub const SUMMARY_COLUMNS: [(&str, SourceKind); 7] = [
+    ("Environment", SourceKind::EnvironmentVariable),
+    ("Known folder", SourceKind::KnownFolder),
+    ("Shell moniker", SourceKind::ShellMoniker),
+    ("CSIDL", SourceKind::Csidl),
+    ("Registry (64)", SourceKind::Registry("KEY_WOW64_64KEY")),
+    ("Registry (32)", SourceKind::Registry("KEY_WOW64_32KEY")),
+    ("Registry (default)", SourceKind::Registry("default view")),
+];
+
+/// Finds the name of the entry, if any, that a source uses for a logical directory.
+///
+/// Where a source has an entry specific to the directory's architecture, that is used, rather
+/// than an entry whose meaning depends on the architecture of the process reading it. So, for
+/// example, the 64-bit directory is looked up with `ProgramW6432`, not `ProgramFiles`. The
+/// exceptions are:
+///
+/// - The 32-bit and 64-bit registry views, in which `ProgramFilesDir` has a fixed meaning.
+///
+/// - CSIDLs, which have no 64-bit-specific value. For a 64-bit process, `CSIDL_PROGRAM_FILES`
+///   is the 64-bit directory. For a 32-bit process, there is no CSIDL for it. Which the process
+///   that looked them up was is given by `platform`.
+///
+/// CSIDLs looked up through `SHGetFolderLocation` have no column of their own, since any way they
+/// differ from `SHGetFolderPathW` is already reported as a discrepancy.
+///
+/// There is no known folder, `shell:` moniker, or CSIDL for the 32-bit ARM program files
+/// directory, and only the known folders and their monikers have an entry for the per-user program
+/// files directory.
+pub fn summary_entry_name(
+    directory: LogicalDirectory,
+    source: SourceKind,
+    platform: Platform,
+) -> Option<&'static str> {
+    use LogicalDirectory::*;
+
+    let is_64_bit_process = platform.process.is_64_bit();
+
+    match (directory, source) {
+        (
+            _,
+            SourceKind::EnvironmentRegistry(_)
+            | SourceKind::FreshEnvironment
+            | SourceKind::Wmi
+            | SourceKind::Shells
+            | SourceKind::Msi
+            | SourceKind::SystemDirectories
+            | SourceKind::CsidlLocation
+            | SourceKind::ArmProgramFiles
+            | SourceKind::PerUserInstalls
+            | SourceKind::Virtualization
+            | SourceKind::Package
+            | SourceKind::Policy
+            | SourceKind::ShellFolders { .. },
+        ) => None,
+
+        (ProgramFiles64, SourceKind::EnvironmentVariable) => Some("ProgramW6432"),
+        (ProgramFiles64, SourceKind::KnownFolder | SourceKind::KnownFolderDefinition) => {
+            Some("FOLDERID_ProgramFilesX64")
+        }
+        (ProgramFiles64, SourceKind::ShellMoniker) => Some("shell:ProgramFilesX64"),
+        (ProgramFiles64, SourceKind::Csidl) if is_64_bit_process => Some("CSIDL_PROGRAM_FILES"),
+        (ProgramFiles64, SourceKind::Csidl) => None,
+        (ProgramFiles64, SourceKind::Registry("KEY_WOW64_64KEY")) => Some("ProgramFilesDir"),
+        (ProgramFiles64, SourceKind::Registry(_)) => Some("ProgramW6432Dir"),
+
+        (ProgramFiles32, SourceKind::EnvironmentVariable) => Some("ProgramFiles(x86)"),
+        (ProgramFiles32, SourceKind::KnownFolder | SourceKind::KnownFolderDefinition) => {
+            Some("FOLDERID_ProgramFilesX86")
+        }
+        (ProgramFiles32, SourceKind::ShellMoniker) => Some("shell:ProgramFilesX86"),
+        (ProgramFiles32, SourceKind::Csidl) => Some("CSIDL_PROGRAM_FILESX86"),
+        (ProgramFiles32, SourceKind::Registry("KEY_WOW64_32KEY")) => Some("ProgramFilesDir"),
+        (ProgramFiles32, SourceKind::Registry(_)) => Some("ProgramFilesDir (x86)"),
+
+        (ProgramFilesArm, SourceKind::EnvironmentVariable) => Some("ProgramFiles(Arm)"),
+        (ProgramFilesArm, SourceKind::Registry(_)) => Some("ProgramFilesDir (Arm)"),
+        (ProgramFilesArm, _) => None,
+
+        (UserProgramFiles, SourceKind::KnownFolder | SourceKind::KnownFolderDefinition) => {
+            Some("FOLDERID_UserProgramFiles")
+        }
+        (UserProgramFiles, SourceKind::ShellMoniker) => Some("shell:UserProgramFiles"),
+        (UserProgramFiles, _) => None,
+    }
+}
+
+/// Finds the logical directory an entry gives the path of, if it is about one.
+///
+/// Unlike `summary_entry_name()`, this covers entries whose meaning depends on the architecture of
+/// the process reading them, such as `ProgramFiles`, which give the 64-bit directory in a 64-bit
+/// process and the 32-bit x86 directory otherwise, and the registry views other than the 32-bit
+/// and 64-bit ones. Windows Installer's `ProgramFilesFolder` is the 32-bit x86 directory, as
+/// described on `report_msi()`, and shell folders values are about the known folder they are for.
+/// Entries that are not about one program files directory, such as the system directories, give
+/// `None`.
+pub fn directory_of_entry(
+    source: SourceKind,
+    name: &str,
+    platform: Platform,
+) -> Option<LogicalDirectory> {
+    use LogicalDirectory::*;
+
+    let of_process = if platform.process.is_64_bit() {
+        ProgramFiles64
+    } else {
+        ProgramFiles32
+    };
+
+    match (source, name) {
+        (SourceKind::ShellFolders { .. }, _) => {
+            let (_, known_folder) = PROGRAM_FILES_SHELL_FOLDER_VALUES
+                .into_iter()
+                .find(|&(value, _)| value == name)?;
+            directory_of_entry(SourceKind::KnownFolder, known_folder, platform)
+        }
+
+        (SourceKind::Registry("KEY_WOW64_32KEY" | LITERAL_WOW6432NODE_VIEW), "ProgramFilesDir") => {
+            Some(ProgramFiles32)
+        }
+        (SourceKind::Registry("KEY_WOW64_64KEY" | LITERAL_PATH_VIEW), "ProgramFilesDir") => {
+            Some(ProgramFiles64)
+        }
+        (SourceKind::Registry(_), "ProgramFilesDir")
+        | (
+            _,
+            "ProgramFiles" | "FOLDERID_ProgramFiles" | "shell:ProgramFiles" | "CSIDL_PROGRAM_FILES",
+        ) => Some(of_process),
+
+        (
+            _,
+            "ProgramFiles(x86)"
+            | "FOLDERID_ProgramFilesX86"
+            | "shell:ProgramFilesX86"
+            | "CSIDL_PROGRAM_FILESX86"
+            | "ProgramFilesDir (x86)"
+            | "ProgramFilesFolder",
+        ) => Some(ProgramFiles32),
+
+        (
+            _,
+            "ProgramW6432"
+            | "FOLDERID_ProgramFilesX64"
+            | "shell:ProgramFilesX64"
+            | "ProgramW6432Dir"
+            | "ProgramFiles64Folder",
+        ) => Some(ProgramFiles64),
+
+        (_, "ProgramFiles(Arm)" | "ProgramFilesDir (Arm)") => Some(ProgramFilesArm),
+
+        (_, "FOLDERID_UserProgramFiles" | "shell:UserProgramFiles") => Some(UserProgramFiles),
+
+        _ => None,
+    }
+}
+
+/// One cell of the summary table, as found by `summarize()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SummaryCell {
+    /// The source has no entry for the directory.
+    NotApplicable,
+
+    /// The lookup failed. Details are in the section for the source.
+    Error,
+
+    /// The path found, which is the path most sources found for the same directory.
+    Path(String),
+
+    /// The path found, which differs from the path most sources found for the same directory.
+    Outlier(String),
+}
+
+/// A row of the summary table, with a cell for each of `SUMMARY_COLUMNS`.
+#[derive(Clone, Debug)]
+pub struct SummaryRow {
+    pub directory: LogicalDirectory,
+    pub cells: [SummaryCell; SUMMARY_COLUMNS.len()],
+}
+
+/// Summarizes the report with a row for each logical directory and a cell for each source.
+///
+/// This shows the same information as the sections of the report, reorganized so that all the
+/// ways of finding the same directory are side by side. The entry in each cell is determined by
+/// `summary_entry_name()`. Within a row, if the paths found do not all agree, each one that
+/// differs from the path most sources found is an outlier.
+pub fn summarize(report: &Report, platform: Platform) -> Vec<SummaryRow> {
+    LogicalDirectory::ALL
+        .into_iter()
+        .map(|directory| {
+            let entries = SUMMARY_COLUMNS.map(|(_, source)| {
+                let name = summary_entry_name(directory, source, platform)?;
+                report.section(source)?.find(name)
+            });
+
+            // Paths are compared as Windows gave them, so two that differ only in what is not
+            // valid Unicode are not taken to agree.
+            let consensus =
+                most_common(entries.iter().flatten().filter_map(|entry| entry.os_path()));
+
+            let cells = entries.map(|entry| {
+                let Some(entry) = entry else {
+                    return SummaryCell::NotApplicable;
+                };
+                match entry.path() {
+                    None => SummaryCell::Error,
+                    Some(path) if entry.os_path() == consensus => {
+                        SummaryCell::Path(path.to_owned())
+                    }
+                    Some(path) => SummaryCell::Outlier(path.to_owned()),
+                }
+            });
+
+            SummaryRow { directory, cells }
+        })
+        .collect()
+}
+
+/// The environment variable compared by `reconcile_program_w6432()`.
+const W6432_VARIABLE: &str = "ProgramW6432";
+
+/// The registry value compared by `reconcile_program_w6432()`.
+const W6432_REGISTRY_VALUE: &str = "ProgramW6432Dir";
+
+/// How far the `ProgramW6432` environment variable can be trusted, according to the registry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The variable matches `ProgramW6432Dir` in every view where it was found.
+    Trusted,
+
+    /// The variable is set, but `ProgramW6432Dir` was not found in any view.
+    Unverified,
+
+    /// The variable differs from `ProgramW6432Dir`, so it may have been altered.
+    Altered,
+
+    /// The variable is missing but `ProgramW6432Dir` is present, so it may have been removed.
+    Removed,
+
+    /// Neither is present, as on 32-bit Windows.
+    NotApplicable,
+}
+
+impl Verdict {
+    /// Whether the variable looks like it was altered or removed.
+    pub fn is_suspect(self) -> bool {
+        matches!(self, Self::Altered | Self::Removed)
+    }
+
+    /// A one-line statement of the verdict, starting with a label in capital letters.
+    pub fn description(self) -> String {
+        let (env_name, key_name) = (W6432_VARIABLE, W6432_REGISTRY_VALUE);
+        match self {
+            Self::Trusted => {
+                format!("TRUSTED: {env_name} matches {key_name} in every view where it was found.")
+            }
+            Self::Unverified => {
+                format!("UNVERIFIED: {env_name} is set, but {key_name} was not found in any view.")
+            }
+            Self::Altered => {
+                format!("SUSPECT: {env_name} differs from {key_name}. It may have been altered.")
+            }
+            Self::Removed => format!(
+                "SUSPECT: {env_name} is missing but {key_name} is present. \
+                It may have been removed."
+            ),
+            Self::NotApplicable => {
+                format!("NOT APPLICABLE: neither {env_name} nor {key_name} is present.")
+            }
+        }
+    }
+}
+
+/// The comparison of `ProgramW6432` with `ProgramW6432Dir`, made by `reconcile_program_w6432()`.
+#[derive(Clone, Debug)]
+pub struct Reconciliation {
+    pub heading: String,
+
+    /// The environment variable, followed by the registry value in each view.
+    pub rows: Vec<Row>,
+
+    pub verdict: Verdict,
+}
+
+/// Checks whether the `ProgramW6432` environment variable agrees with the registry.
+///
+/// As described in the crate documentation and on `report_environment_variables()`, the
+/// `ProgramW6432` environment variable is the easiest way for a 32-bit process to find the 64-bit
+/// program files directory, but it is only as trustworthy as the environment the process was given.
+/// The `ProgramW6432Dir` registry key holds the same information and is not affected by how a
+/// parent process built its child's environment, and it appears to be available through any view.
+///
+/// This compares the environment variable to the registry key in each of the registry views in the
+/// report:
+///
+/// - If the variable matches every view where the registry key could be read, it can be trusted.
+///
+/// - If the variable differs from any such view, or is missing while the registry key is present,
+///   it is suspect, since that is what an ancestor process that removed or altered program files
+///   related variables would produce.
+///
+/// - If neither is present, as on 32-bit Windows, where there is no 64-bit program files
+///   directory, there is nothing to reconcile.
+///
+/// This returns `None` unless the report has both environment variables and the registry.
+pub fn reconcile_program_w6432(report: &Report) -> Option<Reconciliation> {
+    let (env_name, key_name) = (W6432_VARIABLE, W6432_REGISTRY_VALUE);
+
+    let environment_variables = report.environment_variables.as_ref()?;
+    if report.registry.is_empty() {
+        return None;
+    }
+
+    let env_row = match environment_variables.find(env_name) {
+        Some(entry) => Row::relabel(entry, env_name),
+        None => Row {
+            name: env_name.to_owned(),
+            lookup: Lookup::new(Err::<String, _>(VarError::NotPresent)),
+        },
+    };
+
+    let key_rows: Vec<Row> = report
+        .registry
+        .iter()
+        .filter_map(|view| {
+            let entry = view.keys.find(key_name)?;
+            Some(Row::relabel(entry, format!("{key_name} ({})", view.view)))
+        })
+        .collect();
+
+    let found: Vec<&str> = key_rows.iter().filter_map(|row| row.path()).collect();
+
+    let verdict = match env_row.path() {
+        Some(_) if found.is_empty() => Verdict::Unverified,
+        Some(path) if found.iter().all(|&value| value == path) => Verdict::Trusted,
+        Some(_) => Verdict::Altered,
+        None if found.is_empty() => Verdict::NotApplicable,
+        None => Verdict::Removed,
+    };
+
+    Some(Reconciliation {
+        heading: format!("Reconciliation of {env_name} with {key_name}"),
+        rows: std::iter::once(env_row).chain(key_rows).collect(),
+        verdict,
+    })
+}
+
+/// The program files directories of an ARM64 system, each with a label saying what it is for.
+pub const ARM64_DIRECTORIES: [(LogicalDirectory, &str); 3] = [
+    (LogicalDirectory::ProgramFiles64, "64-bit (ARM64 and x64)"),
+    (LogicalDirectory::ProgramFiles32, "32-bit x86 (emulated)"),
+    (LogicalDirectory::ProgramFilesArm, "32-bit ARM"),
+];
+
+/// Gathers every entry, from every source, for a logical directory.
+///
+/// Entries are found as mapped by `summary_entry_name()`. Registry entries are labeled with the
+/// registry view they came from, since the same name appears in each view.
+pub fn entries_for_directory(
+    report: &Report,
+    directory: LogicalDirectory,
+    platform: Platform,
+) -> Vec<Row> {
+    SUMMARY_COLUMNS
+        .into_iter()
+        .filter_map(|(_, source)| {
+            let name = summary_entry_name(directory, source, platform)?;
+            let entry = report.section(source)?.find(name)?;
+            let label = match source {
+                SourceKind::Registry(caption) => format!("{name} ({caption})"),
+                _ => name.to_owned(),
+            };
+            Some(Row::relabel(entry, label))
+        })
+        .collect()
+}
+
+/// Merges the registry views into one section, with a row for each value that every view agrees
+/// on, and a row for each view of a value that they do not, or `None` if there is only one view.
+///
+/// Views agree on a value if they give the same path or the same error. Usually all of them agree
+/// on all but `ProgramFilesDir`, whose meaning depends on the view. The rows of a value the views
+/// do not agree on are labeled with the view each came from, as in `entries_for_directory()`, and
+/// are in the order of the views. The values are in the order of the first view.
+pub fn merge_registry_views(report: &Report) -> Option<Section<Row>> {
+    let [first, rest @ ..] = report.registry.as_slice() else {
+        return None;
+    };
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut rows = Vec::new();
+    for entry in &first.keys.entries {
+        let views: Vec<(&str, &RegistryEntry)> = report
+            .registry
+            .iter()
+            .filter_map(|view| Some((view.view, view.keys.find(&entry.name)?)))
+            .collect();
+        let item = entry.lookup.path_item();
+        if views
+            .iter()
+            .all(|(_, other)| other.lookup.path_item() == item)
+        {
+            rows.push(Row::relabel(entry, entry.name.clone()));
+        } else {
+            rows.extend(views.into_iter().map(|(caption, other)| {
+                Row::relabel(other, format!("{} ({caption})", other.name))
+            }));
+        }
+    }
+
+    let heading = match first.keys.heading.rsplit_once(" - ") {
+        Some((base, _)) => format!("{base} - in every view"),
+        None => String::from("Relevant registry keys - in every view"),
+    };
+    Some(Section::new(heading, rows))
+}
+
+/// Explains why an entry has the value, or error, that it has.
+///
+/// This applies the rules described in the crate documentation and on the `report_*` functions
+/// for each source to the architecture of the process and system and to whether the entry was
+/// found. The explanation says whether the result is expected and, when it is not, what the likely
+/// cause is. For example, it says that `ProgramFiles` reflects the 32-bit x86 directory because
+/// this is a 32-bit process on 64-bit Windows, or that `FOLDERID_ProgramFilesX64` is documented to
+/// be unavailable to 32-bit processes.
+pub fn explain_entry(source: SourceKind, entry: &dyn Entry, platform: Platform) -> String {
+    let name = entry.name();
+    let found = entry.path().is_some();
+    let context = platform.description();
+    let os_64 = platform.native.is_64_bit();
+    let arm64 = platform.native == Machine::Arm64;
+    let is_env = source == SourceKind::EnvironmentVariable;
+    let discrepancy = entry.lookup().error.as_ref().is_some_and(|e| e.discrepancy);
+
+    let missing_unexpectedly = || {
+        if is_env {
+            format!("Missing, though expected because {context}. It may have been removed.")
+        } else {
+            format!("Unavailable, though expected because {context}.")
+        }
+    };
+
+    if source == SourceKind::ShellMoniker {
+        if let Some(known_folder) = known_folder_of_moniker(name) {
+            let row = Row::relabel(entry, known_folder);
+            return explain_entry(SourceKind::KnownFolder, &row, platform);
+        }
+    }
+
+    match (source, name) {
+        (SourceKind::EnvironmentRegistry(_), _) if found => String::from(
+            "Stored in the registry, so it overrides the value Windows would otherwise compute \
+            for new environments.",
+        ),
+        (SourceKind::EnvironmentRegistry(_), _) => String::from(
+            "Not stored, as is usual. Windows computes it when it creates a new environment.",
+        ),
+
+        (SourceKind::FreshEnvironment, _) if found => String::from(
+            "What a process started for this user now would get. If this process inherited a \
+            different value, it is stale.",
+        ),
+        (SourceKind::FreshEnvironment, _) => String::from(
+            "A process started for this user now would not get it, which is usual if the system \
+            has no such directory. If this process inherited it anyway, it is stale.",
+        ),
+
+        (SourceKind::Wmi, OS_ARCHITECTURE) if found => String::from(
+            "How WMI describes the architecture of the OS. Unlike ProgramFiles, it is the same for \
+            32-bit and 64-bit processes.",
+        ),
+        (SourceKind::Wmi, OS_ARCHITECTURE) => String::from(
+            "Unavailable, so WMI could not be queried. Tools that read system information \
+            through WMI, such as configuration management agents, may fail in the same way.",
+        ),
+        (SourceKind::Wmi, _) if found => String::from(
+            "Stored in the registry for new environments, which is what WMI reports. It overrides \
+            the value Windows would otherwise compute, and tools reading WMI see it.",
+        ),
+        (SourceKind::Wmi, _) => String::from(
+            "Not reported by WMI, as is usual. WMI gives only the variables stored in the \
+            registry, and Windows computes this one when it creates a new environment.",
+        ),
+
+        (SourceKind::Shells, _) if found => String::from(
+            "What the shell gives when started by this process. If it differs from what a shell \
+            started from the Start menu gives, the architectures of the shells likely differ.",
+        ),
+        (SourceKind::Shells, _) => String::from(
+            "The shell gave nothing. If this process found the same thing, the shell looked it up \
+            in a different way, or was not started as expected.",
+        ),
+
+        (SourceKind::Msi, "ProgramFiles64Folder") if found => String::from(
+            "Where MSI packages install 64-bit programs by default. Windows Installer resolves it \
+            on its own, so if it differs from other sources, MSI installs go somewhere else.",
+        ),
+        (SourceKind::Msi, _) if found => String::from(
+            "Where MSI packages install 32-bit programs by default, which is the only program \
+            files directory on 32-bit Windows.",
+        ),
+        (SourceKind::Msi, _) if discrepancy => String::from(
+            "Windows Installer disagrees with the registry, so MSI packages install somewhere \
+            other than where other installers and programs expect.",
+        ),
+        (SourceKind::Msi, "ProgramFiles64Folder") if !os_64 => String::from(
+            "Unavailable, as expected, since Windows Installer sets it only on 64-bit Windows.",
+        ),
+        (SourceKind::Msi, _) => String::from(
+            "Unavailable. If Windows Installer could not open a session, as when its service is \
+            disabled, MSI packages cannot be installed either.",
+        ),
+
+        (SourceKind::SystemDirectories, WINDOWS_DIRECTORY | SYSTEM_DIRECTORY) if found => {
+            String::from(
+                "Where Windows and its system files are. Even under WOW64, the system directory is \
+                given as System32, which is redirected when it is accessed.",
+            )
+        }
+        (SourceKind::SystemDirectories, SYSTEM_WOW64_DIRECTORY) if found => String::from(
+            "Where 32-bit x86 programs find System32. Under WOW64, System32 is redirected here.",
+        ),
+        (SourceKind::SystemDirectories, SYSTEM_WOW64_DIRECTORY) if os_64 => missing_unexpectedly(),
+        (SourceKind::SystemDirectories, SYSTEM_WOW64_DIRECTORY) => String::from(
+            "Unavailable, as expected, since only 64-bit systems run 32-bit programs under WOW64.",
+        ),
+        (SourceKind::SystemDirectories, SYSTEM_WOW64_ARM_DIRECTORY) if found => String::from(
+            "Where 32-bit ARM programs find System32. Under WOW64, System32 is redirected here.",
+        ),
+        (SourceKind::SystemDirectories, SYSTEM_WOW64_ARM_DIRECTORY) => String::from(
+            "Unavailable, which is usual. Only ARM64 systems have it, and newer versions of \
+            Windows no longer run 32-bit ARM programs.",
+        ),
+        (SourceKind::SystemDirectories, SYSNATIVE) if found => String::from(
+            "Found, so this is a 32-bit process under WOW64, whose access to System32 is \
+            redirected. Sysnative reaches the real System32.",
+        ),
+        (SourceKind::SystemDirectories, SYSNATIVE) if platform.is_wow64() => {
+            missing_unexpectedly()
+        }
+        (SourceKind::SystemDirectories, SYSNATIVE) => String::from(
+            "Unavailable, as expected, since only 32-bit processes under WOW64 see it. This \
+            process's access to System32 is not redirected.",
+        ),
+        (SourceKind::SystemDirectories, _) => missing_unexpectedly(),
+
+        (SourceKind::ArmProgramFiles, _) if found => String::from(
+            "The 32-bit ARM program files directory. There is no FOLDERID or CSIDL for it, so \
+            this is how to find it.",
+        ),
+        (SourceKind::ArmProgramFiles, _) if arm64 => missing_unexpectedly(),
+        (SourceKind::ArmProgramFiles, _) => String::from(
+            "Unavailable, as expected, since only ARM64 systems have an ARM program files \
+            directory.",
+        ),
+
+        (SourceKind::PerUserInstalls, USER_PROGRAM_FILES_COMMON) if found => String::from(
+            "The per-user directory for components shared between programs installed only for \
+            this user.",
+        ),
+        (SourceKind::PerUserInstalls, USER_PROGRAM_FILES_COMMON) => String::from(
+            "Unavailable. This is usual until a per-user install creates it, or when there is no \
+            loaded user profile.",
+        ),
+        (SourceKind::PerUserInstalls, USER_PROGRAM_FILES_COMMON_SHELL_FOLDER) if found => {
+            String::from("Set, so the per-user common directory was redirected.")
+        }
+        (SourceKind::PerUserInstalls, USER_PROGRAM_FILES_COMMON_SHELL_FOLDER) => {
+            String::from("Not set, as is usual when the per-user common directory is not moved.")
+        }
+        (SourceKind::PerUserInstalls, USER_WINDOWS_APPS) if found => String::from(
+            "Where the app execution aliases of MSIX packages installed for this user are. It is \
+            in this user's PATH.",
+        ),
+        (SourceKind::PerUserInstalls, PACKAGE_ROOT_VALUE) if found => String::from(
+            "Where MSIX packages are installed, once per machine, even when installed for only \
+            one user.",
+        ),
+        (SourceKind::PerUserInstalls, _) => String::from(
+            "Unavailable. This is usual when there is no loaded user profile, as for services, or \
+            when MSIX deployment is not configured.",
+        ),
+
+        (SourceKind::Virtualization, TOKEN_VIRTUALIZATION_ENABLED)
+            if entry.path() == Some(ENABLED) =>
+        {
+            String::from(
+                "Enabled, so this process's writes to the program files directories, which it \
+                cannot otherwise make, go to its virtual store instead.",
+            )
+        }
+        (SourceKind::Virtualization, TOKEN_VIRTUALIZATION_ENABLED) if found => String::from(
+            "Disabled, as is usual for programs with a manifest, such as this one, and for 64-bit \
+            or elevated processes.",
+        ),
+        (SourceKind::Virtualization, TOKEN_VIRTUALIZATION_ALLOWED)
+            if entry.path() == Some(ALLOWED) =>
+        {
+            String::from(
+                "Allowed, so 32-bit programs without a manifest that this user runs unelevated \
+                have their writes to the program files directories silently redirected.",
+            )
+        }
+        (SourceKind::Virtualization, TOKEN_VIRTUALIZATION_ALLOWED) if found => String::from(
+            "Not allowed, as when this process is elevated or UAC virtualization is turned off, \
+            so writes are not redirected.",
+        ),
+        (SourceKind::Virtualization, name) if !VIRTUAL_STORES.contains(&name) => {
+            String::from("Unavailable, since this process's token could not be queried.")
+        }
+        (SourceKind::Virtualization, _) if found => String::from(
+            "Exists, so UAC virtualization has redirected some program's writes to a program \
+            files directory here. Files in it are seen only by this user, and only by programs \
+            that are virtualized, so to everyone else they have vanished.",
+        ),
+        (SourceKind::Virtualization, _) => String::from(
+            "Does not exist, as is usual, since it is created only when a write to the program \
+            files directory of the same name is redirected.",
+        ),
+
+        (SourceKind::Package, TOKEN_IS_APP_CONTAINER) if entry.path() == Some(IN_APP_CONTAINER) => {
+            String::from(
+                "This process runs in an AppContainer, so it has only the access its capabilities \
+                grant. Known folders may be denied, or given as the container's own folders.",
+            )
+        }
+        (SourceKind::Package, TOKEN_IS_APP_CONTAINER) if found => String::from(
+            "Not in an AppContainer, so lookups have the access this user usually has.",
+        ),
+        (SourceKind::Package, TOKEN_IS_APP_CONTAINER) => {
+            String::from("Unavailable, since this process's token could not be queried.")
+        }
+        (SourceKind::Package, PACKAGE_FULL_NAME) if found => String::from(
+            "This process has the identity of an MSIX package, so it may see the package's own \
+            files merged into the program files directories, and its AppData writes redirected.",
+        ),
+        (SourceKind::Package, PACKAGE_PATH) if found => String::from(
+            "Where the package is installed, normally under WindowsApps in the 64-bit program \
+            files directory, which only the system can write to.",
+        ),
+        (SourceKind::Package, PACKAGE_FULL_NAME | PACKAGE_PATH) => String::from(
+            "Unavailable, as is usual for a program not installed from an MSIX package. This \
+            process has no package identity, so nothing it sees is virtualized by a package.",
+        ),
+        (SourceKind::Package, _) if found => String::from(
+            "The package's files for the program files directory of this name. This process sees \
+            them merged into that directory, but no other process does.",
+        ),
+        (SourceKind::Package, _) => String::from(
+            "Unavailable, since this process has no package identity or its package has no such \
+            files.",
+        ),
+
+        (SourceKind::Policy, name) if REDIRECTION_CAPABILITIES.iter().any(|&(n, _)| n == name) => {
+            match entry.path() {
+                Some(REDIRECTED_BY_POLICY) => String::from(
+                    "Policy has redirected this folder, so it may not be where it would otherwise \
+                    be. The User Shell Folders key shows where.",
+                ),
+                Some(DENIED_BY_POLICY) => String::from(
+                    "Policy forbids redirecting this folder, so tools that move it will fail.",
+                ),
+                Some(REDIRECTABLE) => String::from(
+                    "This folder may be redirected, as per-user folders usually may, and policy \
+                    does not forbid it.",
+                ),
+                Some(_) => String::from(
+                    "This folder cannot be redirected, as is usual for a fixed folder such as a \
+                    machine-wide program files folder.",
+                ),
+                None => String::from("Unavailable, since the known folder could not be queried."),
+            }
+        }
+        (SourceKind::Policy, _) if entry.path() == Some(NO_VALUES) => String::from(
+            "The key exists, but no policy sets anything in it.",
+        ),
+        (SourceKind::Policy, _) if found => String::from(
+            "Policy sets these values, so Explorer and the Shell may behave differently than on an \
+            unmanaged machine.",
+        ),
+        (SourceKind::Policy, _) => String::from(
+            "Not configured, as is usual on a machine that is not managed by Group Policy.",
+        ),
+
+        (SourceKind::ShellFolders { .. }, _) if found => String::from(
+            "Listed in a legacy shell folders key, which old software may read instead of the \
+            known folder. This usually means the folder was redirected or set by policy.",
+        ),
+        (SourceKind::ShellFolders { .. }, _) => String::from(
+            "Not listed, as is usual. The program files folders are defined in CurrentVersion.",
+        ),
+
+        (SourceKind::Registry("KEY_WOW64_32KEY"), "ProgramFilesDir") if found && os_64 => {
+            String::from("The 32-bit view always gives the 32-bit x86 directory.")
+        }
+        (SourceKind::Registry("KEY_WOW64_64KEY"), "ProgramFilesDir") if found && os_64 => {
+            String::from("The 64-bit view always gives the 64-bit directory.")
+        }
+        (SourceKind::Registry(LITERAL_WOW6432NODE_VIEW), _) if !os_64 => format!(
+            "Not found, as expected, because {context}, which has no WOW6432Node key."
+        ),
+        (SourceKind::Registry(LITERAL_WOW6432NODE_VIEW), "ProgramFilesDir") if found => {
+            String::from(
+                "WOW6432Node holds the 32-bit view, so it gives the 32-bit x86 directory.",
+            )
+        }
+        (SourceKind::Registry(LITERAL_PATH_VIEW), "ProgramFilesDir") if found && os_64 => {
+            String::from("The key as written, not redirected, so it gives the 64-bit directory.")
+        }
+        (SourceKind::Registry(_), "ProgramFilesDir")
+        | (_, "ProgramFiles" | "FOLDERID_ProgramFiles" | "CSIDL_PROGRAM_FILES") => {
+            if !found {
+                missing_unexpectedly()
+            } else if !os_64 {
+                format!("The only program files directory, because {context}.")
+            } else if platform.process.is_64_bit() {
+                format!("Reflects the 64-bit directory, because {context}.")
+            } else {
+                format!("Reflects the 32-bit x86 directory, because {context}.")
+            }
+        }
+
+        (
+            _,
+            "ProgramFiles(x86)"
+            | "FOLDERID_ProgramFilesX86"
+            | "CSIDL_PROGRAM_FILESX86"
+            | "ProgramFilesDir (x86)",
+        ) => match (found, os_64) {
+            (true, true) => format!("The 32-bit x86 directory, present because {context}."),
+            (true, false) => format!("The only program files directory, because {context}."),
+            (false, true) => missing_unexpectedly(),
+            (false, false) => format!("Not found, as expected, because {context}."),
+        },
+
+        (_, "FOLDERID_ProgramFilesX64") if !found && os_64 && !platform.process.is_64_bit() => {
+            String::from(
+                "Documented to be unavailable to 32-bit processes, even on 64-bit Windows.",
+            )
+        }
+        (_, "ProgramW6432" | "FOLDERID_ProgramFilesX64" | "ProgramW6432Dir") => {
+            match (found, os_64) {
+                (true, _) => format!("The 64-bit directory, present because {context}."),
+                (false, true) => missing_unexpectedly(),
+                (false, false) => format!(
+                    "Not found, as expected, because {context}, which has no 64-bit directory."
+                ),
+            }
+        }
+
+        (_, "ProgramFiles(Arm)" | "ProgramFilesDir (Arm)") => match (found, arm64) {
+            (true, _) => String::from("The 32-bit ARM directory, present on ARM64 Windows."),
+            (false, false) => format!("Not found, as expected, because {context}."),
+            (false, true)
+                if matches!(
+                    source,
+                    SourceKind::Registry("KEY_WOW64_32KEY" | LITERAL_WOW6432NODE_VIEW)
+                ) =>
+            {
+                String::from("Not in the 32-bit registry view, even on ARM64 Windows.")
+            }
+            (false, true) => missing_unexpectedly(),
+        },
+
+        (_, "FOLDERID_UserProgramFiles") if found => {
+            String::from("The per-user directory, for programs installed only for this user.")
+        }
+        (_, "FOLDERID_UserProgramFiles") => String::from(
+            "Unavailable. This is usual when there is no loaded user profile, as for services.",
+        ),
+
+        _ => String::from("No explanation is available."),
+    }
+}
+
+/// A source that found a different path for a directory than most sources did.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dissent {
+    /// The header of the source's column in `SUMMARY_COLUMNS`.
+    pub source: &'static str,
+
+    /// The name of the source's entry for the directory, as given by `summary_entry_name()`.
+    pub name: &'static str,
+
+    pub path: String,
+}
+
+/// Where a logical directory is, as agreed on by the sources, found by `resolve_consensus()`.
+#[derive(Clone, Debug)]
+pub struct Resolution {
+    pub directory: LogicalDirectory,
+
+    /// The path most sources found, or `None` if no source found any path.
+    pub consensus: Option<String>,
+
+    /// The path most sources found, as Windows gave it, which is what the filesystem is checked
+    /// with.
+    pub os_path: Option<OsString>,
+
+    /// The sources that found a different path. If this is empty, all sources that found a path
+    /// agree.
+    pub dissents: Vec<Dissent>,
+}
+
+/// Resolves each logical directory to the path the sources agree on, noting any that disagree.
+///
+/// This is the summary table found by `summarize()`, reduced to one path per directory. Sources
+/// that have no entry for a directory, or whose lookup failed, neither agree nor disagree.
+pub fn resolve_consensus(report: &Report, platform: Platform) -> Vec<Resolution> {
+    summarize(report, platform)
+        .into_iter()
+        .map(|row| {
+            let mut consensus = None;
+            let mut os_path = None;
+            let mut dissents = Vec::new();
+
+            for ((source, kind), cell) in SUMMARY_COLUMNS.into_iter().zip(row.cells) {
+                match cell {
+                    SummaryCell::Path(path) => {
+                        consensus = Some(path);
+                        os_path = summary_entry_name(row.directory, kind, platform)
+                            .and_then(|name| report.section(kind)?.find(name)?.os_path())
+                            .map(OsStr::to_owned);
+                    }
+                    SummaryCell::Outlier(path) => dissents.push(Dissent {
+                        source,
+                        name: summary_entry_name(row.directory, kind, platform).unwrap_or_default(),
+                        path,
+                    }),
+                    SummaryCell::NotApplicable | SummaryCell::Error => {}
+                }
+            }
+
+            Resolution {
+                directory: row.directory,
+                consensus,
+                os_path,
+                dissents,
+            }
+        })
+        .collect()
+}
+
+/// A sign that a program files directory has been moved from where Windows puts it, found by
+/// `relocations()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RelocationSign {
+    /// The path is not on the system drive, which is given.
+    OffSystemDrive(String),
+
+    /// The path, or one of its parent directories, is a junction or symbolic link to a directory on
+    /// another volume, whose final path is given.
+    LinkedToOtherVolume(String),
+
+    /// The path is not the default path for the directory, which is given.
+    NotDefault(String),
+}
+
+impl RelocationSign {
+    pub fn description(&self) -> String {
+        match self {
+            Self::OffSystemDrive(drive) => format!("not on the system drive {drive}"),
+            Self::LinkedToOtherVolume(path) => format!("links to another volume, at {path}"),
+            Self::NotDefault(path) => format!("not the default {path}"),
+        }
+    }
+}
+
+/// A program files directory that appears to have been relocated, found by `relocations()`.
+#[derive(Clone, Debug)]
+pub struct Relocation {
+    pub directory: LogicalDirectory,
+
+    /// The path the sources agree on, as found by `resolve_consensus()`.
+    pub path: String,
+
+    /// Every sign that the directory was relocated. This is never empty.
+    pub signs: Vec<RelocationSign>,
+}
+
+/// The default path of a machine-wide program files directory, given the system drive.
+///
+/// On 32-bit Windows, there is only one program files directory, and it is the 32-bit one. The
+/// per-user program files directory is in the user's profile, not under the system drive's root,
+/// so it has no default in this sense, and neither does any directory on a system whose
+/// architecture is unknown.
+#[cfg(windows)]
+fn default_path(
+    directory: LogicalDirectory,
+    system_drive: &str,
+    native: Machine,
+) -> Option<String> {
+    let name = match (directory, native) {
+        (_, Machine::Unknown) | (LogicalDirectory::UserProgramFiles, _) => return None,
+        (LogicalDirectory::ProgramFiles32, Machine::X86) => "Program Files",
+        (LogicalDirectory::ProgramFiles32, _) => "Program Files (x86)",
+        (LogicalDirectory::ProgramFiles64, _) => "Program Files",
+        (LogicalDirectory::ProgramFilesArm, _) => "Program Files (Arm)",
+    };
+    Some(format!(r"{system_drive}\{name}"))
+}
+
+/// The drive letter and colon a path starts with, such as `C:`, if it starts with one.
+#[cfg(windows)]
+fn drive_of(path: &str) -> Option<&str> {
+    let drive = path.get(..2)?;
+    (drive.ends_with(':') && drive.starts_with(|c: char| c.is_ascii_alphabetic())).then_some(drive)
+}
+
+/// Finds the machine-wide program files directories that appear to have been moved from where
+/// Windows puts them, on the local system.
+///
+/// Windows supports only the default locations, `Program Files` and, on 64-bit systems,
+/// `Program Files (x86)` and on ARM64 `Program Files (Arm)`, in the root of the system drive. It
+/// is possible to put them elsewhere, by editing the registry, by an unattended setup answer file,
+/// or by replacing a directory with a junction to another volume, but this is not supported, and
+/// many installers assume the default locations or the system drive, and fail or install to the
+/// wrong place. So for each directory whose path the sources agree on, as found by
+/// `resolve_consensus()`, this looks for three signs of relocation:
+///
+/// - The path is not on the system drive, as given by the `SystemDrive` environment variable.
+///
+/// - The path is on the system drive, but its final path, as found by `verify_path()`, is on
+///   another volume, because it or a parent directory is a junction or symbolic link.
+///
+/// - The path differs, other than in case, from the default path for the directory.
+///
+/// The second sign is checked on disk, so this is only meaningful for the system this is running
+/// on. If `SystemDrive` is not set, as it may not be if an ancestor stripped the environment, only
+/// the second sign is checked. The per-user program files directory is not checked, since it is in
+/// the user's profile, which may be anywhere.
+#[cfg(windows)]
+pub fn relocations(report: &Report, platform: Platform) -> Vec<Relocation> {
+    let system_drive = std::env::var("SystemDrive").ok();
+
+    resolve_consensus(report, platform)
+        .into_iter()
+        .filter(|resolution| resolution.directory != LogicalDirectory::UserProgramFiles)
+        .filter_map(|resolution| {
+            let path = resolution.consensus?;
+            let os_path = resolution.os_path?;
+            let mut signs = Vec::new();
+
+            if let Some(system_drive) = &system_drive {
+                if !drive_of(&path).is_some_and(|drive| drive.eq_ignore_ascii_case(system_drive)) {
+                    signs.push(RelocationSign::OffSystemDrive(system_drive.clone()));
+                }
+            }
+
+            if let Ok(final_path) = verify_path(&os_path).final_path {
+                if drive_of(&final_path).map(str::to_ascii_uppercase)
+                    != drive_of(&path).map(str::to_ascii_uppercase)
+                {
+                    signs.push(RelocationSign::LinkedToOtherVolume(final_path));
+                }
+            }
+
+            let default = system_drive.as_deref().and_then(|system_drive| {
+                default_path(resolution.directory, system_drive, platform.native)
+            });
+            if let Some(default) = default {
+                if !path.trim_end_matches('\\').eq_ignore_ascii_case(&default) {
+                    signs.push(RelocationSign::NotDefault(default));
+                }
+            }
+
+            (!signs.is_empty()).then_some(Relocation {
+                directory: resolution.directory,
+                path,
+                signs,
+            })
+        })
+        .collect()
+}
+
+/// An entry, for one program files directory, that reaches the same directory on disk as an entry
+/// for another one, found by `aliases()`.
+#[derive(Clone, Debug)]
+pub struct AliasedEntry {
+    /// The directory the entry is for.
+    pub directory: LogicalDirectory,
+
+    /// The entry's name, labeled as by `entries_for_directory()`.
+    pub name: String,
+
+    /// The path the entry found.
+    pub path: String,
+}
+
+/// Entries for different program files directories that all reach the same directory on disk,
+/// found by `aliases()`.
+#[derive(Clone, Debug)]
+pub struct Alias {
+    /// What identifies the directory on disk that they all reach.
+    pub file_id: FileId,
+
+    /// The entries, which are for at least two different program files directories.
+    pub entries: Vec<AliasedEntry>,
+}
+
+/// Finds entries for different program files directories that are really the same directory on
+/// disk, on the local system.
+///
+/// Each program files directory should be separate from the others, but they can be made the same
+/// by misconfiguration, such as `ProgramFilesDir` and `ProgramW6432Dir` being set to the same path,
+/// or by a junction that makes one directory an alias of another, such as when `Program Files
+/// (x86)` is replaced by a junction to `Program Files`. Comparing paths as text catches the first
+/// but not the second. So each entry for each directory that exists on the system, as found by
+/// `entries_for_directory()`, is opened and identified by its volume serial number and file ID, as
+/// found by `file_id()`, and entries for different directories that have the same ID are grouped
+/// together. Entries that could not be opened, such as those for directories that do not exist,
+/// are skipped.
+///
+/// This is checked on disk, so it is only meaningful for the system this is running on. Entries
+/// for the same directory that reach it by different paths are not reported, since they agree.
+#[cfg(windows)]
+pub fn aliases(report: &Report, platform: Platform) -> Vec<Alias> {
+    let mut aliases: Vec<Alias> = Vec::new();
+
+    for directory in LogicalDirectory::ALL {
+        if !directory.exists_on(platform.native) {
+            continue;
+        }
+        for row in entries_for_directory(report, directory, platform) {
+            let (Some(path), Some(os_path)) = (row.lookup.path, row.lookup.os_path) else {
+                continue;
+            };
+            let Ok(id) = file_id(&os_path) else {
+                continue;
+            };
+            let entry = AliasedEntry {
+                directory,
+                name: row.name,
+                path,
+            };
+            match aliases.iter_mut().find(|alias| alias.file_id == id) {
+                Some(alias) => alias.entries.push(entry),
+                None => aliases.push(Alias {
+                    file_id: id,
+                    entries: vec![entry],
+                }),
+            }
+        }
+    }
+
+    aliases.retain(|alias| {
+        let first = alias.entries[0].directory;
+        alias.entries.iter().any(|entry| entry.directory != first)
+    });
+    aliases
+}
+
+/// Installed applications grouped by the program files directory they are in, found by
+/// `app_census()`.
+#[derive(Clone, Debug)]
+pub struct AppCensus {
+    /// Each program files directory that was found, with its path and the applications in it.
+    pub directories: Vec<(LogicalDirectory, String, Vec<InstalledApp>)>,
+
+    /// The applications that are not in any program files directory.
+    pub elsewhere: Vec<InstalledApp>,
+
+    /// The applications whose location the registry does not give.
+    pub unknown: Vec<InstalledApp>,
+}
+
+/// Whether `path` is `directory` or inside it, compared without regard to case.
+fn is_within(path: &str, directory: &str) -> bool {
+    let directory = directory.trim_end_matches('\\');
+    path.get(..directory.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(directory))
+        && matches!(path[directory.len()..].chars().next(), None | Some('\\'))
+}
+
+/// Groups installed applications, as found by `installed_apps()`, by the program files directory
+/// each is installed in, to show which tree really hosts them.
+///
+/// Each directory's path is the one the sources agree on, as found by `resolve_consensus()`, and an
+/// application is in it if its location is that path or under it. Directories no source found are
+/// left out. Paths are compared as text, so an application whose location is written as an 8.3
+/// short path, or reaches the directory through a junction, is counted as elsewhere.
+pub fn app_census(report: &Report, platform: Platform, apps: Vec<InstalledApp>) -> AppCensus {
+    let mut census = AppCensus {
+        directories: resolve_consensus(report, platform)
+            .into_iter()
+            .filter_map(|resolution| {
+                Some((resolution.directory, resolution.consensus?, Vec::new()))
+            })
+            .collect(),
+        elsewhere: Vec::new(),
+        unknown: Vec::new(),
+    };
+
+    for app in apps {
+        let Some(location) = &app.location else {
+            census.unknown.push(app);
+            continue;
+        };
+        match census
+            .directories
+            .iter_mut()
+            .find(|(_, path, _)| is_within(location, path))
+        {
+            Some((_, _, apps)) => apps.push(app),
+            None => census.elsewhere.push(app),
+        }
+    }
+
+    census
+}
+
+/// A sign that UAC virtualization may be redirecting writes meant for a program files directory,
+/// found by `virtualization_signs()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VirtualizationSign {
+    /// Virtualization is enabled for this process's token.
+    Enabled,
+
+    /// A virtual store directory for a program files directory exists, at the given path.
+    VirtualStore(String),
+}
+
+impl VirtualizationSign {
+    pub fn description(&self) -> String {
+        match self {
+            Self::Enabled => String::from("UAC virtualization is enabled for this process"),
+            Self::VirtualStore(path) => format!("writes have been redirected to {path}"),
+        }
+    }
+}
+
+/// Finds signs, in the UAC virtualization section, that writes meant for the program files
+/// directories are being redirected, as described on `report_virtualization()`.
+///
+/// Virtualization being allowed is not itself a sign, since it is allowed for most users who are
+/// not elevated, and only affects programs without a manifest. If the report has no UAC
+/// virtualization section, or there are no signs, this is empty.
+pub fn virtualization_signs(report: &Report) -> Vec<VirtualizationSign> {
+    let Some(section) = &report.virtualization else {
+        return Vec::new();
+    };
+
+    section
+        .entries
+        .iter()
+        .filter_map(
+            |entry| match (entry.name.as_str(), entry.lookup.path.as_deref()) {
+                (TOKEN_VIRTUALIZATION_ENABLED, Some(ENABLED)) => Some(VirtualizationSign::Enabled),
+                (name, Some(path)) if VIRTUAL_STORES.contains(&name) => {
+                    Some(VirtualizationSign::VirtualStore(path.to_owned()))
+                }
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// A way that running in an AppContainer or with package identity affects what this process sees,
+/// found by `package_effects()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PackageEffect {
+    /// This process runs in an AppContainer.
+    AppContainer,
+
+    /// This process has the identity of the MSIX package with the given full name.
+    PackageIdentity(String),
+}
+
+impl PackageEffect {
+    pub fn description(&self) -> String {
+        match self {
+            Self::AppContainer => String::from(
+                "this process runs in an AppContainer, so known folders may be denied to it or \
+                given as the container's own folders",
+            ),
+            Self::PackageIdentity(name) => format!(
+                "this process has the identity of the package {name}, so it sees the package's \
+                virtual file system merged into the folders it stands for"
+            ),
+        }
+    }
+}
+
+/// Finds the ways, in the package and AppContainer section, that this process sees different
+/// results than an unpackaged process would, as described on `report_package()`.
+///
+/// If the report has no package and AppContainer section, or this process is neither packaged nor
+/// in an AppContainer, as is usual, this is empty.
+pub fn package_effects(report: &Report) -> Vec<PackageEffect> {
+    let Some(section) = &report.package else {
+        return Vec::new();
+    };
+
+    section
+        .entries
+        .iter()
+        .filter_map(
+            |entry| match (entry.name.as_str(), entry.lookup.path.as_deref()) {
+                (TOKEN_IS_APP_CONTAINER, Some(IN_APP_CONTAINER)) => {
+                    Some(PackageEffect::AppContainer)
+                }
+                (PACKAGE_FULL_NAME, Some(name)) => {
+                    Some(PackageEffect::PackageIdentity(name.to_owned()))
+                }
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// A sign that policy affects the program files known folders, found by `policy_signs()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicySign {
+    /// Policy sets the given values in the policy key with the given caption.
+    Configured { key: String, values: String },
+
+    /// Policy has redirected the known folder with the given name.
+    Redirected(String),
+
+    /// Policy forbids redirecting the known folder with the given name.
+    RedirectionDenied(String),
+}
+
+impl PolicySign {
+    pub fn description(&self) -> String {
+        match self {
+            Self::Configured { key, values } => format!("policy sets {values} in {key}"),
+            Self::Redirected(folder) => format!("{folder} has been redirected by policy"),
+            Self::RedirectionDenied(folder) => {
+                format!("{folder} may not be redirected, by policy")
+            }
+        }
+    }
+}
+
+/// Finds signs, in the policy and folder redirection section, that policy affects the program
+/// files known folders, as described on `report_policy()`.
+///
+/// If the report has no policy section, or no policy is set, as is usual on a machine that is not
+/// managed, this is empty.
+pub fn policy_signs(report: &Report) -> Vec<PolicySign> {
+    let Some(section) = &report.policy else {
+        return Vec::new();
+    };
+
+    section
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let value = entry.lookup.path.as_deref()?;
+            let folder = || {
+                let name = entry.name.as_str();
+                name.strip_suffix(" redirection").unwrap_or(name).to_owned()
+            };
+            match value {
+                REDIRECTED_BY_POLICY => Some(PolicySign::Redirected(folder())),
+                DENIED_BY_POLICY => Some(PolicySign::RedirectionDenied(folder())),
+                NO_VALUES => None,
+                _ if POLICY_KEYS
+                    .iter()
+                    .any(|&(caption, _)| caption == entry.name) =>
+                {
+                    Some(PolicySign::Configured {
+                        key: entry.name.clone(),
+                        values: value.to_owned(),
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// A reason `FOLDERID_UserProgramFiles` may be unavailable to a service, found by
+/// `user_program_files_diagnosis()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServiceSign {
+    /// This process is in session 0, where services run.
+    SessionZero,
+
+    /// This process runs as a built-in service account.
+    ServiceAccount(ServiceAccount),
+
+    /// This process's user profile could not be found.
+    NoProfile,
+}
+
+impl ServiceSign {
+    pub fn description(&self) -> String {
+        match self {
+            Self::SessionZero => String::from("this process is in session 0, where services run"),
+            Self::ServiceAccount(account) => format!(
+                "this process runs as {}, whose profile has no per-user program files directory",
+                account.description()
+            ),
+            Self::NoProfile => String::from("this process has no loaded user profile"),
+        }
+    }
+}
+
+/// Finds why `FOLDERID_UserProgramFiles` is unavailable, if it is and this process looks like a
+/// service, as described on `ServiceContext`.
+///
+/// If the report has no known folders section, or `FOLDERID_UserProgramFiles` was found, or
+/// nothing about the context explains its absence, this is empty. Then the usual explanation, given
+/// by `explain_entry()`, applies.
+pub fn user_program_files_diagnosis(report: &Report, context: &ServiceContext) -> Vec<ServiceSign> {
+    let failed = report.known_folders.as_ref().is_some_and(|section| {
+        section
+            .entries
+            .iter()
+            .any(|entry| entry.name == "FOLDERID_UserProgramFiles" && entry.lookup.path.is_none())
+    });
+    if !failed || !context.is_service_like() {
+        return Vec::new();
+    }
+
+    let mut signs = Vec::new();
+    if context.is_session_zero() {
+        signs.push(ServiceSign::SessionZero);
+    }
+    if let Some(account) = context.account {
+        signs.push(ServiceSign::ServiceAccount(account));
+    }
+    if !context.has_profile() {
+        signs.push(ServiceSign::NoProfile);
+    }
+    signs
+}
+
+/// Where an environment variable this process inherited would come from in a new environment,
+/// found by `environment_origins()`.
+#[derive(Clone, Debug)]
+pub struct EnvironmentOrigin {
+    pub variable: &'static str,
+
+    /// The value this process inherited, if it has the variable.
+    pub inherited: Option<String>,
+
+    /// The captions of the registry keys that store the variable, with the values they give, in
+    /// increasing order of precedence. If this is empty, Windows computes the variable instead.
+    pub stored: Vec<(&'static str, String)>,
+}
+
+impl EnvironmentOrigin {
+    /// The stored value that a new environment would get, which is the last one, if any.
+    pub fn effective(&self) -> Option<&(&'static str, String)> {
+        self.stored.last()
+    }
+
+    /// Whether the inherited value is the one a new environment would get.
+    ///
+    /// When the variable is computed rather than stored, this cannot be known from the registry
+    /// keys alone, so it is assumed.
+    pub fn matches(&self) -> bool {
+        match self.effective() {
+            Some((_, stored)) => self.inherited.as_deref() == Some(stored),
+            None => true,
+        }
+    }
+
+    /// A sentence describing where the variable comes from and how that compares to what was
+    /// inherited.
+    pub fn description(&self) -> String {
+        match (self.effective(), &self.inherited) {
+            (None, Some(_)) => String::from("Computed by Windows, not stored in the registry."),
+            (None, None) => String::from("Neither stored in the registry nor inherited."),
+            (Some((key, _)), Some(_)) if self.matches() => {
+                format!("Stored in {key}, which overrides what Windows would compute.")
+            }
+            (Some((key, stored)), Some(inherited)) => format!(
+                "Stored in {key} as {stored}, but inherited as {inherited}. An ancestor process \
+                changed it, or the environment has not been refreshed since it was stored."
+            ),
+            (Some((key, stored)), None) => format!(
+                "Stored in {key} as {stored}, but not inherited. An ancestor process removed it, \
+                or the environment has not been refreshed since it was stored."
+            ),
+        }
+    }
+}
+
+/// Compares the program files environment variables this process inherited with the ones stored
+/// in the registry keys from which new environments are built.
+///
+/// This uses the inherited variables from `report.environment_variables` and the stored ones from
+/// `report.environment_registry`, so both must have been collected for the result to be useful. A
+/// stored value is compared by its expansion, since that is what appears in an environment block.
+/// If a variable is stored in more than one key, the one with the highest precedence, as ordered
+/// in `ENVIRONMENT_REGISTRY_KEYS`, is the one a new environment gets.
+pub fn environment_origins(report: &Report) -> Vec<EnvironmentOrigin> {
+    PROGRAM_FILES_ENVIRONMENT_VARIABLES
+        .into_iter()
+        .map(|variable| EnvironmentOrigin {
+            variable,
+            inherited: report
+                .environment_variables
+                .as_ref()
+                .and_then(|section| section.find(variable))
+                .and_then(|entry| entry.path())
+                .map(str::to_owned),
+            stored: report
+                .environment_registry
+                .iter()
+                .filter_map(|key| {
+                    let path = key.values.find(variable)?.path()?;
+                    Some((key.key, path.to_owned()))
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Finds the most common item, preferring the earliest if more than one is equally common.
+fn most_common<T: PartialEq + Copy>(items: impl IntoIterator<Item = T>) -> Option<T> {
+    let items: Vec<T> = items.into_iter().collect();
+    let count = |item: T| items.iter().filter(|&&other| other == item).count();
+
+    items.iter().copied().rev().max_by_key(|&item| count(item))
+}
+
+/// A consistency check made by `self_test()`, with the problems it found.
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub description: &'static str,
+    pub problems: Vec<String>,
+}
+
+impl Check {
+    fn new(description: &'static str, problems: Vec<String>) -> Self {
+        Self {
+            description,
+            problems,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Checks that the tables relating sources, entries, and logical directories are consistent.
+///
+/// This does not look anything up. It checks that:
+///
+/// - No source has the same entry name twice.
+///
+/// - Every CSIDL corresponds to one of the known folders that are looked up.
+///
+/// - Every entry named in `KNOWN_FOLDER_ENUM_EQUIVALENTS` has a `KnownFolder` variant whose name
+///   matches the symbolic name of the GUID it is paired with, so the cross-check in
+///   `resolve_guid()` compares the same folders. This is only checked with the
+///   `known-folders-crosscheck` feature.
+///
+/// - Every value name in `PROGRAM_FILES_SHELL_FOLDER_VALUES` is the GUID of the known folder it
+///   is paired with.
+///
+/// - Every moniker in `PROGRAM_FILES_SHELL_MONIKERS` is paired with a known folder that is looked
+///   up, by its name and GUID.
+///
+/// - Every entry name given by `summary_entry_name()` is one its source looks up.
+///
+/// - Every logical directory has at least one source that can look it up.
+///
+/// The last two are checked both for a 64-bit process and for a 32-bit one on 64-bit Windows,
+/// since the entries in the summary differ between them.
+///
+/// - `JSON_SCHEMA` is valid JSON, and gives `SCHEMA_VERSION` as the version of reports. This is
+///   only checked with the `serde` feature.
+///
+/// Each check is returned with the problems it found, if any. It passes if it found none.
+pub fn self_test() -> Vec<Check> {
+    let sources = SUMMARY_COLUMNS.map(|(_, source)| source);
+    let platforms = [Machine::X64, Machine::X86].map(|process| Platform {
+        process,
+        native: Machine::X64,
+    });
+
+    let mut checks = Vec::new();
+
+    let mut problems = Vec::new();
+    for source in sources {
+        let names = source.names();
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                problems.push(format!("{source:?} has {name} more than once"));
+            }
+        }
+    }
+    checks.push(Check::new("No duplicate names within a source", problems));
+
+    let problems = PROGRAM_FILES_CSIDLS
+        .into_iter()
+        .filter(|&(_, _, id)| !PROGRAM_FILES_KNOWN_FOLDERS.iter().any(|&(_, kf)| kf == id))
+        .map(|(name, _, id)| format!("{name} corresponds to {id:?}, which is not looked up"))
+        .collect();
+    checks.push(Check::new("Every CSIDL has a known folder", problems));
+
+    #[cfg(all(windows, feature = "known-folders-crosscheck"))]
+    checks.push(check_known_folder_enum_equivalents());
+
+    let problems = PROGRAM_FILES_SHELL_FOLDER_VALUES
+        .into_iter()
+        .filter_map(|(value_name, symbol)| {
+            match PROGRAM_FILES_KNOWN_FOLDERS
+                .iter()
+                .find(|&&(name, _)| name == symbol)
+            {
+                Some((_, id)) if value_name.eq_ignore_ascii_case(&format!("{{{id:?}}}")) => None,
+                Some((_, id)) => Some(format!("{value_name} is paired with {symbol}, {{{id:?}}}")),
+                None => Some(format!(
+                    "{value_name} is paired with {symbol}, which is unlisted"
+                )),
+            }
+        })
+        .collect();
+    checks.push(Check::new(
+        "Shell folders value names match their known folders",
+        problems,
+    ));
+
+    let problems = PROGRAM_FILES_SHELL_MONIKERS
+        .into_iter()
+        .filter(|&(_, symbol, id)| !PROGRAM_FILES_KNOWN_FOLDERS.contains(&(symbol, id)))
+        .map(|(moniker, symbol, _)| format!("{moniker} is paired with {symbol}, which is unlisted"))
+        .collect();
+    checks.push(Check::new(
+        "Shell monikers name known folders that are looked up",
+        problems,
+    ));
+
+    let mut problems = Vec::new();
+    for platform in platforms {
+        for directory in LogicalDirectory::ALL {
+            for source in sources {
+                if let Some(name) = summary_entry_name(directory, source, platform) {
+                    let problem = format!("{source:?} does not look up {name}");
+                    if !source.names().contains(&name) && !problems.contains(&problem) {
+                        problems.push(problem);
+                    }
+                }
+            }
+        }
+    }
+    checks.push(Check::new(
+        "Logical directories map to real entries",
+        problems,
+    ));
+
+    let problems = LogicalDirectory::ALL
+        .into_iter()
+        .filter(|&directory| {
+            platforms.iter().any(|&platform| {
+                sources
+                    .iter()
+                    .all(|&source| summary_entry_name(directory, source, platform).is_none())
+            })
+        })
+        .map(|directory| format!("Nothing looks up the {}", directory.description()))
+        .collect();
+    checks.push(Check::new("Every logical directory has a source", problems));
+
+    #[cfg(feature = "serde")]
+    checks.push(check_json_schema());
+
+    checks
+}
+
+/// Checks that `JSON_SCHEMA` is valid JSON, and that the `schema_version` it requires is
+/// `SCHEMA_VERSION`, so the schema is not left behind when the version is incremented.
+#[cfg(feature = "serde")]
+fn check_json_schema() -> Check {
+    let problems = match serde_json::from_str::<serde_json::Value>(JSON_SCHEMA) {
+        Ok(schema) => {
+            let version = &schema["properties"]["schema_version"]["const"];
+            if version.as_u64() == Some(SCHEMA_VERSION.into()) {
+                Vec::new()
+            } else {
+                vec![format!(
+                    "The schema is for version {version}, not {SCHEMA_VERSION}"
+                )]
+            }
+        }
+        Err(e) => vec![format!("The schema is not valid JSON: {e}")],
+    };
+    Check::new("The JSON schema is for this version", problems)
+}
+
+/// Checks that every entry named in `KNOWN_FOLDER_ENUM_EQUIVALENTS` has a `KnownFolder` variant
+/// whose name matches the symbolic name of the GUID it is paired with.
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+fn check_known_folder_enum_equivalents() -> Check {
+    let problems = KNOWN_FOLDER_ENUM_EQUIVALENTS
+        .into_iter()
+        .filter_map(|(id, kf)| {
+            let expected = format!("FOLDERID_{kf:?}");
+            match PROGRAM_FILES_KNOWN_FOLDERS
+                .iter()
+                .find(|&&(_, guid)| guid == id)
+            {
+                Some(&(name, _)) if name == expected => None,
+                Some(&(name, _)) => Some(format!("KnownFolder::{kf:?} is paired with {name}")),
+                None => Some(format!(
+                    "KnownFolder::{kf:?} is paired with an unlisted GUID"
+                )),
+            }
+        })
+        .collect();
+    Check::new("known-folders enum variants match their GUIDs", problems)
+}
+
+/// Finds which of the environment variables `ProgramW6432` and `ProgramFiles(x86)` are missing,
+/// on 64-bit Windows, where both should always be inherited.
+///
+/// Either being missing means an ancestor process removed it, as described on
+/// `report_environment_variables()`, which `ancestors()` can help find. If the environment
+/// variables were not looked up, or the system is not 64-bit, this is empty.
+pub fn stripped_variables(report: &Report, platform: Platform) -> Vec<&'static str> {
+    let Some(section) = &report.environment_variables else {
+        return Vec::new();
+    };
+    if !platform.native.is_64_bit() {
+        return Vec::new();
+    }
+
+    ["ProgramW6432", "ProgramFiles(x86)"]
+        .into_iter()
+        .filter(|&name| {
+            section
+                .find(name)
+                .is_some_and(|entry| entry.path().is_none())
+        })
+        .collect()
+}
+
+/// Finds any required entries that did not resolve to a path, and why.
+///
+/// Each name is the symbolic name of an entry, such as `ProgramW6432`, `FOLDERID_ProgramFilesX86`,
+/// or `ProgramFilesDir (x86)`. A required entry is missing if any section where it appears has an
+/// error for it, or if no section has an entry of that name. The latter usually means the name is
+/// misspelled. Entries that are not required are not checked, so sources that are legitimately
+/// unavailable on some systems do not cause failures unless asked.
+///
+/// Registry key names appear once per registry view, so requiring one requires it in every view.
+/// Environment variables stored in the registry, in a fresh environment block, or as WMI gives them
+/// are not checked, since they are copies of the inherited variables, which are.
+///
+/// Each failure is given as the name and a description of what went wrong. If all required entries
+/// resolved, this is empty.
+pub fn missing_requirements(report: &Report, required: &[String]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+
+    for name in required {
+        let mut found = false;
+
+        for section in report.sections() {
+            // Inherited variables are checked, not stored, fresh, or WMI copies of them.
+            if matches!(
+                section.source,
+                SourceKind::EnvironmentRegistry(_) | SourceKind::FreshEnvironment | SourceKind::Wmi
+            ) {
+                continue;
+            }
+            let Some(entry) = section.find(name) else {
+                continue;
+            };
+            found = true;
+            if entry.path().is_none() {
+                let path_item = entry.lookup().path_item();
+                failures.push((name.clone(), format!("{}: {path_item}", section.heading)));
+            }
+        }
+
+        if !found {
+            failures.push((name.clone(), String::from("[no such entry in any section]")));
+        }
+    }
+
+    failures
+}
+
+/// Finds the path for a name given to `--copy`, or why there is none.
+///
+/// The name is either a logical directory's label, as given by `LogicalDirectory::label()`, such as
+/// `program-files-64`, which gives the path the sources agree on, as found by
+/// `resolve_consensus()`, or the symbolic name of an entry, as for `missing_requirements()`, which
+/// gives the path of the first entry of that name that has one, in the order sections are shown.
+/// Labels are matched without regard to case, and entry names exactly.
+///
+/// The path is given as Windows gave it, so a path that is not valid Unicode is given exactly.
+pub fn named_path(report: &Report, platform: Platform, name: &str) -> Result<OsString, String> {
+    let directory = LogicalDirectory::ALL
+        .into_iter()
+        .find(|directory| directory.label().eq_ignore_ascii_case(name));
+
+    if let Some(directory) = directory {
+        return resolve_consensus(report, platform)
+            .into_iter()
+            .find(|resolution| resolution.directory == directory)
+            .and_then(|resolution| resolution.os_path)
+            .ok_or_else(|| {
+                format!(
+                    "[no source found the {} directory]",
+                    directory.description()
+                )
+            });
+    }
+
+    let mut failure = None;
+    for section in report.sections() {
+        let Some(entry) = section.find(name) else {
+            continue;
+        };
+        if let Some(path) = entry.os_path() {
+            return Ok(path.to_owned());
+        }
+        failure
+            .get_or_insert_with(|| format!("{}: {}", section.heading, entry.lookup().path_item()));
+    }
+    Err(failure.unwrap_or_else(|| String::from("[no such entry in any section]")))
+}
+
+/// A path an entry is expected to have, as given by `--expect NAME=VALUE` or in a baseline file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expectation {
+    /// The symbolic name of the entry, as for `missing_requirements()`.
+    pub name: String,
+
+    /// The path the entry is expected to have.
+    pub value: String,
+}
+
+impl FromStr for Expectation {
+    type Err = String;
+
+    /// Parses `NAME=VALUE`. The name is everything before the first `=`, so the value may have
+    /// `=` in it, but the name may not.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, value)) if !name.is_empty() => Ok(Self {
+                name: name.to_owned(),
+                value: value.to_owned(),
+            }),
+            _ => Err(format!("expected NAME=VALUE, got {s:?}")),
+        }
+    }
+}
+
+/// Whether the live system met an `Expectation`, as found by `check_expectations()`.
+#[derive(Clone, Debug)]
+pub struct ExpectationOutcome {
+    pub expectation: Expectation,
+
+    /// Each section with an entry of the expected name, by heading, with that entry's path, or
+    /// error in square brackets, and whether it matched. If this is empty, there was no such
+    /// entry.
+    pub actual: Vec<(String, String, bool)>,
+}
+
+impl ExpectationOutcome {
+    /// Whether the expectation was met: an entry of that name was found, and every section with
+    /// one had the expected path.
+    pub fn passed(&self) -> bool {
+        !self.actual.is_empty() && self.actual.iter().all(|&(_, _, matched)| matched)
+    }
+}
+
+/// Checks the paths of entries against expected values, such as to validate a deployment.
+///
+/// Entries are found in the sections as described on `missing_requirements()`, so a registry value
+/// must have the expected path in every registry view. A path matches its expected value if they
+/// are the same other than in case or a trailing backslash, since neither changes what directory
+/// the path names. An entry that could not be looked up never matches, and neither does one whose
+/// path is not valid Unicode, since an expected value, which is text, cannot be that path.
+pub fn check_expectations(
+    report: &Report,
+    expectations: &[Expectation],
+) -> Vec<ExpectationOutcome> {
+    let normalize = |path: &str| path.trim_end_matches('\\').to_lowercase();
+
+    expectations
+        .iter()
+        .map(|expectation| {
+            let expected = normalize(&expectation.value);
+            let actual = report
+                .sections()
+                .into_iter()
+                .filter(|section| {
+                    !matches!(
+                        section.source,
+                        SourceKind::EnvironmentRegistry(_)
+                            | SourceKind::FreshEnvironment
+                            | SourceKind::Wmi
+                    )
+                })
+                .filter_map(|section| {
+                    let entry = section.find(&expectation.name)?;
+                    let matched = entry
+                        .os_path()
+                        .and_then(OsStr::to_str)
+                        .is_some_and(|path| normalize(path) == expected);
+                    Some((
+                        section.heading.to_owned(),
+                        entry.lookup().path_item(),
+                        matched,
+                    ))
+                })
+                .collect();
+
+            ExpectationOutcome {
+                expectation: expectation.clone(),
+                actual,
+            }
+        })
+        .collect()
+}
+
+/// Finds any entries whose two ways of being looked up disagreed, such as known folders for which
+/// `SHGetKnownFolderPath` and the `known-folders` crate gave different results.
+///
+/// These are not failures to look anything up, but signs of a bug in one of the lookups. They are
+/// shown inline as `DISCREPANCY` entries and do not stop the rest of the report from being
+/// collected. Each is given as its section heading and the entry's name.
+pub fn discrepancies(report: &Report) -> Vec<(String, String)> {
+    report
+        .sections()
+        .into_iter()
+        .flat_map(|section| {
+            section
+                .entries
+                .iter()
+                .filter(|entry| entry.lookup().error.as_ref().is_some_and(|e| e.discrepancy))
+                .map(|entry| (section.heading.to_owned(), entry.name().to_owned()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Whether an entry is expected to be found, given the architecture of the process and system.
+///
+/// This applies the same rules as `explain_entry()`, under which a `shell:` moniker is expected
+/// exactly when the known folder it names is. Entries that are legitimately unavailable,
+/// such as `ProgramW6432` on 32-bit Windows or `FOLDERID_ProgramFilesX64` in a 32-bit process, are
+/// not expected. Nor is `FOLDERID_UserProgramFiles`, which is usually unavailable when there is no
+/// loaded user profile.
+pub fn is_expected(source: SourceKind, name: &str, platform: Platform) -> bool {
+    let os_64 = platform.native.is_64_bit();
+    let arm64 = platform.native == Machine::Arm64;
+
+    let name = match source {
+        SourceKind::ShellMoniker => known_folder_of_moniker(name).unwrap_or(name),
+        _ => name,
+    };
+
+    match (source, name) {
+        (SourceKind::Wmi, OS_ARCHITECTURE) => true,
+        (SourceKind::SystemDirectories, SYSTEM_WOW64_DIRECTORY) => os_64,
+        (SourceKind::SystemDirectories, SYSTEM_WOW64_ARM_DIRECTORY) => false,
+        (SourceKind::SystemDirectories, SYSNATIVE) => platform.is_wow64(),
+        (SourceKind::SystemDirectories, _) => true,
+        (SourceKind::Msi, "ProgramFiles64Folder") => os_64,
+        (SourceKind::Msi, _) => true,
+        (
+            SourceKind::EnvironmentRegistry(_)
+            | SourceKind::FreshEnvironment
+            | SourceKind::Wmi
+            | SourceKind::Shells
+            | SourceKind::PerUserInstalls
+            | SourceKind::Virtualization
+            | SourceKind::Package,
+            _,
+        ) => false,
+        (SourceKind::Policy, name) => !POLICY_KEYS.iter().any(|&(caption, _)| caption == name),
+        (SourceKind::ArmProgramFiles, _) => arm64,
+        (SourceKind::Registry(LITERAL_WOW6432NODE_VIEW), _) if !os_64 => false,
+
+        (SourceKind::Registry(_), "ProgramFilesDir")
+        | (_, "ProgramFiles" | "FOLDERID_ProgramFiles" | "CSIDL_PROGRAM_FILES") => true,
+
+        (
+            _,
+            "ProgramFiles(x86)"
+            | "FOLDERID_ProgramFilesX86"
+            | "CSIDL_PROGRAM_FILESX86"
+            | "ProgramFilesDir (x86)",
+        ) => os_64,
+
+        (_, "FOLDERID_ProgramFilesX64") => os_64 && platform.process.is_64_bit(),
+        (_, "ProgramW6432" | "ProgramW6432Dir") => os_64,
+
+        (
+            SourceKind::Registry("KEY_WOW64_32KEY" | LITERAL_WOW6432NODE_VIEW),
+            "ProgramFilesDir (Arm)",
+        ) => false,
+        (_, "ProgramFiles(Arm)" | "ProgramFilesDir (Arm)") => arm64,
+
+        _ => false,
+    }
+}
+
+/// Win32 error codes that mean only that the value looked up does not exist.
+///
+/// These are `ERROR_FILE_NOT_FOUND` and `ERROR_PATH_NOT_FOUND`. They count whether an error has
+/// them as Win32 error codes or as the `HRESULT` values that wrap them, as found by
+/// `EntryError::win32_code()`. Any other error code means the API call itself failed.
+const NOT_FOUND_CODES: [u32; 2] = [2, 3];
+
+/// Whether an error has a numeric code, other than one of `NOT_FOUND_CODES`.
+fn is_api_failure(error: &EntryError) -> bool {
+    error.code.is_some()
+        && !error
+            .win32_code()
+            .is_some_and(|code| NOT_FOUND_CODES.contains(&code))
+}
+
+/// Classifies the outcome of a lookup, for machine-readable output.
+///
+/// This is `found` if there is a path, `not-found` if the error means only that the value does
+/// not exist, `discrepancy` if two ways of looking it up disagreed, and otherwise `error`. An error
+/// with no numeric code, such as a missing environment variable, is counted as `not-found`.
+pub fn lookup_status(lookup: &Lookup) -> &'static str {
+    match (&lookup.path, &lookup.error) {
+        (Some(_), _) => "found",
+        (None, Some(error)) if error.discrepancy => "discrepancy",
+        (None, Some(error)) if is_api_failure(error) => "error",
+        (None, _) => "not-found",
+    }
+}
+
+/// A kind of problem with a report, as found by `anomalies()`, from least to most serious.
+///
+/// Each kind has an exit status, given by `exit_code()`, so that scripts can tell them apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AnomalyKind {
+    /// An entry that is expected on this system, as determined by `is_expected()`, was not found.
+    Missing,
+
+    /// Sources disagree about the path of the same directory, or two ways of looking up the same
+    /// entry disagree, as found by `summarize()` and `discrepancies()`.
+    Mismatch,
+
+    /// An API call failed for a reason other than the value not existing.
+    ApiFailure,
+}
+
+impl AnomalyKind {
+    /// The exit status of the `pfdirs` program when this is the most serious kind of anomaly.
+    ///
+    /// A report with no anomalies has exit status 0.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Missing => 1,
+            Self::Mismatch => 2,
+            Self::ApiFailure => 3,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::Mismatch => "mismatch",
+            Self::ApiFailure => "API failure",
+        }
+    }
+}
+
+/// A problem with one entry of a report, as found by `anomalies()`.
+#[derive(Clone, Debug)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+
+    /// The heading of the section the entry is in, or the summary column it is in.
+    pub section: String,
+
+    pub name: String,
+
+    /// What is wrong with the entry.
+    pub detail: String,
+}
+
+/// Finds everything wrong with a report that suggests a broken program files configuration.
+///
+/// This finds:
+///
+/// - Entries expected on this system that have no path. If the error has a numeric code other
+///   than one meaning the value does not exist, the anomaly is an API failure.
+///
+/// - Paths that differ from the path most sources found for the same directory, as shown in the
+///   summary table.
+///
+/// - Inherited environment variables that are stale, because they differ from those in a fresh
+///   environment block, as described on `report_fresh_environment()`.
+///
+/// - Entries whose two ways of being looked up disagree, as found by `discrepancies()`.
+///
+/// Entries that are legitimately unavailable are not anomalies. If there are no anomalies, this
+/// is empty, and all sources are consistent.
+pub fn anomalies(report: &Report, platform: Platform) -> Vec<Anomaly> {
+    let mut found = Vec::new();
+
+    for section in report.sections() {
+        for entry in &section.entries {
+            let Some(error) = &entry.lookup().error else {
+                continue;
+            };
+            if error.discrepancy || !is_expected(section.source, entry.name(), platform) {
+                continue;
+            }
+            let kind = if is_api_failure(error) {
+                AnomalyKind::ApiFailure
+            } else {
+                AnomalyKind::Missing
+            };
+            found.push(Anomaly {
+                kind,
+                section: section.heading.to_owned(),
+                name: entry.name().to_owned(),
+                detail: error.message.clone(),
+            });
+        }
+    }
+
+    for row in summarize(report, platform) {
+        let consensus = row.cells.iter().find_map(|cell| match cell {
+            SummaryCell::Path(path) => Some(path.as_str()),
+            _ => None,
+        });
+
+        for ((header, source), cell) in SUMMARY_COLUMNS.iter().zip(&row.cells) {
+            let SummaryCell::Outlier(path) = cell else {
+                continue;
+            };
+            let name = summary_entry_name(row.directory, *source, platform).unwrap_or_default();
+            found.push(Anomaly {
+                kind: AnomalyKind::Mismatch,
+                section: format!("Summary ({header})"),
+                name: name.to_owned(),
+                detail: format!(
+                    "{path}, but most sources give {}",
+                    consensus.unwrap_or("[no information]"),
+                ),
+            });
+        }
+    }
+
+    if let Some(section) = &report.fresh_environment {
+        for entry in section.entries.iter().filter(|entry| entry.stale) {
+            found.push(Anomaly {
+                kind: AnomalyKind::Mismatch,
+                section: section.heading.clone(),
+                name: entry.name.clone(),
+                detail: format!(
+                    "inherited {}, but a fresh environment gives {}",
+                    entry.inherited.as_deref().unwrap_or("[absent]"),
+                    entry.lookup.path.as_deref().unwrap_or("[absent]"),
+                ),
+            });
+        }
+    }
+
+    for (section, name) in discrepancies(report) {
+        found.push(Anomaly {
+            kind: AnomalyKind::Mismatch,
+            section,
+            name,
+            detail: String::from("two ways of looking it up disagree"),
+        });
+    }
+
+    found
+}
+
+/// How much a finding matters, as found by `findings()`, from least to most serious.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Worth knowing to make sense of the report, but not a problem.
+    Info,
+
+    /// Likely to make some programs or installers use the wrong directory.
+    Warning,
+
+    /// The program files configuration is broken, or could not be checked.
+    Critical,
+}
+
+impl Severity {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// A conclusion drawn from a report, as found by `findings()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Finding {
+    pub severity: Severity,
+
+    /// What was found, in a few words, such as `ProgramW6432 is missing on 64-bit Windows`.
+    pub title: String,
+
+    /// Why it matters, and what to do about it, in a sentence or two.
+    pub explanation: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, title: impl Into<String>, explanation: impl Into<String>) -> Self {
+        Self {
+            severity,
+            title: title.into(),
+            explanation: explanation.into(),
+        }
+    }
+}
+
+/// The path in a summary cell, whether or not it is an outlier.
+fn cell_path(cell: &SummaryCell) -> Option<&str> {
+    match cell {
+        SummaryCell::Path(path) | SummaryCell::Outlier(path) => Some(path),
+        SummaryCell::NotApplicable | SummaryCell::Error => None,
+    }
+}
+
+/// Finds where, if anywhere, the environment variable for a directory differs from the registry,
+/// as the environment and registry value names, the registry view, and the two paths.
+fn environment_registry_mismatch(
+    row: &SummaryRow,
+    platform: Platform,
+) -> Option<(&'static str, &'static str, &'static str, &str, &str)> {
+    let columns = SUMMARY_COLUMNS.iter().zip(&row.cells);
+    let (_, environment_cell) = columns
+        .clone()
+        .find(|((_, source), _)| *source == SourceKind::EnvironmentVariable)?;
+    let environment_path = cell_path(environment_cell)?;
+
+    columns.into_iter().find_map(|((_, source), cell)| {
+        let SourceKind::Registry(view) = source else {
+            return None;
+        };
+        let registry_path = cell_path(cell).filter(|&path| path != environment_path)?;
+        Some((
+            summary_entry_name(row.directory, SourceKind::EnvironmentVariable, platform)?,
+            summary_entry_name(row.directory, *source, platform)?,
+            *view,
+            environment_path,
+            registry_path,
+        ))
+    })
+}
+
+/// Draws conclusions from a report, each with a severity and a short explanation, so that what is
+/// wrong, and what to do about it, is clear without reading every section.
+///
+/// Where `anomalies()` lists each entry that is wrong, this says what the entries that are wrong
+/// mean together. It finds:
+///
+/// - **Critical:** the 32-bit and 64-bit program files directories resolving to the same path on
+///   64-bit Windows, as found by `resolve_consensus()`, so that programs of both architectures are
+///   installed together, and lookups failing for reasons other than the value not existing.
+///
+/// - **Warning:** `ProgramW6432` or `ProgramFiles(x86)` missing on 64-bit Windows, as found by
+///   `stripped_variables()`; other entries expected on this system being missing; an environment
+///   variable differing from the registry value for the same directory; other sources disagreeing
+///   about a directory, which is not reported separately for a directory whose environment
+///   variable differs from the registry; stale inherited environment variables; and two ways of
+///   looking up the same entry disagreeing.
+///
+/// - **Info:** this being a 32-bit process on 64-bit Windows, which explains why many entries give
+///   the 32-bit directory, and is the commonest reason for confusion.
+///
+/// The findings are given from most to least serious. If there are none, the configuration is
+/// consistent, as far as the sources in the report show.
+pub fn findings(report: &Report, platform: Platform) -> Vec<Finding> {
+    let mut found = Vec::new();
+    let anomalies = anomalies(report, platform);
+    let stripped = stripped_variables(report, platform);
+
+    let names_of = |kind: AnomalyKind| {
+        let names: Vec<&str> = anomalies
+            .iter()
+            .filter(|anomaly| anomaly.kind == kind && !stripped.contains(&anomaly.name.as_str()))
+            .map(|anomaly| anomaly.name.as_str())
+            .collect();
+        names.join(", ")
+    };
+
+    let resolutions = resolve_consensus(report, platform);
+    let consensus_of = |directory: LogicalDirectory| {
+        resolutions
+            .iter()
+            .find(|resolution| resolution.directory == directory)
+            .and_then(|resolution| resolution.consensus.as_deref())
+    };
+    if let (true, Some(path_64), Some(path_32)) = (
+        platform.native.is_64_bit(),
+        consensus_of(LogicalDirectory::ProgramFiles64),
+        consensus_of(LogicalDirectory::ProgramFiles32),
+    ) {
+        if path_64.eq_ignore_ascii_case(path_32) {
+            found.push(Finding::new(
+                Severity::Critical,
+                "The 32-bit and 64-bit program files directories are the same",
+                format!(
+                    "Both are {path_64}, so 32-bit and 64-bit programs are installed together, \
+                    and may overwrite each other. Usually a registry value or environment variable \
+                    was changed by hand; compare ProgramFilesDir in the 32-bit and 64-bit registry \
+                    views."
+                ),
+            ));
+        }
+    }
+
+    let failures = names_of(AnomalyKind::ApiFailure);
+    if !failures.is_empty() {
+        found.push(Finding::new(
+            Severity::Critical,
+            "Some lookups failed",
+            format!(
+                "Looking up {failures} failed for a reason other than the value not existing. \
+                Programs that look them up the same way may fail too. The errors are in the report."
+            ),
+        ));
+    }
+
+    for name in &stripped {
+        found.push(Finding::new(
+            Severity::Warning,
+            format!("{name} is missing on 64-bit Windows"),
+            "Windows gives it to every process on 64-bit Windows, so a process this one was \
+            started from removed it. Scripts and installers that read it may fail or use the wrong \
+            directory.",
+        ));
+    }
+
+    let missing = names_of(AnomalyKind::Missing);
+    if !missing.is_empty() {
+        found.push(Finding::new(
+            Severity::Warning,
+            "Expected entries are missing",
+            format!(
+                "{missing} should be found, since {}, but were not. Programs that rely on them \
+                may fail or use the wrong directory.",
+                platform.description(),
+            ),
+        ));
+    }
+
+    let rows = summarize(report, platform);
+    for (row, resolution) in rows.iter().zip(&resolutions) {
+        if let Some((variable, value, view, environment_path, registry_path)) =
+            environment_registry_mismatch(row, platform)
+        {
+            found.push(Finding::new(
+                Severity::Warning,
+                format!("{variable} differs from the registry"),
+                format!(
+                    "{variable} is {environment_path}, but {value} with {view} is \
+                    {registry_path}. Programs that read the environment and those that read the \
+                    registry will use different directories. If the registry was changed, signing \
+                    out and in updates the environment."
+                ),
+            ));
+        } else if let (Some(consensus), false) =
+            (&resolution.consensus, resolution.dissents.is_empty())
+        {
+            let dissents: Vec<String> = resolution
+                .dissents
+                .iter()
+                .map(|dissent| format!("{} gives {}", dissent.name, dissent.path))
+                .collect();
+            found.push(Finding::new(
+                Severity::Warning,
+                format!(
+                    "Sources disagree about the {} directory",
+                    resolution.directory.description()
+                ),
+                format!(
+                    "Most sources give {consensus}, but {}. Programs that look it up those ways \
+                    will use a different directory.",
+                    dissents.join(", and ")
+                ),
+            ));
+        }
+    }
+
+    if let Some(section) = &report.fresh_environment {
+        let stale: Vec<&str> = section
+            .entries
+            .iter()
+            .filter(|entry| entry.stale)
+            .map(|entry| entry.name.as_str())
+            .collect();
+        if !stale.is_empty() {
+            found.push(Finding::new(
+                Severity::Warning,
+                "The inherited environment is stale",
+                format!(
+                    "{} differ from what a process started now would get, so this process was \
+                    started with an old environment. Restarting the program that started it, or \
+                    signing out and in, picks up the change.",
+                    stale.join(", ")
+                ),
+            ));
+        }
+    }
+
+    let disagreements: Vec<String> = discrepancies(report)
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+    if !disagreements.is_empty() {
+        found.push(Finding::new(
+            Severity::Warning,
+            "Two ways of looking up the same entry disagree",
+            format!(
+                "{} each give different paths depending on how they are looked up, so programs \
+                that use different APIs will use different directories. The report shows both.",
+                disagreements.join(", ")
+            ),
+        ));
+    }
+
+    if platform.is_wow64() {
+        found.push(Finding::new(
+            Severity::Info,
+            "This is a 32-bit process on 64-bit Windows",
+            "So ProgramFiles, FOLDERID_ProgramFiles, and CSIDL_PROGRAM_FILES give the 32-bit \
+            directory, as Windows intends. A 32-bit program that needs the 64-bit directory should \
+            read ProgramW6432.",
+        ));
+    }
+
+    found.sort_by_key(|finding| Reverse(finding.severity));
+    found
+}
+
+/// A difference in one entry between two reports, as found by `changes()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// The heading of the section the entry is in.
+    pub section: String,
+
+    pub name: String,
+
+    /// The path, or error in square brackets, before the change, or `[absent]` if there was none.
+    pub before: String,
+
+    /// The path, or error in square brackets, after the change, or `[absent]` if there is none, as
+    /// can be when comparing saved reports with `diff_sections()`.
+    pub after: String,
+}
+
+/// Finds the entries of `after` whose path or error differs from that of the same entry in
+/// `before`.
+///
+/// Entries are matched by the heading of their section and their name, so the order of entries
+/// does not matter. Sections that are in `before` but not `after` are not compared, so `after` can
+/// be a report of only the sources that may have changed. Paths are compared, and given, with what
+/// is not valid Unicode escaped, as by `escape_non_unicode()`, so that a change only there is not
+/// missed.
+pub fn changes(before: &Report, after: &Report) -> Vec<Change> {
+    let old_sections = before.sections();
+    let mut changes = Vec::new();
+
+    for section in after.sections() {
+        let old_section = old_sections
+            .iter()
+            .find(|old_section| old_section.heading == section.heading);
+
+        for entry in &section.entries {
+            let before = match old_section.and_then(|old_section| old_section.find(entry.name())) {
+                Some(old_entry) => old_entry.lookup().escaped_path_item(),
+                None => String::from("[absent]"),
+            };
+            let after = entry.lookup().escaped_path_item();
+
+            if before != after {
+                changes.push(Change {
+                    section: section.heading.to_owned(),
+                    name: entry.name().to_owned(),
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    changes
+}
@@ -0,0 +1,124 @@
+//! Finding the ancestors of this process, to diagnose which one altered its environment.
+
+use std::collections::{HashMap, HashSet};
+
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
+use windows::Win32::System::SystemInformation::{IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_UNKNOWN};
+use windows::Win32::System::Threading::{
+    GetCurrentProcessId, IsWow64Process2, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+use crate::error::Error;
+use crate::platform::Machine;
+
+/// A process that is, or appears to be, an ancestor of this one.
+#[derive(Clone, Debug)]
+pub struct Ancestor {
+    /// The process ID.
+    pub pid: u32,
+
+    /// The file name of the process's executable, such as `explorer.exe`.
+    pub name: String,
+
+    /// The architecture of the process's executable image, or `None` if the process could not be
+    /// opened to find it, such as if it belongs to another user or has exited.
+    pub machine: Option<Machine>,
+}
+
+/// Finds the architecture of a running process's image, as described on `ancestors()`.
+fn process_machine(pid: u32) -> Option<Machine> {
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+
+    let mut process_machine = IMAGE_FILE_MACHINE::default();
+    let mut native_machine = IMAGE_FILE_MACHINE::default();
+    let result =
+        unsafe { IsWow64Process2(process, &mut process_machine, Some(&mut native_machine)) };
+    let _ = unsafe { CloseHandle(process) };
+    result.ok()?;
+
+    // IMAGE_FILE_MACHINE_UNKNOWN means the process is not running under WOW64.
+    Some(Machine::from_image_file_machine(
+        if process_machine == IMAGE_FILE_MACHINE_UNKNOWN {
+            native_machine
+        } else {
+            process_machine
+        },
+    ))
+}
+
+/// Takes a snapshot of all processes, giving each one's parent process ID and executable name by
+/// process ID.
+pub(crate) fn snapshot_processes() -> Result<HashMap<u32, (u32, String)>, Error> {
+    let snapshot =
+        unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }.map_err(Error::Ancestry)?;
+
+    let mut processes = HashMap::new();
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+
+    let mut result = unsafe { Process32FirstW(snapshot, &mut entry) };
+    while result.is_ok() {
+        let length = entry
+            .szExeFile
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(entry.szExeFile.len());
+        let name = String::from_utf16_lossy(&entry.szExeFile[..length]);
+        processes.insert(entry.th32ProcessID, (entry.th32ParentProcessID, name));
+        result = unsafe { Process32NextW(snapshot, &mut entry) };
+    }
+
+    let _ = unsafe { CloseHandle(snapshot) };
+    Ok(processes)
+}
+
+/// Finds the chain of ancestors of this process, from its parent outward.
+///
+/// This takes a [Toolhelp snapshot][th] of all processes and follows each one's parent process ID.
+/// The chain ends at a process whose parent is not running, which is usual, since Windows does not
+/// keep parents alive for their children. Windows also reuses process IDs, so once an ancestor has
+/// exited, a newer, unrelated process can have its ID and appear in its place. The chain is thus
+/// best effort. It stops if a process ID would repeat, which can only happen through such reuse.
+///
+/// Each ancestor's architecture is found with [`IsWow64Process2`][iwp2]. A process that is not
+/// running under WOW64 is assumed to have the native architecture of the system, so an x86-64
+/// process emulated on ARM64 is shown as ARM64.
+///
+/// When `ProgramW6432` or `ProgramFiles(x86)` is missing on 64-bit Windows, as described on
+/// `report_environment_variables()`, usually some ancestor created its child with an environment
+/// that omitted it. That ancestor is often the first one in the chain of a different bitness than
+/// its child, or a launcher such as a build tool or service host that builds environments itself.
+///
+/// [th]: https://learn.microsoft.com/en-us/windows/win32/toolhelp/taking-a-snapshot-and-viewing-processes
+/// [iwp2]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-iswow64process2
+pub fn ancestors() -> Result<Vec<Ancestor>, Error> {
+    let processes = snapshot_processes()?;
+
+    let mut chain = Vec::new();
+    let mut seen = HashSet::from([unsafe { GetCurrentProcessId() }]);
+    let mut pid = processes
+        .get(&unsafe { GetCurrentProcessId() })
+        .map(|&(parent, _)| parent);
+
+    while let Some(current) = pid {
+        if current == 0 || !seen.insert(current) {
+            break;
+        }
+        let Some((parent, name)) = processes.get(&current) else {
+            break;
+        };
+        chain.push(Ancestor {
+            pid: current,
+            name: name.clone(),
+            machine: process_machine(current),
+        });
+        pid = Some(*parent);
+    }
+
+    Ok(chain)
+}
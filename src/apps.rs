@@ -0,0 +1,178 @@
+//! Finding where installed applications are, to see which program files directory hosts each.
+
+#[cfg(windows)]
+use std::io;
+
+#[cfg(windows)]
+use winreg::{
+    enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_32KEY, KEY_WOW64_64KEY},
+    RegKey, HKEY,
+};
+
+#[cfg(windows)]
+use crate::registry::expand;
+
+/// The registry key, under `HKEY_LOCAL_MACHINE` or `HKEY_CURRENT_USER`, whose subkeys describe
+/// installed applications, as listed in Programs and Features.
+pub const UNINSTALL_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall";
+
+/// The registry key, under `HKEY_LOCAL_MACHINE` or `HKEY_CURRENT_USER`, whose subkeys register
+/// applications' executables so they can be run by name.
+pub const APP_PATHS_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths";
+
+/// The keys that applications are enumerated from, each with a caption, its hive, and the flag, if
+/// any, that selects the registry view to read it through.
+///
+/// `HKEY_CURRENT_USER\SOFTWARE` is shared between the views, so it is read only once.
+#[cfg(windows)]
+pub const APP_KEYS: [(&str, HKEY, &str, u32); 6] = [
+    (
+        "Uninstall, KEY_WOW64_64KEY",
+        HKEY_LOCAL_MACHINE,
+        UNINSTALL_SUBKEY,
+        KEY_WOW64_64KEY,
+    ),
+    (
+        "Uninstall, KEY_WOW64_32KEY",
+        HKEY_LOCAL_MACHINE,
+        UNINSTALL_SUBKEY,
+        KEY_WOW64_32KEY,
+    ),
+    ("Uninstall, HKCU", HKEY_CURRENT_USER, UNINSTALL_SUBKEY, 0),
+    (
+        "App Paths, KEY_WOW64_64KEY",
+        HKEY_LOCAL_MACHINE,
+        APP_PATHS_SUBKEY,
+        KEY_WOW64_64KEY,
+    ),
+    (
+        "App Paths, KEY_WOW64_32KEY",
+        HKEY_LOCAL_MACHINE,
+        APP_PATHS_SUBKEY,
+        KEY_WOW64_32KEY,
+    ),
+    ("App Paths, HKCU", HKEY_CURRENT_USER, APP_PATHS_SUBKEY, 0),
+];
+
+/// An installed application, found by `installed_apps()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstalledApp {
+    /// The application's display name, or, for App Paths, the name of its executable.
+    pub name: String,
+
+    /// The caption, from `APP_KEYS`, of the key it was found in.
+    pub source: &'static str,
+
+    /// The directory it is installed in, if the registry says.
+    pub location: Option<String>,
+}
+
+/// Cleans up a path read from the registry, expanding any environment variable references and
+/// removing quotes and a trailing backslash. An empty path is taken to be absent.
+#[cfg(windows)]
+fn clean_path(raw: &str) -> Option<String> {
+    let expanded = if raw.contains('%') {
+        expand(raw).map_or_else(
+            |_| raw.to_owned(),
+            |path| path.to_string_lossy().into_owned(),
+        )
+    } else {
+        raw.to_owned()
+    };
+    let path = expanded.trim().trim_matches('"').trim_end_matches('\\');
+    (!path.is_empty()).then(|| path.to_owned())
+}
+
+/// Reads an application from a subkey of `UNINSTALL_SUBKEY`.
+///
+/// Subkeys with no `DisplayName` are skipped, as Programs and Features skips them, since they are
+/// usually parts of other applications, or updates, rather than applications in their own right.
+#[cfg(windows)]
+fn uninstall_entry(key: &RegKey, source: &'static str) -> Option<InstalledApp> {
+    let name: String = key.get_value("DisplayName").ok()?;
+    let location = key
+        .get_value::<String, _>("InstallLocation")
+        .ok()
+        .and_then(|raw| clean_path(&raw));
+    Some(InstalledApp {
+        name,
+        source,
+        location,
+    })
+}
+
+/// Reads an application from a subkey of `APP_PATHS_SUBKEY`, which is named for its executable.
+///
+/// Its location is the `Path` value, which gives the directory to run it in, if there is one, and
+/// otherwise the directory of the executable given by the default value.
+#[cfg(windows)]
+fn app_paths_entry(key: &RegKey, subkey_name: String, source: &'static str) -> InstalledApp {
+    let location = key
+        .get_value::<String, _>("Path")
+        .ok()
+        .and_then(|raw| clean_path(&raw))
+        .or_else(|| {
+            let executable = clean_path(&key.get_value::<String, _>("").ok()?)?;
+            let (directory, _) = executable.rsplit_once('\\')?;
+            (!directory.is_empty()).then(|| directory.to_owned())
+        });
+    InstalledApp {
+        name: subkey_name,
+        source,
+        location,
+    }
+}
+
+/// Reads the applications from one of `APP_KEYS`.
+#[cfg(windows)]
+fn apps_in_key(
+    (source, hive, subkey, flag): (&'static str, HKEY, &str, u32),
+) -> Result<Vec<InstalledApp>, io::Error> {
+    let key = RegKey::predef(hive).open_subkey_with_flags(subkey, KEY_READ | flag)?;
+    let apps = key
+        .enum_keys()
+        .filter_map(Result::ok)
+        .filter_map(|name| {
+            let app_key = key.open_subkey_with_flags(&name, KEY_READ | flag).ok()?;
+            if subkey == APP_PATHS_SUBKEY {
+                Some(app_paths_entry(&app_key, name, source))
+            } else {
+                uninstall_entry(&app_key, source)
+            }
+        })
+        .collect();
+    Ok(apps)
+}
+
+/// Finds the applications installed on this system, and for this user, and where each is.
+///
+/// This enumerates the subkeys of [`Uninstall`][uninstall], where installers register applications
+/// for Programs and Features, taking each one's `InstallLocation`, and of [`App Paths`][app-paths],
+/// where they register executables so they can be run by name, taking the directory of each. Both
+/// are read from `HKEY_LOCAL_MACHINE` through the 64-bit and 32-bit registry views, since 32-bit
+/// installers register applications in the 32-bit view, and from `HKEY_CURRENT_USER`, where
+/// per-user installers register them. All the keys are listed in `APP_KEYS`.
+///
+/// Many applications have no `InstallLocation`, so some have no location. On 32-bit Windows, where
+/// there is only one view, and wherever an application is registered in more than one key, the
+/// same application may be found more than once, so an application with the same name and location
+/// as one already found is skipped. A key that cannot be opened, as when it does not exist, is
+/// skipped, as is any subkey that cannot be read.
+///
+/// [uninstall]: https://learn.microsoft.com/en-us/windows/win32/msi/uninstall-registry-key
+/// [app-paths]: https://learn.microsoft.com/en-us/windows/win32/shell/app-registration
+#[cfg(windows)]
+pub fn installed_apps() -> Vec<InstalledApp> {
+    let mut apps: Vec<InstalledApp> = Vec::new();
+    for key in APP_KEYS {
+        for app in apps_in_key(key).unwrap_or_default() {
+            if !apps
+                .iter()
+                .any(|other| other.name == app.name && other.location == app.location)
+            {
+                apps.push(app);
+            }
+        }
+    }
+    apps
+}
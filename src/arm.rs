@@ -0,0 +1,69 @@
+//! Looking up the 32-bit ARM program files directory, which has no known folder or CSIDL.
+
+#[cfg(windows)]
+use std::env::VarError;
+
+#[cfg(windows)]
+use winreg::enums::KEY_WOW64_64KEY;
+
+#[cfg(windows)]
+use crate::registry::{expand, open_current_version, read_string_value, CURRENT_VERSION_SUBKEY};
+#[cfg(windows)]
+use crate::report::{Lookup, Row, Section};
+
+/// The environment variable reference whose expansion is the ARM program files directory.
+pub const ARM_VARIABLE_REFERENCE: &str = "%ProgramFiles(Arm)%";
+
+/// The registry value that holds the ARM program files directory.
+pub const ARM_REGISTRY_VALUE: &str = "ProgramFilesDir (Arm)";
+
+/// Expands `ARM_VARIABLE_REFERENCE`, treating it as not found if it is left unexpanded.
+#[cfg(windows)]
+fn expand_arm_variable() -> Lookup {
+    match expand(ARM_VARIABLE_REFERENCE) {
+        Ok(path) if path == ARM_VARIABLE_REFERENCE => {
+            Lookup::new(Err::<String, _>(VarError::NotPresent))
+        }
+        result => Lookup::new(result),
+    }
+}
+
+/// Report the 32-bit ARM program files directory in the ways available, for ARM64 systems.
+///
+/// On ARM64 Windows, 32-bit ARM programs have their own program files directory, usually
+/// `C:\Program Files (Arm)`, as described on `report_environment_variables()`. But Windows defines
+/// no known folder for it, so there is no `FOLDERID` to pass to `SHGetKnownFolderPath`, and there
+/// is no CSIDL for it either. The known folders and CSIDL sections therefore cannot show it. This
+/// section shows it instead, in the two ways that work:
+///
+/// - `ARM_VARIABLE_REFERENCE`, expanded with `ExpandEnvironmentStringsW`, as a program would do to
+///   find it in a path or command line. This uses this process's environment, so it fails if an
+///   ancestor removed the `ProgramFiles(Arm)` variable.
+///
+/// - `ARM_REGISTRY_VALUE`, read through the 64-bit registry view, which is where Windows stores it
+///   and so works from any process.
+///
+/// On other systems, there is no such directory, and both are expected to be unavailable.
+#[cfg(windows)]
+pub fn report_arm_program_files() -> Section<Row> {
+    let registry = match open_current_version(None, CURRENT_VERSION_SUBKEY, KEY_WOW64_64KEY) {
+        Ok(key) => read_string_value(&key, ARM_REGISTRY_VALUE).2,
+        Err(e) => Lookup::new(Err::<String, _>(e)),
+    };
+
+    let entries = vec![
+        Row {
+            name: ARM_VARIABLE_REFERENCE.to_owned(),
+            lookup: expand_arm_variable(),
+        },
+        Row {
+            name: ARM_REGISTRY_VALUE.to_owned(),
+            lookup: registry,
+        },
+    ];
+
+    Section::new(
+        "ARM program files, which has no known folder or CSIDL",
+        entries,
+    )
+}
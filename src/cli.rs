@@ -0,0 +1,2123 @@
+//! The command-line program, as described in the documentation of `main.rs`.
+
+use std::ffi::OsStr;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use pfdirs::analysis::{
+    aliases, anomalies, app_census, changes, check_expectations, directory_of_entry, findings,
+    merge_registry_views, missing_requirements, named_path, relocations, resolve_consensus,
+    self_test, stripped_variables, user_program_files_diagnosis, AnomalyKind, Check, Expectation,
+    ExpectationOutcome, LogicalDirectory,
+};
+use pfdirs::ancestry::ancestors;
+use pfdirs::apps::installed_apps;
+use pfdirs::clipboard::copy_to_clipboard;
+use pfdirs::elevation::Elevation;
+use pfdirs::eventlog::{register_event_source, summary_event, write_event, EVENT_SOURCE};
+use pfdirs::experiment::{find_builds, inheritance, matrix, Build};
+use pfdirs::explorer::open_in_explorer;
+use pfdirs::filesystem::{
+    long_path, probe_write, short_path, verify_path, verify_path_unredirected, volume_of,
+};
+use pfdirs::known_folder::{
+    known_folder_flag_variations, known_folder_id_list_round_trips, known_folder_redirection,
+    resolve_guid,
+};
+use pfdirs::known_folder_definition::{report_all_known_folders, report_known_folder};
+use pfdirs::narrative::explain;
+use pfdirs::output::{Encoding, RedirectedOutput};
+use pfdirs::platform::{os_version, remote_machine, ProcessArchitecture};
+use pfdirs::redact::{Redact, Redactor};
+use pfdirs::registry::{Backend, CURRENT_VERSION_SUBKEY};
+use pfdirs::render::{self, Color, Style};
+use pfdirs::resolve::{self, resolve_program_files};
+use pfdirs::saved::{
+    diff, find_snapshot, history, history_directory, load_expectations, save, save_to_history,
+};
+use pfdirs::security::directory_security;
+use pfdirs::service::{user_program_files_as_default_user, ServiceContext};
+use pfdirs::timing::time_calls;
+use pfdirs::token::Identity;
+#[cfg(feature = "tui")]
+use pfdirs::tui;
+use pfdirs::watch::{refresh_environment, timestamp, Watcher};
+use pfdirs::{
+    Entry, Error, KnownFolderDefinitionEntry, Lookup, Machine, Platform, Report, Section,
+    Selection, SortOrder, SourceKind,
+};
+use tracing::Level;
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::System::Console::{
+    GetConsoleMode, GetStdHandle, SetConsoleCtrlHandler, SetConsoleMode, CONSOLE_MODE,
+    CTRL_BREAK_EVENT, CTRL_C_EVENT, ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_OUTPUT_HANDLE,
+};
+use windows::Win32::UI::Shell::{FOLDERID_UserProgramFiles, KF_FLAG_DEFAULT};
+
+/// Whether text output is colored, as decided by `enable_color()`.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Whether paths that are not valid Unicode are shown escaped, as `--escape-non-unicode` says.
+static ESCAPE_NON_UNICODE: AtomicBool = AtomicBool::new(false);
+
+/// What masks user names in the output, set only if `--redact` is passed.
+static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// The exit status when the command line could not be parsed, which is different from every
+/// `AnomalyKind::exit_code()`, so a mistyped option is not taken for a problem with the system.
+const USAGE_EXIT_CODE: u8 = 4;
+
+/// The exit status when `--self-test` finds the program's tables inconsistent.
+const SELF_TEST_EXIT_CODE: u8 = 5;
+
+/// Shown after a report whose collection was interrupted, so it is not taken to be complete.
+const TRUNCATED_MARKER: &str = "[report truncated: interrupted before every source was looked up]";
+
+/// Shown when `--verify-unredirected` is passed to a process with no file system redirection.
+const NO_REDIRECTION_NOTE: &str =
+    "[not checking paths without redirection: this process is not running under WOW64]";
+
+/// How text output is styled, as decided by `enable_color()`, `--escape-non-unicode`, and
+/// `--redact`.
+fn style() -> Style<'static> {
+    Style {
+        color: COLOR.load(Ordering::Relaxed),
+        escape_non_unicode: ESCAPE_NON_UNICODE.load(Ordering::Relaxed),
+        redactor: REDACTOR.get(),
+    }
+}
+
+/// Gives a path as text, as described on `Style::path_text()`, with any user name masked.
+fn path_text(path: &OsStr) -> String {
+    let style = style();
+    style.masked(&style.path_text(path))
+}
+
+/// Masks the user names in part of a report, as described on `Redactor`, if `--redact` was passed.
+fn redact(value: &mut impl Redact) {
+    if let Some(redactor) = REDACTOR.get() {
+        value.redact(redactor);
+    }
+}
+
+/// Decides whether to color the text output, and prepares the console for it if so.
+///
+/// Color is used only if it was not turned off with `--no-color` or a nonempty `NO_COLOR`
+/// environment variable, as described at <https://no-color.org/>, and standard output is a
+/// console that accepts ANSI escape sequences, which Windows consoles do once
+/// `ENABLE_VIRTUAL_TERMINAL_PROCESSING` is set.
+fn enable_color(no_color: bool) {
+    let disabled = no_color || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if disabled || !std::io::stdout().is_terminal() {
+        return;
+    }
+
+    let Ok(console) = (unsafe { GetStdHandle(STD_OUTPUT_HANDLE) }) else {
+        return;
+    };
+    let mut mode = CONSOLE_MODE::default();
+    if unsafe { GetConsoleMode(console, &mut mode) }.is_err() {
+        return;
+    }
+    let enabled = unsafe { SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) };
+    COLOR.store(enabled.is_ok(), Ordering::Relaxed);
+}
+
+/// Handles Ctrl+C and Ctrl+Break by interrupting the collection of the report, if one is in
+/// progress, as described on `interrupt_collection()`.
+///
+/// This runs on a thread Windows starts for it. Returning `TRUE` keeps the process running so the
+/// sources looked up so far can be shown. Otherwise the next handler, which ends the process, runs.
+unsafe extern "system" fn handle_interrupt(control_type: u32) -> BOOL {
+    let interrupting = matches!(control_type, CTRL_C_EVENT | CTRL_BREAK_EVENT);
+    (interrupting && pfdirs::interrupt_collection()).into()
+}
+
+/// Lets Ctrl+C and Ctrl+Break interrupt the collection of the report, as `handle_interrupt()`
+/// does, rather than end the process and lose what was collected.
+///
+/// If the handler cannot be installed, interrupting ends the process as it otherwise would.
+fn install_interrupt_handler() {
+    let _ = unsafe { SetConsoleCtrlHandler(Some(handle_interrupt), true) };
+}
+
+/// Gives the text in the specified color, if color is enabled, or otherwise unchanged.
+fn paint(text: &str, color: Color) -> String {
+    style().paint(text, color)
+}
+
+/// Prints a section as a heading followed by a table of names and paths.
+fn print_section(heading: &str, entries: &[&dyn Entry]) {
+    print!("{}", render::section(heading, entries, &style()));
+}
+
+/// Gives the filesystem verification column for an entry, as found by `verify_path()` and
+/// described by `render::describe_verification()`. Entries with no path have nothing to verify.
+fn verification_item(lookup: &Lookup) -> String {
+    let (Some(path), Some(os_path)) = (&lookup.path, &lookup.os_path) else {
+        return String::new();
+    };
+    render::describe_verification(path, &verify_path(os_path), &style())
+}
+
+/// Gives the column verifying an entry with WOW64 file system redirection disabled, as found by
+/// `verify_path_unredirected()` and described by `render::describe_unredirected_verification()`.
+fn unredirected_verification_item(lookup: &Lookup) -> String {
+    let (Some(path), Some(os_path)) = (&lookup.path, &lookup.os_path) else {
+        return String::new();
+    };
+    let verification = verify_path_unredirected(os_path);
+    render::describe_unredirected_verification(path, &verification, &style())
+}
+
+/// Gives the column showing the 8.3 short form of an entry's path, as found by `short_path()`.
+fn short_path_item(lookup: &Lookup) -> String {
+    let Some(path) = &lookup.os_path else {
+        return String::new();
+    };
+    render::describe_path_form("short", &short_path(path))
+}
+
+/// Gives the column showing the long form of an entry's path, as found by `long_path()`.
+fn long_path_item(lookup: &Lookup) -> String {
+    let Some(path) = &lookup.os_path else {
+        return String::new();
+    };
+    render::describe_path_form("long", &long_path(path))
+}
+
+/// Prints a section as `print_section()` does, with more columns about each entry, as described
+/// on `render::section_with_columns()`.
+///
+/// Each column is given by one of `columns`, in order, such as `verification_item()`, which
+/// verifies each path on disk. The columns are found from `checked`, the same entries before any
+/// user names were masked, since masked paths do not exist.
+fn print_section_with_columns(
+    heading: &str,
+    entries: &[&dyn Entry],
+    checked: &[&dyn Entry],
+    columns: &[fn(&Lookup) -> String],
+) {
+    let cells: Vec<Vec<String>> = checked
+        .iter()
+        .map(|entry| {
+            columns
+                .iter()
+                .map(|column| column(entry.lookup()))
+                .collect()
+        })
+        .collect();
+    print!(
+        "{}",
+        render::section_with_columns(heading, entries, &cells, &style())
+    );
+}
+
+/// Prints all sections as text tables, followed by the reconciliation of `ProgramW6432`.
+///
+/// If `explain` is `true`, each section is followed by notes on why its entries have the values
+/// they do, as described on `explain_entry()`. If `verify_fs` is `true`, each path is also checked
+/// on disk, as described on `verification_item()`, and if `unredirected` is also `true`, it is
+/// checked again with WOW64 file system redirection disabled. If `short_names` is `true`, the 8.3
+/// short and long forms of each path are shown. If `compact` is `true`, the registry views are
+/// shown as one section, as found by `merge_registry_views()`, without their raw values. On ARM64
+/// systems, the entries are then shown again, grouped by directory.
+///
+/// `checked` is the report before any user names were masked, as `--redact` does, and is what
+/// paths are checked on disk from.
+#[allow(clippy::too_many_arguments)]
+fn print_report(
+    report: &Report,
+    checked: &Report,
+    platform: Platform,
+    explain: bool,
+    verify_fs: bool,
+    unredirected: bool,
+    short_names: bool,
+    compact: bool,
+) {
+    let mut columns: Vec<fn(&Lookup) -> String> = Vec::new();
+    if verify_fs {
+        columns.push(verification_item);
+    }
+    if verify_fs && unredirected {
+        columns.push(unredirected_verification_item);
+    }
+    if short_names {
+        columns.extend([short_path_item as fn(&Lookup) -> String, long_path_item]);
+    }
+
+    let show = |heading: &str, entries: &[&dyn Entry], checked: &[&dyn Entry]| {
+        if columns.is_empty() {
+            print_section(heading, entries);
+        } else {
+            print_section_with_columns(heading, entries, checked, &columns);
+        }
+    };
+
+    let style = style();
+
+    // Views are merged before masking, since different users' paths are not the same path.
+    let mut merged = if compact {
+        merge_registry_views(checked)
+    } else {
+        None
+    };
+    let merging = merged.is_some();
+    for (section, checked_section) in report.sections().into_iter().zip(checked.sections()) {
+        if merging && matches!(section.source, SourceKind::Registry(_)) {
+            // The views are adjacent, so they are all shown in place of the first.
+            if let Some(views) = merged.take() {
+                let mut shown = views.clone();
+                redact(&mut shown);
+                let entries: Vec<&dyn Entry> = shown.entries.iter().map(|row| row as _).collect();
+                let checked: Vec<&dyn Entry> = views.entries.iter().map(|row| row as _).collect();
+                show(&shown.heading, &entries, &checked);
+            }
+            if explain {
+                print!("{}", render::explanations(&section, platform));
+            }
+            continue;
+        }
+
+        show(section.heading, &section.entries, &checked_section.entries);
+        match section.source {
+            SourceKind::KnownFolder => print!("{}", render::known_folder_ids(report)),
+            SourceKind::KnownFolderDefinition => {
+                if let Some(definitions) = &report.known_folder_definitions {
+                    print!("{}", render::known_folder_definitions(definitions));
+                }
+            }
+            SourceKind::FreshEnvironment => print!("{}", render::stale_environment(report, &style)),
+            SourceKind::Csidl => print!("{}", render::csidl_defaults(report, &style)),
+            SourceKind::EnvironmentRegistry(_) => {
+                print!(
+                    "{}",
+                    render::environment_raw_values(report, section.heading)
+                );
+            }
+            SourceKind::Registry(view) => print!("{}", render::registry_raw_values(report, view)),
+            SourceKind::ShellFolders { .. } => {
+                print!(
+                    "{}",
+                    render::shell_folder_raw_values(report, section.heading)
+                );
+            }
+            _ => {}
+        }
+        if explain {
+            print!("{}", render::explanations(&section, platform));
+        }
+    }
+    print!("{}", render::environment_origins(report, &style));
+    print!("{}", render::program_w6432_reconciliation(report, &style));
+    report_ancestors(report, platform);
+    if platform.native == Machine::Arm64 {
+        print!("{}", render::arm64_directories(report, platform, &style));
+    }
+}
+
+/// The information about the system that Markdown and HTML output are headed by, as pairs of
+/// properties and values.
+///
+/// This gives the version of Windows, as found by `os_version()`, the architectures of the process
+/// and system, and whether the process is running under WOW64. If `computer` is given, the report
+/// is of that computer, which is named, and whose version of Windows is shown. If `offline` is
+/// `true`, the report is of an offline image, whose version of Windows is not looked up.
+fn system_properties(
+    platform: Platform,
+    computer: Option<&str>,
+    offline: bool,
+) -> Vec<(String, String)> {
+    let os_version = if offline {
+        String::from("[offline image]")
+    } else {
+        os_version(computer).unwrap_or_else(|| String::from("[unknown]"))
+    };
+    let wow64 = if platform.is_wow64() { "yes" } else { "no" };
+
+    let mut system = Vec::new();
+    if let Some(computer) = computer {
+        system.push((String::from("Computer"), computer.to_owned()));
+    }
+    system.push((String::from("Windows version"), os_version));
+    system.push((
+        String::from("Process architecture"),
+        platform.process.description().to_owned(),
+    ));
+    system.push((
+        String::from("System architecture"),
+        platform.native.description().to_owned(),
+    ));
+    system.push((String::from("WOW64"), wow64.to_owned()));
+    // The WOW64 row above is for the system being reported, so only the others are added here.
+    let [_, emulated, arm64ec] = render::architecture_flags(ProcessArchitecture::detect());
+    for (name, value) in [emulated, arm64ec] {
+        system.push((format!("{name} (this process)"), value));
+    }
+    // These describe this process and the local system, even when reporting another computer.
+    for (name, value) in render::elevation_flags(Elevation::detect()) {
+        system.push((format!("{name} (this process)"), value));
+    }
+    system.push((
+        String::from("pfdirs version"),
+        env!("CARGO_PKG_VERSION").to_owned(),
+    ));
+    system
+}
+
+/// Prints the report as GitHub-flavored Markdown, for pasting into issues, as described on
+/// `render::markdown()`, headed by the information given by `system_properties()`.
+///
+/// If `pivot` is `true`, the sections are replaced by one table comparing the sources, as
+/// described on `render::pivot_markdown()`.
+fn print_markdown(
+    report: &Report,
+    platform: Platform,
+    computer: Option<&str>,
+    offline: bool,
+    pivot: bool,
+) {
+    let system = system_properties(platform, computer, offline);
+    if pivot {
+        print!("{}", render::pivot_markdown(report, platform, &system));
+    } else {
+        print!("{}", render::markdown(report, platform, &system));
+    }
+}
+
+/// Prints the report as a standalone HTML page, for attaching to support tickets, as described on
+/// `render::html()`, headed by the information given by `system_properties()`.
+fn print_html(report: &Report, platform: Platform, computer: Option<&str>, offline: bool) {
+    let system = system_properties(platform, computer, offline);
+    let findings = findings(report, platform);
+    print!("{}", render::html(report, platform, &system, &findings));
+}
+
+/// Show every known folder on the system, as found by `report_all_known_folders()`.
+///
+/// As text or Markdown, this is a table of each folder's ID, canonical name, category, and path.
+/// Folders whose definitions could not be retrieved are shown with `[unknown]` in those columns.
+/// As JSON, YAML, or TOML, the section is serialized with the folders' full definitions.
+fn dump_known_folders(format: Format) -> Result<(), Error> {
+    let mut section = report_all_known_folders()?;
+    redact(&mut section);
+    print_known_folders(&section, format)
+}
+
+/// Prints a section of known folders with their definitions, as described on
+/// `dump_known_folders()`.
+fn print_known_folders(
+    section: &Section<KnownFolderDefinitionEntry>,
+    format: Format,
+) -> Result<(), Error> {
+    match format {
+        Format::Text => print!("{}", render::known_folders(section)),
+        Format::Markdown => print!("{}", render::known_folders_markdown(section)),
+        Format::Html => print!("{}", render::known_folders_html(section)),
+        Format::Json => println!("{}", serde_json::to_string_pretty(section)?),
+        Format::Yaml => print!("{}", serde_yaml::to_string(section)?),
+        Format::Jsonl => {
+            for entry in &section.entries {
+                println!("{}", serde_json::to_string(entry)?);
+            }
+        }
+        Format::Toml => print!("{}", toml::to_string(section)?),
+        Format::Csv | Format::Tsv => print!(
+            "{}",
+            render::known_folders_delimited(section, format.separator())
+        ),
+    }
+
+    Ok(())
+}
+
+/// Show one known folder, found by its ID or canonical name by `report_known_folder()`.
+///
+/// As text, this is the folder's path followed by its ID and full definition. In other formats,
+/// it is shown as `dump-known-folders` shows each folder.
+fn inspect_known_folder(folder: &str, format: Format) -> Result<(), Error> {
+    let mut section = report_known_folder(folder)?;
+    redact(&mut section);
+
+    if format == Format::Text {
+        let entries: Vec<&dyn Entry> = section
+            .entries
+            .iter()
+            .map(|entry| entry as &dyn Entry)
+            .collect();
+        print_section(&section.heading, &entries);
+        print!("{}", render::known_folder_definitions(&section));
+        Ok(())
+    } else {
+        print_known_folders(&section, format)
+    }
+}
+
+/// Show the installed applications grouped by the program files directory each is in.
+///
+/// The applications are found by `installed_apps()` and grouped by `app_census()`, using the
+/// program files directories that the sources in `report` agree on, and are shown as described on
+/// `render::app_census()`.
+///
+/// `report` should be the report before any user names were masked, since the applications'
+/// locations are real paths. User names are masked only in what is shown, if `--redact` was passed.
+fn report_app_census(report: &Report, platform: Platform) {
+    let census = app_census(report, platform, installed_apps());
+    print!("{}", render::app_census(&census, &style()));
+}
+
+/// Report an explanation, as given by `narrative::explain()`, of what the sources in `report`
+/// show about this system, as described on `render::narrative()`.
+fn report_narrative(report: &Report, platform: Platform) {
+    print!(
+        "{}",
+        render::narrative(&explain(report, platform), &style())
+    );
+}
+
+/// Report whether each *program files* known folder appears to have been redirected, as found by
+/// `known_folder_redirection()` and described on `render::known_folder_redirection()`.
+fn report_known_folder_redirection() {
+    print!(
+        "{}",
+        render::known_folder_redirection(&known_folder_redirection(), &style())
+    );
+}
+
+/// Report each *program files* known folder as looked up with each flag, side by side, as
+/// described on `render::known_folder_flag_variations()`.
+fn report_known_folder_flag_variations() {
+    print!(
+        "{}",
+        render::known_folder_flag_variations(&known_folder_flag_variations())
+    );
+}
+
+/// Report each *program files* known folder as looked up both as a path and as an item ID list,
+/// as described on `render::known_folder_id_lists()`.
+fn report_known_folder_id_lists() {
+    print!(
+        "{}",
+        render::known_folder_id_lists(&known_folder_id_list_round_trips())
+    );
+}
+
+/// Report the chain of ancestors of this process, if `ProgramW6432` or `ProgramFiles(x86)` is
+/// missing on 64-bit Windows, to help find which one removed it.
+///
+/// The variables are found by `stripped_variables()` and the ancestors by `ancestors()`, and are
+/// shown as described on `render::ancestors()`. Otherwise nothing is shown.
+fn report_ancestors(report: &Report, platform: Platform) {
+    let stripped = stripped_variables(report, platform);
+    if !stripped.is_empty() {
+        print!("{}", render::ancestors(&stripped, &ancestors()));
+    }
+}
+
+/// Report the volume that each program files directory is on.
+///
+/// Each directory's path is the one the sources agree on, as found by `resolve_consensus()`, and
+/// its volume is found by `volume_of()`, which follows junctions and symbolic links to the volume
+/// the directory is really on. Directories no source found are left out.
+fn report_volumes(report: &Report, platform: Platform) {
+    let volumes: Vec<_> = resolve_consensus(report, platform)
+        .into_iter()
+        .filter_map(|resolution| {
+            let (path, os_path) = (resolution.consensus?, resolution.os_path?);
+            let volume = volume_of(&os_path);
+            Some((resolution.directory, path, volume))
+        })
+        .collect();
+    print!("{}", render::volumes(&volumes, &style()));
+}
+
+/// Report who owns each program files directory, and who can do what to it.
+///
+/// Each directory's path is the one the sources agree on, as found by `resolve_consensus()`, and
+/// its owner and permissions are found by `directory_security()`, with the accounts masked if
+/// `--redact` was passed. Directories no source found are left out.
+fn report_security(report: &Report, platform: Platform) {
+    let securities: Vec<_> = resolve_consensus(report, platform)
+        .into_iter()
+        .filter_map(|resolution| {
+            let (path, os_path) = (resolution.consensus?, resolution.os_path?);
+            let mut security = directory_security(&os_path);
+            if let Ok(security) = &mut security {
+                redact(security);
+            }
+            Some((resolution.directory, path, security))
+        })
+        .collect();
+    print!("{}", render::security(&securities, &style()));
+}
+
+/// Report whether this process can really write to each program files directory.
+///
+/// Each directory's path is the one the sources agree on, as found by `resolve_consensus()`, and
+/// is checked by `probe_write()`, which creates and deletes a file in it. Directories no source
+/// found are left out.
+fn report_write_probes(report: &Report, platform: Platform) {
+    let probes: Vec<_> = resolve_consensus(report, platform)
+        .into_iter()
+        .filter_map(|resolution| {
+            let (path, os_path) = (resolution.consensus?, resolution.os_path?);
+            let probe = probe_write(&os_path);
+            Some((resolution.directory, path, probe))
+        })
+        .collect();
+    print!("{}", render::write_probes(&probes, &style()));
+}
+
+/// Report why `FOLDERID_UserProgramFiles` is unavailable, if this process looks like a service,
+/// and, if asked, where it would be in the default user's profile.
+///
+/// The reasons are found by `user_program_files_diagnosis()`. Nothing is shown if there are none,
+/// which is usual when not running as a service.
+fn report_service_diagnosis(report: &Report, retry: bool) {
+    let reasons = user_program_files_diagnosis(report, &ServiceContext::detect());
+    if reasons.is_empty() {
+        return;
+    }
+
+    let default_profile = retry.then(user_program_files_as_default_user);
+    print!(
+        "{}",
+        render::service_diagnosis(&reasons, default_profile.as_ref(), &style())
+    );
+}
+
+/// Report changes to program files folder locations as they happen, until interrupted.
+///
+/// Each time the `Watcher` sees something that may have changed them, the sources it may have
+/// affected, of those selected, are looked up again, and any entries whose paths or errors have
+/// changed are shown, as found by `changes()`, under a line with the time and the trigger. If
+/// looking up the sources fails, the error is shown and watching continues.
+fn watch(cli: &Cli, mut report: Report) -> Result<(), Error> {
+    let mut watcher = Watcher::new(&cli.registry_subkey)?;
+
+    println!("Watching for changes. Press Ctrl+C to stop.");
+    println!();
+
+    loop {
+        let trigger = watcher.wait()?;
+
+        let selection = cli.selection().intersect(trigger.affected());
+        if selection.environment_variables {
+            refresh_environment()?;
+        }
+
+        let newer = Report::collect_from(
+            selection,
+            &cli.registry_subkey,
+            None,
+            cli.registry_backend.into(),
+            cli.timeout(),
+        );
+        let changes = newer.map(|mut newer| {
+            redact(&mut newer);
+            cli.filter(&mut newer, Platform::detect());
+            let changes = changes(&report, &newer);
+            report.update(newer);
+            changes
+        });
+
+        print!(
+            "{}",
+            render::watched_changes(&timestamp(), &trigger.description(), changes.as_deref())
+        );
+    }
+}
+
+/// Report the entries that differ between two saved reports, as found by `diff()`, as described on
+/// `render::diff()`.
+fn report_diff(old: &Path, new: &Path) -> Result<(), Error> {
+    let changes = diff(old, new)?;
+    print!("{}", render::diff(old, new, &changes, &style()));
+    Ok(())
+}
+
+/// Report the snapshots saved in the history directory, as listed by `history()`.
+fn report_history() -> Result<(), Error> {
+    let snapshots = history()?;
+    print!("{}", render::history(&history_directory()?, &snapshots));
+    Ok(())
+}
+
+/// Compare two snapshots, as described on the `history compare` command, with `report_diff()`.
+fn compare_history(old: Option<&str>, new: Option<&str>) -> Result<(), Error> {
+    let snapshots = history()?;
+    let latest = |back: usize| {
+        snapshots
+            .len()
+            .checked_sub(back)
+            .map(|index| snapshots[index].path.clone())
+    };
+
+    let (old, new) = match (old, new) {
+        (Some(old), Some(new)) => (Some(find_snapshot(old)?), Some(find_snapshot(new)?)),
+        (Some(old), None) => (Some(find_snapshot(old)?), latest(1)),
+        (None, _) => (latest(2), latest(1)),
+    };
+
+    match old.zip(new) {
+        Some((old, new)) => report_diff(&old, &new),
+        None => {
+            println!("There are not enough snapshots to compare. Save some with --save.");
+            Ok(())
+        }
+    }
+}
+
+/// Report what a child process receives when program files environment variables are removed.
+///
+/// The trials are run by `inheritance()`, with this program as the child, and are shown as
+/// described on `render::inheritance()`.
+fn report_inheritance_experiment(platform: Platform) -> Result<(), Error> {
+    let program = std::env::current_exe().map_err(Error::Child)?;
+    let trials = inheritance(&program)?;
+    print!("{}", render::inheritance(platform, &trials));
+    Ok(())
+}
+
+/// Report how each entry differs between builds of this program for different architectures.
+///
+/// The builds are run, and their results merged, by `matrix()`, and are shown as described on
+/// `render::matrix()`.
+fn report_matrix(builds: Vec<Build>) {
+    print!("{}", render::matrix(&matrix(builds)));
+}
+
+/// Report whether the tables relating sources, entries, and logical directories are consistent,
+/// as checked by `self_test()`.
+///
+/// Returns `true` if every check passes.
+fn report_self_test() -> bool {
+    let checks = self_test();
+    print!("{}", render::self_test(&checks));
+    checks.iter().all(Check::passed)
+}
+
+/// Report how long each API call to look up each entry takes, as measured by `time_calls()`, as
+/// described on `render::timings()`.
+fn report_timings(repetitions: u32) {
+    print!(
+        "{}",
+        render::timings(&time_calls(repetitions), repetitions, &style())
+    );
+}
+
+/// Report, to standard error, any required entries that did not resolve to a path.
+///
+/// Which entries are missing, and why, is found by `missing_requirements()`.
+///
+/// Returns `true` if all required entries resolved.
+fn report_missing_requirements(report: &Report, required: &[String]) -> bool {
+    let failures = missing_requirements(report, required);
+    eprint!("{}", render::missing_requirements(&failures));
+    failures.is_empty()
+}
+
+/// Put the path for `name` on the clipboard, as found by `named_path()`, saying on standard error
+/// what was copied, or why nothing was.
+///
+/// Returns `true` if there was a path to copy.
+fn copy_path(report: &Report, platform: Platform, name: &str) -> Result<bool, Error> {
+    match named_path(report, platform, name) {
+        Ok(path) => {
+            copy_to_clipboard(&path)?;
+            eprintln!("Copied {name} to the clipboard: {}", path_text(&path));
+            Ok(true)
+        }
+        Err(reason) => {
+            eprintln!("Nothing copied to the clipboard for {name}: {reason}");
+            eprintln!();
+            Ok(false)
+        }
+    }
+}
+
+/// Report, to standard error, whether each expected entry has its expected path, and a summary.
+///
+/// Whether each expectation passed is found by `check_expectations()`, and is shown as described
+/// on `render::expectations()`. Nothing is shown if there were no expectations.
+///
+/// `report` should be the report before any user names were masked, since the expected paths are
+/// real paths. User names are masked only in what is shown, if `--redact` was passed.
+///
+/// Returns `true` if all expectations passed.
+fn report_expectations(report: &Report, expectations: &[Expectation]) -> bool {
+    let outcomes = check_expectations(report, expectations);
+    eprint!("{}", render::expectations(&outcomes, &style()));
+    outcomes.iter().all(ExpectationOutcome::passed)
+}
+
+/// Report, to standard error, anything wrong with the report that suggests a broken program files
+/// configuration.
+///
+/// What is wrong is found by `anomalies()`. Entries that are legitimately unavailable on this
+/// system are not listed.
+///
+/// Returns the most serious kind of anomaly, if there were any.
+fn report_anomalies(report: &Report, platform: Platform) -> Option<AnomalyKind> {
+    let found = anomalies(report, platform);
+    eprint!("{}", render::anomalies(&found));
+    found.iter().map(|anomaly| anomaly.kind).max()
+}
+
+impl Cli {
+    /// The output format selected by the options.
+    fn format(&self) -> Format {
+        if self.toml {
+            Format::Toml
+        } else {
+            self.format
+        }
+    }
+
+    /// Whose known folders to look up, as given by `--token` or `--as-user`.
+    fn identity(&self) -> Identity {
+        match (self.token, &self.as_user) {
+            (Some(TokenKind::DefaultUser), _) => Identity::DefaultUser,
+            (None, Some(name)) => Identity::User(name.clone()),
+            (None, None) => Identity::Current,
+        }
+    }
+
+    /// How long to wait for each source, or `None` to wait as long as it takes.
+    fn timeout(&self) -> Option<Duration> {
+        (self.timeout != 0).then(|| Duration::from_secs(self.timeout))
+    }
+
+    /// The sources selected by the options.
+    ///
+    /// If no source is named, all are selected, except that for another computer only the
+    /// registry sources are selected, since only they can be read remotely. Other shells are run
+    /// only if `--cross-check-shells` is passed, and a Windows Installer session is opened only if
+    /// `--cross-check-msi` is passed, which add them to whatever else is selected.
+    ///
+    /// If `--arch` or `--kind` is passed, only the sources that have entries for those
+    /// directories, as given by `arch_selection()`, and of those kinds, as given by
+    /// `kind_selection()`, are selected from these.
+    fn selection(&self) -> Selection {
+        let mut selection = self.named_selection();
+        if !self.arch.is_empty() {
+            selection = selection.intersect(self.arch_selection());
+        }
+        if !self.kind.is_empty() {
+            selection = selection.intersect(self.kind_selection());
+        }
+        selection
+    }
+
+    /// The sources that have entries for any of the directories passed to `--arch`.
+    ///
+    /// Only the environment variables and registry values cover the 32-bit ARM directory, and only
+    /// the known folders, their monikers, and the shell folders cover the per-user directory.
+    fn arch_selection(&self) -> Selection {
+        let any = |wanted: &[Arch]| self.arch.iter().any(|arch| wanted.contains(arch));
+        let environment = any(&[Arch::X86, Arch::X64, Arch::Arm64]);
+        let known_folders = any(&[Arch::X86, Arch::X64, Arch::User]);
+        let machine_wide = any(&[Arch::X86, Arch::X64]);
+        Selection {
+            environment_variables: environment,
+            known_folders,
+            known_folder_definitions: known_folders,
+            shell_monikers: known_folders,
+            csidls: machine_wide,
+            registry: environment,
+            shell_folders: known_folders,
+            environment_registry: environment,
+            fresh_environment: environment,
+            wmi: false,
+            system_directories: false,
+            shells: false,
+            msi: machine_wide,
+        }
+    }
+
+    /// The sources of the kinds passed to `--kind`.
+    fn kind_selection(&self) -> Selection {
+        let has = |kind| self.kind.contains(&kind);
+        Selection {
+            environment_variables: has(Kind::Env),
+            known_folders: has(Kind::Kf),
+            known_folder_definitions: has(Kind::Kf),
+            shell_monikers: has(Kind::Kf),
+            csidls: has(Kind::Csidl),
+            registry: has(Kind::Registry),
+            shell_folders: has(Kind::Registry),
+            environment_registry: has(Kind::Env),
+            fresh_environment: has(Kind::Env),
+            wmi: false,
+            system_directories: false,
+            shells: false,
+            msi: false,
+        }
+    }
+
+    /// Drops the entries that are not for any of the directories passed to `--arch`, if it was
+    /// passed, as found by `directory_of_entry()`.
+    fn filter(&self, report: &mut Report, platform: Platform) {
+        if self.arch.is_empty() {
+            return;
+        }
+        let directories: Vec<LogicalDirectory> =
+            self.arch.iter().map(|&arch| arch.into()).collect();
+        report.retain(|source, name| {
+            directory_of_entry(source, name, platform)
+                .is_some_and(|directory| directories.contains(&directory))
+        });
+    }
+
+    /// The sources named by the options, as described on `selection()`.
+    fn named_selection(&self) -> Selection {
+        let any = self.env
+            || self.env_registry
+            || self.fresh_env
+            || self.wmi
+            || self.system_directories
+            || self.known_folders
+            || self.known_folder_definitions
+            || self.shell_monikers
+            || self.csidl
+            || self.registry
+            || self.shell_folders;
+
+        if self.computer.is_some() && !any {
+            Selection {
+                environment_variables: false,
+                known_folders: false,
+                known_folder_definitions: false,
+                shell_monikers: false,
+                csidls: false,
+                registry: true,
+                shell_folders: true,
+                environment_registry: false,
+                fresh_environment: false,
+                wmi: false,
+                system_directories: false,
+                shells: false,
+                msi: false,
+            }
+        } else if self.all || !any {
+            Selection {
+                shells: self.cross_check_shells,
+                msi: self.cross_check_msi,
+                ..Selection::ALL
+            }
+        } else {
+            Selection {
+                environment_variables: self.env,
+                known_folders: self.known_folders,
+                known_folder_definitions: self.known_folder_definitions,
+                shell_monikers: self.shell_monikers,
+                csidls: self.csidl,
+                registry: self.registry,
+                shell_folders: self.shell_folders,
+                environment_registry: self.env_registry,
+                fresh_environment: self.fresh_env,
+                wmi: self.wmi,
+                system_directories: self.system_directories,
+                shells: self.cross_check_shells,
+                msi: self.cross_check_msi,
+            }
+        }
+    }
+}
+
+/// Options that cannot be combined with `--token` or `--as-user`, because they report sources or
+/// systems that are not looked up with a token.
+const TOKEN_CONFLICTS: [&str; 17] = [
+    "env",
+    "env_registry",
+    "fresh_env",
+    "wmi",
+    "system_directories",
+    "cross_check_shells",
+    "cross_check_msi",
+    "known_folder_definitions",
+    "shell_monikers",
+    "csidl",
+    "registry",
+    "shell_folders",
+    "all",
+    "computer",
+    "hive",
+    "watch",
+    "experiment",
+];
+
+/// Options that cannot be combined with `--all-users`, because they report sources or systems
+/// other than the per-user registry values of this computer's profiles.
+const ALL_USERS_CONFLICTS: [&str; 20] = [
+    "env",
+    "env_registry",
+    "fresh_env",
+    "wmi",
+    "system_directories",
+    "cross_check_shells",
+    "cross_check_msi",
+    "known_folders",
+    "known_folder_definitions",
+    "shell_monikers",
+    "csidl",
+    "registry",
+    "shell_folders",
+    "all",
+    "computer",
+    "hive",
+    "token",
+    "as_user",
+    "watch",
+    "experiment",
+];
+
+/// A token to look up known folders with, other than this process's, as given on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TokenKind {
+    /// The default user, whose profile new users' profiles are copied from.
+    DefaultUser,
+}
+
+/// An experiment to run instead of reporting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Experiment {
+    /// Run this program as a child with environment variables removed, to see what it receives.
+    Inheritance,
+}
+
+/// How to output the report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Text tables, for people to read.
+    Text,
+
+    /// JSON, with each entry's path or error as a separate field.
+    Json,
+
+    /// YAML, with each entry's path or error as a separate field, as for JSON.
+    Yaml,
+
+    /// TOML, with each entry's path or error as a separate field.
+    Toml,
+
+    /// GitHub-flavored Markdown tables, headed by information about the system.
+    Markdown,
+
+    /// A standalone HTML page, headed by information about the system and the findings, with
+    /// collapsible sections and anomalies highlighted.
+    Html,
+
+    /// Comma-separated values, one line per entry, with stable column headers.
+    Csv,
+
+    /// Tab-separated values, one line per entry, with stable column headers.
+    Tsv,
+
+    /// JSON lines, one object per entry, each written as soon as its source is looked up.
+    Jsonl,
+}
+
+impl Format {
+    /// The character that separates cells, for the delimited formats.
+    fn separator(self) -> char {
+        if self == Self::Tsv {
+            '\t'
+        } else {
+            ','
+        }
+    }
+}
+
+/// How to order the entries within each section, as given on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Sort {
+    /// Alphabetically by symbolic name.
+    Name,
+
+    /// By path, with entries that have errors last.
+    Path,
+
+    /// In the order they are looked up, which groups related entries.
+    None,
+}
+
+impl From<Sort> for SortOrder {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Name => Self::Name,
+            Sort::Path => Self::Path,
+            Sort::None => Self::None,
+        }
+    }
+}
+
+/// A program files directory to report on, as given on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Arch {
+    /// The 32-bit x86 directory.
+    X86,
+
+    /// The 64-bit directory, used by both x64 and ARM64 programs.
+    X64,
+
+    /// The 32-bit ARM directory, present only on ARM64 systems.
+    Arm64,
+
+    /// The per-user directory.
+    User,
+}
+
+impl From<Arch> for LogicalDirectory {
+    fn from(arch: Arch) -> Self {
+        match arch {
+            Arch::X86 => Self::ProgramFiles32,
+            Arch::X64 => Self::ProgramFiles64,
+            Arch::Arm64 => Self::ProgramFilesArm,
+            Arch::User => Self::UserProgramFiles,
+        }
+    }
+}
+
+/// Find a program files directory for the `open` command, or say why it could not be found.
+///
+/// The per-user directory has no environment variable or registry value that names it, so it is
+/// not one `resolve_program_files()` resolves, and is found from its known folder alone.
+fn resolve_directory(directory: Arch) -> Result<PathBuf, String> {
+    let arch = match directory {
+        Arch::X86 => resolve::Arch::X86,
+        Arch::X64 => resolve::Arch::X64,
+        Arch::Arm64 => resolve::Arch::Arm,
+        Arch::User => {
+            return resolve_guid(FOLDERID_UserProgramFiles, KF_FLAG_DEFAULT)
+                .map(PathBuf::from)
+                .map_err(|e| {
+                    format!(
+                        "could not find the user program files directory: \
+                         known folder FOLDERID_UserProgramFiles: {e}"
+                    )
+                });
+        }
+    };
+    resolve_program_files(arch).map_err(|e| e.to_string())
+}
+
+/// A kind of source to report, as given on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Kind {
+    /// Environment variables, as inherited, as stored in the registry, and as a new environment
+    /// would have them.
+    Env,
+
+    /// Known folders, their definitions, and their `shell:` monikers.
+    Kf,
+
+    /// CSIDLs.
+    Csidl,
+
+    /// Registry values, in each view, and the shell folders keys.
+    Registry,
+}
+
+/// Which library to read registry values with, as given on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum RegistryBackend {
+    /// The winreg crate.
+    Winreg,
+
+    /// Microsoft's windows-registry crate, which cannot tell REG_SZ from REG_EXPAND_SZ.
+    WindowsRegistry,
+
+    /// Both, reporting any value on which they diverge as a discrepancy.
+    CrossCheck,
+}
+
+impl From<RegistryBackend> for Backend {
+    fn from(backend: RegistryBackend) -> Self {
+        match backend {
+            RegistryBackend::Winreg => Self::Winreg,
+            RegistryBackend::WindowsRegistry => Self::WindowsRegistry,
+            RegistryBackend::CrossCheck => Self::CrossCheck,
+        }
+    }
+}
+
+/// An encoding for output that is not to a console, as given on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputEncoding {
+    /// UTF-8, with no byte order mark.
+    #[value(name = "utf-8")]
+    Utf8,
+
+    /// UTF-8 with a byte order mark, as Windows PowerShell 5.1 and Excel expect.
+    #[value(name = "utf-8-bom")]
+    Utf8Bom,
+
+    /// UTF-16LE with a byte order mark, as Windows PowerShell 5.1 writes with `>`.
+    #[value(name = "utf-16")]
+    Utf16,
+
+    /// The system's ANSI code page, with `?` for characters it does not have.
+    Ansi,
+}
+
+impl From<OutputEncoding> for Encoding {
+    fn from(encoding: OutputEncoding) -> Self {
+        match encoding {
+            OutputEncoding::Utf8 => Self::Utf8,
+            OutputEncoding::Utf8Bom => Self::Utf8Bom,
+            OutputEncoding::Utf16 => Self::Utf16,
+            OutputEncoding::Ansi => Self::Ansi,
+        }
+    }
+}
+
+/// Commands that do something other than report on this process.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run builds for different architectures and show how each source differs between them.
+    ///
+    /// Builds not given as options are looked for next to this program, as `pfdirs-x86.exe` and so
+    /// on, and in the Cargo target directory this program is in.
+    Matrix(MatrixArgs),
+
+    /// Show every known folder on this system, not just the program files folders, and exit.
+    ///
+    /// Each folder is shown with its ID, canonical name, category, and path. With `--format json`,
+    /// `--format yaml`, or `--format toml`, each folder's full definition is given.
+    DumpKnownFolders,
+
+    /// Show any known folder, found by its ID or canonical name, with its definition, and exit.
+    ///
+    /// This is not limited to the program files folders. The ID is a GUID, with or without
+    /// braces. Anything else is taken to be a canonical name, such as `ProgramFilesX86`.
+    Kf {
+        /// The known folder's ID or canonical name.
+        folder: String,
+    },
+
+    /// Show the installed applications, grouped by the program files directory each is in, and
+    /// exit.
+    ///
+    /// Applications are found in the Uninstall and App Paths registry keys, through both registry
+    /// views and for the current user. The program files directories are found from the selected
+    /// sources, as for the usual report.
+    Apps,
+
+    /// Explain, in prose, what the selected sources show about this system, and exit.
+    ///
+    /// The explanation is tailored to this process and system: what their architectures mean for
+    /// which directory each way of looking it up gives, which environment variables and registry
+    /// values are present, and whether anything looks wrong. Each paragraph cites the Microsoft
+    /// documentation it is based on.
+    Explain,
+
+    /// Compare two reports saved with `--format json`, and show only the entries that differ.
+    ///
+    /// The reports may be of the same machine at different times, or of two machines. Entries in
+    /// only one of them are shown as `[absent]` in the other.
+    Diff(DiffArgs),
+
+    /// List or compare snapshots saved with `--save` and no path.
+    ///
+    /// Snapshots are kept in `pfdirs` in the local application data folder.
+    #[command(subcommand)]
+    History(HistoryCommand),
+
+    /// Time each API call to look up each entry, repeated many times, and exit.
+    ///
+    /// The minimum, mean, and maximum time of each call are shown, to help choose the cheapest
+    /// source that is reliable enough.
+    Bench(BenchArgs),
+
+    /// Explore the selected sources interactively in the terminal, as they change, and exit when
+    /// done.
+    ///
+    /// Sections expand to show their entries, and the selected entry's details are shown beside
+    /// them, including raw error codes, known folder IDs, and registry value types. Changes are
+    /// watched for, as with `--watch`. Press `c` to copy the selected path to the clipboard, `r` to
+    /// look everything up again, and `q` to quit.
+    #[cfg(feature = "tui")]
+    Tui,
+
+    /// Open a program files directory in File Explorer, and exit.
+    ///
+    /// The directory is found with `resolve_program_files()`, which tries its known folder, then
+    /// its environment variable, then its registry value, so it is the same from 32-bit and 64-bit
+    /// processes. The per-user directory, which that does not resolve, is found through
+    /// FOLDERID_UserProgramFiles. Its path is shown before it is opened.
+    Open {
+        /// The program files directory to open.
+        #[arg(value_enum)]
+        directory: Arch,
+    },
+}
+
+/// Subcommands of the `history` command.
+#[derive(Debug, Subcommand)]
+enum HistoryCommand {
+    /// List the saved snapshots, oldest first, with where and when each was taken.
+    List,
+
+    /// Compare two snapshots, as `diff` does, by name or path.
+    ///
+    /// If none are given, the two most recent snapshots are compared. If one is given, it is
+    /// compared to the most recent snapshot.
+    Compare {
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+/// Options for the `diff` command.
+#[derive(Debug, Args)]
+struct DiffArgs {
+    /// The older report, or the one to compare against.
+    old: PathBuf,
+
+    /// The newer report.
+    new: PathBuf,
+}
+
+/// Options for the `bench` command.
+#[derive(Debug, Args)]
+struct BenchArgs {
+    /// How many times to make each call.
+    #[arg(long, short = 'n', default_value_t = 1000)]
+    repetitions: u32,
+}
+
+/// Options for the `matrix` command.
+#[derive(Debug, Args)]
+struct MatrixArgs {
+    /// A 32-bit x86 build of this program.
+    #[arg(long, value_name = "PATH")]
+    x86: Option<PathBuf>,
+
+    /// A 64-bit x64 build of this program.
+    #[arg(long, value_name = "PATH")]
+    x64: Option<PathBuf>,
+
+    /// A 64-bit ARM64 build of this program.
+    #[arg(long, value_name = "PATH")]
+    arm64: Option<PathBuf>,
+}
+
+impl MatrixArgs {
+    /// The builds to run: those given as options, and those found by `find_builds()` otherwise.
+    fn builds(&self) -> Result<Vec<Build>, Error> {
+        let given = [
+            (Machine::X86, &self.x86),
+            (Machine::X64, &self.x64),
+            (Machine::Arm64, &self.arm64),
+        ];
+        let found = find_builds()?;
+
+        let builds = given
+            .into_iter()
+            .filter_map(|(machine, program)| match program {
+                Some(program) => Some(Build {
+                    machine,
+                    program: program.clone(),
+                }),
+                None => found.iter().find(|build| build.machine == machine).cloned(),
+            })
+            .collect();
+
+        Ok(builds)
+    }
+}
+
+/// Command-line arguments.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Report program files locations from environment variables.
+    #[arg(long, help_heading = "Sources")]
+    env: bool,
+
+    /// Report program files environment variables as stored in the registry for new environments.
+    #[arg(long, help_heading = "Sources")]
+    env_registry: bool,
+
+    /// Report program files environment variables from a fresh environment block, and stale ones.
+    #[arg(long, help_heading = "Sources")]
+    fresh_env: bool,
+
+    /// Report program files environment variables, and the OS architecture, as WMI gives them.
+    #[arg(long, help_heading = "Sources")]
+    wmi: bool,
+
+    /// Report the Windows and system directories, and whether Sysnative is found under WOW64.
+    #[arg(long, help_heading = "Sources")]
+    system_directories: bool,
+
+    /// Report program files locations from known folders.
+    #[arg(long, help_heading = "Sources")]
+    known_folders: bool,
+
+    /// Report program files locations, and folder definitions, from known folders through COM.
+    #[arg(long, help_heading = "Sources")]
+    known_folder_definitions: bool,
+
+    /// Report program files locations from shell: monikers, parsed in the Shell namespace.
+    #[arg(long, help_heading = "Sources")]
+    shell_monikers: bool,
+
+    /// Report program files locations from CSIDLs.
+    #[arg(long, help_heading = "Sources")]
+    csidl: bool,
+
+    /// Report program files locations from the registry, in each registry view.
+    #[arg(long, help_heading = "Sources")]
+    registry: bool,
+
+    /// Report program files locations from the legacy shell folders keys, in each registry view.
+    #[arg(long, help_heading = "Sources")]
+    shell_folders: bool,
+
+    /// Report program files locations from all sources. This is the default.
+    #[arg(long, help_heading = "Sources")]
+    all: bool,
+
+    /// Report only the entries for these program files directories, from the sources that have
+    /// any, as a comma-separated list.
+    ///
+    /// Entries whose meaning depends on the process, such as ProgramFiles, are included for the
+    /// directory they give in this process.
+    #[arg(long, value_enum, value_delimiter = ',', help_heading = "Sources")]
+    arch: Vec<Arch>,
+
+    /// Report only these kinds of sources, as a comma-separated list.
+    #[arg(long, value_enum, value_delimiter = ',', help_heading = "Sources")]
+    kind: Vec<Kind>,
+
+    /// Also run cmd and PowerShell, and report the program files locations they give.
+    #[arg(long, help_heading = "Sources")]
+    cross_check_shells: bool,
+
+    /// Also open a Windows Installer session, and report the program files folder properties it
+    /// resolves for MSI packages.
+    #[arg(long, help_heading = "Sources")]
+    cross_check_msi: bool,
+
+    /// Report the registry sources of another computer, through the Remote Registry service.
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = [
+            "env",
+            "env_registry",
+            "fresh_env",
+            "wmi",
+            "system_directories",
+            "cross_check_shells",
+            "cross_check_msi",
+            "known_folders",
+            "known_folder_definitions",
+            "shell_monikers",
+            "csidl",
+            "all",
+            "watch",
+            "experiment",
+        ]
+    )]
+    computer: Option<String>,
+
+    /// Report the registry values in an offline SOFTWARE hive, such as from a mounted image.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = [
+            "env",
+            "env_registry",
+            "fresh_env",
+            "wmi",
+            "system_directories",
+            "cross_check_shells",
+            "cross_check_msi",
+            "known_folders",
+            "known_folder_definitions",
+            "shell_monikers",
+            "csidl",
+            "registry",
+            "shell_folders",
+            "all",
+            "computer",
+            "watch",
+            "experiment",
+        ]
+    )]
+    hive: Option<PathBuf>,
+
+    /// Report the per-user environment and shell folders registry values of every local profile.
+    #[arg(long, conflicts_with_all = ALL_USERS_CONFLICTS)]
+    all_users: bool,
+
+    /// Report only the known folders, as the user of this kind of token would see them.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KIND",
+        conflicts_with_all = TOKEN_CONFLICTS
+    )]
+    token: Option<TokenKind>,
+
+    /// Report only the known folders, as this user, who must be logged on, would see them.
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with = "token",
+        conflicts_with_all = TOKEN_CONFLICTS
+    )]
+    as_user: Option<String>,
+
+    /// How to output the report.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Output the report as TOML. This is the same as `--format toml`.
+    #[arg(long, conflicts_with = "format")]
+    toml: bool,
+
+    /// Exit with a failure status if any of these entries do not resolve to a path.
+    ///
+    /// Names are as shown in the first column of the text output, and may be separated by commas
+    /// or given in separate uses of this option.
+    #[arg(long, value_name = "NAME", value_delimiter = ',')]
+    require: Vec<String>,
+
+    /// Put the path of NAME on the clipboard, after showing the report.
+    ///
+    /// NAME is a program files directory, which is program-files-64, program-files-32,
+    /// program-files-arm, or user-program-files, to copy the path the sources agree on, or an entry
+    /// name, as for `--require`, to copy that entry's path. If there is no such path, this exits
+    /// with a failure status, as `--require` does.
+    #[arg(long, value_name = "NAME")]
+    copy: Option<String>,
+
+    /// Exit with a failure status unless the entry NAME resolves to the path VALUE.
+    ///
+    /// Case and a trailing backslash are ignored. This may be given more than once.
+    #[arg(long, value_name = "NAME=VALUE")]
+    expect: Vec<Expectation>,
+
+    /// Check the entries named in this TOML file against the paths given for them, as `--expect`.
+    #[arg(long, value_name = "PATH")]
+    expect_file: Option<PathBuf>,
+
+    /// Also show a table comparing every source's result for each program files directory.
+    #[arg(long)]
+    summary: bool,
+
+    /// Show the registry views as one section, with a row for each view only where they differ.
+    ///
+    /// The views usually agree on every value but ProgramFilesDir, so this shortens the text
+    /// output. The raw values read from each view are not shown.
+    #[arg(long)]
+    compact: bool,
+
+    /// Show the report as one table comparing every source's result for each program files
+    /// directory, instead of a table for each source.
+    ///
+    /// This applies to the text, Markdown, CSV, and TSV formats. The other formats already keep
+    /// each source's results together, and are unaffected.
+    #[arg(long)]
+    pivot: bool,
+
+    /// Follow each section with notes on why its entries have the values they do.
+    #[arg(long)]
+    explain: bool,
+
+    /// Check each path on disk, showing whether it is a directory or junction, and its final path.
+    #[arg(long)]
+    verify_fs: bool,
+
+    /// With --verify-fs in a 32-bit process on 64-bit Windows, check each path again without WOW64
+    /// file system redirection.
+    #[arg(long, requires = "verify_fs")]
+    verify_unredirected: bool,
+
+    /// Also show the 8.3 short form and the long form of each path, for comparison with paths that
+    /// installers stored as short names, such as C:\PROGRA~1.
+    #[arg(long)]
+    short_names: bool,
+
+    /// If FOLDERID_UserProgramFiles is unavailable because this runs as a service, retry it in the
+    /// default user's profile.
+    #[arg(long, conflicts_with_all = ["computer", "hive", "token", "as_user"])]
+    retry_default_profile: bool,
+
+    /// Also show the volume each program files directory is on: its GUID path, filesystem, whether
+    /// it is a Dev Drive, and its free space.
+    #[arg(long, conflicts_with_all = ["computer", "hive"])]
+    volumes: bool,
+
+    /// Also show who owns each program files directory and who can modify it, flagging any that
+    /// all users can write to.
+    #[arg(long, conflicts_with_all = ["computer", "hive"])]
+    security: bool,
+
+    /// Also check whether this process can write to each program files directory, by creating and
+    /// deleting a temporary file in it.
+    #[arg(long, conflicts_with_all = ["computer", "hive"])]
+    probe_write: bool,
+
+    /// Also show whether each program files known folder has been redirected from its default.
+    #[arg(long)]
+    redirection: bool,
+
+    /// Also show each program files known folder as looked up with each of several KF_FLAG values.
+    #[arg(long)]
+    kf_flags: bool,
+
+    /// Also check each program files known folder's item ID list against its path.
+    #[arg(long)]
+    id_lists: bool,
+
+    /// Also show how long each API call to look up each entry takes.
+    #[arg(long)]
+    timing: bool,
+
+    /// Do not color the text output. Setting the NO_COLOR environment variable also does this.
+    #[arg(long)]
+    no_color: bool,
+
+    /// Show only where the program files directories are, and the summary table, as text.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log how long each source took on standard error. Pass twice to also log each API call, with
+    /// its arguments, its result or error code, and how long it took.
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Show paths that are not valid Unicode with that escaped, rather than replaced with U+FFFD.
+    #[arg(long)]
+    escape_non_unicode: bool,
+
+    /// Mask the user name in paths, and in account names, so the output can be shared publicly.
+    ///
+    /// The directory just inside the profiles directory, as in C:\Users\ek\AppData, is shown as
+    /// <user>, in every output format. Reports saved with `--save` are masked too, but their
+    /// `snapshot` object still names the user, for `pfdirs history`.
+    #[arg(long)]
+    redact: bool,
+
+    /// How to order the entries within each section.
+    #[arg(long, value_enum, default_value_t = Sort::None)]
+    sort: Sort,
+
+    /// How many seconds to wait for each source before showing it as timed out, or 0 to wait as
+    /// long as it takes.
+    #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+    timeout: u64,
+
+    /// Which library to read the program files registry values with.
+    #[arg(long, value_enum, default_value_t = RegistryBackend::Winreg)]
+    registry_backend: RegistryBackend,
+
+    /// After reporting, keep running, and report each change to the registry or environment.
+    ///
+    /// Changes are shown as they happen, each with a timestamp and what triggered it, until the
+    /// program is interrupted. Only text output is supported.
+    #[arg(long, conflicts_with_all = ["format", "toml"])]
+    watch: bool,
+
+    /// Also save the report as JSON, with the host name, user, Windows version, and time.
+    ///
+    /// If no path is given, the report is saved as a snapshot in the history directory, to be
+    /// listed and compared with `pfdirs history`.
+    #[arg(long, value_name = "PATH", num_args = 0..=1)]
+    save: Option<Option<PathBuf>>,
+
+    /// Write the output to this file instead of standard output, replacing it atomically.
+    ///
+    /// The output is written to a temporary file beside it first, which is then renamed over it,
+    /// so readers never see a partial report. If anything fails, the file is left as it was.
+    #[arg(short, long, value_name = "FILE", conflicts_with = "watch")]
+    output: Option<PathBuf>,
+
+    /// With `--output`, add the output to the end of the file instead of replacing it.
+    ///
+    /// The file is locked while the output is added to it in one write, so reports appended at the
+    /// same time, such as by scheduled tasks writing to a shared location, do not interleave.
+    #[arg(long, requires = "output")]
+    append: bool,
+
+    /// The encoding of output to a file or pipe, whether with `--output` or redirected by a shell.
+    ///
+    /// Output to a console is always shown correctly, whatever the console's code page is, so this
+    /// does not change it. Standard error is always UTF-8 when it is not a console.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ENCODING",
+        default_value_t = OutputEncoding::Utf8,
+        conflicts_with = "watch"
+    )]
+    output_encoding: OutputEncoding,
+
+    /// Also write a summary of the report, and any anomalies, to the Application event log.
+    ///
+    /// The event is written under the `pfdirs` source, which is registered first if it is not
+    /// already, if this is run as an administrator. Its ID is 1000 plus the exit status.
+    #[arg(long)]
+    eventlog: bool,
+
+    /// Run an experiment, instead of reporting, and exit.
+    #[arg(long, value_enum, value_name = "NAME")]
+    experiment: Option<Experiment>,
+
+    /// Check that internal tables are consistent, without looking anything up, and exit.
+    #[arg(long)]
+    self_test: bool,
+
+    /// Print the JSON Schema document that `--format json` output follows, and exit.
+    #[arg(long)]
+    json_schema: bool,
+
+    /// Registry key to read values from instead of the usual one, for diagnostics and testing.
+    #[arg(long, hide = true, value_name = "KEY", default_value = CURRENT_VERSION_SUBKEY)]
+    registry_subkey: String,
+}
+
+pub fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            // Showing `--help` or `--version` is also an "error" to clap, but it succeeded.
+            return if e.use_stderr() {
+                ExitCode::from(USAGE_EXIT_CODE)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    match run_to_output(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::from(AnomalyKind::ApiFailure.exit_code())
+        }
+    }
+}
+
+/// Does what `run()` does, with standard output going to the `--output` file, if there is one, in
+/// the `--output-encoding`.
+///
+/// The output is only moved or appended to the file, by `RedirectedOutput::commit()`, if `run()`
+/// succeeds. Otherwise the file is left as it was, and the error is shown on standard error. With
+/// no `--output`, standard output is only captured, to be written back in the encoding, if it is
+/// not a console and the encoding is not UTF-8, which Rust's standard library already writes.
+fn run_to_output(cli: Cli) -> Result<ExitCode, Error> {
+    let encoding: Encoding = cli.output_encoding.into();
+    let output = match &cli.output {
+        Some(path) => RedirectedOutput::start(path, cli.append, encoding)?,
+        None if encoding == Encoding::Utf8 || io::stdout().is_terminal() => return run(cli),
+        None => RedirectedOutput::capture(encoding)?,
+    };
+    let code = run(cli)?;
+    output.commit()?;
+    Ok(code)
+}
+
+/// Writes a summary of the report to the Application event log, as made by `summary_event()`,
+/// with `worst` as the most serious problem found, which gives the exit status.
+///
+/// The `pfdirs` event source is registered first, if it is not already, as described on
+/// `register_event_source()`. If it cannot be, as when this is not run as an administrator, that
+/// is logged as a warning, and the event is written anyway. If the event cannot be written, that
+/// is logged as a warning too, since the report has been shown and the exit status still says
+/// how it went.
+fn log_event(report: &Report, platform: Platform, worst: Option<AnomalyKind>) {
+    if let Err(error) = register_event_source() {
+        tracing::warn!(%error, "could not register the {EVENT_SOURCE} event source");
+    }
+    if let Err(error) = write_event(&summary_event(report, platform, worst)) {
+        tracing::warn!(%error, "could not write to the event log");
+    }
+}
+
+/// Sends `tracing` events from the library to standard error, at the level `--verbose` asks for.
+///
+/// With no `--verbose`, only warnings and errors are shown. Once shows how long each source took,
+/// at the `DEBUG` level, and twice also shows each API call, at the `TRACE` level. With `--quiet`,
+/// only errors are shown.
+fn init_logging(quiet: bool, verbose: u8) {
+    let level = match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(false)
+        .init();
+}
+
+/// Does what the command-line arguments ask, giving the exit status if nothing fails outright.
+fn run(cli: Cli) -> Result<ExitCode, Error> {
+    ESCAPE_NON_UNICODE.store(cli.escape_non_unicode, Ordering::Relaxed);
+    if cli.redact {
+        let _ = REDACTOR.set(Redactor::detect());
+    }
+    init_logging(cli.quiet, cli.verbose);
+
+    if cli.self_test {
+        return Ok(if report_self_test() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(SELF_TEST_EXIT_CODE)
+        });
+    }
+
+    if cli.json_schema {
+        print!("{}", render::JSON_SCHEMA);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match &cli.command {
+        Some(Command::Matrix(args)) => {
+            report_matrix(args.builds()?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::DumpKnownFolders) => {
+            dump_known_folders(cli.format())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Kf { folder }) => {
+            enable_color(cli.no_color);
+            inspect_known_folder(folder, cli.format())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Apps) => {
+            enable_color(cli.no_color);
+            let report = Report::collect_from(
+                cli.selection(),
+                &cli.registry_subkey,
+                None,
+                cli.registry_backend.into(),
+                cli.timeout(),
+            )?;
+            report_app_census(&report, Platform::detect());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Explain) => {
+            enable_color(cli.no_color);
+            let mut report = Report::collect_from(
+                cli.selection(),
+                &cli.registry_subkey,
+                None,
+                cli.registry_backend.into(),
+                cli.timeout(),
+            )?;
+            redact(&mut report);
+            report_narrative(&report, Platform::detect());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Diff(args)) => {
+            enable_color(cli.no_color);
+            report_diff(&args.old, &args.new)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Bench(args)) => {
+            enable_color(cli.no_color);
+            report_timings(args.repetitions);
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "tui")]
+        Some(Command::Tui) => {
+            let platform = Platform::detect();
+            tui::run(
+                cli.selection(),
+                &cli.registry_subkey,
+                platform,
+                REDACTOR.get(),
+                |selection| {
+                    let mut report = Report::collect_from(
+                        selection,
+                        &cli.registry_subkey,
+                        None,
+                        cli.registry_backend.into(),
+                        cli.timeout(),
+                    )?;
+                    cli.filter(&mut report, platform);
+                    Ok(report)
+                },
+            )?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Open { directory }) => {
+            let path = match resolve_directory(*directory) {
+                Ok(path) => path,
+                Err(reason) => {
+                    eprintln!("{reason}");
+                    return Ok(ExitCode::from(AnomalyKind::Missing.exit_code()));
+                }
+            };
+            println!("Opening {}", path_text(path.as_os_str()));
+            open_in_explorer(&path)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::History(HistoryCommand::List)) => {
+            report_history()?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::History(HistoryCommand::Compare { old, new })) => {
+            enable_color(cli.no_color);
+            compare_history(old.as_deref(), new.as_deref())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {}
+    }
+
+    if let Some(Experiment::Inheritance) = cli.experiment {
+        report_inheritance_experiment(Platform::detect())?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // While watching, interrupting is how the program is meant to stop, so it is left to do that.
+    if !cli.watch {
+        install_interrupt_handler();
+    }
+
+    // With JSON lines, each source is written as soon as it is looked up, where that is possible.
+    let mut streamed = false;
+    let mut stream_error = None;
+    let mut observe = |part: &Report, duration: Duration| {
+        if cli.format() != Format::Jsonl || stream_error.is_some() {
+            return;
+        }
+        streamed = true;
+        let mut part = part.clone();
+        redact(&mut part);
+        cli.filter(&mut part, Platform::detect());
+        if part.sections().is_empty() {
+            return;
+        }
+        match render::json_lines(&part, Some(duration)) {
+            Ok(lines) => print!("{lines}"),
+            Err(e) => stream_error = Some(e),
+        }
+    };
+
+    let computer = cli.computer.as_deref();
+    let identity = cli.identity();
+    let (platform, mut report) = match (&cli.hive, computer) {
+        _ if identity != Identity::Current => (Platform::detect(), Report::collect_as(&identity)?),
+        _ if cli.all_users => (Platform::detect(), Report::collect_all_users()?),
+        (Some(hive), _) => {
+            let platform = Platform {
+                process: Machine::of_this_build(),
+                native: Machine::Unknown,
+            };
+            (platform, Report::collect_offline(hive)?)
+        }
+        (None, Some(name)) => {
+            let platform = Platform {
+                process: Machine::of_this_build(),
+                native: remote_machine(name).unwrap_or(Machine::Unknown),
+            };
+            let report = Report::collect_observed(
+                cli.selection(),
+                &cli.registry_subkey,
+                computer,
+                cli.registry_backend.into(),
+                cli.timeout(),
+                &mut observe,
+            )?;
+            (platform, report)
+        }
+        (None, None) => {
+            let report = Report::collect_observed(
+                cli.selection(),
+                &cli.registry_subkey,
+                None,
+                cli.registry_backend.into(),
+                cli.timeout(),
+                &mut observe,
+            )?;
+            (Platform::detect(), report)
+        }
+    };
+    if let Some(e) = stream_error {
+        return Err(e);
+    }
+    cli.filter(&mut report, platform);
+    report.sort(cli.sort.into());
+
+    // Paths are checked on disk, and copied, as they really are, and only shown with user names
+    // masked.
+    let checked = report.clone();
+    redact(&mut report);
+
+    match &cli.save {
+        Some(Some(path)) => {
+            save(&report, path)?;
+        }
+        Some(None) => {
+            let path = save_to_history(&report)?;
+            eprintln!("Saved snapshot {}", path.display());
+        }
+        None => {}
+    }
+
+    match cli.format() {
+        Format::Text if cli.quiet => {
+            enable_color(cli.no_color);
+            let style = style();
+            print!("{}", render::resolved(&report, platform, &style));
+            print!("{}", render::summary(&report, platform, &style));
+        }
+        Format::Text if cli.pivot => {
+            enable_color(cli.no_color);
+            let style = style();
+            print!(
+                "{}",
+                render::architecture(ProcessArchitecture::detect(), Elevation::detect())
+            );
+            print!("{}", render::summary(&report, platform, &style));
+            if report.truncated {
+                println!("{}", paint(TRUNCATED_MARKER, Color::Red));
+                println!();
+            }
+            print!("{}", render::findings(&findings(&report, platform), &style));
+        }
+        Format::Text => {
+            enable_color(cli.no_color);
+            let style = style();
+            let architecture = ProcessArchitecture::detect();
+            print!(
+                "{}",
+                render::architecture(architecture, Elevation::detect())
+            );
+            print!("{}", render::user_profiles(&report, &style));
+            print!("{}", render::resolved(&report, platform, &style));
+            if cli.verify_unredirected && !architecture.is_wow64() {
+                println!("{}", paint(NO_REDIRECTION_NOTE, Color::Yellow));
+                println!();
+            }
+            print_report(
+                &report,
+                &checked,
+                platform,
+                cli.explain,
+                cli.verify_fs,
+                cli.verify_unredirected && architecture.is_wow64(),
+                cli.short_names,
+                cli.compact,
+            );
+            if report.truncated {
+                println!("{}", paint(TRUNCATED_MARKER, Color::Red));
+                println!();
+            }
+            if computer.is_none() && cli.hive.is_none() {
+                print!(
+                    "{}",
+                    render::relocations(&relocations(&checked, platform), &style)
+                );
+                print!("{}", render::aliases(&aliases(&checked, platform), &style));
+            }
+            print!("{}", render::virtualization_signs(&report, &style));
+            print!("{}", render::package_effects(&report, &style));
+            print!("{}", render::policy_signs(&report, &style));
+            if computer.is_none() && cli.hive.is_none() && identity == Identity::Current {
+                report_service_diagnosis(&report, cli.retry_default_profile);
+            }
+            if cli.volumes {
+                report_volumes(&checked, platform);
+            }
+            if cli.security {
+                report_security(&checked, platform);
+            }
+            if cli.probe_write {
+                report_write_probes(&checked, platform);
+            }
+            if cli.redirection {
+                report_known_folder_redirection();
+            }
+            if cli.kf_flags {
+                report_known_folder_flag_variations();
+            }
+            if cli.id_lists {
+                report_known_folder_id_lists();
+            }
+            if cli.timing {
+                report_timings(1);
+            }
+            if cli.summary {
+                print!("{}", render::summary(&report, platform, &style));
+            }
+            print!("{}", render::findings(&findings(&report, platform), &style));
+        }
+        Format::Json => print!("{}", render::json(&report, &findings(&report, platform))?),
+        Format::Yaml => print!("{}", render::yaml(&report, &findings(&report, platform))?),
+        Format::Toml => print!("{}", render::toml(&report, &findings(&report, platform))?),
+        Format::Markdown => {
+            print_markdown(&report, platform, computer, cli.hive.is_some(), cli.pivot)
+        }
+        Format::Html => print_html(&report, platform, computer, cli.hive.is_some()),
+        Format::Csv | Format::Tsv if cli.pivot => {
+            print!(
+                "{}",
+                render::pivot_delimited(&report, platform, cli.format().separator())
+            );
+        }
+        Format::Csv | Format::Tsv => {
+            print!("{}", render::delimited(&report, cli.format().separator()));
+            if report.truncated {
+                eprintln!("{TRUNCATED_MARKER}");
+            }
+        }
+        Format::Jsonl => {
+            if !streamed {
+                print!("{}", render::json_lines(&report, None)?);
+            }
+            if report.truncated {
+                eprintln!("{TRUNCATED_MARKER}");
+            }
+        }
+    }
+
+    let copied = match &cli.copy {
+        Some(name) => copy_path(&checked, platform, name)?,
+        None => true,
+    };
+
+    let mut worst = report_anomalies(&report, platform);
+    if !report_missing_requirements(&report, &cli.require) || !copied {
+        worst = worst.max(Some(AnomalyKind::Missing));
+    }
+
+    let mut expectations = cli.expect.clone();
+    if let Some(path) = &cli.expect_file {
+        expectations.extend(load_expectations(path)?);
+    }
+    if !report_expectations(&checked, &expectations) {
+        worst = worst.max(Some(AnomalyKind::Mismatch));
+    }
+
+    if cli.eventlog {
+        log_event(&report, platform, worst);
+    }
+
+    if cli.watch {
+        watch(&cli, report.clone())?;
+    }
+
+    Ok(worst.map_or(ExitCode::SUCCESS, |kind| ExitCode::from(kind.exit_code())))
+}
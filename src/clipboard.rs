@@ -0,0 +1,47 @@
+//! Putting paths on the clipboard, so they can be pasted into installer configuration and the like.
+
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+
+use windows::Win32::Foundation::{GlobalFree, HANDLE, HWND};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+use windows::Win32::System::Ole::CF_UNICODETEXT;
+
+use crate::error::Error;
+
+/// Puts a path on the clipboard as [`CF_UNICODETEXT`][cf].
+///
+/// The path's UTF-16 code units are copied as they are, so a path that is not valid Unicode is
+/// not changed. They are copied into global memory, which the clipboard owns once it is set.
+///
+/// [cf]: https://learn.microsoft.com/en-us/windows/win32/dataxchg/standard-clipboard-formats
+pub fn copy_to_clipboard(path: &OsStr) -> Result<(), Error> {
+    let wide: Vec<u16> = path.encode_wide().chain([0]).collect();
+
+    unsafe {
+        OpenClipboard(HWND::default()).map_err(Error::Clipboard)?;
+        let result = EmptyClipboard().and_then(|()| {
+            let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * size_of::<u16>())?;
+            let destination = GlobalLock(memory).cast::<u16>();
+            if destination.is_null() {
+                let e = windows::core::Error::from_win32();
+                let _ = GlobalFree(memory);
+                return Err(e);
+            }
+            ptr::copy_nonoverlapping(wide.as_ptr(), destination, wide.len());
+            let _ = GlobalUnlock(memory);
+
+            if let Err(e) = SetClipboardData(CF_UNICODETEXT.0.into(), HANDLE(memory.0)) {
+                let _ = GlobalFree(memory);
+                return Err(e);
+            }
+            Ok(())
+        });
+        let _ = CloseClipboard();
+        result.map_err(Error::Clipboard)
+    }
+}
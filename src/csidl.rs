@@ -0,0 +1,262 @@
+//! Looking up program files folder locations with CSIDLs.
+
+#[cfg(windows)]
+use std::ffi::OsString;
+#[cfg(windows)]
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+#[cfg(windows)]
+use windows::Win32::Foundation::MAX_PATH;
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{
+    SHGetFolderLocation, SHGetFolderPathW, KF_FLAG_DEFAULT, KF_FLAG_DEFAULT_PATH, SHGFP_TYPE,
+    SHGFP_TYPE_CURRENT, SHGFP_TYPE_DEFAULT,
+};
+use windows_core::GUID;
+
+#[cfg(windows)]
+use crate::error::Error;
+#[cfg(windows)]
+use crate::known_folder::get_known_folder_path_or_detailed_error;
+use crate::known_folder::{FOLDERID_ProgramFiles, FOLDERID_ProgramFilesX86};
+#[cfg(windows)]
+use crate::report::{escape_non_unicode, CsidlEntry, Lookup, Section};
+#[cfg(windows)]
+use crate::shell_moniker::path_from_id_list;
+#[cfg(windows)]
+use crate::timing::traced;
+#[cfg(windows)]
+use crate::token::Token;
+
+/// Helper that calls `SHGetFolderPathW()` on behalf of `report_csidl()`.
+///
+/// The `path_type` says whether to get the folder's current path or its default path.
+#[cfg(windows)]
+pub(crate) fn try_get_path_from_csidl(
+    csidl: u32,
+    path_type: SHGFP_TYPE,
+) -> Result<OsString, Error> {
+    let mut buffer = [0u16; MAX_PATH as usize];
+
+    traced(
+        "SHGetFolderPathW",
+        format_args!("{csidl:#x}, {}", path_type.0),
+        || unsafe {
+            SHGetFolderPathW(None, csidl as i32, None, path_type.0 as u32, &mut buffer)
+                .map_err(Error::Csidl)?;
+
+            let length = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+            Ok(OsString::from_wide(&buffer[..length]))
+        },
+    )
+}
+
+/// Helper that calls `SHGetFolderLocation()` on behalf of `report_csidl_locations()`.
+#[cfg(windows)]
+fn try_get_path_from_csidl_location(csidl: u32) -> Result<OsString, Error> {
+    let pidl = traced(
+        "SHGetFolderLocation",
+        format_args!("{csidl:#x}"),
+        || unsafe { SHGetFolderLocation(None, csidl as i32, None, 0) },
+    )
+    .map_err(Error::Csidl)?;
+
+    path_from_id_list(pidl)
+}
+
+/// Describes the result of looking up a path, as the path found or why there was none, for a
+/// discrepancy.
+#[cfg(windows)]
+fn describe(result: &Result<OsString, Error>) -> String {
+    match result {
+        Ok(path) => escape_non_unicode(path),
+        Err(e) => format!("an error ({e})"),
+    }
+}
+
+/// Looks up a CSIDL with `SHGetFolderPathW()`, checking that the path was not too long for it.
+///
+/// `SHGetFolderPathW()` writes to a buffer of `MAX_PATH` characters, including the terminating
+/// null, so it cannot give a path any longer than that, even though the folder may have one, such
+/// as when it is redirected to a deeply nested location. So the corresponding known folder is also
+/// looked up, with `KF_FLAG_DEFAULT` for the current path or `KF_FLAG_DEFAULT_PATH` for the default
+/// path, and if its path does not fit, the CSIDL's result cannot be right, whether it failed or
+/// gave a truncated path. Then the error is an [`Error::Discrepancy`] giving both results, with
+/// their lengths, rather than the capped value. If the known folder cannot be looked up, or its
+/// path fits, the CSIDL's own result is given.
+#[cfg(windows)]
+fn get_path_from_csidl_checking_length(
+    csidl: u32,
+    id: GUID,
+    path_type: SHGFP_TYPE,
+) -> Result<OsString, Error> {
+    let describe_with_length = |result: &Result<OsString, Error>| match result {
+        Ok(path) => format!(
+            "{} ({} characters)",
+            escape_non_unicode(path),
+            path.encode_wide().count()
+        ),
+        Err(_) => describe(result),
+    };
+
+    let by_csidl = try_get_path_from_csidl(csidl, path_type);
+
+    let flags = if path_type == SHGFP_TYPE_DEFAULT {
+        KF_FLAG_DEFAULT_PATH
+    } else {
+        KF_FLAG_DEFAULT
+    };
+    let Ok(by_known_folder) = get_known_folder_path_or_detailed_error(id, flags, &Token::CURRENT)
+    else {
+        return by_csidl;
+    };
+
+    if by_known_folder.encode_wide().count() < MAX_PATH as usize {
+        return by_csidl;
+    }
+
+    Err(Error::Discrepancy {
+        first: (
+            "SHGetKnownFolderPath",
+            describe_with_length(&Ok(by_known_folder)),
+        ),
+        second: ("SHGetFolderPathW", describe_with_length(&by_csidl)),
+    })
+}
+
+/// Looks up a CSIDL through `SHGetFolderLocation()`, checking it against `SHGetFolderPathW()`.
+///
+/// If both fail, the error from `SHGetFolderLocation()` is given. Otherwise, if they do not find
+/// the same path, the error is an [`Error::Discrepancy`] giving both results.
+#[cfg(windows)]
+fn cross_check_csidl_location(csidl: u32) -> Result<OsString, Error> {
+    let by_location = try_get_path_from_csidl_location(csidl);
+    let by_path = try_get_path_from_csidl(csidl, SHGFP_TYPE_CURRENT);
+
+    match (&by_location, &by_path) {
+        (Ok(location_path), Ok(path)) if location_path == path => by_location,
+        (Err(_), Err(_)) => by_location,
+        _ => Err(Error::Discrepancy {
+            first: ("SHGetFolderPathW", describe(&by_path)),
+            second: ("SHGetFolderLocation", describe(&by_location)),
+        }),
+    }
+}
+
+/// The `CSIDL_PROGRAM_FILES` CSIDL, with the same value as in the `windows` crate, as described on
+/// `FOLDERID_ProgramFiles`.
+pub const CSIDL_PROGRAM_FILES: u32 = 0x26;
+
+/// The `CSIDL_PROGRAM_FILESX86` CSIDL, with the same value as in the `windows` crate.
+pub const CSIDL_PROGRAM_FILESX86: u32 = 0x2a;
+
+/// The *program files* CSIDLs, with their symbolic names and the corresponding known folders.
+pub const PROGRAM_FILES_CSIDLS: [(&str, u32, GUID); 2] = [
+    (
+        "CSIDL_PROGRAM_FILES",
+        CSIDL_PROGRAM_FILES,
+        FOLDERID_ProgramFiles,
+    ),
+    (
+        "CSIDL_PROGRAM_FILESX86",
+        CSIDL_PROGRAM_FILESX86,
+        FOLDERID_ProgramFilesX86,
+    ),
+];
+
+/// Report *program files* folder locations via lookups using CSIDLs.
+///
+/// This calls the deprecated [`SHGetFolderPathW`][shgfpw] function.
+///
+/// This is the older way, before the *known folders* facilities were introduced. See [CSIDL].
+///
+/// As noted there, it is recommended to use the known folders APIs instead of CSIDLs, and each
+/// CSIDL value has a corresponding `KNOWNFOLDERID` value. In contrast, not all known folders have
+/// a CSIDL, and also, unlike with CSIDLs, it is possible to register new known folders
+/// programmatically.
+///
+/// From the [remarks section][csidl-remarks] of that article:
+///
+/// > These values supersede the use of environment variables for this purpose. They are in turn
+/// > superseded in Windows Vista and later by the [KNOWNFOLDERID] values.
+///
+/// (This seems to imply, by transitivity, that getting the paths of known folders is also
+/// preferable to accessing the values of environment variables, when both are applicable.)
+///
+/// One limitation of using CSIDLs is that it cannot properly handle the unusual case that the path
+/// is a `\\?\` long path and exceeds [MAX_PATH] characters. As [commented][dotnet-comment] in the
+/// implementation of the .NET Runtime:
+///
+/// > We're using SHGetKnownFolderPath instead of SHGetFolderPath as SHGetFolderPath is capped at
+/// > MAX_PATH.
+///
+/// [shgfpw]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetfolderpathw
+/// [CSIDL]: https://learn.microsoft.com/en-us/windows/win32/shell/csidl
+/// [csidl-remarks]: https://learn.microsoft.com/en-us/windows/win32/shell/csidl#remarks
+/// [KNOWNFOLDERID]: https://learn.microsoft.com/en-us/windows/win32/shell/knownfolderid
+/// [MAX_PATH]: https://learn.microsoft.com/en-us/windows/win32/fileio/maximum-file-path-limitation
+/// Each CSIDL is looked up twice, with `SHGFP_TYPE_CURRENT` for the folder's current path, which
+/// is the entry's path, and with `SHGFP_TYPE_DEFAULT` for its default path. These differ when the
+/// folder has been redirected, or when its location was otherwise changed from the default, such as
+/// by a user or an administrator customizing it.
+///
+///
+/// So that a path capped at `MAX_PATH` is not silently shown, each lookup is also compared with
+/// the corresponding known folder, as described on `get_path_from_csidl_checking_length()`, and
+/// is a discrepancy if the known folder's path is too long for `SHGetFolderPathW` to give.
+///
+/// [dotnet-comment]: https://github.com/dotnet/runtime/blob/v8.0.7/src/libraries/System.Private.CoreLib/src/System/Environment.Win32.cs#L210-L211
+#[cfg(windows)]
+pub fn report_csidl() -> Result<Section<CsidlEntry>, Error> {
+    let entries = PROGRAM_FILES_CSIDLS
+        .into_iter()
+        .map(|(symbol, csidl, id)| CsidlEntry {
+            name: symbol.to_owned(),
+            csidl,
+            lookup: Lookup::new(get_path_from_csidl_checking_length(
+                csidl,
+                id,
+                SHGFP_TYPE_CURRENT,
+            )),
+            default: Some(Lookup::new(get_path_from_csidl_checking_length(
+                csidl,
+                id,
+                SHGFP_TYPE_DEFAULT,
+            ))),
+        })
+        .collect();
+
+    Ok(Section::new("Relevant CSIDLs", entries))
+}
+
+/// Report *program files* folder locations via CSIDLs, the other way old software looks them up.
+///
+/// Instead of asking for a path, as `report_csidl()` does, this calls the also deprecated
+/// [`SHGetFolderLocation`][shgfl] function, which gives the folder's item ID list, and then gets
+/// the path from that, as described on `path_from_id_list()`. Old software that works with the
+/// Shell namespace, such as to show a folder in a browse dialog, tends to do it this way. The
+/// result is subject to the same `MAX_PATH` limit.
+///
+/// Both ways are expected to agree, since both look up the same known folder. To fully
+/// characterize how old applications might resolve these directories, each entry is checked
+/// against `SHGetFolderPathW`, and is an [`Error::Discrepancy`] if they differ. So this section
+/// has the same entries as `report_csidl()` whenever nothing is amiss.
+///
+/// [shgfl]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetfolderlocation
+#[cfg(windows)]
+pub fn report_csidl_locations() -> Result<Section<CsidlEntry>, Error> {
+    let entries = PROGRAM_FILES_CSIDLS
+        .into_iter()
+        .map(|(symbol, csidl, _)| CsidlEntry {
+            name: symbol.to_owned(),
+            csidl,
+            lookup: Lookup::new(cross_check_csidl_location(csidl)),
+            default: None,
+        })
+        .collect();
+
+    Ok(Section::new(
+        "Relevant CSIDLs - through SHGetFolderLocation",
+        entries,
+    ))
+}
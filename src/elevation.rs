@@ -0,0 +1,240 @@
+//! Detecting whether this process is elevated, its integrity level, and whether UAC is on.
+
+use std::ffi::c_void;
+use std::io;
+
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Security::{
+    GetSidSubAuthority, GetSidSubAuthorityCount, GetTokenInformation, TokenElevation,
+    TokenElevationType, TokenElevationTypeDefault, TokenElevationTypeFull,
+    TokenElevationTypeLimited, TokenIntegrityLevel, TOKEN_ELEVATION, TOKEN_ELEVATION_TYPE,
+    TOKEN_INFORMATION_CLASS, TOKEN_MANDATORY_LABEL, TOKEN_QUERY,
+};
+use windows::Win32::System::SystemServices::{
+    SECURITY_MANDATORY_HIGH_RID, SECURITY_MANDATORY_LOW_RID, SECURITY_MANDATORY_MEDIUM_PLUS_RID,
+    SECURITY_MANDATORY_MEDIUM_RID, SECURITY_MANDATORY_PROTECTED_PROCESS_RID,
+    SECURITY_MANDATORY_SYSTEM_RID,
+};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_QUERY_VALUE};
+use winreg::RegKey;
+
+/// The registry key, under `HKEY_LOCAL_MACHINE`, holding the UAC policy values.
+const POLICIES_SYSTEM_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System";
+
+/// How a token was made with respect to UAC, as given by `TokenElevationType`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElevationType {
+    /// The token is not one of a linked pair, because UAC is off or the user is not an
+    /// administrator, or is the built-in Administrator account, which UAC does not filter.
+    Default,
+
+    /// The full token of an administrator, as when a program is run as administrator.
+    Full,
+
+    /// The filtered token of an administrator, as when a program is run normally.
+    Limited,
+}
+
+impl ElevationType {
+    /// Gives the elevation type for a value of `TOKEN_ELEVATION_TYPE`, or `None` if it is not one
+    /// that Windows documents.
+    fn from_token_elevation_type(elevation_type: TOKEN_ELEVATION_TYPE) -> Option<Self> {
+        [
+            (TokenElevationTypeDefault, Self::Default),
+            (TokenElevationTypeFull, Self::Full),
+            (TokenElevationTypeLimited, Self::Limited),
+        ]
+        .into_iter()
+        .find_map(|(value, this)| (value == elevation_type).then_some(this))
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Default => "default (not a split token)",
+            Self::Full => "full (elevated administrator)",
+            Self::Limited => "limited (filtered administrator)",
+        }
+    }
+}
+
+/// The mandatory integrity level of a token, as given by the relative identifier of its label.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IntegrityLevel {
+    Untrusted,
+    Low,
+    Medium,
+    MediumPlus,
+    High,
+    System,
+    Protected,
+}
+
+impl IntegrityLevel {
+    /// Gives the integrity level whose range a relative identifier falls in.
+    ///
+    /// Windows compares integrity levels by their relative identifiers, so a value between two of
+    /// the standard ones, which Windows does not assign but a token could have, is treated as the
+    /// lower of them.
+    fn from_rid(rid: u32) -> Self {
+        let at_least = |level: i32| rid >= level as u32;
+
+        if at_least(SECURITY_MANDATORY_PROTECTED_PROCESS_RID) {
+            Self::Protected
+        } else if at_least(SECURITY_MANDATORY_SYSTEM_RID) {
+            Self::System
+        } else if at_least(SECURITY_MANDATORY_HIGH_RID) {
+            Self::High
+        } else if rid >= SECURITY_MANDATORY_MEDIUM_PLUS_RID {
+            Self::MediumPlus
+        } else if at_least(SECURITY_MANDATORY_MEDIUM_RID) {
+            Self::Medium
+        } else if at_least(SECURITY_MANDATORY_LOW_RID) {
+            Self::Low
+        } else {
+            Self::Untrusted
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Untrusted => "untrusted",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::MediumPlus => "medium plus",
+            Self::High => "high",
+            Self::System => "system",
+            Self::Protected => "protected process",
+        }
+    }
+}
+
+/// The security context of this process with respect to UAC, for the header of the output.
+///
+/// Elevated and non-elevated runs can legitimately give different results. For example, an
+/// administrator's elevated process sees that administrator's per-user folders, which differ from
+/// those of a standard user who supplied the administrator's credentials to elevate. So reports
+/// cannot be compared without knowing this.
+#[derive(Clone, Copy, Debug)]
+pub struct Elevation {
+    /// Whether this process's token is elevated, from `TokenElevation`, or `None` if it could not
+    /// be queried.
+    pub elevated: Option<bool>,
+
+    /// How this process's token was made, from `TokenElevationType`, or `None` if it could not be
+    /// queried.
+    pub elevation_type: Option<ElevationType>,
+
+    /// The integrity level of this process's token, from `TokenIntegrityLevel`, or `None` if it
+    /// could not be queried.
+    pub integrity_level: Option<IntegrityLevel>,
+
+    /// Whether UAC is on, from the `EnableLUA` policy value, or `None` if it could not be read.
+    pub uac_enabled: Option<bool>,
+}
+
+/// Calls `GetTokenInformation` on a token for a class of information that fits in `T`.
+///
+/// # Safety
+///
+/// `T` must be the type Windows gives for `class`, or, for information that is variable-length,
+/// large and aligned enough to hold its fixed part, which is all that is read here.
+unsafe fn token_information<T: Default>(
+    token: HANDLE,
+    class: TOKEN_INFORMATION_CLASS,
+) -> Option<T> {
+    let mut information = T::default();
+    let mut length = 0;
+    unsafe {
+        GetTokenInformation(
+            token,
+            class,
+            Some((&mut information as *mut T).cast::<c_void>()),
+            size_of::<T>() as u32,
+            &mut length,
+        )
+    }
+    .ok()?;
+    Some(information)
+}
+
+/// Finds the integrity level of a token, from the last subauthority of its mandatory label's SID.
+fn integrity_level(token: HANDLE) -> Option<IntegrityLevel> {
+    let mut length = 0;
+    let _ = unsafe { GetTokenInformation(token, TokenIntegrityLevel, None, 0, &mut length) };
+
+    // Use u64 elements so the buffer is aligned enough to hold a TOKEN_MANDATORY_LABEL.
+    let mut buffer = vec![0_u64; (length as usize).div_ceil(8)];
+    unsafe {
+        GetTokenInformation(
+            token,
+            TokenIntegrityLevel,
+            Some(buffer.as_mut_ptr().cast()),
+            length,
+            &mut length,
+        )
+    }
+    .ok()?;
+    let sid = unsafe { &*buffer.as_ptr().cast::<TOKEN_MANDATORY_LABEL>() }
+        .Label
+        .Sid;
+
+    let rid = unsafe {
+        let count = *GetSidSubAuthorityCount(sid);
+        *GetSidSubAuthority(sid, u32::from(count.checked_sub(1)?))
+    };
+    Some(IntegrityLevel::from_rid(rid))
+}
+
+/// Reads whether UAC is on, from the `EnableLUA` policy value, which is on if absent.
+fn uac_enabled() -> Option<bool> {
+    let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey_with_flags(POLICIES_SYSTEM_SUBKEY, KEY_QUERY_VALUE)
+        .ok()?;
+    match key.get_value::<u32, _>("EnableLUA") {
+        Ok(value) => Some(value != 0),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Some(true),
+        Err(_) => None,
+    }
+}
+
+impl Elevation {
+    /// Detects the elevation and integrity level of this process, and whether UAC is on.
+    ///
+    /// The token is queried with [`GetTokenInformation`][gti] for `TokenElevation`,
+    /// `TokenElevationType`, and `TokenIntegrityLevel`. Whether UAC is on is read from the
+    /// [`EnableLUA`][lua] policy value in `POLICIES_SYSTEM_SUBKEY` of `HKEY_LOCAL_MACHINE`, which
+    /// takes effect at the next restart, so it may not yet describe the running system.
+    ///
+    /// [gti]: https://learn.microsoft.com/en-us/windows/win32/api/securitybaseapi/nf-securitybaseapi-gettokeninformation
+    /// [lua]: https://learn.microsoft.com/en-us/windows/security/application-security/application-control/user-account-control/settings-and-configuration
+    pub fn detect() -> Self {
+        let mut token = HANDLE::default();
+        let opened = unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) };
+
+        let (elevated, elevation_type, integrity_level) = match opened {
+            Ok(()) => {
+                let elevation: Option<TOKEN_ELEVATION> =
+                    unsafe { token_information(token, TokenElevation) };
+                let elevation_type: Option<TOKEN_ELEVATION_TYPE> =
+                    unsafe { token_information(token, TokenElevationType) };
+                let integrity_level = integrity_level(token);
+                let _ = unsafe { CloseHandle(token) };
+
+                (
+                    elevation.map(|elevation| elevation.TokenIsElevated != 0),
+                    elevation_type.and_then(ElevationType::from_token_elevation_type),
+                    integrity_level,
+                )
+            }
+            Err(_) => (None, None, None),
+        };
+
+        Self {
+            elevated,
+            elevation_type,
+            integrity_level,
+            uac_enabled: uac_enabled(),
+        }
+    }
+}
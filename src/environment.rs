@@ -0,0 +1,315 @@
+//! Looking up program files folder locations in environment variables.
+
+#[cfg(windows)]
+use core::ffi::c_void;
+use std::env::VarError;
+#[cfg(windows)]
+use std::io;
+
+#[cfg(windows)]
+use windows::core::PCWSTR;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+#[cfg(windows)]
+use windows::Win32::Security::{TOKEN_DUPLICATE, TOKEN_QUERY};
+#[cfg(windows)]
+use windows::Win32::System::Environment::{CreateEnvironmentBlock, DestroyEnvironmentBlock};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+#[cfg(windows)]
+use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_QUERY_VALUE};
+#[cfg(windows)]
+use winreg::{RegKey, HKEY};
+
+#[cfg(windows)]
+use crate::error::Error;
+use crate::os::OsFacade;
+#[cfg(windows)]
+use crate::os::RealOs;
+#[cfg(windows)]
+use crate::registry::read_string_value;
+use crate::report::{EnvVarEntry, Lookup, Section};
+#[cfg(windows)]
+use crate::report::{EnvironmentKey, FreshEnvVarEntry, RegistryEntry};
+
+/// The environment variables that may hold *program files* folder locations.
+pub const PROGRAM_FILES_ENVIRONMENT_VARIABLES: [&str; 4] = [
+    "ProgramFiles",
+    "ProgramFiles(Arm)",
+    "ProgramFiles(x86)",
+    "ProgramW6432",
+];
+
+/// Report *program files* folder locations contained in environment variables.
+///
+/// Environment variables are convenient, but less reliable than known folders, and probably less
+/// reliable than the other methods. Everything is fine so long as no ancestor process has removed
+/// program files related variables from its environment or created its child with a custom
+/// environment that omits them. If they are all omitted, such as if the parent process passed down
+/// an empty environment, then this will obviously fail. But the more subtle case is where some but
+/// not all of them are passed down. It is easy for a parent process to get it wrong. Key points:
+///
+/// 1. On a 32-bit x86 Windows system, there is exactly one program files directory, and the
+///    `ProgramFiles` environment variable should have its path. The other environment variables
+///    are not typically set on a 32-bit Windows system. (Currently there is no Rust target for
+///    32-bit ARM systems, so a Rust program is very unlikely to run on one.)
+///
+/// 2. On a 64-bit Windows system, including ARM64, there are at least two program files
+///    directories. An x86-64 (AMD64) system has two, and an ARM64 (AArch64) system has three.
+///    Processes thus inherit the `ProgramFiles` environment variable, as well as two or three
+///    others that indicate program files directories associated with particular architectures.
+///
+/// 3. On 64-bit Windows, the `ProgramFiles` environment variable is inherited by the child process
+///    to hold the path of the program files directory associated with the architecture of that
+///    child process. But how can this be? After all, the parent may be a different architecture,
+///    and environment variables (or most of them, including these) are inherited from the parent.
+///
+/// 4. On 64-bit Windows, a child inherits `ProgramFiles` from its parent, but it does not usually
+///    inherit it from its parent's `Program Files` variable. Instead, and regardless of the
+///    "bitness" of the parent process, a 64-bit child process receives `ProgramFiles` from the
+///    value the parent passed down as `ProgramW6432` (whether that child is x86-64 or ARM64, since
+///    both 64-bit architectures use the same program files directory), a 32-bit x86 child process
+///    receives `ProgramFiles` from the value the parent passed down as `ProgramFiles(x86)`, and a
+///    32-bit ARM child process receives `ProgramFiles` from the value the parent passed down as
+///    `ProgramFiles(ARM)`.
+///
+/// 5. On 64-bit Windows, only if the environment variable corresponding to the child process's
+///    architecture was not passed down does the child receive `ProgramFiles` from the value the
+///    parent passed down as `ProgramFiles`. While this is the normal situation on a 32-bit system,
+///    it is a fallback situation on a 64-bit system and unreliable, because if the parent and
+///    child architectures differ and the parent overly sanitizes the environment for the child,
+///    then code in the child that needs a program files directory of the same architecture as the
+///    child will malfunction, and code in the child that seeks to discover all program files
+///    directories will fail if it (solely) makes use of environment variables to do so.
+///
+/// Some of this behavior is documented in [WOW64 Implementation Details][wow64ev].
+///
+/// [wow64ev]: https://learn.microsoft.com/en-us/windows/win32/winprog64/wow64-implementation-details#environment-variables
+#[cfg(windows)]
+pub fn report_environment_variables() -> Section<EnvVarEntry> {
+    environment_variables_from(&RealOs)
+}
+
+/// Looks up the *program files* environment variables through `os`, as
+/// `report_environment_variables()` does for this process, and as `Report::collect_with()` does
+/// for a simulated system.
+pub(crate) fn environment_variables_from(os: &impl OsFacade) -> Section<EnvVarEntry> {
+    let entries = PROGRAM_FILES_ENVIRONMENT_VARIABLES
+        .into_iter()
+        .map(|name| EnvVarEntry {
+            name: name.to_owned(),
+            lookup: Lookup::new(os.env_var(name).ok_or(VarError::NotPresent)),
+        })
+        .collect();
+
+    Section::new("Relevant environment variables", entries)
+}
+
+/// The registry keys from which Windows builds a new environment block, each with a caption, its
+/// hive, and its path, in increasing order of precedence.
+///
+/// System variables come first. A user's variables override them, and the user's volatile
+/// variables, which are set for each logon session, override both.
+#[cfg(windows)]
+pub const ENVIRONMENT_REGISTRY_KEYS: [(&str, HKEY, &str); 3] = [
+    (
+        r"HKLM\...\Session Manager\Environment",
+        HKEY_LOCAL_MACHINE,
+        r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment",
+    ),
+    (r"HKCU\Environment", HKEY_CURRENT_USER, "Environment"),
+    (
+        r"HKCU\Volatile Environment",
+        HKEY_CURRENT_USER,
+        "Volatile Environment",
+    ),
+];
+
+/// Report the values, if any, stored for the *program files* environment variables in one of the
+/// registry keys from which new environments are built.
+///
+/// Each value is read as described on `read_string_value()`, so that its type, raw data, and
+/// expansion are shown. If the key cannot be opened, every entry has that error.
+#[cfg(windows)]
+pub fn report_environment_registry_key(
+    caption: &'static str,
+    hive: HKEY,
+    path: &str,
+) -> EnvironmentKey {
+    let key = RegKey::predef(hive).open_subkey_with_flags(path, KEY_QUERY_VALUE);
+    environment_key(
+        caption,
+        format!("Stored environment variables - {caption}"),
+        key,
+    )
+}
+
+/// Reads the *program files* environment variables from a key that has been opened, or gives the
+/// error opening it for each, as described on `report_environment_registry_key()`.
+#[cfg(windows)]
+pub(crate) fn environment_key(
+    caption: &'static str,
+    heading: String,
+    key: io::Result<RegKey>,
+) -> EnvironmentKey {
+    let entries = match key {
+        Ok(key) => PROGRAM_FILES_ENVIRONMENT_VARIABLES
+            .into_iter()
+            .map(|name| {
+                let (value_type, raw, lookup) = read_string_value(&key, name);
+                RegistryEntry {
+                    name: name.to_owned(),
+                    value_type,
+                    raw,
+                    lookup,
+                }
+            })
+            .collect(),
+        Err(e) => {
+            let lookup = Lookup::new(Err::<String, _>(e));
+            PROGRAM_FILES_ENVIRONMENT_VARIABLES
+                .into_iter()
+                .map(|name| RegistryEntry {
+                    name: name.to_owned(),
+                    value_type: None,
+                    raw: None,
+                    lookup: lookup.clone(),
+                })
+                .collect()
+        }
+    };
+
+    EnvironmentKey {
+        key: caption,
+        values: Section::new(heading, entries),
+    }
+}
+
+/// Report where, if anywhere, the *program files* environment variables are stored in the
+/// registry.
+///
+/// A new environment block, as made by `CreateEnvironmentBlock` for a new logon or by Explorer
+/// when it refreshes its environment, is built from the keys in `ENVIRONMENT_REGISTRY_KEYS`.
+/// However, Windows does not normally store the program files variables there. It computes them
+/// from the `ProgramFilesDir` registry values, in the way described on
+/// `report_environment_variables()`, as part of building the block. So the usual result is that
+/// none of these values is found. A value that is found overrides what Windows computes, which is
+/// a common cause of a wrong program files variable that survives reboots.
+///
+/// Comparing these with the variables this process inherited, as `environment_origins()` does,
+/// shows whether each variable came from the registry, was computed, or was changed by an
+/// ancestor process.
+#[cfg(windows)]
+pub fn report_environment_registry() -> Vec<EnvironmentKey> {
+    ENVIRONMENT_REGISTRY_KEYS
+        .into_iter()
+        .map(|(caption, hive, path)| report_environment_registry_key(caption, hive, path))
+        .collect()
+}
+
+/// Builds a new environment block with [`CreateEnvironmentBlock`][ceb] for the user of `token`, or
+/// from the system environment alone if `token` is null, and gives its variables as names and
+/// values.
+///
+/// Variables that are not valid UTF-16 are skipped.
+///
+/// [ceb]: https://learn.microsoft.com/en-us/windows/win32/api/userenv/nf-userenv-createenvironmentblock
+#[cfg(windows)]
+pub(crate) fn create_environment_block(
+    token: HANDLE,
+) -> Result<Vec<(String, String)>, windows::core::Error> {
+    let mut block: *mut c_void = std::ptr::null_mut();
+    unsafe { CreateEnvironmentBlock(&mut block, token, false) }?;
+
+    let mut variables = Vec::new();
+    let mut cursor = block as *const u16;
+    loop {
+        let variable = unsafe { PCWSTR::from_raw(cursor).to_string() };
+        let length = unsafe { PCWSTR::from_raw(cursor).len() };
+        if length == 0 {
+            break;
+        }
+        // Entries for the current directory of each drive, like `=C:=C:\`, begin with `=`.
+        if let Some((name, value)) = variable.ok().and_then(|v| {
+            let (name, value) = v.get(1..)?.split_once('=')?;
+            Some((format!("{}{name}", &v[..1]), value.to_owned()))
+        }) {
+            variables.push((name, value));
+        }
+        cursor = unsafe { cursor.add(length + 1) };
+    }
+
+    unsafe { DestroyEnvironmentBlock(block) }?;
+    Ok(variables)
+}
+
+/// Builds a fresh environment block for the user this process runs as, as described on
+/// `report_fresh_environment()`.
+#[cfg(windows)]
+fn fresh_environment() -> Result<Vec<(String, String)>, Error> {
+    let mut token = HANDLE::default();
+    unsafe {
+        OpenProcessToken(
+            GetCurrentProcess(),
+            TOKEN_QUERY | TOKEN_DUPLICATE,
+            &mut token,
+        )
+    }
+    .map_err(Error::EnvironmentBlock)?;
+
+    let variables = create_environment_block(token).map_err(Error::EnvironmentBlock);
+    let _ = unsafe { CloseHandle(token) };
+    variables
+}
+
+/// Report *program files* folder locations from a freshly created environment block, compared to
+/// the environment variables this process inherited.
+///
+/// This calls [`CreateEnvironmentBlock`][ceb] with the token of the user this process runs as, to
+/// get the environment a process started for that user right now, such as by Explorer after it
+/// refreshes its environment, would receive. That block is built from the registry keys in
+/// `ENVIRONMENT_REGISTRY_KEYS`, and the program files variables in it are computed by Windows
+/// unless stored there, as described on `report_environment_registry()`.
+///
+/// Each entry's path is the variable's value in the fresh block, and it also carries the value
+/// this process inherited. An entry is *stale* if they differ, including if one is absent and the
+/// other is not. Unlike an inherited variable that merely looks wrong, a stale one is known to
+/// differ from what Windows now gives, because an ancestor process changed or removed it, or
+/// because the environment changed after an ancestor started.
+///
+/// If the block cannot be created, every entry has that error, and none is stale.
+///
+/// [ceb]: https://learn.microsoft.com/en-us/windows/win32/api/userenv/nf-userenv-createenvironmentblock
+#[cfg(windows)]
+pub fn report_fresh_environment() -> Section<FreshEnvVarEntry> {
+    let block = fresh_environment().map_err(|e| Lookup::new(Err::<String, _>(e)));
+
+    let entries = PROGRAM_FILES_ENVIRONMENT_VARIABLES
+        .into_iter()
+        .map(|name| {
+            let inherited = std::env::var(name).ok();
+            match &block {
+                Ok(variables) => {
+                    let fresh = variables
+                        .iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                        .map(|(_, value)| value.clone());
+                    FreshEnvVarEntry {
+                        name: name.to_owned(),
+                        stale: fresh != inherited,
+                        inherited,
+                        lookup: Lookup::new(fresh.ok_or(VarError::NotPresent)),
+                    }
+                }
+                Err(lookup) => FreshEnvVarEntry {
+                    name: name.to_owned(),
+                    stale: false,
+                    inherited,
+                    lookup: lookup.clone(),
+                },
+            }
+        })
+        .collect();
+
+    Section::new("Fresh environment block (CreateEnvironmentBlock)", entries)
+}
@@ -0,0 +1,428 @@
+//! Errors from looking up program files folder locations.
+
+use std::env::VarError;
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
+use std::string::FromUtf16Error;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// An error from looking up or reporting program files folder locations.
+#[derive(Debug)]
+pub enum Error {
+    /// A registry key could not be opened or a value could not be read.
+    Registry(io::Error),
+
+    /// `SHGetKnownFolderPath`, or a method of `IKnownFolderManager` or `IKnownFolder`, failed.
+    KnownFolder(windows_core::Error),
+
+    /// `SHGetFolderPathW` failed.
+    Csidl(windows_core::Error),
+
+    /// A location in the Shell namespace could not be parsed, or had no filesystem path.
+    ShellNamespace(windows_core::Error),
+
+    /// Looking up a source took longer than the given time, so it was not waited for.
+    TimedOut(Duration),
+
+    /// Looking up a source panicked, so it gave no result. The panic message was already shown.
+    Panicked,
+
+    /// COM could not be initialized, or a COM object could not be created.
+    Com(windows_core::Error),
+
+    /// Watching for changes, or refreshing the environment after one, failed.
+    Watch(windows_core::Error),
+
+    /// `CreateEnvironmentBlock` failed, or the token to pass to it could not be opened.
+    EnvironmentBlock(windows_core::Error),
+
+    /// The running processes could not be enumerated, such as to find this process's ancestors.
+    Ancestry(windows_core::Error),
+
+    /// Information about this process's access token could not be queried.
+    Token(windows_core::Error),
+
+    /// No process running as the user with this name could be opened to take a token from.
+    NoSuchUser(String),
+
+    /// This text, given as the ID of a known folder, is not a GUID.
+    InvalidGuid(String),
+
+    /// `ExpandEnvironmentStringsW` failed to expand a `REG_EXPAND_SZ` registry value.
+    Expand(windows_core::Error),
+
+    /// Windows Installer could not open a session, or a property could not be read from one.
+    Installer(windows_core::Error),
+
+    /// The clipboard could not be opened, or text could not be put on it.
+    Clipboard(windows_core::Error),
+
+    /// File Explorer could not be opened on a directory.
+    Explorer(windows_core::Error),
+
+    /// An event could not be written to the event log.
+    EventLog(windows_core::Error),
+
+    /// A child process could not be run, or did not succeed.
+    Child(io::Error),
+
+    /// The terminal could not be set up, read from, or drawn to, as for `pfdirs tui`.
+    Terminal(io::Error),
+
+    /// A report saved to a file, at this path, could not be read or written.
+    SavedReport(PathBuf, io::Error),
+
+    /// Standard output could not be redirected to a temporary file, or the output could not be
+    /// written from it to its destination. The path is of whichever file failed.
+    Output(PathBuf, io::Error),
+
+    /// A baseline file of expected values, at this path, could not be read or parsed, for this
+    /// reason.
+    Baseline(PathBuf, String),
+
+    /// A path returned by a Windows API function was not valid UTF-16.
+    Utf16(FromUtf16Error),
+
+    /// Two ways of looking up the same location gave different results, such as
+    /// `SHGetKnownFolderPath` and the `known-folders` crate for the same known folder. Each way is
+    /// given with its result, described as the path found or why there was none.
+    Discrepancy {
+        first: (&'static str, String),
+        second: (&'static str, String),
+    },
+
+    /// The report could not be serialized as TOML.
+    #[cfg(feature = "serde")]
+    Toml(toml::ser::Error),
+
+    /// The report could not be serialized as JSON.
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+
+    /// The report could not be serialized as YAML.
+    #[cfg(feature = "serde")]
+    Yaml(serde_yaml::Error),
+
+    /// An error that occurred while looking up the entries of a particular section of the report.
+    InSection { section: String, source: Box<Error> },
+}
+
+impl Error {
+    /// Wraps this error to say which section of the report it occurred in.
+    pub fn in_section(self, section: impl Into<String>) -> Self {
+        Self::InSection {
+            section: section.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The error this wraps, if it says which section it occurred in, or otherwise this error.
+    fn innermost(&self) -> &Self {
+        match self {
+            Self::InSection { source, .. } => source.innermost(),
+            _ => self,
+        }
+    }
+
+    /// The numeric error code, if any, as described on `EntryError::code`.
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            Self::Registry(e)
+            | Self::Child(e)
+            | Self::Terminal(e)
+            | Self::SavedReport(_, e)
+            | Self::Output(_, e) => e.raw_os_error().map(Into::into),
+            Self::KnownFolder(e)
+            | Self::Csidl(e)
+            | Self::ShellNamespace(e)
+            | Self::Com(e)
+            | Self::Watch(e)
+            | Self::EnvironmentBlock(e)
+            | Self::Ancestry(e)
+            | Self::Token(e)
+            | Self::Expand(e)
+            | Self::Installer(e)
+            | Self::Clipboard(e)
+            | Self::Explorer(e)
+            | Self::EventLog(e) => Some(e.code().0.into()),
+            Self::TimedOut(_) => Some(ERROR_TIMEOUT),
+            Self::Utf16(_)
+            | Self::Panicked
+            | Self::NoSuchUser(_)
+            | Self::InvalidGuid(_)
+            | Self::Baseline(..)
+            | Self::Discrepancy { .. } => None,
+            #[cfg(feature = "serde")]
+            Self::Toml(_) | Self::Json(_) | Self::Yaml(_) => None,
+            Self::InSection { source, .. } => source.code(),
+        }
+    }
+
+    /// What kind of numeric error code `code()` gives, if it gives one.
+    pub fn code_kind(&self) -> Option<CodeKind> {
+        match self {
+            Self::InSection { source, .. } => source.code_kind(),
+            Self::Registry(_)
+            | Self::Child(_)
+            | Self::Terminal(_)
+            | Self::SavedReport(..)
+            | Self::Output(..)
+            | Self::TimedOut(_) => self.code().map(|_| CodeKind::Win32),
+            _ => self.code().map(|_| CodeKind::Hresult),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Registry(e) | Self::Child(e) | Self::Terminal(e) => write!(f, "{e}"),
+            Self::SavedReport(path, e) | Self::Output(path, e) => {
+                write!(f, "{}: {e}", path.display())
+            }
+            Self::Baseline(path, reason) => write!(f, "{}: {reason}", path.display()),
+            Self::KnownFolder(e)
+            | Self::Csidl(e)
+            | Self::ShellNamespace(e)
+            | Self::Com(e)
+            | Self::Watch(e)
+            | Self::EnvironmentBlock(e)
+            | Self::Ancestry(e)
+            | Self::Token(e)
+            | Self::Expand(e)
+            | Self::Installer(e)
+            | Self::Clipboard(e)
+            | Self::Explorer(e)
+            | Self::EventLog(e) => write!(f, "{e}"),
+            Self::Utf16(e) => write!(f, "{e}"),
+            Self::TimedOut(timeout) => {
+                write!(f, "timed out after {} seconds", timeout.as_secs_f64())
+            }
+            Self::Panicked => write!(f, "looking this up panicked"),
+            Self::NoSuchUser(name) => {
+                write!(
+                    f,
+                    "no process running as {name} could be opened to get a token"
+                )
+            }
+            Self::InvalidGuid(text) => write!(f, "{text} is not a GUID"),
+            Self::Discrepancy {
+                first: (first_way, first_result),
+                second: (second_way, second_result),
+            } => write!(
+                f,
+                "DISCREPANCY: {first_way} gave {first_result}, \
+                but {second_way} gave {second_result}"
+            ),
+            #[cfg(feature = "serde")]
+            Self::Toml(e) => write!(f, "{e}"),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => write!(f, "{e}"),
+            #[cfg(feature = "serde")]
+            Self::Yaml(e) => write!(f, "{e}"),
+            Self::InSection { section, source } => write!(f, "{section}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Registry(e)
+            | Self::Child(e)
+            | Self::Terminal(e)
+            | Self::SavedReport(_, e)
+            | Self::Output(_, e) => Some(e),
+            Self::KnownFolder(e)
+            | Self::Csidl(e)
+            | Self::ShellNamespace(e)
+            | Self::Com(e)
+            | Self::Watch(e)
+            | Self::EnvironmentBlock(e)
+            | Self::Ancestry(e)
+            | Self::Token(e)
+            | Self::Expand(e)
+            | Self::Installer(e)
+            | Self::Clipboard(e)
+            | Self::Explorer(e)
+            | Self::EventLog(e) => Some(e),
+            Self::Utf16(e) => Some(e),
+            Self::NoSuchUser(_)
+            | Self::InvalidGuid(_)
+            | Self::Baseline(..)
+            | Self::Discrepancy { .. }
+            | Self::TimedOut(_)
+            | Self::Panicked => None,
+            #[cfg(feature = "serde")]
+            Self::Toml(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Self::Yaml(e) => Some(e),
+            Self::InSection { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<FromUtf16Error> for Error {
+    fn from(e: FromUtf16Error) -> Self {
+        Self::Utf16(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<toml::ser::Error> for Error {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::Toml(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Self::Yaml(e)
+    }
+}
+
+/// What kind of numeric error code an `EntryError` has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum CodeKind {
+    /// An `HRESULT`, from a Windows API function called through the `windows` crate.
+    Hresult,
+
+    /// A Win32 error code, such as from the registry.
+    Win32,
+}
+
+impl CodeKind {
+    /// Parses the kind of code from its name in serialized output, such as a saved report.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hresult" => Some(Self::Hresult),
+            "win32" => Some(Self::Win32),
+            _ => None,
+        }
+    }
+}
+
+/// The Win32 error code `ERROR_TIMEOUT`, which `Error::TimedOut` is given as.
+const ERROR_TIMEOUT: i64 = 1460;
+
+/// The facility of an `HRESULT` that wraps a Win32 error code, as `HRESULT_FROM_WIN32` makes.
+const FACILITY_WIN32: u16 = 7;
+
+/// Details of why looking up a program files folder location did not produce a path.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct EntryError {
+    /// Human-readable description of the error.
+    pub message: String,
+
+    /// Numeric error code, if available. This is an `HRESULT` for errors from Windows API
+    /// functions called through the `windows` crate, or a Win32 error code for registry errors,
+    /// as `code_kind` says.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub code: Option<i64>,
+
+    /// Whether `code` is an `HRESULT` or a Win32 error code, if there is a code.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub code_kind: Option<CodeKind>,
+
+    /// The facility of `code`, if it is an `HRESULT`, which says what part of the system it came
+    /// from. This is `7`, `FACILITY_WIN32`, for an `HRESULT` that wraps a Win32 error code.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub facility: Option<u16>,
+
+    /// Whether this is a disagreement between two ways of looking up the same location, as
+    /// described on `resolve_guid()`, rather than a failure to look it up.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "std::ops::Not::not"))]
+    pub discrepancy: bool,
+}
+
+impl EntryError {
+    /// Makes an entry error with a message and, if there is one, a code of the given kind.
+    ///
+    /// The facility is found from the code, if it is an `HRESULT`.
+    pub fn new(message: String, code: Option<(i64, CodeKind)>, discrepancy: bool) -> Self {
+        let facility = match code {
+            Some((code, CodeKind::Hresult)) => Some(((code as u32 >> 16) & 0x1FFF) as u16),
+            _ => None,
+        };
+
+        Self {
+            message,
+            code: code.map(|(code, _)| code),
+            code_kind: code.map(|(_, kind)| kind),
+            facility,
+            discrepancy,
+        }
+    }
+
+    /// The Win32 error code, if `code` is one, or is an `HRESULT` that wraps one.
+    pub fn win32_code(&self) -> Option<u32> {
+        match (self.code, self.code_kind, self.facility) {
+            (Some(code), Some(CodeKind::Win32), _) => Some(code as u32),
+            (Some(code), Some(CodeKind::Hresult), Some(FACILITY_WIN32)) => {
+                Some(code as u32 & 0xFFFF)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<VarError> for EntryError {
+    fn from(e: VarError) -> Self {
+        Self::new(e.to_string(), None, false)
+    }
+}
+
+impl From<Error> for EntryError {
+    fn from(e: Error) -> Self {
+        let code = e.code().zip(e.code_kind());
+        let discrepancy = matches!(e.innermost(), Error::Discrepancy { .. });
+        Self::new(e.to_string(), code, discrepancy)
+    }
+}
+
+impl From<io::Error> for EntryError {
+    fn from(e: io::Error) -> Self {
+        let code = e.raw_os_error().map(|code| (code.into(), CodeKind::Win32));
+        Self::new(e.to_string(), code, false)
+    }
+}
+
+/// An error from `resolve_program_files()`, saying why each way of finding the directory failed.
+#[derive(Clone, Debug)]
+pub struct ResolveError {
+    /// What was being looked for, such as "64-bit program files directory".
+    pub directory: &'static str,
+
+    /// Each source tried, in order, with why it did not give the directory.
+    pub attempts: Vec<(String, EntryError)>,
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not find the {}", self.directory)?;
+        for (i, (source, error)) in self.attempts.iter().enumerate() {
+            let separator = if i == 0 { ": " } else { "; " };
+            write!(f, "{separator}{source}: {}", error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResolveError {}
@@ -0,0 +1,166 @@
+//! Writing the result of a report to the Windows Application event log, for monitoring.
+
+use std::io;
+
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Security::PSID;
+use windows::Win32::System::EventLog::{
+    DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+    EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE,
+};
+use winreg::enums::{RegType, HKEY_LOCAL_MACHINE, KEY_READ};
+use winreg::{RegKey, RegValue};
+
+use crate::analysis::{anomalies, resolve_consensus, AnomalyKind};
+use crate::error::Error;
+use crate::platform::Platform;
+use crate::report::Report;
+
+/// The name of the event source that events are written under, in the Application log.
+pub const EVENT_SOURCE: &str = "pfdirs";
+
+/// The registry key, under `HKEY_LOCAL_MACHINE`, that registers `EVENT_SOURCE`.
+pub const EVENT_SOURCE_SUBKEY: &str =
+    r"SYSTEM\CurrentControlSet\Services\EventLog\Application\pfdirs";
+
+/// The message file that `EVENT_SOURCE` is registered with.
+///
+/// This is the message file that .NET registers its own event sources with. It has a message for
+/// every event ID that is just the event's string, so the summary is shown as it is written. It is
+/// part of .NET Framework 4, which every supported version of Windows has.
+const EVENT_MESSAGE_FILE: &str =
+    r"%SystemRoot%\Microsoft.NET\Framework\v4.0.30319\EventLogMessages.dll";
+
+/// The event ID of a report with no anomalies. Reports with anomalies have this plus the exit
+/// status for the most serious one, as given by `AnomalyKind::exit_code()`, so monitoring can
+/// filter on the same distinctions scripts do.
+pub const CONSISTENT_EVENT_ID: u32 = 1000;
+
+/// Registers `EVENT_SOURCE` in the Application log, if it is not registered already.
+///
+/// This creates `EVENT_SOURCE_SUBKEY`, with `EVENT_MESSAGE_FILE` as its message file, which needs
+/// administrator rights. Events can be written without it, but then Event Viewer says the
+/// description of each event cannot be found, before showing the summary.
+pub fn register_event_source() -> Result<(), io::Error> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    if hklm
+        .open_subkey_with_flags(EVENT_SOURCE_SUBKEY, KEY_READ)
+        .is_ok()
+    {
+        return Ok(());
+    }
+
+    let (key, _) = hklm.create_subkey(EVENT_SOURCE_SUBKEY)?;
+    let bytes = EVENT_MESSAGE_FILE
+        .encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    key.set_raw_value(
+        "EventMessageFile",
+        &RegValue {
+            bytes,
+            vtype: RegType::REG_EXPAND_SZ,
+        },
+    )?;
+    let types_supported =
+        EVENTLOG_ERROR_TYPE.0 | EVENTLOG_WARNING_TYPE.0 | EVENTLOG_INFORMATION_TYPE.0;
+    key.set_value("TypesSupported", &u32::from(types_supported))
+}
+
+/// An event summarizing a report, as made by `summary_event()`.
+#[derive(Clone, Debug)]
+pub struct Event {
+    /// Information, if there are no anomalies, a warning, if entries are missing, or an error.
+    pub kind: REPORT_EVENT_TYPE,
+
+    /// `CONSISTENT_EVENT_ID`, plus the exit status for the most serious anomaly, if any.
+    pub id: u32,
+
+    pub message: String,
+}
+
+/// Summarizes a report as an event, with where each program files directory is and any anomalies.
+///
+/// The directories are those the sources agree on, as found by `resolve_consensus()`, and the
+/// anomalies are as found by `anomalies()`. The kind and ID of the event follow from `worst`, the
+/// most serious problem found, which also gives the exit status: a missing entry is a warning, and
+/// a mismatch or API failure is an error. That is the most serious anomaly, or something worse,
+/// such as a required entry that is missing or an entry without its expected path.
+pub fn summary_event(report: &Report, platform: Platform, worst: Option<AnomalyKind>) -> Event {
+    let found = anomalies(report, platform);
+    let worst_anomaly = found.iter().map(|anomaly| anomaly.kind).max();
+    let worst = worst.max(worst_anomaly);
+
+    let mut lines = vec![if found.is_empty() {
+        String::from("The program files directories are consistent.")
+    } else {
+        format!(
+            "The program files directories have {} anomalies.",
+            found.len()
+        )
+    }];
+    if worst > worst_anomaly {
+        lines.push(String::from(
+            "Entries given with --require, --copy, or --expect were missing or had other paths.",
+        ));
+    }
+
+    lines.push(String::new());
+    for resolution in resolve_consensus(report, platform) {
+        if let Some(path) = &resolution.consensus {
+            lines.push(format!("{}: {path}", resolution.directory.description()));
+        }
+    }
+
+    if !found.is_empty() {
+        lines.push(String::new());
+        for anomaly in &found {
+            lines.push(format!(
+                "{}: {}: {}: {}",
+                anomaly.kind.description(),
+                anomaly.section,
+                anomaly.name,
+                anomaly.detail
+            ));
+        }
+    }
+
+    let kind = match worst {
+        None => EVENTLOG_INFORMATION_TYPE,
+        Some(AnomalyKind::Missing) => EVENTLOG_WARNING_TYPE,
+        Some(AnomalyKind::Mismatch | AnomalyKind::ApiFailure) => EVENTLOG_ERROR_TYPE,
+    };
+    Event {
+        kind,
+        id: CONSISTENT_EVENT_ID + u32::from(worst.map_or(0, AnomalyKind::exit_code)),
+        message: lines.join("\r\n"),
+    }
+}
+
+/// Writes an event to the Application log, under `EVENT_SOURCE`.
+///
+/// The event is written with [`ReportEventW`][rew], with its message as its only string. If the
+/// source is not registered, as `register_event_source()` does, the event is still written, but
+/// Event Viewer does not show the message as its description.
+///
+/// [rew]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-reporteventw
+pub fn write_event(event: &Event) -> Result<(), Error> {
+    let source = unsafe { RegisterEventSourceW(PCWSTR::null(), &HSTRING::from(EVENT_SOURCE)) }
+        .map_err(Error::EventLog)?;
+    let message = HSTRING::from(event.message.as_str());
+    let written = unsafe {
+        ReportEventW(
+            source,
+            event.kind,
+            0,
+            event.id,
+            PSID::default(),
+            0,
+            Some(&[PCWSTR(message.as_ptr())]),
+            None,
+        )
+    };
+    let _ = unsafe { DeregisterEventSource(source) };
+    written.map_err(Error::EventLog)
+}
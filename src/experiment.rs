@@ -0,0 +1,293 @@
+//! Experiments that run this program as a child process to observe how Windows treats children.
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::analysis::AnomalyKind;
+use crate::environment::PROGRAM_FILES_ENVIRONMENT_VARIABLES;
+use crate::error::Error;
+use crate::platform::Machine;
+use crate::report::Lookup;
+use crate::saved::{sections_from_json, JsonSection};
+
+/// One run of a child process by `inheritance()`, and what it received.
+#[derive(Clone, Debug)]
+pub struct Trial {
+    /// The environment variables left out of the environment given to the child.
+    pub removed: Vec<&'static str>,
+
+    /// Each of `PROGRAM_FILES_ENVIRONMENT_VARIABLES`, with its value in the child, if it was set.
+    pub received: Vec<(&'static str, Option<String>)>,
+}
+
+/// Runs `program` with its program files environment variables perturbed, to see what it receives.
+///
+/// As described on `report_environment_variables()`, on 64-bit Windows a child process does not
+/// simply inherit `ProgramFiles`, but has it set from `ProgramW6432`, `ProgramFiles(x86)`, or
+/// `ProgramFiles(Arm)`, whichever is for the child's architecture, falling back to the value
+/// passed as `ProgramFiles` only when that variable is missing. This tests those rules on the
+/// running system by giving a child an environment that is a copy of this process's, except with
+/// the variables in `PROGRAM_FILES_ENVIRONMENT_VARIABLES` removed: none of them, then each of them
+/// in turn, then all of them. Each time, the child reports what it received.
+///
+/// The child is run as `program --env --format json`, so `program` should be this program, usually
+/// found with `std::env::current_exe()`. It can also be a build of this program for another
+/// architecture, to see what children of that architecture receive.
+pub fn inheritance(program: &Path) -> Result<Vec<Trial>, Error> {
+    let nothing = Vec::new();
+    let each = PROGRAM_FILES_ENVIRONMENT_VARIABLES.map(|name| vec![name]);
+    let all = PROGRAM_FILES_ENVIRONMENT_VARIABLES.to_vec();
+
+    std::iter::once(nothing)
+        .chain(each)
+        .chain(std::iter::once(all))
+        .map(|removed| {
+            let received = run_child(program, &removed)?;
+            Ok(Trial { removed, received })
+        })
+        .collect()
+}
+
+/// Runs `program` with the named variables removed from its environment, for `inheritance()`.
+///
+/// The child always gets an environment block built here, even when nothing is removed, so that
+/// every trial differs only in which variables are present.
+fn run_child(
+    program: &Path,
+    removed: &[&str],
+) -> Result<Vec<(&'static str, Option<String>)>, Error> {
+    let environment: Vec<(OsString, OsString)> = std::env::vars_os()
+        .filter(|(name, _)| {
+            !removed
+                .iter()
+                .any(|removed| name.eq_ignore_ascii_case(removed))
+        })
+        .collect();
+
+    let mut command = Command::new(program);
+    command
+        .args(["--env", "--format", "json"])
+        .env_clear()
+        .envs(environment);
+    let sections = run_json(command)?;
+
+    let entries = sections
+        .into_iter()
+        .find(|(heading, _)| heading == "Relevant environment variables")
+        .map(|(_, entries)| entries)
+        .unwrap_or_default();
+
+    let received = PROGRAM_FILES_ENVIRONMENT_VARIABLES
+        .map(|name| {
+            let path = entries
+                .iter()
+                .find(|(entry_name, _)| entry_name == name)
+                .and_then(|(_, lookup)| lookup.path.clone());
+            (name, path)
+        })
+        .to_vec();
+
+    Ok(received)
+}
+
+/// Runs a command that outputs a report as JSON, and gets the entries of each of its sections.
+///
+/// The output is read as described on `read_child_report()`.
+fn run_json(mut command: Command) -> Result<Vec<JsonSection>, Error> {
+    let output = command.output().map_err(Error::Child)?;
+    read_child_report(command.get_program(), output.status.code(), &output.stdout)
+}
+
+/// Gets the entries of each section of the report a run of this program output as JSON, given its
+/// exit status.
+///
+/// An exit status below that of `AnomalyKind::ApiFailure` means the report was made, even if it
+/// has anomalies. A child of `inheritance()` has program files environment variables removed on
+/// purpose, so on 64-bit Windows it exits with the status of `AnomalyKind::Missing`, and that is
+/// accepted. Any other status, or no status, means there may be no report, so it is an error, as
+/// is output that is not JSON.
+///
+/// The report is read as described on `sections_from_json()`, so this works with output from
+/// builds of this program that do not report exactly the same sections.
+pub fn read_child_report(
+    program: &OsStr,
+    code: Option<i32>,
+    stdout: &[u8],
+) -> Result<Vec<JsonSection>, Error> {
+    let made =
+        code.is_some_and(|code| (0..AnomalyKind::ApiFailure.exit_code().into()).contains(&code));
+    if !made {
+        let status = match code {
+            Some(code) => format!("exit code {code}"),
+            None => String::from("no exit code"),
+        };
+        let message = format!("{} failed with {status}", program.to_string_lossy());
+        return Err(Error::Child(io::Error::other(message)));
+    }
+
+    let report: serde_json::Value = serde_json::from_slice(stdout)?;
+    Ok(sections_from_json(&report))
+}
+
+/// A build of this program for a particular architecture, as used by `matrix()`.
+#[derive(Clone, Debug)]
+pub struct Build {
+    pub machine: Machine,
+    pub program: PathBuf,
+}
+
+/// The Rust target triples of the builds `find_builds()` looks for, by architecture.
+const BUILD_TARGETS: [(Machine, &str, &str); 3] = [
+    (Machine::X86, "i686-pc-windows-msvc", "pfdirs-x86.exe"),
+    (Machine::X64, "x86_64-pc-windows-msvc", "pfdirs-x64.exe"),
+    (
+        Machine::Arm64,
+        "aarch64-pc-windows-msvc",
+        "pfdirs-arm64.exe",
+    ),
+];
+
+/// Finds builds of this program for the x86, x64, and ARM64 architectures, where available.
+///
+/// The running program is used for its own architecture. For the others, this looks for:
+///
+/// - A file named like `pfdirs-x86.exe`, `pfdirs-x64.exe`, or `pfdirs-arm64.exe` in the same
+///   directory as the running program, for builds that have been copied together.
+///
+/// - A build of the same profile in the Cargo target directory the running program is in, as made
+///   by `cargo build --target i686-pc-windows-msvc` and so on.
+///
+/// Architectures for which no build is found are omitted.
+pub fn find_builds() -> Result<Vec<Build>, Error> {
+    let current = std::env::current_exe().map_err(Error::Child)?;
+    let this_machine = Machine::of_this_build();
+    let directory = current.parent().unwrap_or(Path::new("."));
+
+    let profile = directory.file_name();
+    let target_directory = current
+        .ancestors()
+        .find(|ancestor| ancestor.file_name().is_some_and(|name| name == "target"));
+
+    let builds = BUILD_TARGETS
+        .into_iter()
+        .filter_map(|(machine, triple, file_name)| {
+            if machine == this_machine {
+                return Some(Build {
+                    machine,
+                    program: current.clone(),
+                });
+            }
+
+            let sibling = directory.join(file_name);
+            let cargo_build = target_directory
+                .zip(profile)
+                .map(|(target, profile)| target.join(triple).join(profile).join("pfdirs.exe"));
+
+            std::iter::once(sibling)
+                .chain(cargo_build)
+                .find(|candidate| candidate.is_file())
+                .map(|program| Build { machine, program })
+        })
+        .collect();
+
+    Ok(builds)
+}
+
+/// A row of the matrix made by `matrix()`: one entry, as each build reported it.
+#[derive(Clone, Debug)]
+pub struct MatrixRow {
+    /// The heading of the section the entry is in.
+    pub section: String,
+
+    pub name: String,
+
+    /// What each build that ran reported, in the order of `Matrix::builds`, or `None` if that
+    /// build did not report this entry.
+    pub cells: Vec<Option<Lookup>>,
+}
+
+impl MatrixRow {
+    /// Whether the builds that reported this entry did not all find the same path.
+    pub fn differs(&self) -> bool {
+        let paths: Vec<Option<&str>> = self
+            .cells
+            .iter()
+            .flatten()
+            .map(|lookup| lookup.path.as_deref())
+            .collect();
+        paths.windows(2).any(|pair| pair[0] != pair[1])
+    }
+}
+
+/// Every entry from every source, as reported by builds for different architectures.
+#[derive(Debug)]
+pub struct Matrix {
+    /// The builds that ran, in the order of the cells of each row.
+    pub builds: Vec<Build>,
+
+    /// The builds that could not be run, or whose output could not be read, and why.
+    pub failures: Vec<(Build, Error)>,
+
+    pub rows: Vec<MatrixRow>,
+}
+
+/// Runs each build with `--format json` and merges the results into one table.
+///
+/// This is the comparison the crate documentation describes between processes of different
+/// architectures. The rows are in the order the first build to run reports them, followed by any
+/// entries that only later builds report.
+pub fn matrix(builds: Vec<Build>) -> Matrix {
+    let mut ran = Vec::new();
+    let mut failures = Vec::new();
+    let mut rows: Vec<MatrixRow> = Vec::new();
+
+    for build in builds {
+        let mut command = Command::new(&build.program);
+        command.args(["--format", "json"]);
+
+        let sections = match run_json(command) {
+            Ok(sections) => sections,
+            Err(e) => {
+                failures.push((build, e));
+                continue;
+            }
+        };
+
+        let column = ran.len();
+        ran.push(build);
+
+        for (section, entries) in sections {
+            for (name, lookup) in entries {
+                let index = match rows
+                    .iter()
+                    .position(|row| row.section == section && row.name == name)
+                {
+                    Some(index) => index,
+                    None => {
+                        rows.push(MatrixRow {
+                            section: section.clone(),
+                            name,
+                            cells: Vec::new(),
+                        });
+                        rows.len() - 1
+                    }
+                };
+                let cells = &mut rows[index].cells;
+                cells.resize(column, None);
+                cells.push(Some(lookup));
+            }
+        }
+    }
+
+    for row in &mut rows {
+        row.cells.resize(ran.len(), None);
+    }
+
+    Matrix {
+        builds: ran,
+        failures,
+        rows,
+    }
+}
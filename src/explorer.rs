@@ -0,0 +1,42 @@
+//! Showing directories in File Explorer.
+
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+
+use windows::core::{w, HRESULT, PCWSTR};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Shell::ShellExecuteW;
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+use crate::error::Error;
+
+/// Opens a File Explorer window on a directory.
+///
+/// This runs the `explore` verb on the directory with [`ShellExecuteW`][sew], which is what
+/// choosing to open it from the Shell does, so it opens however the user has Explorer set up, and
+/// it returns once the window is on its way rather than when it is closed.
+///
+/// `ShellExecuteW` reports failure with a value of 32 or less in place of an instance handle. Those
+/// values are Win32 error codes, such as `ERROR_FILE_NOT_FOUND`, or `SE_ERR_*` codes that share
+/// their numbers, so the error is made from the value as a Win32 error code.
+///
+/// [sew]: https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-shellexecutew
+pub fn open_in_explorer(directory: &Path) -> Result<(), Error> {
+    let directory: Vec<u16> = directory.as_os_str().encode_wide().chain([0]).collect();
+
+    let instance = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            w!("explore"),
+            PCWSTR(directory.as_ptr()),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+
+    match instance.0 as usize {
+        code @ 0..=32 => Err(Error::Explorer(HRESULT::from_win32(code as u32).into())),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,555 @@
+//! Checking reported paths against the filesystem.
+
+#[cfg(windows)]
+use std::ffi::{c_void, OsStr, OsString};
+#[cfg(windows)]
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+#[cfg(windows)]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(windows)]
+use windows::core::HSTRING;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, HANDLE, MAX_PATH};
+#[cfg(windows)]
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, FileIdInfo, FindClose, FindFirstFileW, GetDiskFreeSpaceExW, GetFileAttributesW,
+    GetFileInformationByHandleEx, GetFinalPathNameByHandleW, GetLongPathNameW, GetShortPathNameW,
+    GetVolumeInformationW, GetVolumeNameForVolumeMountPointW, GetVolumePathNameW,
+    Wow64DisableWow64FsRedirection, Wow64RevertWow64FsRedirection, CREATE_NEW, DELETE,
+    FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_REPARSE_POINT, FILE_ATTRIBUTE_TEMPORARY,
+    FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_DELETE_ON_CLOSE, FILE_GENERIC_WRITE, FILE_ID_INFO,
+    FILE_NAME_NORMALIZED, FILE_READ_ATTRIBUTES, FILE_SHARE_DELETE, FILE_SHARE_MODE,
+    FILE_SHARE_READ, FILE_SHARE_WRITE, GETFINALPATHNAMEBYHANDLE_FLAGS, INVALID_FILE_ATTRIBUTES,
+    OPEN_EXISTING, VOLUME_NAME_DOS, WIN32_FIND_DATAW,
+};
+#[cfg(windows)]
+use windows::Win32::System::Ioctl::{
+    FILE_FS_PERSISTENT_VOLUME_INFORMATION, FSCTL_QUERY_PERSISTENT_VOLUME_STATE,
+    PERSISTENT_VOLUME_STATE_DEV_VOLUME,
+};
+#[cfg(windows)]
+use windows::Win32::System::SystemServices::{IO_REPARSE_TAG_MOUNT_POINT, IO_REPARSE_TAG_SYMLINK};
+#[cfg(windows)]
+use windows::Win32::System::IO::DeviceIoControl;
+
+/// What kind of reparse point a directory is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReparseKind {
+    /// A junction, also called a mount point, as made by `mklink /J` or by mounting a volume.
+    Junction,
+
+    /// A symbolic link, as made by `mklink /D`.
+    SymbolicLink,
+
+    /// Some other kind of reparse point, with this reparse tag, such as a cloud files placeholder.
+    Other(u32),
+}
+
+impl ReparseKind {
+    pub fn description(self) -> String {
+        match self {
+            Self::Junction => String::from("junction"),
+            Self::SymbolicLink => String::from("symbolic link"),
+            Self::Other(tag) => format!("reparse point with tag {tag:#010X}"),
+        }
+    }
+}
+
+/// What the filesystem says about a reported path, found by `verify_path()`.
+#[derive(Clone, Debug)]
+pub struct Verification {
+    /// Whether anything exists at the path.
+    pub exists: bool,
+
+    /// Whether what exists at the path is a directory, or a reparse point to one.
+    pub is_directory: bool,
+
+    /// What kind of reparse point the path is, or `None` if it is not one.
+    pub reparse: Option<ReparseKind>,
+
+    /// The final path of the directory, with all junctions and symbolic links resolved, or why it
+    /// could not be found.
+    pub final_path: Result<String, windows_core::Error>,
+}
+
+impl Verification {
+    /// Whether the final path differs from `path`, other than in case or a trailing backslash.
+    ///
+    /// Paths are not case sensitive on Windows, so a difference only in case is not a difference
+    /// in where the directory is. If the final path could not be found, this is `false`.
+    pub fn differs_from(&self, path: &str) -> bool {
+        self.final_path.as_ref().is_ok_and(|final_path| {
+            !final_path
+                .trim_end_matches('\\')
+                .eq_ignore_ascii_case(path.trim_end_matches('\\'))
+        })
+    }
+}
+
+/// Finds the reparse tag of the file or directory at `path`.
+///
+/// The tag is only given by [`FindFirstFileW`][fff], in the `dwReserved0` field of the data it
+/// fills in, when the item found is a reparse point.
+///
+/// [fff]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findfirstfilew
+#[cfg(windows)]
+fn reparse_tag(path: &HSTRING) -> Option<u32> {
+    let mut data = WIN32_FIND_DATAW::default();
+    let find = unsafe { FindFirstFileW(path, &mut data) }.ok()?;
+    let _ = unsafe { FindClose(find) };
+    Some(data.dwReserved0)
+}
+
+/// Opens the directory at `path` only to query its attributes, which needs no other access to it.
+///
+/// The handle must be closed with `CloseHandle`.
+#[cfg(windows)]
+fn open_directory(path: &HSTRING) -> Result<HANDLE, windows_core::Error> {
+    unsafe {
+        CreateFileW(
+            path,
+            FILE_READ_ATTRIBUTES.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            None,
+        )
+    }
+}
+
+/// Finds the final path of the directory at `path`, with `GetFinalPathNameByHandleW`.
+///
+/// The `\\?\` prefix that function gives is removed, and `\\?\UNC\` is turned back into `\\`, so
+/// the result can be compared to reported paths, which never have them.
+#[cfg(windows)]
+fn final_path(path: &HSTRING) -> Result<String, windows_core::Error> {
+    final_wide_path(path).map(|path| String::from_utf16_lossy(&path))
+}
+
+/// Finds the final path of the directory at `path`, as `final_path()` does, but as it is on disk,
+/// even if it is not valid Unicode.
+#[cfg(windows)]
+fn final_wide_path(path: &HSTRING) -> Result<Vec<u16>, windows_core::Error> {
+    let handle = open_directory(path)?;
+    let result = final_wide_path_of_handle(handle);
+    let _ = unsafe { CloseHandle(handle) };
+    result
+}
+
+/// Finds the final path of an open file or directory, as described on `final_path()`.
+#[cfg(windows)]
+fn final_path_of_handle(handle: HANDLE) -> Result<String, windows_core::Error> {
+    final_wide_path_of_handle(handle).map(|path| String::from_utf16_lossy(&path))
+}
+
+/// Finds the final path of an open file or directory, as described on `final_wide_path()`.
+#[cfg(windows)]
+fn final_wide_path_of_handle(handle: HANDLE) -> Result<Vec<u16>, windows_core::Error> {
+    let flags = GETFINALPATHNAMEBYHANDLE_FLAGS(FILE_NAME_NORMALIZED.0 | VOLUME_NAME_DOS.0);
+    let mut buffer = vec![0_u16; 260];
+    let mut path = loop {
+        let length = unsafe { GetFinalPathNameByHandleW(handle, &mut buffer, flags) } as usize;
+        if length == 0 {
+            return Err(windows_core::Error::from_win32());
+        } else if length < buffer.len() {
+            buffer.truncate(length);
+            break buffer;
+        }
+        // The buffer was too small, and the length is the size needed, including the null.
+        buffer.resize(length, 0);
+    };
+
+    let unc: Vec<u16> = r"\\?\UNC\".encode_utf16().collect();
+    let local: Vec<u16> = r"\\?\".encode_utf16().collect();
+    if path.starts_with(&unc) {
+        // Keep the last two characters of `\\?\UNC\` as the first of `\\`.
+        path.drain(..unc.len() - 2);
+        path[0] = u16::from(b'\\');
+    } else if path.starts_with(&local) {
+        path.drain(..local.len());
+    }
+    Ok(path)
+}
+
+/// Checks what is on disk at a reported path.
+///
+/// This checks that the path exists and is a directory, with [`GetFileAttributesW`][gfa], and
+/// whether it is a [reparse point][rp], such as a junction, whose kind is found as described on
+/// `reparse_tag()`. A junction or symbolic link at a program files path means the directory is
+/// really somewhere else, often on another volume, which some installers do not handle.
+///
+/// It then opens the directory and finds its final path with
+/// [`GetFinalPathNameByHandleW`][gfpnbh]. This resolves junctions and symbolic links, not only at
+/// the path itself but in any of its parent directories, and gives each component in the case it
+/// has on disk and in its long form, rather than as an 8.3 short name. So when the final path
+/// differs from the reported one, as checked by `Verification::differs_from()`, the reported path
+/// is not where the directory really is, or is not written the way the filesystem writes it.
+///
+/// [gfa]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getfileattributesw
+/// [rp]: https://learn.microsoft.com/en-us/windows/win32/fileio/reparse-points
+/// [gfpnbh]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getfinalpathnamebyhandlew
+#[cfg(windows)]
+pub fn verify_path(path: &OsStr) -> Verification {
+    let path = HSTRING::from(path);
+    let attributes = unsafe { GetFileAttributesW(&path) };
+
+    if attributes == INVALID_FILE_ATTRIBUTES {
+        return Verification {
+            exists: false,
+            is_directory: false,
+            reparse: None,
+            final_path: Err(windows_core::Error::from_win32()),
+        };
+    }
+
+    let reparse =
+        (attributes & FILE_ATTRIBUTE_REPARSE_POINT.0 != 0).then(|| match reparse_tag(&path) {
+            Some(IO_REPARSE_TAG_MOUNT_POINT) => ReparseKind::Junction,
+            Some(IO_REPARSE_TAG_SYMLINK) => ReparseKind::SymbolicLink,
+            Some(tag) => ReparseKind::Other(tag),
+            None => ReparseKind::Other(0),
+        });
+
+    Verification {
+        exists: true,
+        is_directory: attributes & FILE_ATTRIBUTE_DIRECTORY.0 != 0,
+        reparse,
+        final_path: final_path(&path),
+    }
+}
+
+/// What identifies a directory on disk, however it is reached, found by `file_id()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FileId {
+    /// The serial number of the volume the directory is on.
+    pub volume_serial: u64,
+
+    /// The directory's file ID, which is unique on its volume.
+    pub file_id: u128,
+}
+
+/// Finds what identifies the directory at `path` on disk, with
+/// [`GetFileInformationByHandleEx`][gfibhe] and `FileIdInfo`.
+///
+/// Two paths have the same `FileId` exactly when they reach the same directory, even if they are
+/// written differently, or one of them, or one of its parents, is a junction or symbolic link to
+/// the other. Opening the directory follows such links, so this identifies the directory they lead
+/// to. The 128-bit file ID from `FileIdInfo` is used, rather than the 64-bit file index from
+/// `GetFileInformationByHandle`, since on ReFS only the 128-bit ID is unique.
+///
+/// [gfibhe]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getfileinformationbyhandleex
+#[cfg(windows)]
+pub fn file_id(path: &OsStr) -> Result<FileId, windows_core::Error> {
+    let handle = open_directory(&HSTRING::from(path))?;
+    let mut info = FILE_ID_INFO::default();
+    let result = unsafe {
+        GetFileInformationByHandleEx(
+            handle,
+            FileIdInfo,
+            std::ptr::from_mut(&mut info).cast(),
+            size_of::<FILE_ID_INFO>() as u32,
+        )
+    };
+    let _ = unsafe { CloseHandle(handle) };
+
+    result?;
+    Ok(FileId {
+        volume_serial: info.VolumeSerialNumber,
+        file_id: u128::from_le_bytes(info.FileId.Identifier),
+    })
+}
+
+/// What happened when a file was written to a directory by `probe_write()`, when it could be.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WriteProbe {
+    /// The file was written in the directory.
+    Written,
+
+    /// The file was written, but somewhere else, at this final path, as when UAC virtualization
+    /// redirects it to the user's virtual store.
+    Redirected(String),
+}
+
+/// Checks whether this process can really write to the directory at `path`, by creating a file in
+/// it and deleting it.
+///
+/// A directory that exists may still not be writable, so this distinguishes a path that exists
+/// from one an installer running with this process's token could install to. The file is given a
+/// name no other file should have, made from this process's ID and the current time, and is
+/// created with `CREATE_NEW`, so no existing file is ever overwritten. It is opened with
+/// `FILE_FLAG_DELETE_ON_CLOSE`, so it is deleted as soon as it is closed, even if this process is
+/// terminated first, and nothing is ever written to it.
+///
+/// Before closing it, its final path is found, as described on `verify_path()`. If UAC
+/// virtualization is on for this process, creating a file in a program files directory that it
+/// cannot write to does not fail, but is redirected to the user's virtual store, so the file is
+/// not where it appears to be. This is detected by the final path not being in the directory, and
+/// gives `WriteProbe::Redirected`. If the file cannot be created, such as because access is
+/// denied, the error is given.
+#[cfg(windows)]
+pub fn probe_write(path: &OsStr) -> Result<WriteProbe, windows_core::Error> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let name = format!(".pfdirs-probe-{}-{nanos:x}.tmp", std::process::id());
+    let mut directory: Vec<u16> = path.encode_wide().collect();
+    while directory.last() == Some(&u16::from(b'\\')) {
+        directory.pop();
+    }
+    let directory = OsString::from_wide(&directory);
+    let mut file_path = directory.clone();
+    file_path.push(format!(r"\{name}"));
+
+    let handle = unsafe {
+        CreateFileW(
+            &HSTRING::from(&file_path),
+            (FILE_GENERIC_WRITE | DELETE).0,
+            FILE_SHARE_MODE(0),
+            None,
+            CREATE_NEW,
+            FILE_ATTRIBUTE_TEMPORARY | FILE_FLAG_DELETE_ON_CLOSE,
+            None,
+        )
+    }?;
+    let final_file_path = final_path_of_handle(handle);
+    let _ = unsafe { CloseHandle(handle) };
+
+    let expected = final_path(&HSTRING::from(directory)).map_or_else(
+        |_| file_path.to_string_lossy().into_owned(),
+        |final_directory| format!(r"{}\{name}", final_directory.trim_end_matches('\\')),
+    );
+    Ok(match final_file_path {
+        Ok(actual) if !actual.eq_ignore_ascii_case(&expected) => WriteProbe::Redirected(actual),
+        _ => WriteProbe::Written,
+    })
+}
+
+/// Disabling of WOW64 file system redirection on the current thread, which is undone when this is
+/// dropped.
+#[cfg(windows)]
+struct RedirectionDisabled(*mut c_void);
+
+#[cfg(windows)]
+impl RedirectionDisabled {
+    fn disable() -> Result<Self, windows_core::Error> {
+        let mut old_value = std::ptr::null_mut();
+        unsafe { Wow64DisableWow64FsRedirection(&mut old_value) }?;
+        Ok(Self(old_value))
+    }
+}
+
+#[cfg(windows)]
+impl Drop for RedirectionDisabled {
+    fn drop(&mut self) {
+        let _ = unsafe { Wow64RevertWow64FsRedirection(self.0) };
+    }
+}
+
+/// Checks what is on disk at a reported path, as `verify_path()` does, but with [WOW64 file system
+/// redirection][redir] disabled, so the path is checked as a 64-bit process would see it.
+///
+/// In a 32-bit process on 64-bit Windows, accesses to `%windir%\System32` go to
+/// `%windir%\SysWOW64` instead, and a few other directories are redirected similarly. The program
+/// files directories are not redirected this way, since a 32-bit process is instead given the
+/// 32-bit directory as `ProgramFiles`, so the 64-bit directory that 64-bit-oriented sources report
+/// should be reachable either way. Comparing the two checks shows whether it is, and shows what
+/// redirection changes for the paths it does affect, such as `GetSystemDirectoryW`, whose final
+/// path is `SysWOW64` only while redirection is on, and `Sysnative`, which exists only then.
+///
+/// Redirection is disabled with [`Wow64DisableWow64FsRedirection`][dis] only on the calling
+/// thread, and only while the path is checked, then restored with
+/// [`Wow64RevertWow64FsRedirection`][rev]. Nothing else is loaded from disk in that time. This is
+/// only meaningful in a WOW64 process, since elsewhere there is no redirection to disable. If
+/// `Wow64DisableWow64FsRedirection` fails, as it does on 32-bit Windows, its error is given.
+///
+/// [redir]: https://learn.microsoft.com/en-us/windows/win32/winprog64/file-system-redirector
+/// [dis]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-wow64disablewow64fsredirection
+/// [rev]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-wow64revertwow64fsredirection
+#[cfg(windows)]
+pub fn verify_path_unredirected(path: &OsStr) -> Result<Verification, windows_core::Error> {
+    let _disabled = RedirectionDisabled::disable()?;
+    Ok(verify_path(path))
+}
+
+/// Converts a path with `GetShortPathNameW` or `GetLongPathNameW`, which are called the same way.
+///
+/// Each is called first to find the size needed, including the terminating null, then to convert
+/// the path. If the path changed in between so that the buffer is too small, this tries again.
+#[cfg(windows)]
+fn convert_path(
+    path: &OsStr,
+    convert: impl Fn(&HSTRING, Option<&mut [u16]>) -> u32,
+) -> Result<String, windows_core::Error> {
+    let path = HSTRING::from(path);
+
+    let mut buffer = Vec::new();
+    loop {
+        let length = convert(&path, Some(&mut buffer)) as usize;
+        if length == 0 {
+            return Err(windows_core::Error::from_win32());
+        } else if length < buffer.len() {
+            return Ok(String::from_utf16_lossy(&buffer[..length]));
+        }
+        // The buffer was too small, and the length is the size needed, including the null.
+        buffer.resize(length, 0);
+    }
+}
+
+/// Finds the 8.3 short form of a path, such as `C:\PROGRA~1`, with
+/// [`GetShortPathNameW`][gspn].
+///
+/// Some legacy installers store paths in this form, so it helps to know what a reported path looks
+/// like in it. Each component that has no short name, as on a volume where their creation is
+/// disabled, is given in its long form, so the result may be the path unchanged. The path must
+/// exist.
+///
+/// [gspn]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getshortpathnamew
+#[cfg(windows)]
+pub fn short_path(path: &OsStr) -> Result<String, windows_core::Error> {
+    convert_path(path, |path, buffer| unsafe {
+        GetShortPathNameW(path, buffer)
+    })
+}
+
+/// Finds the long form of a path, with each 8.3 short name expanded, with
+/// [`GetLongPathNameW`][glpn].
+///
+/// A reported path written with short names, such as a registry value holding `C:\PROGRA~1`, is
+/// given as it would usually be written. Unlike the final path found by `verify_path()`, this does
+/// not resolve junctions or symbolic links. The path must exist.
+///
+/// [glpn]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getlongpathnamew
+#[cfg(windows)]
+pub fn long_path(path: &OsStr) -> Result<String, windows_core::Error> {
+    convert_path(path, |path, buffer| unsafe {
+        GetLongPathNameW(path, buffer)
+    })
+}
+
+/// What volume a reported path is on, found by `volume_of()`.
+#[derive(Clone, Debug)]
+pub struct Volume {
+    /// The path at which the volume is mounted, such as `C:\`, with a trailing backslash.
+    pub mount_point: String,
+
+    /// The volume GUID path, such as `\\?\Volume{...}\`, which names the volume however it is
+    /// mounted, or why it could not be found, as for a network share.
+    pub guid_path: Result<String, windows_core::Error>,
+
+    /// The name of the volume's filesystem, such as `NTFS` or `ReFS`, or why it could not be found.
+    pub file_system: Result<String, windows_core::Error>,
+
+    /// Whether the volume is a [Dev Drive][dev-drive], or why that could not be found, as on
+    /// versions of Windows that do not support them.
+    ///
+    /// [dev-drive]: https://learn.microsoft.com/en-us/windows/dev-drive/
+    pub is_dev_drive: Result<bool, windows_core::Error>,
+
+    /// The free space, in bytes, available to this user on the volume, and its total size, or why
+    /// they could not be found.
+    pub space: Result<(u64, u64), windows_core::Error>,
+}
+
+impl Volume {
+    /// Whether the filesystem is ReFS, as Dev Drives and some storage spaces are formatted.
+    pub fn is_refs(&self) -> bool {
+        self.file_system
+            .as_ref()
+            .is_ok_and(|name| name.eq_ignore_ascii_case("ReFS"))
+    }
+}
+
+/// Gets a string, such as a path or name, that a function writes to a buffer of `MAX_PATH + 1`
+/// characters, which is enough for any of the volume paths and names used in `volume_of()`.
+#[cfg(windows)]
+fn get_volume_string(
+    get: impl FnOnce(&mut [u16]) -> Result<(), windows_core::Error>,
+) -> Result<String, windows_core::Error> {
+    let mut buffer = [0_u16; MAX_PATH as usize + 1];
+    get(&mut buffer)?;
+    let length = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Ok(String::from_utf16_lossy(&buffer[..length]))
+}
+
+/// Checks whether the volume mounted at `mount_point` is a Dev Drive, by querying its persistent
+/// state with [`FSCTL_QUERY_PERSISTENT_VOLUME_STATE`][fsctl] for the
+/// `PERSISTENT_VOLUME_STATE_DEV_VOLUME` flag.
+///
+/// [fsctl]: https://learn.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_query_persistent_volume_state
+#[cfg(windows)]
+fn is_dev_drive(mount_point: &HSTRING) -> Result<bool, windows_core::Error> {
+    let input = FILE_FS_PERSISTENT_VOLUME_INFORMATION {
+        FlagMask: PERSISTENT_VOLUME_STATE_DEV_VOLUME,
+        Version: 1,
+        ..Default::default()
+    };
+    let mut output = FILE_FS_PERSISTENT_VOLUME_INFORMATION::default();
+    let size = size_of::<FILE_FS_PERSISTENT_VOLUME_INFORMATION>() as u32;
+
+    let handle = open_directory(mount_point)?;
+    let result = unsafe {
+        DeviceIoControl(
+            handle,
+            FSCTL_QUERY_PERSISTENT_VOLUME_STATE,
+            Some(std::ptr::from_ref(&input).cast()),
+            size,
+            Some(std::ptr::from_mut(&mut output).cast()),
+            size,
+            None,
+            None,
+        )
+    };
+    let _ = unsafe { CloseHandle(handle) };
+
+    result?;
+    Ok(output.VolumeFlags & PERSISTENT_VOLUME_STATE_DEV_VOLUME != 0)
+}
+
+/// Finds what volume a reported path is on, for diagnosing installs onto unusual volumes.
+///
+/// The path's final path is used when it can be found, as described on `verify_path()`, so that a
+/// directory that is a junction to another volume, or is under one, is reported on the volume it
+/// is really on. The volume's mount point is found from that with [`GetVolumePathNameW`][gvpn],
+/// which also accounts for volumes mounted in folders. If that fails, the error is given, since
+/// nothing else can then be found. Otherwise, the volume GUID path is found with
+/// [`GetVolumeNameForVolumeMountPointW`][gvnfvmp], the filesystem with
+/// [`GetVolumeInformationW`][gvi], whether it is a Dev Drive as described on `is_dev_drive()`, and
+/// the free space with [`GetDiskFreeSpaceExW`][gdfse], any of which may fail on its own.
+///
+/// [gvpn]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumepathnamew
+/// [gvnfvmp]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumenameforvolumemountpointw
+/// [gvi]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumeinformationw
+/// [gdfse]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getdiskfreespaceexw
+#[cfg(windows)]
+pub fn volume_of(path: &OsStr) -> Result<Volume, windows_core::Error> {
+    let path = HSTRING::from(path);
+    let path = final_wide_path(&path)
+        .and_then(|final_path| HSTRING::from_wide(&final_path))
+        .unwrap_or(path);
+
+    let mount_point = get_volume_string(|buffer| unsafe { GetVolumePathNameW(&path, buffer) })?;
+    let root = HSTRING::from(mount_point.as_str());
+
+    let guid_path =
+        get_volume_string(|buffer| unsafe { GetVolumeNameForVolumeMountPointW(&root, buffer) });
+
+    let file_system = get_volume_string(|buffer| unsafe {
+        GetVolumeInformationW(&root, None, None, None, None, Some(buffer))
+    });
+
+    let mut free = 0;
+    let mut total = 0;
+    let space = unsafe { GetDiskFreeSpaceExW(&root, Some(&mut free), Some(&mut total), None) }
+        .map(|()| (free, total));
+
+    Ok(Volume {
+        is_dev_drive: is_dev_drive(&root),
+        mount_point,
+        guid_path,
+        file_system,
+        space,
+    })
+}
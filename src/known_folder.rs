@@ -0,0 +1,561 @@
+//! Looking up program files folder locations as known folders.
+
+#[cfg(windows)]
+use core::ffi::c_void;
+use std::ffi::OsString;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStringExt;
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+use std::path::PathBuf;
+#[cfg(all(windows, feature = "com"))]
+use std::string::FromUtf16Error;
+
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+use known_folders::{get_known_folder_path, KnownFolder};
+#[cfg(all(windows, feature = "com"))]
+use windows::core::HSTRING;
+#[cfg(windows)]
+use windows::core::PWSTR;
+#[cfg(windows)]
+use windows::Win32::System::Com::CoTaskMemFree;
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::System::Com::IBindCtx;
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::UI::Shell::Common::ITEMIDLIST;
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::UI::Shell::{
+    ILFree, ILIsEqual, SHGetKnownFolderIDList, SHGetNameFromIDList, SHParseDisplayName, SIGDN,
+    SIGDN_DESKTOPABSOLUTEPARSING, SIGDN_FILESYSPATH,
+};
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{
+    SHGetKnownFolderPath, KF_FLAG_DEFAULT, KF_FLAG_DEFAULT_PATH, KF_FLAG_DONT_VERIFY,
+    KF_FLAG_NO_ALIAS, KNOWN_FOLDER_FLAG,
+};
+use windows_core::GUID;
+
+#[cfg(all(windows, feature = "com"))]
+use crate::error::EntryError;
+use crate::error::Error;
+#[cfg(all(windows, feature = "com"))]
+use crate::known_folder_definition::ComApartment;
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+use crate::report::escape_non_unicode;
+use crate::report::{KnownFolderEntry, Lookup, Section};
+#[cfg(windows)]
+use crate::timing::traced;
+#[cfg(windows)]
+use crate::token::Token;
+
+/// Owner of a `PWSTR` that must be freed with `CoTaskMemFree`.
+#[cfg(windows)]
+pub(crate) struct CoStr {
+    pwstr: PWSTR,
+}
+
+#[cfg(windows)]
+impl CoStr {
+    pub(crate) fn new(pwstr: PWSTR) -> Self {
+        Self { pwstr }
+    }
+
+    #[cfg(feature = "com")]
+    pub(crate) fn to_string(&self) -> Result<String, FromUtf16Error> {
+        unsafe { self.pwstr.to_string() }
+    }
+
+    /// Like `to_string()`, but keeps what is not valid Unicode, rather than failing on it.
+    pub(crate) fn to_os_string(&self) -> OsString {
+        OsString::from_wide(unsafe { self.pwstr.as_wide() })
+    }
+
+    /// Like `to_string()`, but gives `None` for a null pointer, which some APIs use for "absent".
+    #[cfg(feature = "com")]
+    pub(crate) fn to_optional_string(&self) -> Result<Option<String>, FromUtf16Error> {
+        if self.pwstr.is_null() {
+            Ok(None)
+        } else {
+            self.to_string().map(Some)
+        }
+    }
+}
+
+// TODO: Figure out whether to implement windows::core::Owned instead.
+#[cfg(windows)]
+impl Drop for CoStr {
+    fn drop(&mut self) {
+        unsafe { CoTaskMemFree(Some(self.pwstr.as_ptr().cast::<c_void>())) };
+    }
+}
+
+/// Helper that calls `ShGetKnownFolderPath` on behalf of `resolve_guid()`.
+#[cfg(windows)]
+pub(crate) fn get_known_folder_path_or_detailed_error(
+    id: GUID,
+    flags: KNOWN_FOLDER_FLAG,
+    token: &Token,
+) -> Result<OsString, Error> {
+    traced(
+        "SHGetKnownFolderPath",
+        format_args!("{{{id:?}}}, {:#x}", flags.0),
+        || match unsafe { SHGetKnownFolderPath(&id, flags, token.handle()) } {
+            Ok(pwstr) => Ok(CoStr::new(pwstr).to_os_string()),
+            Err(e) => Err(Error::KnownFolder(e)),
+        },
+    )
+}
+
+/// Known folders that have equivalents in the `KnownFolder` enum of the `known-folders` crate.
+///
+/// That enum covers many known folders, but only those defined by Windows itself. This lists the
+/// ones we look up in `report_known_folders()`, which are the ones we can cross-check.
+///
+/// This is only available with the `known-folders-crosscheck` feature.
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+pub const KNOWN_FOLDER_ENUM_EQUIVALENTS: [(GUID, KnownFolder); 4] = [
+    (FOLDERID_ProgramFiles, KnownFolder::ProgramFiles),
+    (FOLDERID_ProgramFilesX64, KnownFolder::ProgramFilesX64),
+    (FOLDERID_ProgramFilesX86, KnownFolder::ProgramFilesX86),
+    (FOLDERID_UserProgramFiles, KnownFolder::UserProgramFiles),
+];
+
+/// Resolves the path of the known folder with an arbitrary GUID, using the specified flags.
+///
+/// This works for any known folder, including those registered by OEMs and applications, which
+/// have no `KnownFolder` enum variant in the `known-folders` crate. When `id` is one of the
+/// folders in `KNOWN_FOLDER_ENUM_EQUIVALENTS` and `flags` is `KF_FLAG_DEFAULT`, which is what the
+/// `known-folders` crate passes, the result is also cross-checked against `get_known_folder_path()`
+/// as described on `report_known_folders()`. Otherwise, or without the `known-folders-crosscheck`
+/// feature, that cross-check is skipped.
+#[cfg(windows)]
+pub fn resolve_guid(id: GUID, flags: KNOWN_FOLDER_FLAG) -> Result<OsString, Error> {
+    resolve_guid_as(id, flags, &Token::CURRENT)
+}
+
+/// Like `resolve_guid()`, but resolves the path as the user of the given token would see it.
+///
+/// The `known-folders` crate always uses the current user, so the cross-check is also skipped
+/// unless `token` is `Token::CURRENT`.
+#[cfg(windows)]
+pub fn resolve_guid_as(
+    id: GUID,
+    flags: KNOWN_FOLDER_FLAG,
+    token: &Token,
+) -> Result<OsString, Error> {
+    // Calling SHGetKnownFolderPath ourselves gives more detailed error information.
+    let path_or_error = get_known_folder_path_or_detailed_error(id, flags, token);
+
+    #[cfg(feature = "known-folders-crosscheck")]
+    if flags == KF_FLAG_DEFAULT && token.is_current() {
+        return cross_check(id, path_or_error);
+    }
+
+    path_or_error
+}
+
+/// Parses a GUID written as `XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX`, with or without braces, such as
+/// to give to `resolve_guid()`.
+///
+/// Unlike `GUID::from`, this does not panic on malformed input, but gives `Error::InvalidGuid`.
+pub fn parse_guid(text: &str) -> Result<GUID, Error> {
+    let digits = text
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .unwrap_or(text);
+
+    let groups: Vec<&str> = digits.split('-').collect();
+    let well_formed = groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|group| group.bytes().all(|b| b.is_ascii_hexdigit()));
+    if !well_formed {
+        return Err(Error::InvalidGuid(text.to_owned()));
+    }
+
+    u128::from_str_radix(&groups.concat(), 16)
+        .map(GUID::from_u128)
+        .map_err(|_| Error::InvalidGuid(text.to_owned()))
+}
+
+/// Compares the result of `SHGetKnownFolderPath` for the known folder with the specified GUID to
+/// what `get_known_folder_path()` gives, if the folder is in `KNOWN_FOLDER_ENUM_EQUIVALENTS`.
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+fn cross_check(id: GUID, path_or_error: Result<OsString, Error>) -> Result<OsString, Error> {
+    let Some(kf) = KNOWN_FOLDER_ENUM_EQUIVALENTS
+        .into_iter()
+        .find_map(|(guid, kf)| (guid == id).then_some(kf))
+    else {
+        return path_or_error;
+    };
+
+    // The `known-folders` crate is simple and easy to use, but gives `Option`, not `Result`.
+    let maybe_path = get_known_folder_path(kf).map(PathBuf::into_os_string);
+
+    // Compare the information from both approaches. If inconsistent, report the details.
+    match (path_or_error, maybe_path) {
+        (Ok(my_kf_path), Some(lib_kf_path)) if my_kf_path == lib_kf_path => Ok(my_kf_path),
+        (Err(e), None) => Err(e),
+        (my_thing, lib_thing) => Err(Error::Discrepancy {
+            first: (
+                "SHGetKnownFolderPath",
+                match my_thing {
+                    Ok(path) => escape_non_unicode(&path),
+                    Err(e) => format!("an error ({e})"),
+                },
+            ),
+            second: (
+                "the known-folders crate",
+                lib_thing.map_or_else(|| String::from("nothing"), |p| escape_non_unicode(&p)),
+            ),
+        }),
+    }
+}
+
+/// The `FOLDERID_ProgramFiles` known folder, the program files directory of this process's
+/// architecture.
+///
+/// This and the other `FOLDERID_*` constants here have the same values as those of the same names
+/// in the `windows` crate, which has them only on Windows, so the tables that use them, and
+/// `FakeOs`, are available everywhere.
+#[allow(non_upper_case_globals)]
+pub const FOLDERID_ProgramFiles: GUID = GUID::from_u128(0x905e63b6_c1bf_494e_b29c_65b732d3d21a);
+
+/// The `FOLDERID_ProgramFilesX64` known folder, the 64-bit program files directory.
+#[allow(non_upper_case_globals)]
+pub const FOLDERID_ProgramFilesX64: GUID = GUID::from_u128(0x6d809377_6af0_444b_8957_a3773f02200e);
+
+/// The `FOLDERID_ProgramFilesX86` known folder, the 32-bit x86 program files directory.
+#[allow(non_upper_case_globals)]
+pub const FOLDERID_ProgramFilesX86: GUID = GUID::from_u128(0x7c5a40ef_a0fb_4bfc_874a_c0f2e0b9fa8e);
+
+/// The `FOLDERID_UserProgramFiles` known folder, where per-user installs go.
+#[allow(non_upper_case_globals)]
+pub const FOLDERID_UserProgramFiles: GUID = GUID::from_u128(0x5cd7aee2_2219_4a67_b85d_6c9ce15660cb);
+
+/// The *program files* known folders, with their symbolic names.
+pub const PROGRAM_FILES_KNOWN_FOLDERS: [(&str, GUID); 4] = [
+    ("FOLDERID_ProgramFiles", FOLDERID_ProgramFiles),
+    ("FOLDERID_ProgramFilesX64", FOLDERID_ProgramFilesX64),
+    ("FOLDERID_ProgramFilesX86", FOLDERID_ProgramFilesX86),
+    ("FOLDERID_UserProgramFiles", FOLDERID_UserProgramFiles),
+];
+
+/// Report *program files* folder locations by querying *known folders*.
+///
+/// See [Known Folders][kf]. This is a recommended approach. This can be done through the Windows
+/// API or indirectly through a crate that wraps it. This function showcases both and checks that
+/// the information provided, where overlapping, is identical.
+///
+/// #### Windows API
+///
+/// Windows provides two approaches in its API for accessing the paths of known folders:
+///
+/// - The [`SHGetKnownFolderPath`][shgkfp] function. This approach is more straightforward and
+///   typically sufficient when the GUIDs are known and only paths are needed. (There are a small
+///   number of other related functions for obtaining other information.) This is the approach used
+///   here.
+///
+/// - The [`IKnownFolder::GetPath`][ikf-gp] method. This is more involved, but `IKnownFolder` COM
+///   objects are a richer source of information. For example, `IKnownFolder` supports iterating
+///   over all known folders.
+///
+/// #### known-folders crate
+///
+/// The [kfcrate] crate provides a `get_known_folder_path()` function that takes care of calling
+/// `SHGetKnownFolderPath` from Rust code. However, this is limited to simple uses:
+///
+/// - It does not accept custom `KNOWN_FOLDER_FLAGS` or a custom access token.
+///
+/// - It returns an `Option` rather than a `Result`, so when a known folder path is unavailable,
+///   the different errors that can cause this are not distinguished.
+///
+/// But in the most common cases `get_known_folder_path()` is sufficient.
+///
+/// #### What this function does
+///
+/// This uses both `SHGetKnownFolderPath`, called through the `windows` crate, and
+/// `get_known_folder_path()`, provided by the `known-folders` crate, and compares the results for
+/// whether there was an error and, if not, whether the paths match. If they disagree, the entry's
+/// error is an [`Error::Discrepancy`] giving both results, and the other entries are still looked
+/// up, rather than the whole report being abandoned. Calling both is for
+/// experimentation and demonstration purposes. Generally at most one of these two approaches
+/// should be used, depending on requirements. Without the `known-folders-crosscheck` feature,
+/// only `SHGetKnownFolderPath` is used, and the `known-folders` crate is not a dependency.
+///
+/// This looks up only the four folder IDs for *program files* folders. Their GUIDs are available
+/// as symbolic constants both in the `windows` crate as `GUID` objects and, as a higher level
+/// abstraction, in the `KnownFolder` enum of the `known-folders` crate.
+///
+/// [kf]: https://learn.microsoft.com/en-us/windows/win32/shell/known-folders
+/// [shgkfp]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetknownfolderpath
+/// [ikf-gp]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfolder-getpath
+/// [kfcrate]: https://crates.io/crates/known-folders
+#[cfg(windows)]
+pub fn report_known_folders() -> Result<Section<KnownFolderEntry>, Error> {
+    report_known_folders_as(&Token::CURRENT, "the current user")
+}
+
+/// Like `report_known_folders()`, but looks up the folders as the user of the given token would.
+///
+/// This passes the token to `SHGetKnownFolderPath`, as described on `resolve_guid_as()`, so that
+/// administrators and service authors can see what other security contexts would resolve the
+/// folders to. `Token::DEFAULT_USER` gives the folders of the default user. The `whose`
+/// description is shown in the heading unless the token is `Token::CURRENT`.
+///
+/// The program files folders are the same for every user, except `FOLDERID_UserProgramFiles`,
+/// which is in each user's profile, and which is usually unavailable for the default user.
+#[cfg(windows)]
+pub fn report_known_folders_as(
+    token: &Token,
+    whose: &str,
+) -> Result<Section<KnownFolderEntry>, Error> {
+    let heading = if token.is_current() {
+        String::from("Relevant known folders")
+    } else {
+        format!("Relevant known folders - as {whose}")
+    };
+
+    Ok(known_folders_section(heading, |id| {
+        resolve_guid_as(id, KF_FLAG_DEFAULT, token)
+    }))
+}
+
+/// Makes a section of the *program files* known folders, each looked up with `look_up`, for
+/// `report_known_folders_as()` and for `Report::collect_with()`.
+pub(crate) fn known_folders_section(
+    heading: String,
+    look_up: impl Fn(GUID) -> Result<OsString, Error>,
+) -> Section<KnownFolderEntry> {
+    let entries = PROGRAM_FILES_KNOWN_FOLDERS
+        .into_iter()
+        .map(|(symbol, id)| KnownFolderEntry {
+            name: symbol.to_owned(),
+            id,
+            lookup: Lookup::new(look_up(id)),
+        })
+        .collect();
+
+    Section::new(heading, entries)
+}
+
+/// Whether a known folder has been redirected from its default location.
+#[cfg(windows)]
+#[derive(Debug)]
+pub enum Redirection {
+    /// The folder is at its default path.
+    Default(OsString),
+
+    /// The folder is somewhere other than its default path.
+    Redirected {
+        current: OsString,
+        default: OsString,
+    },
+
+    /// Looking up the folder's current or default path failed, so this could not be determined.
+    Unknown(Error),
+}
+
+/// Finds whether each *program files* known folder appears to have been redirected.
+///
+/// Known folders can be redirected, so that their paths differ from their defaults. This is rare
+/// for program files folders, but policy or virtualization can do it. To detect it, this looks up
+/// each folder both normally and with [`KF_FLAG_DEFAULT_PATH`][kff], which gives the path the
+/// folder would have if it were not redirected. If the paths are the same, the folder is at its
+/// default location. Otherwise it has been redirected.
+///
+/// If either lookup fails, the error is given instead. The `known-folders` crate is not used
+/// here, since it does not support passing flags.
+///
+/// [kff]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/ne-shlobj_core-known_folder_flag
+#[cfg(windows)]
+pub fn known_folder_redirection() -> Vec<(&'static str, Redirection)> {
+    PROGRAM_FILES_KNOWN_FOLDERS
+        .into_iter()
+        .map(|(symbol, id)| {
+            let current = resolve_guid(id, KF_FLAG_DEFAULT);
+            let default = resolve_guid(id, KF_FLAG_DEFAULT_PATH);
+
+            let redirection = match (current, default) {
+                (Ok(current), Ok(default)) if current == default => Redirection::Default(current),
+                (Ok(current), Ok(default)) => Redirection::Redirected { current, default },
+                (Err(e), _) | (_, Err(e)) => Redirection::Unknown(e),
+            };
+
+            (symbol, redirection)
+        })
+        .collect()
+}
+
+/// The [`KNOWN_FOLDER_FLAG`][kff] values that `known_folder_flag_variations()` looks up each
+/// folder with, with their symbolic names.
+///
+/// [kff]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/ne-shlobj_core-known_folder_flag
+#[cfg(windows)]
+pub const KNOWN_FOLDER_FLAG_VARIATIONS: [(&str, KNOWN_FOLDER_FLAG); 4] = [
+    ("KF_FLAG_DEFAULT", KF_FLAG_DEFAULT),
+    ("KF_FLAG_DONT_VERIFY", KF_FLAG_DONT_VERIFY),
+    ("KF_FLAG_DEFAULT_PATH", KF_FLAG_DEFAULT_PATH),
+    ("KF_FLAG_NO_ALIAS", KF_FLAG_NO_ALIAS),
+];
+
+/// Looks up each *program files* known folder with each of the flags in
+/// `KNOWN_FOLDER_FLAG_VARIATIONS`, giving one lookup per flag, in that order.
+///
+/// Folders that are redirected or missing behave differently depending on the flags. With
+/// `KF_FLAG_DEFAULT`, a folder whose path does not exist is an error. `KF_FLAG_DONT_VERIFY` gives
+/// the path anyway. `KF_FLAG_DEFAULT_PATH` gives the path the folder would have if it had not been
+/// redirected, as used by `known_folder_redirection()`. `KF_FLAG_NO_ALIAS` gives the path without
+/// mapping it to an alias, which can differ for folders reached through a junction or another
+/// known folder.
+///
+/// Each lookup is made as described on `resolve_guid()`, so only the `KF_FLAG_DEFAULT` lookups are
+/// cross-checked against the `known-folders` crate.
+#[cfg(windows)]
+pub fn known_folder_flag_variations() -> Vec<(&'static str, Vec<Lookup>)> {
+    PROGRAM_FILES_KNOWN_FOLDERS
+        .into_iter()
+        .map(|(symbol, id)| {
+            let lookups = KNOWN_FOLDER_FLAG_VARIATIONS
+                .into_iter()
+                .map(|(_, flags)| Lookup::new(resolve_guid(id, flags)))
+                .collect();
+            (symbol, lookups)
+        })
+        .collect()
+}
+
+/// Owner of an item ID list, which must be freed with `ILFree`.
+#[cfg(all(windows, feature = "com"))]
+struct IdList(*mut ITEMIDLIST);
+
+#[cfg(all(windows, feature = "com"))]
+impl IdList {
+    /// Parses a path to the item ID list of the item in the Shell namespace it names.
+    fn parse(path: &str) -> Result<Self, Error> {
+        let mut pidl = std::ptr::null_mut();
+        unsafe { SHParseDisplayName(&HSTRING::from(path), None::<&IBindCtx>, &mut pidl, 0, None) }
+            .map_err(Error::ShellNamespace)?;
+        Ok(Self(pidl))
+    }
+
+    /// Gets one of the names of the item, of the kind given by `sigdn`.
+    fn name(&self, sigdn: SIGDN) -> Result<OsString, Error> {
+        let name = unsafe { SHGetNameFromIDList(self.0, sigdn) }.map_err(Error::ShellNamespace)?;
+        Ok(CoStr::new(name).to_os_string())
+    }
+}
+
+#[cfg(all(windows, feature = "com"))]
+impl Drop for IdList {
+    fn drop(&mut self) {
+        unsafe { ILFree(Some(self.0)) };
+    }
+}
+
+/// A *program files* known folder looked up both as a path and as an item ID list, as described
+/// on `known_folder_id_list_round_trips()`.
+#[cfg(all(windows, feature = "com"))]
+#[derive(Clone, Debug)]
+pub struct IdListRoundTrip {
+    /// The folder's path, from `SHGetKnownFolderPath`.
+    pub path: Lookup,
+
+    /// The filesystem path of the folder's item ID list, from `SHGetKnownFolderIDList`, as given
+    /// by `SHGetNameFromIDList` with `SIGDN_FILESYSPATH`.
+    pub id_list_path: Lookup,
+
+    /// The name that identifies the folder's item ID list in the Shell namespace, as given by
+    /// `SHGetNameFromIDList` with `SIGDN_DESKTOPABSOLUTEPARSING`. For a filesystem folder this is
+    /// its path, but for a virtual folder it is a `::{CLSID}` name.
+    pub parsing_name: Lookup,
+
+    /// Whether `path`, parsed back into an item ID list, is the same item, or the error parsing
+    /// it. This is `None` if there was no path or no item ID list to compare.
+    pub same_item: Option<Result<bool, EntryError>>,
+}
+
+#[cfg(all(windows, feature = "com"))]
+impl IdListRoundTrip {
+    /// Whether both ways of looking up the folder found it, gave the same path, and name the same
+    /// item in the Shell namespace.
+    pub fn agrees(&self) -> bool {
+        self.path.path.is_some()
+            && self.path.path == self.id_list_path.path
+            && matches!(self.same_item, Some(Ok(true)))
+    }
+}
+
+/// Looks up a known folder as a path and as an item ID list, for
+/// `known_folder_id_list_round_trips()`.
+#[cfg(all(windows, feature = "com"))]
+fn id_list_round_trip(id: GUID) -> IdListRoundTrip {
+    let path = get_known_folder_path_or_detailed_error(id, KF_FLAG_DEFAULT, &Token::CURRENT);
+    let id_list =
+        unsafe { SHGetKnownFolderIDList(&id, KF_FLAG_DEFAULT.0 as u32, Token::CURRENT.handle()) }
+            .map(IdList);
+
+    let path = Lookup::new(path);
+    let (id_list_path, parsing_name, same_item) = match &id_list {
+        Ok(id_list) => {
+            let same_item = path.path.as_deref().map(|path| {
+                IdList::parse(path)
+                    .map(|parsed| unsafe { ILIsEqual(parsed.0, id_list.0) }.as_bool())
+                    .map_err(EntryError::from)
+            });
+            (
+                Lookup::new(id_list.name(SIGDN_FILESYSPATH)),
+                Lookup::new(id_list.name(SIGDN_DESKTOPABSOLUTEPARSING)),
+                same_item,
+            )
+        }
+        Err(e) => {
+            let lookup = Lookup::new(Err::<String, _>(Error::KnownFolder(e.clone())));
+            (lookup.clone(), lookup, None)
+        }
+    };
+
+    IdListRoundTrip {
+        path,
+        id_list_path,
+        parsing_name,
+        same_item,
+    }
+}
+
+/// Looks up each *program files* known folder both as a path and as an item ID list, converting
+/// the item ID list back to a path and the path back to an item ID list, to check that they agree.
+///
+/// `SHGetKnownFolderPath` gives only a path, which hides how the Shell sees the folder. The Shell
+/// identifies items by [item ID lists][idl], which [`SHGetKnownFolderIDList`][shgkfidl] gives,
+/// and which need not correspond to a filesystem path at all, as for virtual folders. An alias, a
+/// known folder reached through another, or a folder whose path leads through a junction may also
+/// give a different item than parsing its path does. So this:
+///
+/// - Looks up the path with `SHGetKnownFolderPath`, as `report_known_folders()` does, but without
+///   the cross-check against the `known-folders` crate.
+///
+/// - Looks up the item ID list, and gets its filesystem path and its parsing name with
+///   [`SHGetNameFromIDList`][shgnfidl]. Unlike `SHGetPathFromIDListW`, as used for
+///   `report_shell_monikers()`, this has no `MAX_PATH` limit.
+///
+/// - Parses the path back into an item ID list with [`SHParseDisplayName`][shpdn], and compares
+///   that to the folder's own with [`ILIsEqual`][ilie].
+///
+/// Both lookups pass `KF_FLAG_DEFAULT`. Getting names from and parsing item ID lists requires
+/// COM, which is initialized on this thread for the duration. So this is only available with the
+/// `com` feature.
+///
+/// [idl]: https://learn.microsoft.com/en-us/windows/win32/shell/namespace-intro
+/// [shgkfidl]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetknownfolderidlist
+/// [shgnfidl]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-shgetnamefromidlist
+/// [shpdn]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shparsedisplayname
+/// [ilie]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-ilisequal
+#[cfg(all(windows, feature = "com"))]
+pub fn known_folder_id_list_round_trips() -> Result<Vec<(&'static str, IdListRoundTrip)>, Error> {
+    let _apartment = ComApartment::initialize()?;
+
+    Ok(PROGRAM_FILES_KNOWN_FOLDERS
+        .into_iter()
+        .map(|(symbol, id)| (symbol, id_list_round_trip(id)))
+        .collect())
+}
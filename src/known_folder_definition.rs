@@ -0,0 +1,277 @@
+//! Looking up program files known folders, and their definitions, through `IKnownFolder`.
+//!
+//! This uses COM objects, so it is only available with the `com` feature.
+
+use windows::core::{GUID, HSTRING};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_INPROC_SERVER,
+    COINIT_APARTMENTTHREADED,
+};
+use windows::Win32::UI::Shell::{
+    IKnownFolder, IKnownFolderManager, KnownFolderManager, SHGetLocalizedName,
+    SHLoadIndirectString, KF_CATEGORY, KF_CATEGORY_COMMON, KF_CATEGORY_FIXED, KF_CATEGORY_PERUSER,
+    KF_CATEGORY_VIRTUAL, KF_FLAG_DEFAULT, KNOWNFOLDER_DEFINITION,
+};
+
+use crate::error::Error;
+use crate::known_folder::{parse_guid, CoStr, PROGRAM_FILES_KNOWN_FOLDERS};
+use crate::report::{KnownFolderDefinition, KnownFolderDefinitionEntry, Lookup, Section};
+
+/// Initialization of COM on the current thread, which is undone when this is dropped.
+pub(crate) struct ComApartment;
+
+impl ComApartment {
+    pub(crate) fn initialize() -> Result<Self, Error> {
+        unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }
+            .ok()
+            .map_err(Error::Com)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for ComApartment {
+    fn drop(&mut self) {
+        unsafe { CoUninitialize() };
+    }
+}
+
+/// Gives the name used in `KnownFolderDefinition::category` for a known folder category.
+fn category_name(category: KF_CATEGORY) -> &'static str {
+    match category {
+        KF_CATEGORY_VIRTUAL => "virtual",
+        KF_CATEGORY_FIXED => "fixed",
+        KF_CATEGORY_COMMON => "common",
+        KF_CATEGORY_PERUSER => "per-user",
+        _ => "unknown",
+    }
+}
+
+/// Loads an indirect string, such as `@%SystemRoot%\system32\shell32.dll,-21781`, in the user's
+/// display language, with [`SHLoadIndirectString`][shlis].
+///
+/// [shlis]: https://learn.microsoft.com/en-us/windows/win32/api/shlwapi/nf-shlwapi-shloadindirectstring
+fn load_indirect_string(source: &str) -> Option<String> {
+    let mut buffer = [0u16; 1024];
+    unsafe { SHLoadIndirectString(&HSTRING::from(source), &mut buffer, None) }.ok()?;
+    let length = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16(&buffer[..length]).ok()
+}
+
+/// Finds the indirect string for the name a directory is shown with in Explorer, from its
+/// `desktop.ini` file, with [`SHGetLocalizedName`][shgln].
+///
+/// [shgln]: https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-shgetlocalizedname
+fn localized_name_of_path(path: &str) -> Option<String> {
+    let mut module = [0u16; 260];
+    let mut id = 0;
+    unsafe { SHGetLocalizedName(&HSTRING::from(path), &mut module, &mut id) }.ok()?;
+    let length = module.iter().position(|&c| c == 0).unwrap_or(module.len());
+    let module = String::from_utf16(&module[..length]).ok()?;
+    Some(format!("@{module},-{id}"))
+}
+
+/// Retrieves the definition of a known folder, freeing the strings Windows allocated for it.
+///
+/// This does what the `FreeKnownFolderDefinitionFields` inline function in `ShObjIdl_core.h`
+/// does, by taking ownership of every string field, including those not used here.
+///
+/// The folder's localized name is found from the indirect string in the definition, or, if the
+/// definition has none, from the `desktop.ini` file in its directory, which is at `path`.
+fn get_definition(
+    folder: &IKnownFolder,
+    path: Option<&str>,
+) -> Result<KnownFolderDefinition, Error> {
+    let mut raw = KNOWNFOLDER_DEFINITION::default();
+    unsafe { folder.GetFolderDefinition(&mut raw) }.map_err(Error::KnownFolder)?;
+
+    let name = CoStr::new(raw.pszName);
+    let relative_path = CoStr::new(raw.pszRelativePath);
+    let parsing_name = CoStr::new(raw.pszParsingName);
+    let localized_name = CoStr::new(raw.pszLocalizedName);
+    let _others = [
+        raw.pszDescription,
+        raw.pszTooltip,
+        raw.pszIcon,
+        raw.pszSecurity,
+    ]
+    .map(CoStr::new);
+
+    let localized_name_source = localized_name
+        .to_optional_string()?
+        .or_else(|| path.and_then(localized_name_of_path));
+
+    Ok(KnownFolderDefinition {
+        canonical_name: name.to_string()?,
+        localized_name: localized_name_source
+            .as_deref()
+            .and_then(load_indirect_string),
+        localized_name_source,
+        category: category_name(raw.category),
+        parsing_name: parsing_name.to_optional_string()?,
+        relative_path: relative_path.to_optional_string()?,
+        parent: (raw.fidParent != GUID::zeroed()).then_some(raw.fidParent),
+    })
+}
+
+/// Looks up one known folder's path and definition through the known folder manager.
+fn look_up(manager: &IKnownFolderManager, symbol: &str, id: GUID) -> KnownFolderDefinitionEntry {
+    let folder = unsafe { manager.GetFolder(&id) }.map_err(Error::KnownFolder);
+
+    let (lookup, definition) = match folder {
+        Ok(folder) => {
+            let path = unsafe { folder.GetPath(KF_FLAG_DEFAULT.0 as u32) }
+                .map_err(Error::KnownFolder)
+                .map(|pwstr| CoStr::new(pwstr).to_os_string());
+            let lookup = Lookup::new(path);
+            let definition = get_definition(&folder, lookup.path.as_deref()).ok();
+            (lookup, definition)
+        }
+        Err(e) => (Lookup::new(Err::<String, _>(e)), None),
+    };
+
+    KnownFolderDefinitionEntry {
+        name: symbol.to_owned(),
+        id,
+        lookup,
+        definition,
+    }
+}
+
+/// Report *program files* known folders, with their definitions, by querying `IKnownFolder`.
+///
+/// As noted on `report_known_folders()`, [`IKnownFolder`][ikf] objects are a richer source of
+/// information than `SHGetKnownFolderPath`, which gives only a path. This gets an `IKnownFolder`
+/// for each of the same four folder IDs from an [`IKnownFolderManager`][ikfm], then gets the path
+/// with [`IKnownFolder::GetPath`][ikf-gp] and the folder's [definition][kfd] with
+/// [`IKnownFolder::GetFolderDefinition`][ikf-gfd]. The definition shows how Windows works out the
+/// path: a *fixed* folder, such as the program files folders, has a path that Windows itself
+/// determines, while a *per-user* or *common* folder is usually given by a path relative to a
+/// parent folder.
+///
+/// The definition also gives the folder's localized name, which is what Explorer shows for it. On
+/// Windows in a language other than English, this differs from the name of the directory on disk,
+/// so both may be needed to tell which folder someone is describing. The name is stored as an
+/// indirect string, such as `@%SystemRoot%\system32\shell32.dll,-21781`, which is loaded in the
+/// user's display language. A folder whose definition has no localized name may still be given one
+/// by a `desktop.ini` file in its directory, which is read with [`SHGetLocalizedName`][shgln].
+///
+/// The path should be the same as `report_known_folders()` gives, since `SHGetKnownFolderPath` is
+/// implemented on top of the same facilities.
+///
+/// Unlike the other sources, this requires COM, which is initialized for the duration of the
+/// call. If a folder's definition cannot be retrieved, its path is still reported.
+///
+/// [ikf]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-iknownfolder
+/// [ikfm]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-iknownfoldermanager
+/// [ikf-gp]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfolder-getpath
+/// [kfd]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/ns-shobjidl_core-knownfolder_definition
+/// [ikf-gfd]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfolder-getfolderdefinition
+/// [shgln]: https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-shgetlocalizedname
+pub fn report_known_folder_definitions() -> Result<Section<KnownFolderDefinitionEntry>, Error> {
+    let _apartment = ComApartment::initialize()?;
+
+    let manager: IKnownFolderManager =
+        unsafe { CoCreateInstance(&KnownFolderManager, None, CLSCTX_INPROC_SERVER) }
+            .map_err(Error::Com)?;
+
+    let entries = PROGRAM_FILES_KNOWN_FOLDERS
+        .into_iter()
+        .map(|(symbol, id)| look_up(&manager, symbol, id))
+        .collect();
+
+    Ok(Section::new("Relevant known folder definitions", entries))
+}
+
+/// Gets the IDs of every known folder registered on the system, from the known folder manager.
+fn folder_ids(manager: &IKnownFolderManager) -> Result<Vec<GUID>, Error> {
+    let mut ids = std::ptr::null_mut();
+    let mut count = 0;
+    unsafe { manager.GetFolderIds(&mut ids, &mut count) }.map_err(Error::KnownFolder)?;
+
+    let copied = if ids.is_null() {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(ids, count as usize) }.to_vec()
+    };
+    unsafe { CoTaskMemFree(Some(ids.cast_const().cast())) };
+
+    Ok(copied)
+}
+
+/// Report every known folder on the system, with its definition, by querying `IKnownFolder`.
+///
+/// This is a superset of `report_known_folder_definitions()`, for when more than the program files
+/// folders is needed. Instead of looking up a fixed list of folder IDs, it enumerates the IDs of
+/// all known folders with [`IKnownFolderManager::GetFolderIds`][ikfm-gfi], which includes those
+/// registered by OEMs and applications as well as those defined by Windows. Each is then looked up
+/// in the same way.
+///
+/// Since most of these folders have no symbolic name in the Windows SDK, each entry is named by
+/// the folder's canonical name, or by its ID if its definition could not be retrieved. Entries are
+/// in the order the known folder manager gives them, which is not meaningful.
+///
+/// Many known folders have no path, such as virtual folders like the Control Panel, or folders
+/// whose directories have not been created, so many entries usually have errors.
+///
+/// [ikfm-gfi]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfoldermanager-getfolderids
+pub fn report_all_known_folders() -> Result<Section<KnownFolderDefinitionEntry>, Error> {
+    let _apartment = ComApartment::initialize()?;
+
+    let manager: IKnownFolderManager =
+        unsafe { CoCreateInstance(&KnownFolderManager, None, CLSCTX_INPROC_SERVER) }
+            .map_err(Error::Com)?;
+
+    let entries = folder_ids(&manager)?
+        .into_iter()
+        .map(|id| {
+            let mut entry = look_up(&manager, "", id);
+            entry.name = match &entry.definition {
+                Some(definition) => definition.canonical_name.clone(),
+                None => format!("{{{id:?}}}"),
+            };
+            entry
+        })
+        .collect();
+
+    Ok(Section::new("All known folders", entries))
+}
+
+/// Report any known folder, with its definition, by its ID or canonical name.
+///
+/// This makes the known folder manager usable as a general known folder inspector, for folders
+/// other than the program files folders, such as those registered by OEMs and applications. If
+/// `folder` is a GUID, with or without braces, the folder is found with
+/// [`IKnownFolderManager::GetFolder`][ikfm-gf]. Otherwise, it is taken to be a canonical name,
+/// such as `ProgramFilesX86`, and found with [`IKnownFolderManager::GetFolderByName`][ikfm-gfbn].
+/// The folder is then looked up as `report_known_folder_definitions()` looks up each program files
+/// folder, and its entry is named by its canonical name, as in `report_all_known_folders()`.
+///
+/// If there is no such folder, that is an error.
+///
+/// [ikfm-gf]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfoldermanager-getfolder
+/// [ikfm-gfbn]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfoldermanager-getfolderbyname
+pub fn report_known_folder(folder: &str) -> Result<Section<KnownFolderDefinitionEntry>, Error> {
+    let _apartment = ComApartment::initialize()?;
+
+    let manager: IKnownFolderManager =
+        unsafe { CoCreateInstance(&KnownFolderManager, None, CLSCTX_INPROC_SERVER) }
+            .map_err(Error::Com)?;
+
+    let id = match parse_guid(folder).ok() {
+        Some(id) => {
+            unsafe { manager.GetFolder(&id) }.map_err(Error::KnownFolder)?;
+            id
+        }
+        None => unsafe { manager.GetFolderByName(&HSTRING::from(folder)) }
+            .and_then(|found| unsafe { found.GetId() })
+            .map_err(Error::KnownFolder)?,
+    };
+
+    let mut entry = look_up(&manager, "", id);
+    entry.name = match &entry.definition {
+        Some(definition) => definition.canonical_name.clone(),
+        None => format!("{{{id:?}}}"),
+    };
+
+    Ok(Section::new(format!("Known folder {folder}"), vec![entry]))
+}
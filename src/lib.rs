@@ -0,0 +1,205 @@
+//! Looks up information from multiple sources about where program files directories are located on
+//! a Windows system.
+//!
+//! [`Report::collect()`] looks up every source and returns the results as typed entries, one
+//! section per source. The `pfdirs` program prints them. [`resolve_program_files()`] instead tries
+//! the sources in order of preference and gives just the path of the requested directory.
+//!
+//! Example output of the `pfdirs` program, from a 32-bit process running on an x86-64 Windows
+//! system:
+//!
+//! ```text
+//! Relevant environment variables:
+//!
+//!   ProgramFiles       C:\Program Files (x86)
+//!   ProgramFiles(Arm)  [environment variable not found]
+//!   ProgramFiles(x86)  C:\Program Files (x86)
+//!   ProgramW6432       C:\Program Files
+//!
+//! Relevant known folders:
+//!
+//!   FOLDERID_ProgramFiles      C:\Program Files (x86)
+//!   FOLDERID_ProgramFilesX64   [The system cannot find the file specified. (0x80070002)]
+//!   FOLDERID_ProgramFilesX86   C:\Program Files (x86)
+//!   FOLDERID_UserProgramFiles  C:\Users\ek\AppData\Local\Programs
+//!
+//! Relevant CSIDLs:
+//!
+//!   CSIDL_PROGRAM_FILES     C:\Program Files (x86)
+//!   CSIDL_PROGRAM_FILESX86  C:\Program Files (x86)
+//!
+//! Relevant registry keys - with default view:
+//!
+//!   ProgramFilesDir        C:\Program Files (x86)
+//!   ProgramFilesDir (Arm)  [The system cannot find the file specified. (os error 2)]
+//!   ProgramFilesDir (x86)  C:\Program Files (x86)
+//!   ProgramW6432Dir        C:\Program Files
+//!
+//! Relevant registry keys - with KEY_WOW64_32KEY:
+//!
+//!   ProgramFilesDir        C:\Program Files (x86)
+//!   ProgramFilesDir (Arm)  [The system cannot find the file specified. (os error 2)]
+//!   ProgramFilesDir (x86)  C:\Program Files (x86)
+//!   ProgramW6432Dir        C:\Program Files
+//!
+//! Relevant registry keys - with KEY_WOW64_64KEY:
+//!
+//!   ProgramFilesDir        C:\Program Files
+//!   ProgramFilesDir (Arm)  [The system cannot find the file specified. (os error 2)]
+//!   ProgramFilesDir (x86)  C:\Program Files (x86)
+//!   ProgramW6432Dir        C:\Program Files
+//! ```
+//!
+//! On 64-bit Windows, the `ProgramFiles` environment variable, `FOLDERID_ProgramFiles` known
+//! folder, `CSIDL_PROGRAM_FILES`, and `ProgramFilesDir` registry key, look up a path that differs
+//! depending on whether the program accessing the information is 64-bit or 32-bit.
+//!
+//! On such a system, whether x86_64 (AMD64) or ARM64, a 64-bit process reports the 64-bit program
+//! files directory, most often `C:\Program Files`, while a 32-bit process reports the 32-bit
+//! program files directory, most often `C:\Program Files (x86)`.
+//!
+//! In contrast, *when available*:
+//!
+//! - The `ProgramFiles(x86)` environment variable, `FOLDERID_ProgramFilesX86` known folder,
+//!   `CSIDL_PROGRAM_FILESX86`, and `ProgramFilesDir (x86)` registry key report the 32-bit program
+//!   files directory.
+//!
+//! - The `ProgramW6432` environment variable, `FOLDERID_ProgramFilesX64` known folder, and
+//!   `ProgramW6432Dir` registry key report the 64-bit program files directory.
+//!
+//! However, not all of them are always available to all processes on all Windows systems.
+//!
+//! As detailed in comments on the `report_*` function of each source's module, Microsoft
+//! documentation tends to recommend obtaining such paths through the *known folders* facilities.
+//! However, as shown above, even on a 64-bit system, a 32-bit process unfortunately does not see
+//! any `FOLDERID_ProgramFilesX64` known folder (and there is no CSIDL corresponding to that).
+//!
+//! On such a system it may therefore be necessary to use either the `ProgramW6432` environment
+//! variable or the `ProgramW6432Dir` registry key to get the path of the 64-bit program files
+//! directory:
+//!
+//! - Accessing the `ProgramW6432` environment variable is easy and seems to be more common. Some
+//!   forms of unusual customization by a parent process of its child processes' environments will
+//!   break this. See [`report_environment_variables()`] for details.
+//!
+//! - The `ProgramW6432Dir` registry key appears to be available on 64-bit systems through any
+//!   registry view.
+//!
+//! On a 32-bit system, there is no way to get the 64-bit program files directory, because there is
+//! no such directory.
+
+pub mod analysis;
+#[cfg(windows)]
+pub mod ancestry;
+pub mod apps;
+pub mod arm;
+#[cfg(all(windows, feature = "com"))]
+pub mod clipboard;
+pub mod csidl;
+#[cfg(windows)]
+pub mod elevation;
+pub mod environment;
+pub mod error;
+#[cfg(windows)]
+pub mod eventlog;
+#[cfg(feature = "serde")]
+pub mod experiment;
+#[cfg(all(windows, feature = "com"))]
+pub mod explorer;
+pub mod filesystem;
+pub mod known_folder;
+#[cfg(all(windows, feature = "com"))]
+pub mod known_folder_definition;
+pub mod msi;
+pub mod narrative;
+pub mod os;
+#[cfg(windows)]
+pub mod output;
+pub mod package;
+pub mod per_user;
+pub mod platform;
+pub mod policy;
+pub mod redact;
+pub mod registry;
+pub mod render;
+pub mod report;
+pub mod resolve;
+#[cfg(feature = "serde")]
+pub mod saved;
+#[cfg(windows)]
+pub mod security;
+pub mod service;
+pub mod shell_folders;
+pub mod shell_moniker;
+pub mod shells;
+#[cfg(windows)]
+pub mod source;
+pub mod system_directories;
+#[cfg(windows)]
+pub mod timing;
+#[cfg(windows)]
+pub mod token;
+#[cfg(all(windows, feature = "tui"))]
+pub mod tui;
+#[cfg(windows)]
+pub mod users;
+pub mod virtualization;
+#[cfg(windows)]
+pub mod watch;
+pub mod wmi;
+
+pub use crate::analysis::SourceKind;
+#[cfg(windows)]
+pub use crate::arm::report_arm_program_files;
+#[cfg(windows)]
+pub use crate::csidl::{report_csidl, report_csidl_locations};
+#[cfg(windows)]
+pub use crate::elevation::{Elevation, ElevationType, IntegrityLevel};
+#[cfg(windows)]
+pub use crate::environment::{
+    report_environment_registry, report_environment_variables, report_fresh_environment,
+};
+pub use crate::error::{CodeKind, EntryError, Error, ResolveError};
+#[cfg(windows)]
+pub use crate::known_folder::report_known_folders;
+#[cfg(all(windows, feature = "com"))]
+pub use crate::known_folder_definition::{
+    report_all_known_folders, report_known_folder, report_known_folder_definitions,
+};
+#[cfg(all(windows, feature = "com"))]
+pub use crate::msi::report_msi;
+#[cfg(all(windows, feature = "com"))]
+pub use crate::package::report_package;
+#[cfg(windows)]
+pub use crate::per_user::report_per_user_installs;
+pub use crate::platform::{Machine, Platform, ProcessArchitecture};
+#[cfg(all(windows, feature = "com"))]
+pub use crate::policy::report_policy;
+#[cfg(windows)]
+pub use crate::registry::{
+    report_all_registry_views, report_literal_path_view, report_registry_view,
+};
+pub use crate::report::{
+    escape_non_unicode, interrupt_collection, CsidlEntry, Entry, EnvVarEntry, EnvironmentKey,
+    FreshEnvVarEntry, KnownFolderDefinitionEntry, KnownFolderEntry, Lookup, RegistryEntry,
+    RegistryView, Report, Row, Section, SectionRef, Selection, ShellFolderEntry, ShellFolderKey,
+    SortOrder, UserProfile,
+};
+#[cfg(windows)]
+pub use crate::resolve::resolve_program_files;
+pub use crate::resolve::{resolve_program_files_with, Arch};
+pub use crate::service::{ServiceAccount, ServiceContext};
+#[cfg(windows)]
+pub use crate::shell_folders::report_shell_folders;
+#[cfg(all(windows, feature = "com"))]
+pub use crate::shell_moniker::report_shell_monikers;
+#[cfg(windows)]
+pub use crate::shells::report_shells;
+#[cfg(windows)]
+pub use crate::system_directories::report_system_directories;
+#[cfg(windows)]
+pub use crate::users::report_all_users;
+#[cfg(windows)]
+pub use crate::virtualization::report_virtualization;
+#[cfg(all(windows, feature = "com"))]
+pub use crate::wmi::report_wmi;
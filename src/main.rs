@@ -1,450 +1,268 @@
 //! Reports information from multiple sources about where program files directories are located on
 //! a Windows system.
 //!
-//! Example output, from a 32-bit process running on an x86-64 Windows system:
+//! The information is looked up by the `pfdirs` library crate, whose documentation shows example
+//! output and describes each source. This program chooses what to look up, and how to show it,
+//! from its command-line arguments.
 //!
-//! ```text
-//! Relevant environment variables:
+//! By default, all sources are reported. Passing one or more of `--env`, `--env-registry`,
+//! `--fresh-env`, `--known-folders`, `--known-folder-definitions`, `--shell-monikers`, `--csidl`,
+//! `--registry`, and `--shell-folders` reports only those sources. Passing `--all` reports all of
+//! them.
 //!
-//!   ProgramFiles       C:\Program Files (x86)
-//!   ProgramFiles(Arm)  [environment variable not found]
-//!   ProgramFiles(x86)  C:\Program Files (x86)
-//!   ProgramW6432       C:\Program Files
+//! On ARM64 systems, reporting the known folders or CSIDLs also reports the 32-bit ARM program
+//! files directory, which has neither, from its environment variable and registry value.
+//! Reporting the known folders also reports where per-user installs go other than the per-user
+//! program files directory, including the per-user MSIX `WindowsApps` directory. Reporting the
+//! CSIDLs reports them both through `SHGetFolderPathW` and through `SHGetFolderLocation`.
 //!
-//! Relevant known folders:
+//! The `--fresh-env` section is followed by the variables this process inherited that are stale,
+//! because they differ from what a process started for the same user now would get.
 //!
-//!   FOLDERID_ProgramFiles      C:\Program Files (x86)
-//!   FOLDERID_ProgramFilesX64   [The system cannot find the file specified. (0x80070002)]
-//!   FOLDERID_ProgramFilesX86   C:\Program Files (x86)
-//!   FOLDERID_UserProgramFiles  C:\Users\ek\AppData\Local\Programs
+//! If `ProgramW6432` or `ProgramFiles(x86)` is missing on 64-bit Windows, the chain of ancestor
+//! processes is shown, with the architecture of each, to help find which one removed it.
 //!
-//! Relevant CSIDLs:
+//! When both the inherited environment variables and the ones stored in the registry are
+//! reported, an "Environment origins" section follows, telling for each variable whether a new
+//! environment would get it from the registry or have it computed by Windows, and whether that
+//! matches what this process inherited.
 //!
-//!   CSIDL_PROGRAM_FILES     C:\Program Files (x86)
-//!   CSIDL_PROGRAM_FILESX86  C:\Program Files (x86)
+//! Passing `--computer NAME` reports the registry and shell folders sources of another computer,
+//! read through its Remote Registry service, instead of this one's. The other sources can only be
+//! looked up locally, so they cannot be combined with it.
 //!
-//! Relevant registry keys - with default view:
+//! Passing `--token default-user` reports only the known folders, as the default user would see
+//! them. Passing `--as-user NAME` does the same for the named user, who must be logged on, using a
+//! token taken from one of their processes, which usually requires running as an administrator.
 //!
-//!   ProgramFilesDir        C:\Program Files (x86)
-//!   ProgramFilesDir (Arm)  [The system cannot find the file specified. (os error 2)]
-//!   ProgramFilesDir (x86)  C:\Program Files (x86)
-//!   ProgramW6432Dir        C:\Program Files
+//! Passing `--hive PATH` reports only the program files registry values in an offline `SOFTWARE`
+//! hive, such as the one in a mounted Windows image, including those under `WOW6432Node`.
 //!
-//! Relevant registry keys - with KEY_WOW64_32KEY:
+//! Passing `--all-users` reports only the per-user environment and shell folders registry values,
+//! for every profile on this computer, loading the hives of users who are not logged on.
 //!
-//!   ProgramFilesDir        C:\Program Files (x86)
-//!   ProgramFilesDir (Arm)  [The system cannot find the file specified. (os error 2)]
-//!   ProgramFilesDir (x86)  C:\Program Files (x86)
-//!   ProgramW6432Dir        C:\Program Files
+//! By default the report is printed as text tables, as in the example output in the library
+//! documentation. Passing `--format json`, `--format yaml`, or `--format toml` (or just `--toml`)
+//! prints the same information as JSON, YAML, or TOML instead, with each entry's path or error,
+//! including any numeric error code, as a separate field. Passing `--format markdown` prints each
+//! section as a Markdown table, after a table of information about the system, for pasting into
+//! bug reports. Passing `--format html` prints a standalone HTML page, with collapsible sections
+//! and anomalies highlighted, for attaching to support tickets. Passing `--format csv` or
+//! `--format tsv` prints one line per entry, with the columns `source`, `view`, `symbol`,
+//! `status`, and `path`, for aggregating results from many machines. Passing `--format jsonl`
+//! prints one JSON object per entry, each source's as soon as it is looked up, with how long that
+//! took, for log shippers.
 //!
-//! Relevant registry keys - with KEY_WOW64_64KEY:
+//! The text output begins with a header giving the architectures of this process and of the
+//! system, as reported by `IsWow64Process2`, and whether the process is running under WOW64, is
+//! emulated, or is ARM64EC, followed by whether it is elevated, its integrity level, and whether
+//! UAC is on. The Markdown output shows these in its table about the system.
 //!
-//!   ProgramFilesDir        C:\Program Files
-//!   ProgramFilesDir (Arm)  [The system cannot find the file specified. (os error 2)]
-//!   ProgramFilesDir (x86)  C:\Program Files (x86)
-//!   ProgramW6432Dir        C:\Program Files
-//! ```
+//! The text and Markdown output then have a "Resolved" section giving, for each program files
+//! directory, the path the sources agree on, and flagging any sources that disagree with it.
 //!
-//! On 64-bit Windows, the `ProgramFiles` environment variable, `FOLDERID_ProgramFiles` known
-//! folder, `CSIDL_PROGRAM_FILES`, and `ProgramFilesDir` registry key, look up a path that differs
-//! depending on whether the program accessing the information is 64-bit or 32-bit.
+//! Passing `--verify-fs` adds a column to each section of the text output checking each path on
+//! disk: whether it exists and is a directory, whether it is a junction or other reparse point, and
+//! whether its final path, with links resolved, differs from the reported path. In a 32-bit process
+//! on 64-bit Windows, also passing `--verify-unredirected` adds another column checking each path
+//! again with WOW64 file system redirection disabled, as a 64-bit process would see it.
 //!
-//! On such a system, whether x86_64 (AMD64) or ARM64, a 64-bit process reports the 64-bit program
-//! files directory, most often `C:\Program Files`, while a 32-bit process reports the 32-bit
-//! program files directory, most often `C:\Program Files (x86)`.
+//! Passing `--short-names` adds columns to each section of the text output giving each path's 8.3
+//! short form and long form, for correlating paths such as `C:\PROGRA~1` that legacy installers
+//! may have stored with the paths reported here.
 //!
-//! In contrast, *when available*:
+//! Passing `--volumes` adds a table showing the volume each program files directory is really on,
+//! with its volume GUID path, its filesystem, whether it is a Dev Drive, and its free space, for
+//! diagnosing installs onto unusual volumes.
 //!
-//! - The `ProgramFiles(x86)` environment variable, `FOLDERID_ProgramFilesX86` known folder,
-//!   `CSIDL_PROGRAM_FILESX86`, and `ProgramFilesDir (x86)` registry key report the 32-bit program
-//!   files directory.
+//! Passing `--security` adds a table showing who owns each program files directory and a summary
+//! of what `TrustedInstaller`, `SYSTEM`, `Administrators`, `Users`, `Authenticated Users`, and
+//! `Everyone` can do to it, flagging any directory that all users can write to.
 //!
-//! - The `ProgramW6432` environment variable, `FOLDERID_ProgramFilesX64` known folder, and
-//!   `ProgramW6432Dir` registry key report the 64-bit program files directory.
+//! Passing `--probe-write` adds a table showing whether this process can really write to each
+//! program files directory, found by creating a uniquely named file in it and deleting it, which
+//! distinguishes a directory that exists from one an installer run the same way could install to.
 //!
-//! However, not all of them are always available to all processes on all Windows systems.
+//! When reporting on this system, the text output also flags any program files directory that
+//! appears to have been relocated: not on the system drive, linked to another volume, or not at
+//! its default path, and any entries for different program files directories that are really the
+//! same directory on disk, as compared by volume serial number and file ID. It also flags signs
+//! that UAC virtualization is redirecting writes meant for the program files directories to this
+//! user's virtual store.
 //!
-//! As detailed in comments on specific `report_*` functions below, Microsoft documentation tends
-//! to recommend obtaining such paths through the *known folders* facilities. However, as shown
-//! above, even on a 64-bit system, a 32-bit process unfortunately does not see any
-//! `FOLDERID_ProgramFilesX64` known folder (and there is no CSIDL corresponding to that).
+//! Passing `--redirection` adds a section showing whether each program files known folder has been
+//! redirected from its default location.
 //!
-//! On such a system it may therefore be necessary to use either the `ProgramW6432` environment
-//! variable or the `ProgramW6432Dir` registry key to get the path of the 64-bit program files
-//! directory:
+//! Passing `--kf-flags` adds a table showing each program files known folder as looked up with
+//! `KF_FLAG_DEFAULT`, `KF_FLAG_DONT_VERIFY`, `KF_FLAG_DEFAULT_PATH`, and `KF_FLAG_NO_ALIAS`, side
+//! by side, since redirected or missing folders behave differently with each.
 //!
-//! - Accessing the `ProgramW6432` environment variable is easy and seems to be more common. Some
-//!   forms of unusual customization by a parent process of its child processes' environments will
-//!   break this. See `report_environment_variables()` below for details.
+//! Passing `--id-lists` adds a table showing each program files known folder looked up as an item
+//! ID list, converted back to a path, and checked against the path it is usually looked up as.
 //!
-//! - The `ProgramW6432Dir` registry key appears to be available on 64-bit systems through any
-//!   registry view.
+//! Passing `--arch` with a comma-separated list of `x86`, `x64`, `arm64`, and `user` reports only
+//! the entries for those program files directories, and passing `--kind` with a comma-separated
+//! list of `env`, `kf`, `csidl`, and `registry` reports only those kinds of sources.
 //!
-//! On a 32-bit system, there is no way to get the 64-bit program files directory, because there is
-//! no such directory.
-
-use core::ffi::c_void;
-use std::io;
-use std::string::FromUtf16Error;
-
-use known_folders::{get_known_folder_path, KnownFolder};
-use windows::core::{Error, GUID, PCWSTR, PWSTR};
-use windows::Win32::Foundation::MAX_PATH;
-use windows::Win32::System::Com::CoTaskMemFree;
-use windows::Win32::UI::Shell::{
-    FOLDERID_ProgramFiles, FOLDERID_ProgramFilesX64, FOLDERID_ProgramFilesX86,
-    FOLDERID_UserProgramFiles, SHGetFolderPathW, SHGetKnownFolderPath, CSIDL_PROGRAM_FILES,
-    CSIDL_PROGRAM_FILESX86, KF_FLAG_DEFAULT, SHGFP_TYPE_CURRENT,
-};
-use winreg::{
-    enums::{HKEY_LOCAL_MACHINE, KEY_QUERY_VALUE, KEY_WOW64_32KEY, KEY_WOW64_64KEY},
-    RegKey,
-};
-
-/// Finds the width of the symbolic name column for the table of reported results.
-fn column_width<'a, I>(names: I) -> usize
-where
-    I: IntoIterator<Item = &'a str>,
-{
-    names
-        .into_iter()
-        .map(|name| name.chars().count())
-        .max()
-        .unwrap_or(0)
-}
-
-/// Report *program files* folder locations contained in environment variables.
-///
-/// Environment variables are convenient, but less reliable than known folders, and probably less
-/// reliable than the other methods. Everything is fine so long as no ancestor process has removed
-/// program files related variables from its environment or created its child with a custom
-/// environment that omits them. If they are all omitted, such as if the parent process passed down
-/// an empty environment, then this will obviously fail. But the more subtle case is where some but
-/// not all of them are passed down. It is easy for a parent process to get it wrong. Key points:
-///
-/// 1. On a 32-bit x86 Windows system, there is exactly one program files directory, and the
-///    `ProgramFiles` environment variable should have its path. The other environment variables
-///    are not typically set on a 32-bit Windows system. (Currently there is no Rust target for
-///    32-bit ARM systems, so a Rust program is very unlikely to run on one.)
-///
-/// 2. On a 64-bit Windows system, including ARM64, there are at least two program files
-///    directories. An x86-64 (AMD64) system has two, and an ARM64 (AArch64) system has three.
-///    Processes thus inherit the `ProgramFiles` environment variable, as well as two or three
-///    others that indicate program files directories associated with particular architectures.
-///
-/// 3. On 64-bit Windows, the `ProgramFiles` environment variable is inherited by the child process
-///    to hold the path of the program files directory associated with the architecture of that
-///    child process. But how can this be? After all, the parent may be a different architecture,
-///    and environment variables (or most of them, including these) are inherited from the parent.
-///
-/// 4. On 64-bit Windows, a child inherits `ProgramFiles` from its parent, but it does not usually
-///    inherit it from its parent's `Program Files` variable. Instead, and regardless of the
-///    "bitness" of the parent process, a 64-bit child process receives `ProgramFiles` from the
-///    value the parent passed down as `ProgramW6432` (whether that child is x86-64 or ARM64, since
-///    both 64-bit architectures use the same program files directory), a 32-bit x86 child process
-///    receives `ProgramFiles` from the value the parent passed down as `ProgramFiles(x86)`, and a
-///    32-bit ARM child process receives `ProgramFiles` from the value the parent passed down as
-///    `ProgramFiles(ARM)`.
-///
-/// 5. On 64-bit Windows, only if the environment variable corresponding to the child process's
-///    architecture was not passed down does the child receive `ProgramFiles` from the value the
-///    parent passed down as `ProgramFiles`. While this is the normal situation on a 32-bit system,
-///    it is a fallback situation on a 64-bit system and unreliable, because if the parent and
-///    child architectures differ and the parent overly sanitizes the environment for the child,
-///    then code in the child that needs a program files directory of the same architecture as the
-///    child will malfunction, and code in the child that seeks to discover all program files
-///    directories will fail if it (solely) makes use of environment variables to do so.
-///
-/// Some of this behavior is documented in [WOW64 Implementation Details][wow64ev].
-///
-/// [wow64ev]: https://learn.microsoft.com/en-us/windows/win32/winprog64/wow64-implementation-details#environment-variables
-fn report_environment_variables() {
-    let names = [
-        "ProgramFiles",
-        "ProgramFiles(Arm)",
-        "ProgramFiles(x86)",
-        "ProgramW6432",
-    ];
-    let width = column_width(names);
-
-    println!("Relevant environment variables:");
-    println!();
-
-    for name in names {
-        let path_item = std::env::var(name).unwrap_or_else(|e| format!("[{e}]"));
-        println!("  {name:<width$}  {path_item}");
-    }
-
-    println!();
-}
-
-/// Owner of a `PWSTR` that must be freed with `CoTaskMemFree`.
-struct CoStr {
-    pwstr: PWSTR,
-}
-
-impl CoStr {
-    fn new(pwstr: PWSTR) -> Self {
-        Self { pwstr }
-    }
-
-    fn to_string(&self) -> Result<String, FromUtf16Error> {
-        unsafe { self.pwstr.to_string() }
-    }
-}
-
-// TODO: Figure out whether to implement windows::core::Owned instead.
-impl Drop for CoStr {
-    fn drop(&mut self) {
-        unsafe { CoTaskMemFree(Some(self.pwstr.as_ptr().cast::<c_void>())) };
-    }
-}
-
-/// Helper that calls `ShGetKnownFolderPath` on behalf of `report_known_folders()`.
-///
-/// TODO: Figure out if we should also check with other flags than KF_FLAG_DEFAULT.
-fn get_known_folder_path_or_detailed_error(id: GUID) -> Result<String, Error> {
-    match unsafe { SHGetKnownFolderPath(&id, KF_FLAG_DEFAULT, None) } {
-        Ok(pwstr) => Ok(CoStr::new(pwstr).to_string()?),
-        Err(e) => Err(e),
-    }
-}
-
-/// Report *program files* folder locations by querying *known folders*.
-///
-/// See [Known Folders][kf]. This is a recommended approach. This can be done through the Windows
-/// API or indirectly through a crate that wraps it. This function showcases both and asserts that
-/// the information provided, where overlapping, is identical.
-///
-/// #### Windows API
-///
-/// Windows provides two approaches in its API for accessing the paths of known folders:
-///
-/// - The [`SHGetKnownFolderPath`][shgkfp] function. This approach is more straightforward and
-///   typically sufficient when the GUIDs are known and only paths are needed. (There are a small
-///   number of other related functions for obtaining other information.) This is the approach used
-///   here.
-///
-/// - The [`IKnownFolder::GetPath`][ikf-gp] method. This is more involved, but `IKnownFolder` COM
-///   objects are a richer source of information. For example, `IKnownFolder` supports iterating
-///   over all known folders.
-///
-/// #### known-folders crate
-///
-/// The [kfcrate] crate provides a `get_known_folder_path()` function that takes care of calling
-/// `SHGetKnownFolderPath` from Rust code. However, this is limited to simple uses:
-///
-/// - It does not accept custom `KNOWN_FOLDER_FLAGS` or a custom access token.
-///
-/// - It returns an `Option` rather than a `Result`, so when a known folder path is unavailable,
-///   the different errors that can cause this are not distinguished.
-///
-/// But in the most common cases `get_known_folder_path()` is sufficient.
-///
-/// #### What this function does
-///
-/// This uses both `SHGetKnownFolderPath`, called through the `windows` crate, and
-/// `get_known_folder_path()`, provided by the `known-folders` crate, and compares the results for
-/// whether there was an error and, if not, whether the paths match. Calling both is for
-/// experimentation and demonstration purposes. Generally at most one of these two approaches
-/// should be used, depending on requirements.
-///
-/// This looks up only the four folder IDs for *program files* folders. Their GUIDs are available
-/// as symbolic constants both in the `windows` crate as `GUID` objects and, as a higher level
-/// abstraction, in the `KnownFolder` enum of the `known-folders` crate.
-///
-/// [kf]: https://learn.microsoft.com/en-us/windows/win32/shell/known-folders
-/// [shgkfp]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetknownfolderpath
-/// [ikf-gp]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfolder-getpath
-/// [kfcrate]: https://crates.io/crates/known-folders
-fn report_known_folders() -> Result<(), Error> {
-    // TODO: If we can get the names without initializing COM, do so and display them as well.
-    let folders = [
-        (
-            "FOLDERID_ProgramFiles",
-            FOLDERID_ProgramFiles,
-            KnownFolder::ProgramFiles,
-        ),
-        (
-            "FOLDERID_ProgramFilesX64",
-            FOLDERID_ProgramFilesX64,
-            KnownFolder::ProgramFilesX64,
-        ),
-        (
-            "FOLDERID_ProgramFilesX86",
-            FOLDERID_ProgramFilesX86,
-            KnownFolder::ProgramFilesX86,
-        ),
-        (
-            "FOLDERID_UserProgramFiles",
-            FOLDERID_UserProgramFiles,
-            KnownFolder::UserProgramFiles,
-        ),
-    ];
-    let width = column_width(folders.map(|(name, _, _)| name));
-
-    println!("Relevant known folders:");
-    println!();
-
-    for (symbol, id, kf) in folders {
-        // Calling SHGetKnownFolderPath ourselves gives more detailed error information.
-        let path_or_error = get_known_folder_path_or_detailed_error(id);
-
-        // The `known-folders` crate is simple and easy to use, but gives `Option`, not `Result`.
-        let maybe_path = get_known_folder_path(kf).and_then(|p| p.to_str().map(String::from));
-
-        // Compare the information from both approaches. If inconsistent, panic with the details.
-        let path_item = match (path_or_error, maybe_path) {
-            (Ok(my_kf_path), Some(lib_kf_path)) if my_kf_path == lib_kf_path => my_kf_path,
-            (Err(e), None) => format!("[{e}]"),
-            (my_thing, lib_thing) => {
-                panic!("Mismatch! We got {my_thing:?}, known_folders library got {lib_thing:?}")
-            }
-        };
-
-        // Report the path obtained, or detailed error info from our own SHGetKnownFolderPath call.
-        println!("  {symbol:<width$}  {path_item}");
-    }
-
-    println!();
-    Ok(())
-}
-
-/// Helper that calls `SHGetFolderPathW()` on behalf of `report_csidl()`.
-fn try_get_path_from_csidl(csidl: u32) -> Result<String, Error> {
-    let mut buffer = [0u16; MAX_PATH as usize];
-
-    let path = unsafe {
-        SHGetFolderPathW(
-            None,
-            csidl as i32,
-            None,
-            SHGFP_TYPE_CURRENT.0 as u32,
-            &mut buffer,
-        )?;
-
-        PCWSTR::from_raw(buffer.as_ptr()).to_string()?
-    };
-
-    Ok(path)
-}
-
-/// Report *program files* folder locations via lookups using CSIDLs.
-///
-/// This calls the deprecated [`SHGetFolderPathW`][shgfpw] function.
-///
-/// This is the older way, before the *known folders* facilities were introduced. See [CSIDL].
-///
-/// As noted there, it is recommended to use the known folders APIs instead of CSIDLs, and each
-/// CSIDL value has a corresponding `KNOWNFOLDERID` value. In contrast, not all known folders have
-/// a CSIDL, and also, unlike with CSIDLs, it is possible to register new known folders
-/// programmatically.
-///
-/// From the [remarks section][csidl-remarks] of that article:
-///
-/// > These values supersede the use of environment variables for this purpose. They are in turn
-/// > superseded in Windows Vista and later by the [KNOWNFOLDERID] values.
-///
-/// (This seems to imply, by transitivity, that getting the paths of known folders is also
-/// preferable to accessing the values of environment variables, when both are applicable.)
-///
-/// One limitation of using CSIDLs is that it cannot properly handle the unusual case that the path
-/// is a `\\?\` long path and exceeds [MAX_PATH] characters. As [commented][dotnet-comment] in the
-/// implementation of the .NET Runtime:
-///
-/// > We're using SHGetKnownFolderPath instead of SHGetFolderPath as SHGetFolderPath is capped at
-/// > MAX_PATH.
-///
-/// [shgfpw]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetfolderpathw
-/// [CSIDL]: https://learn.microsoft.com/en-us/windows/win32/shell/csidl
-/// [csidl-remarks]: https://learn.microsoft.com/en-us/windows/win32/shell/csidl#remarks
-/// [KNOWNFOLDERID]: https://learn.microsoft.com/en-us/windows/win32/shell/knownfolderid
-/// [MAX_PATH]: https://learn.microsoft.com/en-us/windows/win32/fileio/maximum-file-path-limitation
-/// [dotnet-comment]: https://github.com/dotnet/runtime/blob/v8.0.7/src/libraries/System.Private.CoreLib/src/System/Environment.Win32.cs#L210-L211
-fn report_csidl() -> Result<(), Error> {
-    let folders = [
-        ("CSIDL_PROGRAM_FILES", CSIDL_PROGRAM_FILES), // Corresponds to: FOLDERID_ProgramFiles
-        ("CSIDL_PROGRAM_FILESX86", CSIDL_PROGRAM_FILESX86), // Corresponds to: FOLDERID_ProgramFilesX86
-    ];
-    let width = column_width(folders.map(|(name, _)| name));
-
-    println!("Relevant CSIDLs:");
-    println!();
-
-    for (symbol, id) in folders {
-        let path_item = try_get_path_from_csidl(id).unwrap_or_else(|e| format!("[{e}]"));
-        println!("  {symbol:<width$}  {path_item}");
-    }
-
-    println!();
-    Ok(())
-}
-
-/// Report *program files* folder locations from a single specified view of the registry.
-///
-/// See `report_all_registry_views()` for more information on views.
-///
-/// This accesses subkeys of `HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion` using the `winreg`
-/// crate, which uses [`RegOpenKeyExW`][regokew].
-///
-/// [regokew]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regopenkeyexw
-fn report_registry_view(caption: &str, flag_for_view: u32) -> Result<(), io::Error> {
-    let key_names = [
-        "ProgramFilesDir",
-        "ProgramFilesDir (Arm)",
-        "ProgramFilesDir (x86)",
-        // "ProgramFilesPath", // Less interesting, usually literal %ProgramFiles% if got this way.
-        "ProgramW6432Dir",
-    ];
-    let width = column_width(key_names);
-
-    let cur_ver = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey_with_flags(
-        r"SOFTWARE\Microsoft\Windows\CurrentVersion",
-        KEY_QUERY_VALUE | flag_for_view,
-    )?;
+//! Passing `--compact` shows the registry views as one section, with a row for each view only for
+//! values the views disagree on.
+//!
+//! Passing `--summary` adds a table at the end with a row for each program files directory and a
+//! column for each source, which makes it easy to see which sources disagree. Passing `--pivot`
+//! shows the report as that table alone, instead of a table for each source, in text, Markdown,
+//! CSV, and TSV.
+//!
+//! Passing `--explain` follows each section with notes, specific to the architecture of the process
+//! and system, on why each entry has the value or error it has.
+//!
+//! Passing `--registry-backend windows-registry` reads the registry values with Microsoft's
+//! `windows-registry` crate instead of `winreg`. Passing `--registry-backend cross-check` reads
+//! them with both, and shows any value on which they diverge as a `DISCREPANCY`.
+//!
+//! When the text output goes to a console, errors are shown in red, paths that disagree with other
+//! sources and other mismatches in yellow, and paths that the sources agree on in green. Passing
+//! `--no-color`, or setting the `NO_COLOR` environment variable to a nonempty value, turns this
+//! off.
+//!
+//! Output to a console is written as UTF-16 with `WriteConsoleW`, which the standard library does
+//! for standard output and standard error whenever they are consoles, so non-ASCII characters in
+//! paths, such as in a localized user name, are shown correctly regardless of the console's code
+//! page. Redirected output is UTF-8, unless `--output-encoding` gives another encoding, such as
+//! UTF-16 or the ANSI code page, for tools that read files written by Windows PowerShell 5.1 or
+//! that read nothing else.
+//!
+//! Paths that are not valid Unicode, which Windows allows, are shown with U+FFFD REPLACEMENT
+//! CHARACTER in place of what cannot be shown. Passing `--escape-non-unicode` shows them with
+//! that escaped instead, as described on `escape_non_unicode()`, so nothing is lost. Structured
+//! output always has the escaped form, as `escaped_path`, for such paths.
+//!
+//! Passing `--redact` masks the user name in each path, as `C:\Users\<user>\AppData`, and in
+//! account names, in every output format, so reports can be shared publicly.
+//!
+//! Passing `--timing` shows, after the text output, how long each API call to look up each entry
+//! took. The `bench` command instead makes each call many times, 1000 unless `-n` says otherwise,
+//! and shows the minimum, mean, and maximum time of each.
+//!
+//! Sources are looked up in parallel. A source that takes longer than 30 seconds, such as when a
+//! Shell API hangs or another computer's registry does not respond, is shown as timed out, with
+//! each of its entries having an error saying so, and the rest of the report is shown as usual.
+//! Passing `--timeout` gives another number of seconds, or 0 to wait as long as it takes.
+//!
+//! Pressing Ctrl+C while the report is being collected, such as when a remote registry or a folder
+//! redirected to a file server is slow, stops waiting for the sources not yet looked up and shows
+//! those that were, followed by a marker saying the report was truncated. Structured output has
+//! `truncated` set instead. While watching, Ctrl+C stops the program as usual.
+//!
+//! Passing `--sort=name` or `--sort=path` orders the entries within each section alphabetically by
+//! name or by path, which makes it easier to compare output from different systems.
+//!
+//! Passing `--watch` keeps the program running after the report, showing, with a timestamp, each
+//! change to an entry caused by a change to the registry key or the system environment, such as
+//! by an installer or a policy update.
+//!
+//! Running `pfdirs tui`, if built with the `tui` feature, shows the report in the terminal for
+//! exploring interactively, with each section expandable to its entries and the selected entry's
+//! details, such as its raw error code, known folder ID, or registry value type, beside them. It
+//! watches for changes as `--watch` does and keeps the report current, and a key copies the
+//! selected path to the clipboard.
+//!
+//! Passing `--experiment inheritance` runs this program as a child process several times, each
+//! time with some program files environment variables removed from the environment it is given,
+//! and shows what the child received, to test the inheritance rules on the running system.
+//!
+//! Running `pfdirs matrix` runs builds of this program for the x86, x64, and ARM64 architectures,
+//! where they are available and can run, and shows every entry side by side, marking those that
+//! differ between architectures.
+//!
+//! Running `pfdirs dump-known-folders` shows every known folder registered on the system, not just
+//! the program files folders, with its ID, canonical name, category, and path, in the selected
+//! format. Running `pfdirs kf FOLDER` shows a single known folder, given by its ID or canonical
+//! name, with its full definition.
+//!
+//! Running `pfdirs apps` shows the applications registered in the `Uninstall` and `App Paths`
+//! registry keys, grouped by the program files directory each is installed in, to show which tree
+//! really hosts them.
+//!
+//! Running `pfdirs diff OLD NEW` compares two reports saved with `--format json` and shows only the
+//! entries that differ between them.
+//!
+//! Passing `--save PATH` also saves the report as JSON, with the host name, user, Windows version,
+//! and time. Passing `--save` alone saves it as a snapshot in `pfdirs` in the local application
+//! data folder, and `pfdirs history list` and `pfdirs history compare` list and compare those.
+//!
+//! Passing `-o FILE` writes the output to a file, which is replaced atomically, so readers never
+//! see a partial report. Passing `--append` as well adds the output to the end of the file, locking
+//! it while doing so, so reports from scheduled tasks sharing one file do not interleave.
+//!
+//! Passing `-q` shows only where the program files directories are and the summary table. Passing
+//! `-v` logs how long each source took on standard error, and `-vv` also logs each API call, with
+//! its arguments, its result or error code, and how long it took.
+//!
+//! Passing `--eventlog` also writes a summary of the report, with any anomalies, to the Application
+//! event log under the `pfdirs` source, with an event ID of 1000 plus the exit status.
+//!
+//! Passing `--json-schema` prints the JSON Schema document that JSON reports follow. Each report
+//! gives the version of that schema as `schema_version`.
+//!
+//! Passing `--self-test` checks the tables that relate the entries of different sources to each
+//! other, without looking anything up, and exits with a failure status if any check fails.
+//!
+//! Passing `--require` with a comma-separated list of entry names, such as
+//! `--require ProgramW6432,FOLDERID_ProgramFilesX86`, makes the program exit with a failure status,
+//! after listing what went wrong, if any of those entries could not be looked up.
+//!
+//! Passing `--copy program-files-64`, or another program files directory, puts the path the
+//! sources agree on for it on the clipboard after the report, ready to paste into installer
+//! configuration. An entry name, such as `--copy ProgramW6432`, copies that entry's path instead.
+//!
+//! Running `pfdirs open x64`, or with `x86`, `arm64`, or `user`, finds that program files directory
+//! with `resolve_program_files()`, shows its path, and opens it in File Explorer.
+//!
+//! Passing `--expect NAME=VALUE`, any number of times, or `--expect-file` with a TOML file of such
+//! names and values, checks that each entry resolves to the expected path, and lists which passed
+//! and which failed.
+//!
+//! If two ways of looking up the same entry disagree, the entry is shown as a `DISCREPANCY` and the
+//! rest of the report is still shown.
+//!
+//! The text output ends with a "Findings" section of conclusions drawn from the whole report, each
+//! with a severity of critical, warning, or info, and a short explanation of why it matters and
+//! what to do about it, such as that `ProgramW6432` is missing on 64-bit Windows, or that the
+//! 32-bit and 64-bit program files directories are the same. The JSON output gives them as a
+//! `findings` array.
+//!
+//! After the report, any anomalies are listed on standard error, and the exit status tells what
+//! the most serious one was, so scripts can fail on a broken program files configuration:
+//!
+//! - 0 means all sources are consistent, and every entry expected on this system was found.
+//! - 1 means some entries expected on this system were not found, or some `--require`d entries,
+//!   or the path to `--copy`, were not found.
+//! - 2 means sources disagree about the path of the same directory, an inherited environment
+//!   variable is stale, an `--expect`ed path was not found, or there was a `DISCREPANCY`.
+//! - 3 means an API call failed for a reason other than the value not existing, or the report could
+//!   not be made at all.
+//! - 4 means the command line could not be parsed, so nothing was looked up.
+//! - 5 means `--self-test` found the program's tables inconsistent.
+//!
+//! Entries that are legitimately unavailable, such as `ProgramW6432` on 32-bit Windows, are never
+//! anomalies.
 
-    println!("Relevant registry keys - with {caption}:");
-    println!();
+use std::process::ExitCode;
 
-    for key_name in key_names {
-        let path_item = cur_ver
-            .get_value(key_name)
-            .unwrap_or_else(|e| format!("[{e}]"));
-        println!("  {key_name:<width$}  {path_item}");
-    }
+#[cfg(windows)]
+mod cli;
 
-    println!();
-    Ok(())
+#[cfg(windows)]
+fn main() -> ExitCode {
+    cli::main()
 }
 
-/// Report *program files* folder locations from multiple views of the registry.
+/// Program files directories are only found on Windows, so elsewhere there is nothing to report.
 ///
-/// See also:
-///
-/// - [Accessing an Alternate Registry View][aarv] for details on registry views that can be
-///   accessed.
-///
-/// - `report_registry_view()` for details on how the lookup is performed.
-///
-/// [aarv]: https://learn.microsoft.com/en-us/windows/win32/winprog64/accessing-an-alternate-registry-view
-fn report_all_registry_views() -> Result<(), io::Error> {
-    let views = [
-        ("default view", 0),
-        ("KEY_WOW64_32KEY", KEY_WOW64_32KEY),
-        ("KEY_WOW64_64KEY", KEY_WOW64_64KEY),
-    ];
-
-    for (caption, flag_for_view) in views {
-        report_registry_view(caption, flag_for_view)?;
-    }
-
-    Ok(())
-}
-
-fn main() -> Result<(), Error> {
-    report_environment_variables();
-    report_known_folders()?;
-    report_csidl()?;
-    report_all_registry_views()?;
-    Ok(())
+/// The library builds everywhere, so that its analysis and rendering can be tested with a
+/// `FakeOs`, and so this program has to build everywhere too.
+#[cfg(not(windows))]
+fn main() -> ExitCode {
+    eprintln!("Error: pfdirs only runs on Windows");
+    ExitCode::FAILURE
 }
@@ -0,0 +1,333 @@
+//! Looking up program files folder locations as Windows Installer resolves them for MSI packages.
+
+#[cfg(all(windows, feature = "com"))]
+use std::env;
+#[cfg(all(windows, feature = "com"))]
+use std::fs;
+#[cfg(all(windows, feature = "com"))]
+use std::io;
+#[cfg(all(windows, feature = "com"))]
+use std::path::{Path, PathBuf};
+#[cfg(all(windows, feature = "com"))]
+use std::ptr;
+#[cfg(all(windows, feature = "com"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(all(windows, feature = "com"))]
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS, MAX_PATH, WIN32_ERROR};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::System::ApplicationInstallationAndServicing::{
+    MsiCloseHandle, MsiDatabaseCommit, MsiDatabaseOpenViewW, MsiGetPropertyW,
+    MsiGetSummaryInformationW, MsiOpenDatabaseW, MsiOpenPackageExW, MsiSetInternalUI,
+    MsiSummaryInfoPersist, MsiSummaryInfoSetPropertyW, MsiViewExecute, INSTALLUILEVEL_NONE,
+    MSIDBOPEN_CREATE, MSIHANDLE, MSIOPENPACKAGEFLAGS_IGNOREMACHINESTATE,
+};
+
+#[cfg(all(windows, feature = "com"))]
+use crate::error::{EntryError, Error};
+#[cfg(all(windows, feature = "com"))]
+use crate::registry::{open_current_version, CURRENT_VERSION_SUBKEY};
+use crate::registry::{KEY_WOW64_32KEY, KEY_WOW64_64KEY};
+#[cfg(all(windows, feature = "com"))]
+use crate::report::{Lookup, Row, Section};
+
+/// The Windows Installer folder properties for program files directories, each with the registry
+/// value, and the flag for the view it is read through, that it is cross-checked against.
+pub const MSI_FOLDER_PROPERTIES: [(&str, &str, u32); 2] = [
+    (
+        "ProgramFilesFolder",
+        "ProgramFilesDir with KEY_WOW64_32KEY",
+        KEY_WOW64_32KEY,
+    ),
+    (
+        "ProgramFiles64Folder",
+        "ProgramFilesDir with KEY_WOW64_64KEY",
+        KEY_WOW64_64KEY,
+    ),
+];
+
+/// The product code, and package code, of the package made to open a session with. These are
+/// arbitrary, and nothing is installed or registered under them.
+#[cfg(all(windows, feature = "com"))]
+const PRODUCT_CODE: &str = "{6B0A3F62-5E0B-4C58-9C43-3D7C1E2F8A41}";
+#[cfg(all(windows, feature = "com"))]
+const PACKAGE_CODE: &str = "{0E7D1C5A-8B27-4F3E-A6D9-52C4B9E1F073}";
+
+/// The summary information properties that a package must have to be opened, with the
+/// `VARENUM` types their values are given as.
+#[cfg(all(windows, feature = "com"))]
+const PID_TEMPLATE: u32 = 7;
+#[cfg(all(windows, feature = "com"))]
+const PID_REVNUMBER: u32 = 9;
+#[cfg(all(windows, feature = "com"))]
+const PID_PAGECOUNT: u32 = 14;
+#[cfg(all(windows, feature = "com"))]
+const VT_I4: u32 = 3;
+#[cfg(all(windows, feature = "com"))]
+const VT_LPSTR: u32 = 30;
+
+/// Gives an error for a status that Windows Installer functions return, unless it is success.
+#[cfg(all(windows, feature = "com"))]
+fn check(status: u32) -> Result<(), Error> {
+    match WIN32_ERROR(status) {
+        ERROR_SUCCESS => Ok(()),
+        status => Err(Error::Installer(status.to_hresult().into())),
+    }
+}
+
+/// A Windows Installer handle, which is closed when this is dropped.
+#[cfg(all(windows, feature = "com"))]
+struct Handle(MSIHANDLE);
+
+#[cfg(all(windows, feature = "com"))]
+impl Handle {
+    /// Calls a function that opens a handle, giving it the place to store the handle.
+    fn open(open: impl FnOnce(&mut MSIHANDLE) -> u32) -> Result<Self, Error> {
+        let mut handle = MSIHANDLE(0);
+        check(open(&mut handle))?;
+        Ok(Self(handle))
+    }
+}
+
+#[cfg(all(windows, feature = "com"))]
+impl Drop for Handle {
+    fn drop(&mut self) {
+        let _ = unsafe { MsiCloseHandle(self.0) };
+    }
+}
+
+/// A file in the temporary directory, which is deleted, if it exists, when this is dropped.
+#[cfg(all(windows, feature = "com"))]
+struct ScratchFile(PathBuf);
+
+#[cfg(all(windows, feature = "com"))]
+impl ScratchFile {
+    fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos());
+        let name = format!("pfdirs-{}-{nanos:x}.msi", std::process::id());
+        Self(env::temp_dir().join(name))
+    }
+}
+
+#[cfg(all(windows, feature = "com"))]
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Runs an SQL statement, that gives no results, on a database.
+#[cfg(all(windows, feature = "com"))]
+fn execute(database: &Handle, statement: &str) -> Result<(), Error> {
+    let view = Handle::open(|view| unsafe {
+        MsiDatabaseOpenViewW(database.0, &HSTRING::from(statement), view)
+    })?;
+    check(unsafe { MsiViewExecute(view.0, MSIHANDLE(0)) })
+}
+
+/// Creates the smallest package that Windows Installer will open a session with, at `path`.
+///
+/// This has only the summary information and `Property` table rows that opening a package
+/// requires. It has no features, components, or files, so it cannot install anything.
+#[cfg(all(windows, feature = "com"))]
+fn create_package(path: &Path) -> Result<Handle, Error> {
+    let database = Handle::open(|database| unsafe {
+        MsiOpenDatabaseW(&HSTRING::from(path), MSIDBOPEN_CREATE, database)
+    })?;
+
+    let summary = Handle::open(|summary| unsafe {
+        MsiGetSummaryInformationW(database.0, PCWSTR::null(), 3, summary)
+    })?;
+    for (property, value) in [(PID_TEMPLATE, "Intel;1033"), (PID_REVNUMBER, PACKAGE_CODE)] {
+        check(unsafe {
+            MsiSummaryInfoSetPropertyW(
+                summary.0,
+                property,
+                VT_LPSTR,
+                0,
+                ptr::null_mut(),
+                &HSTRING::from(value),
+            )
+        })?;
+    }
+    check(unsafe {
+        MsiSummaryInfoSetPropertyW(
+            summary.0,
+            PID_PAGECOUNT,
+            VT_I4,
+            200,
+            ptr::null_mut(),
+            PCWSTR::null(),
+        )
+    })?;
+    check(unsafe { MsiSummaryInfoPersist(summary.0) })?;
+
+    execute(
+        &database,
+        "CREATE TABLE `Property` (`Property` CHAR(72) NOT NULL, \
+        `Value` LONGCHAR NOT NULL LOCALIZABLE PRIMARY KEY `Property`)",
+    )?;
+    for (property, value) in [
+        ("ProductCode", PRODUCT_CODE),
+        ("ProductLanguage", "1033"),
+        ("ProductName", "pfdirs"),
+        ("ProductVersion", "1.0.0"),
+        ("Manufacturer", "pfdirs"),
+    ] {
+        execute(
+            &database,
+            &format!(
+                "INSERT INTO `Property` (`Property`, `Value`) VALUES ('{property}', '{value}')"
+            ),
+        )?;
+    }
+
+    check(unsafe { MsiDatabaseCommit(database.0) })?;
+    Ok(database)
+}
+
+/// Reads a property of a session, which is empty if the property is not set.
+#[cfg(all(windows, feature = "com"))]
+fn get_property(session: &Handle, name: &str) -> Result<String, Error> {
+    let name = HSTRING::from(name);
+    let mut buffer = vec![0_u16; MAX_PATH as usize];
+    loop {
+        let mut length = buffer.len() as u32;
+        let status = unsafe {
+            MsiGetPropertyW(
+                session.0,
+                &name,
+                PWSTR(buffer.as_mut_ptr()),
+                Some(&mut length),
+            )
+        };
+        if WIN32_ERROR(status) == ERROR_MORE_DATA {
+            // The length does not include the terminating null, which must also fit.
+            buffer.resize(length as usize + 1, 0);
+            continue;
+        }
+        check(status)?;
+        return Ok(String::from_utf16(&buffer[..length as usize])?);
+    }
+}
+
+/// Opens a session on a scratch package and reads each of `MSI_FOLDER_PROPERTIES` from it.
+///
+/// The session, the package, and its file are closed and deleted, in that order, on return.
+#[cfg(all(windows, feature = "com"))]
+fn folder_properties() -> Result<Vec<Result<String, Error>>, Error> {
+    let file = ScratchFile::new();
+    let database = create_package(&file.0)?;
+
+    let previous_ui_level = unsafe { MsiSetInternalUI(INSTALLUILEVEL_NONE, None) };
+    let session = Handle::open(|session| unsafe {
+        MsiOpenPackageExW(
+            &HSTRING::from(format!("#{}", database.0 .0)),
+            MSIOPENPACKAGEFLAGS_IGNOREMACHINESTATE.0 as u32,
+            session,
+        )
+    });
+    unsafe { MsiSetInternalUI(previous_ui_level, None) };
+    let session = session?;
+
+    Ok(MSI_FOLDER_PROPERTIES
+        .into_iter()
+        .map(|(name, _, _)| get_property(&session, name))
+        .collect())
+}
+
+/// Checks a folder property's path against the registry value it should agree with.
+///
+/// If the value cannot be read, the path is given unchecked. If it differs, the error is an
+/// [`Error::Discrepancy`] giving both. Paths that differ only in case, or in trailing backslashes,
+/// name the same directory, so they do not differ.
+#[cfg(all(windows, feature = "com"))]
+fn cross_check(
+    path: String,
+    registry_value: &'static str,
+    flag_for_view: u32,
+) -> Result<String, Error> {
+    let Ok(registry_path) = open_current_version(None, CURRENT_VERSION_SUBKEY, flag_for_view)
+        .and_then(|key| key.get_value::<String, _>("ProgramFilesDir"))
+    else {
+        return Ok(path);
+    };
+
+    let registry_path = registry_path.trim_end_matches('\\');
+    if registry_path.eq_ignore_ascii_case(path.trim_end_matches('\\')) {
+        Ok(path)
+    } else {
+        Err(Error::Discrepancy {
+            first: ("Windows Installer", path),
+            second: (registry_value, registry_path.to_owned()),
+        })
+    }
+}
+
+/// Report *program files* folder locations as Windows Installer resolves them for MSI packages.
+///
+/// MSI packages do not look up where to install with `SHGetKnownFolderPath` or the environment.
+/// Instead, Windows Installer sets [system folder properties][sfp] when it opens a session, and
+/// installers use those, so an MSI package can disagree with everything else about where the
+/// program files directories are, which causes real installation bugs that are hard to diagnose.
+/// This reads the two properties for program files directories, `ProgramFilesFolder` and
+/// `ProgramFiles64Folder`, which are listed in `MSI_FOLDER_PROPERTIES`.
+///
+/// Windows Installer only resolves these in a session on a package, so this creates a minimal
+/// package, as described on `create_package()`, in a scratch file in the temporary directory,
+/// opens a session on it with [`MsiOpenPackageExW`][mope], reads the properties with
+/// [`MsiGetPropertyW`][mgp], and closes the session and deletes the file. The package is opened
+/// with `MSIOPENPACKAGEFLAGS_IGNOREMACHINESTATE`, and with no user interface, so nothing is
+/// installed, registered, or shown. This is the same as what `MsiGetFolderPath` would give from a
+/// custom action in a real installation, before any directory is redirected by the package.
+///
+/// The package is a 32-bit package, as most are. So `ProgramFilesFolder` is the 32-bit directory,
+/// and `ProgramFiles64Folder` is the 64-bit directory, which is set only on 64-bit Windows, and is
+/// treated as not found when it is not set. Windows Installer always ends the paths of folder
+/// properties with a backslash, which is removed, so that they can be compared with other paths.
+///
+/// Each path is cross-checked against `ProgramFilesDir` in the registry view where it should be
+/// the same: the 32-bit view for `ProgramFilesFolder`, and the 64-bit view for
+/// `ProgramFiles64Folder`. If they differ, the entry is an [`Error::Discrepancy`] giving both.
+/// If no session can be opened, such as because the Windows Installer service is disabled, each
+/// entry has that error.
+///
+/// [sfp]: https://learn.microsoft.com/en-us/windows/win32/msi/property-reference#system-folder-properties
+/// [mope]: https://learn.microsoft.com/en-us/windows/win32/api/msiquery/nf-msiquery-msiopenpackageexw
+/// [mgp]: https://learn.microsoft.com/en-us/windows/win32/api/msiquery/nf-msiquery-msigetpropertyw
+#[cfg(all(windows, feature = "com"))]
+pub fn report_msi() -> Section<Row> {
+    let values = match folder_properties() {
+        Ok(values) => values
+            .into_iter()
+            .map(|value| value.map_err(EntryError::from))
+            .collect(),
+        Err(e) => vec![Err(EntryError::from(e)); MSI_FOLDER_PROPERTIES.len()],
+    };
+
+    let entries = MSI_FOLDER_PROPERTIES
+        .into_iter()
+        .zip(values)
+        .map(|((name, registry_value, flag_for_view), value)| {
+            let value = value.and_then(|path| {
+                if path.is_empty() {
+                    return Err(
+                        io::Error::other("Windows Installer did not set this property").into(),
+                    );
+                }
+                let path = path.trim_end_matches('\\').to_owned();
+                Ok(cross_check(path, registry_value, flag_for_view)?)
+            });
+            Row {
+                name: name.to_owned(),
+                lookup: Lookup::new(value),
+            }
+        })
+        .collect();
+
+    Section::new("Windows Installer folder properties", entries)
+}
@@ -0,0 +1,346 @@
+//! Explaining, in prose, what a report means for the system it was made on, citing Microsoft's
+//! documentation for each part of the explanation.
+
+use crate::analysis::{findings, resolve_consensus, stripped_variables, Severity, SourceKind};
+use crate::platform::{Machine, Platform};
+use crate::report::Report;
+
+/// A page of Microsoft's documentation that a paragraph of an explanation is based on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Citation {
+    pub title: &'static str,
+    pub url: &'static str,
+}
+
+const WOW64_ENVIRONMENT: Citation = Citation {
+    title: "WOW64 Implementation Details: Environment Variables",
+    url: "https://learn.microsoft.com/en-us/windows/win32/winprog64/wow64-implementation-details#environment-variables",
+};
+
+const FILE_SYSTEM_REDIRECTOR: Citation = Citation {
+    title: "File System Redirector",
+    url: "https://learn.microsoft.com/en-us/windows/win32/winprog64/file-system-redirector",
+};
+
+const IS_WOW64_PROCESS2: Citation = Citation {
+    title: "IsWow64Process2 function",
+    url: "https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-iswow64process2",
+};
+
+const KNOWN_FOLDER_IDS: Citation = Citation {
+    title: "KNOWNFOLDERID",
+    url: "https://learn.microsoft.com/en-us/windows/win32/shell/knownfolderid",
+};
+
+const CSIDLS: Citation = Citation {
+    title: "CSIDL",
+    url: "https://learn.microsoft.com/en-us/windows/win32/shell/csidl",
+};
+
+const ALTERNATE_REGISTRY_VIEW: Citation = Citation {
+    title: "Accessing an Alternate Registry View",
+    url: "https://learn.microsoft.com/en-us/windows/win32/winprog64/accessing-an-alternate-registry-view",
+};
+
+const CREATE_ENVIRONMENT_BLOCK: Citation = Citation {
+    title: "CreateEnvironmentBlock function",
+    url: "https://learn.microsoft.com/en-us/windows/win32/api/userenv/nf-userenv-createenvironmentblock",
+};
+
+const MSI_FOLDER_PROPERTIES: Citation = Citation {
+    title: "Property Reference: System Folder Properties",
+    url: "https://learn.microsoft.com/en-us/windows/win32/msi/property-reference#system-folder-properties",
+};
+
+/// One part of an explanation, as given by `explain()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Paragraph {
+    /// What the paragraph is about, such as `Environment variables`.
+    pub heading: &'static str,
+
+    pub text: String,
+
+    /// The documentation the paragraph is based on, where the reader can learn more.
+    pub citations: Vec<Citation>,
+}
+
+impl Paragraph {
+    fn new(heading: &'static str, text: String, citations: &[Citation]) -> Self {
+        Self {
+            heading,
+            text,
+            citations: citations.to_vec(),
+        }
+    }
+}
+
+/// Formats names as code, listing them as prose does, such as `` `a`, `b`, and `c` ``.
+fn list(names: &[&str]) -> String {
+    let names: Vec<String> = names.iter().map(|name| format!("`{name}`")).collect();
+    match names.as_slice() {
+        [] => String::new(),
+        [only] => only.clone(),
+        [first, second] => format!("{first} and {second}"),
+        [rest @ .., last] => format!("{}, and {last}", rest.join(", ")),
+    }
+}
+
+/// Explains what the architectures of this process and the system mean for which program files
+/// directory it is given.
+fn architecture(platform: Platform) -> Paragraph {
+    let description = platform.description();
+    let context = format!("T{}", &description[1..]);
+
+    let (text, citations): (String, &[Citation]) = match platform.native {
+        Machine::Unknown => (
+            format!(
+                "{context}. So what the sources are expected to give cannot be told apart from \
+                what is wrong, as when reading another computer's registry or an offline hive."
+            ),
+            &[IS_WOW64_PROCESS2],
+        ),
+        native if !native.is_64_bit() => (
+            format!(
+                "{context}, which has only one program files directory. `ProgramFiles(x86)`, \
+                `ProgramW6432`, and `FOLDERID_ProgramFilesX64` do not exist on it, and that is \
+                expected."
+            ),
+            &[KNOWN_FOLDER_IDS, WOW64_ENVIRONMENT],
+        ),
+        _ if platform.is_wow64() => (
+            format!(
+                "{context}, so it runs under WOW64. Windows gives it the 32-bit program files \
+                directory as `ProgramFiles`, `FOLDERID_ProgramFiles`, and \
+                `CSIDL_PROGRAM_FILES`, so that 32-bit programs install where other 32-bit programs \
+                are, and redirects its access to `System32`. `FOLDERID_ProgramFilesX64` is not \
+                available to it at all. To find the 64-bit directory, it must read `ProgramW6432`."
+            ),
+            &[WOW64_ENVIRONMENT, FILE_SYSTEM_REDIRECTOR, KNOWN_FOLDER_IDS],
+        ),
+        native => {
+            let mut text = format!(
+                "{context}. So `ProgramFiles`, `FOLDERID_ProgramFiles`, and `CSIDL_PROGRAM_FILES` \
+                give the 64-bit program files directory, and 32-bit programs are installed in a \
+                separate directory, given by `ProgramFiles(x86)` and `FOLDERID_ProgramFilesX86`."
+            );
+            if native == Machine::Arm64 {
+                text.push_str(
+                    " ARM64 Windows also has a directory for 32-bit ARM programs, given by \
+                    `ProgramFiles(Arm)`, which has no known folder or CSIDL.",
+                );
+            }
+            (text, &[WOW64_ENVIRONMENT, KNOWN_FOLDER_IDS])
+        }
+    };
+
+    Paragraph::new("This process and system", text, citations)
+}
+
+/// Explains where each program files directory that the system has is, and whether the sources
+/// agree, as found by `resolve_consensus()`.
+fn directories(report: &Report, platform: Platform) -> Option<Paragraph> {
+    let sentences: Vec<String> = resolve_consensus(report, platform)
+        .into_iter()
+        .filter(|resolution| resolution.directory.exists_on(platform.native))
+        .filter_map(|resolution| {
+            let description = resolution.directory.description();
+            let consensus = resolution.consensus?;
+            if resolution.dissents.is_empty() {
+                return Some(format!(
+                    "The {description} directory is `{consensus}`, and every source that was \
+                    looked up and has it agrees."
+                ));
+            }
+            let dissents: Vec<String> = resolution
+                .dissents
+                .iter()
+                .map(|dissent| format!("`{}` gives `{}`", dissent.name, dissent.path))
+                .collect();
+            Some(format!(
+                "Most sources give the {description} directory as `{consensus}`, but {}, so \
+                programs that look it up that way will use a different directory.",
+                dissents.join(", and ")
+            ))
+        })
+        .collect();
+
+    (!sentences.is_empty()).then(|| {
+        Paragraph::new(
+            "Where the program files directories are",
+            sentences.join(" "),
+            &[KNOWN_FOLDER_IDS, CSIDLS],
+        )
+    })
+}
+
+/// Explains which of the program files environment variables this process has, and why any that
+/// should be there are missing or stale.
+fn environment(report: &Report, platform: Platform) -> Option<Paragraph> {
+    let section = report.environment_variables.as_ref()?;
+    let path_of = |name: &str| {
+        section
+            .find(name)
+            .and_then(|entry| entry.lookup.path.clone())
+    };
+
+    let mut text = match path_of("ProgramFiles") {
+        Some(path) => format!("`ProgramFiles` is `{path}`."),
+        None => String::from("`ProgramFiles` is not set, which is never expected."),
+    };
+    let mut citations = vec![WOW64_ENVIRONMENT];
+
+    let stripped = stripped_variables(report, platform);
+    if !stripped.is_empty() {
+        let (verb, pronoun) = if stripped.len() == 1 {
+            ("is", "it")
+        } else {
+            ("are", "them")
+        };
+        text.push_str(&format!(
+            " {} {verb} missing. Windows sets `ProgramW6432` and `ProgramFiles(x86)` in every \
+            process on 64-bit Windows, so a process this one was started from removed {pronoun}. \
+            Scripts and installers that read {pronoun} may fail or use the wrong directory.",
+            list(&stripped)
+        ));
+    } else if platform.native.is_64_bit() {
+        text.push_str(
+            " `ProgramFiles(x86)` and `ProgramW6432` are also set, as Windows sets them in every \
+            process on 64-bit Windows, to the 32-bit and 64-bit directories.",
+        );
+    } else if platform.native != Machine::Unknown {
+        text.push_str(" The other variables are only set on 64-bit Windows.");
+    }
+
+    if let Some(fresh) = &report.fresh_environment {
+        let stale: Vec<&str> = fresh
+            .entries
+            .iter()
+            .filter(|entry| entry.stale)
+            .map(|entry| entry.name.as_str())
+            .collect();
+        if !stale.is_empty() {
+            text.push_str(&format!(
+                " This process's {} differ from what a process started for this user now would \
+                get, so its environment is out of date. Restarting the program that started it, \
+                or signing out and in, picks up the change.",
+                list(&stale)
+            ));
+            citations.push(CREATE_ENVIRONMENT_BLOCK);
+        }
+    }
+
+    Some(Paragraph::new("Environment variables", text, &citations))
+}
+
+/// Explains what `ProgramFilesDir` is in each registry view, and why the views differ.
+fn registry(report: &Report, platform: Platform) -> Option<Paragraph> {
+    let path_in = |view: &'static str| {
+        report
+            .section(SourceKind::Registry(view))?
+            .find("ProgramFilesDir")?
+            .path()
+            .map(str::to_owned)
+    };
+    let path_64 = path_in("KEY_WOW64_64KEY");
+    let path_32 = path_in("KEY_WOW64_32KEY");
+    if path_64.is_none() && path_32.is_none() {
+        return None;
+    }
+    let shown = |path: Option<String>| {
+        path.map_or_else(|| String::from("missing"), |path| format!("`{path}`"))
+    };
+
+    let text = if platform.native.is_64_bit() {
+        format!(
+            "In the registry, `ProgramFilesDir` is {} in the 64-bit view and {} in the 32-bit \
+            view. Windows keeps separate views of `HKLM\\SOFTWARE` for 32-bit and 64-bit \
+            programs, and each view's `ProgramFilesDir` is the directory programs of that \
+            architecture install to, so the two should differ. A 32-bit program reads the 32-bit \
+            view unless it asks for the other.",
+            shown(path_64),
+            shown(path_32),
+        )
+    } else {
+        format!(
+            "In the registry, `ProgramFilesDir` is {}. There is only one registry view here, so \
+            every way of reading it gives the same value.",
+            shown(path_64.or(path_32)),
+        )
+    };
+
+    Some(Paragraph::new(
+        "Registry values",
+        text,
+        &[ALTERNATE_REGISTRY_VIEW],
+    ))
+}
+
+/// Explains what Windows Installer gives MSI packages, if it was asked.
+fn installer(report: &Report) -> Option<Paragraph> {
+    let section = report.msi.as_ref()?;
+    let shown: Vec<String> = section
+        .entries
+        .iter()
+        .map(|entry| format!("`{}` is {}", entry.name, entry.lookup.path_item()))
+        .collect();
+    let text = format!(
+        "For MSI packages, Windows Installer resolves its own folder properties: {}. Installers \
+        built as MSI packages use these rather than the environment or known folders.",
+        shown.join(", and ")
+    );
+    Some(Paragraph::new(
+        "Windows Installer",
+        text,
+        &[MSI_FOLDER_PROPERTIES],
+    ))
+}
+
+/// Sums up whether anything is wrong, from the findings of at least `Severity::Warning`.
+fn conclusion(report: &Report, platform: Platform) -> Paragraph {
+    let problems: Vec<String> = findings(report, platform)
+        .into_iter()
+        .filter(|finding| finding.severity >= Severity::Warning)
+        .map(|finding| finding.title)
+        .collect();
+
+    let text = match problems.as_slice() {
+        [] => String::from(
+            "Nothing looks wrong. The sources that were looked up agree with each other and with \
+            what Windows sets up on a system like this one.",
+        ),
+        [problem] => format!(
+            "One thing looks wrong: {problem}. The findings at the end of the usual report say \
+            what to do about it."
+        ),
+        problems => format!(
+            "{} things look wrong: {}. The findings at the end of the usual report say what to do \
+            about each.",
+            problems.len(),
+            problems.join("; ")
+        ),
+    };
+
+    Paragraph::new("Conclusion", text, &[])
+}
+
+/// Explains, in prose, what a report means for the system it was made on.
+///
+/// Where `--explain` annotates each entry, this tells a story about the whole system, tailored to
+/// it: what the architectures of the process and system mean for which directory each way of
+/// looking it up gives, where each program files directory is and whether the sources agree,
+/// which environment variables are set and why any are missing or stale, what the registry views
+/// hold and why they differ, and, if it was asked, what Windows Installer gives MSI packages. It
+/// ends with a conclusion summing up the findings, as found by `findings()`, that are at least
+/// warnings.
+///
+/// Each paragraph cites the pages of Microsoft's documentation it is based on, so the reader can
+/// check the reasoning. Paragraphs about sources that are not in the report are left out.
+pub fn explain(report: &Report, platform: Platform) -> Vec<Paragraph> {
+    let mut paragraphs = vec![architecture(platform)];
+    paragraphs.extend(directories(report, platform));
+    paragraphs.extend(environment(report, platform));
+    paragraphs.extend(registry(report, platform));
+    paragraphs.extend(installer(report));
+    paragraphs.push(conclusion(report, platform));
+    paragraphs
+}
@@ -0,0 +1,187 @@
+//! The operating system calls that resolving program files directories makes, behind a trait, so
+//! that they can be simulated.
+//!
+//! `resolve_program_files()` looks things up through the real system, given by `RealOs`.
+//! `resolve_program_files_with()` takes any `OsFacade`, such as a `FakeOs` that simulates a 32-bit,
+//! x64, or ARM64 system, as seen by a process of a given architecture, so how the resolver chooses
+//! among sources can be checked without such a system.
+
+use std::collections::BTreeMap;
+#[cfg(windows)]
+use std::env::VarError;
+use std::ffi::OsString;
+use std::io;
+
+#[cfg(windows)]
+use windows::Win32::UI::Shell::KF_FLAG_DEFAULT;
+use windows_core::{GUID, HRESULT};
+
+use crate::error::Error;
+#[cfg(windows)]
+use crate::known_folder::get_known_folder_path_or_detailed_error;
+use crate::known_folder::{
+    FOLDERID_ProgramFiles, FOLDERID_ProgramFilesX64, FOLDERID_ProgramFilesX86,
+};
+use crate::platform::{Machine, Platform};
+#[cfg(windows)]
+use crate::registry::{open_current_version, CURRENT_VERSION_SUBKEY};
+#[cfg(windows)]
+use crate::timing::traced;
+#[cfg(windows)]
+use crate::token::Token;
+
+/// The Win32 error code `ERROR_FILE_NOT_FOUND`, with which a `FakeOs` fails to find what it was
+/// not given.
+const ERROR_FILE_NOT_FOUND: u32 = 2;
+
+/// The environment, registry, and Shell calls made to resolve program files directories.
+pub trait OsFacade {
+    /// Gets an environment variable of this process, or `None` if it is not set.
+    fn env_var(&self, name: &str) -> Option<OsString>;
+
+    /// Gets the path of a known folder for the current user, as `SHGetKnownFolderPath` does with
+    /// `KF_FLAG_DEFAULT`.
+    fn known_folder(&self, id: GUID) -> Result<OsString, Error>;
+
+    /// Reads a string value from `CURRENT_VERSION_SUBKEY`, through the default registry view.
+    fn registry_value(&self, name: &str) -> io::Result<String>;
+}
+
+/// The real system this process is running on.
+#[cfg(windows)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealOs;
+
+#[cfg(windows)]
+impl OsFacade for RealOs {
+    fn env_var(&self, name: &str) -> Option<OsString> {
+        traced("GetEnvironmentVariableW", format_args!("{name}"), || {
+            std::env::var_os(name).ok_or(VarError::NotPresent)
+        })
+        .ok()
+    }
+
+    fn known_folder(&self, id: GUID) -> Result<OsString, Error> {
+        get_known_folder_path_or_detailed_error(id, KF_FLAG_DEFAULT, &Token::CURRENT)
+    }
+
+    fn registry_value(&self, name: &str) -> io::Result<String> {
+        open_current_version(None, CURRENT_VERSION_SUBKEY, 0)?.get_value(name)
+    }
+}
+
+/// A simulated system, whose environment variables, known folders, and registry values are held
+/// in memory.
+///
+/// Anything not given is missing, failing as it would on a real system: known folders with
+/// `HRESULT_FROM_WIN32(ERROR_FILE_NOT_FOUND)`, and registry values with `ERROR_FILE_NOT_FOUND`.
+#[derive(Clone, Debug, Default)]
+pub struct FakeOs {
+    environment: BTreeMap<String, OsString>,
+    known_folders: Vec<(GUID, OsString)>,
+    registry: BTreeMap<String, String>,
+}
+
+impl FakeOs {
+    /// Makes a simulated system with nothing in it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an environment variable. Names are matched without regard to case, as on Windows.
+    pub fn with_env_var(mut self, name: &str, value: impl Into<OsString>) -> Self {
+        self.environment
+            .insert(name.to_ascii_lowercase(), value.into());
+        self
+    }
+
+    /// Removes an environment variable, as a parent process that sanitizes its child's
+    /// environment might.
+    pub fn without_env_var(mut self, name: &str) -> Self {
+        self.environment.remove(&name.to_ascii_lowercase());
+        self
+    }
+
+    /// Sets the path of a known folder.
+    pub fn with_known_folder(mut self, id: GUID, path: impl Into<OsString>) -> Self {
+        self.known_folders.retain(|&(other, _)| other != id);
+        self.known_folders.push((id, path.into()));
+        self
+    }
+
+    /// Sets a string value in `CURRENT_VERSION_SUBKEY`, as seen through the default registry view.
+    /// Names are matched without regard to case, as in the registry.
+    pub fn with_registry_value(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.registry
+            .insert(name.to_ascii_lowercase(), value.into());
+        self
+    }
+
+    /// Simulates a system with its program files directories in their default places, as seen by
+    /// a process of the given architecture.
+    ///
+    /// A 32-bit x86 system has only `C:\Program Files`. A 64-bit system also has
+    /// `C:\Program Files (x86)`, and an ARM64 system also has `C:\Program Files (Arm)`. A 64-bit
+    /// process, including an x64 process on ARM64, sees `ProgramFiles`, `FOLDERID_ProgramFiles`,
+    /// and `ProgramFilesDir` as the 64-bit directory. A 32-bit x86 process under WOW64 sees them
+    /// as the 32-bit directory, does not see `FOLDERID_ProgramFilesX64`, and reads the registry
+    /// through the 32-bit view, as described in the crate documentation.
+    pub fn simulate(platform: Platform) -> Self {
+        const NATIVE: &str = r"C:\Program Files";
+        const X86: &str = r"C:\Program Files (x86)";
+        const ARM: &str = r"C:\Program Files (Arm)";
+
+        if !platform.native.is_64_bit() {
+            return Self::new()
+                .with_env_var("ProgramFiles", NATIVE)
+                .with_known_folder(FOLDERID_ProgramFiles, NATIVE)
+                .with_known_folder(FOLDERID_ProgramFilesX86, NATIVE)
+                .with_registry_value("ProgramFilesDir", NATIVE);
+        }
+
+        let own = if platform.process.is_64_bit() {
+            NATIVE
+        } else {
+            X86
+        };
+        let mut os = Self::new()
+            .with_env_var("ProgramFiles", own)
+            .with_env_var("ProgramFiles(x86)", X86)
+            .with_env_var("ProgramW6432", NATIVE)
+            .with_known_folder(FOLDERID_ProgramFiles, own)
+            .with_known_folder(FOLDERID_ProgramFilesX86, X86)
+            .with_registry_value("ProgramFilesDir", own)
+            .with_registry_value("ProgramFilesDir (x86)", X86)
+            .with_registry_value("ProgramW6432Dir", NATIVE);
+        if platform.process.is_64_bit() {
+            os = os.with_known_folder(FOLDERID_ProgramFilesX64, NATIVE);
+        }
+        if platform.native == Machine::Arm64 {
+            os = os
+                .with_env_var("ProgramFiles(Arm)", ARM)
+                .with_registry_value("ProgramFilesDir (Arm)", ARM);
+        }
+        os
+    }
+}
+
+impl OsFacade for FakeOs {
+    fn env_var(&self, name: &str) -> Option<OsString> {
+        self.environment.get(&name.to_ascii_lowercase()).cloned()
+    }
+
+    fn known_folder(&self, id: GUID) -> Result<OsString, Error> {
+        self.known_folders
+            .iter()
+            .find(|&&(other, _)| other == id)
+            .map(|(_, path)| path.clone())
+            .ok_or_else(|| Error::KnownFolder(HRESULT::from_win32(ERROR_FILE_NOT_FOUND).into()))
+    }
+
+    fn registry_value(&self, name: &str) -> io::Result<String> {
+        self.registry
+            .get(&name.to_ascii_lowercase())
+            .cloned()
+            .ok_or_else(|| io::Error::from_raw_os_error(ERROR_FILE_NOT_FOUND as i32))
+    }
+}
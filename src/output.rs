@@ -0,0 +1,276 @@
+//! Writing output to a file as a whole, so readers never see a partial or interleaved report, and
+//! in the encoding it is read in.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::os::windows::io::AsRawHandle;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use windows::core::PCSTR;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Globalization::{WideCharToMultiByte, CP_ACP};
+use windows::Win32::System::Console::{GetStdHandle, SetStdHandle, STD_OUTPUT_HANDLE};
+
+use crate::error::Error;
+
+/// An encoding to write output in, when it goes to a file or pipe rather than a console.
+///
+/// Output to a console is always written as UTF-16 with `WriteConsoleW`, so it is shown correctly
+/// whatever the console's code page is, and has no encoding to choose.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, with no byte order mark, which is what Rust's standard library writes.
+    #[default]
+    Utf8,
+
+    /// UTF-8 beginning with a byte order mark, without which Windows PowerShell 5.1 and Excel take
+    /// a file to be in the ANSI code page.
+    Utf8Bom,
+
+    /// UTF-16LE beginning with a byte order mark, as Windows PowerShell 5.1 writes with `>`.
+    Utf16,
+
+    /// The system's ANSI code page, for tools that read nothing else. Characters it has no
+    /// equivalent for are written as `?`.
+    Ansi,
+}
+
+impl Encoding {
+    /// The byte order mark that output in this encoding begins with, which may be empty.
+    pub fn byte_order_mark(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 | Self::Ansi => &[],
+            Self::Utf8Bom => &[0xEF, 0xBB, 0xBF],
+            Self::Utf16 => &[0xFF, 0xFE],
+        }
+    }
+
+    /// Encodes text in this encoding, without the byte order mark.
+    pub fn encode(self, text: &str) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => Ok(text.as_bytes().to_vec()),
+            Self::Utf16 => Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Self::Ansi => encode_ansi(text),
+        }
+    }
+}
+
+/// Encodes text in the system's ANSI code page with [`WideCharToMultiByte`][wctmb], as described
+/// on `Encoding::Ansi`.
+///
+/// [wctmb]: https://learn.microsoft.com/en-us/windows/win32/api/stringapiset/nf-stringapiset-widechartomultibyte
+fn encode_ansi(text: &str) -> io::Result<Vec<u8>> {
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    if wide.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let length = unsafe { WideCharToMultiByte(CP_ACP, 0, &wide, None, PCSTR::null(), None) };
+    if length <= 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut bytes = vec![0; length as usize];
+    let written =
+        unsafe { WideCharToMultiByte(CP_ACP, 0, &wide, Some(&mut bytes), PCSTR::null(), None) };
+    if written <= 0 {
+        return Err(io::Error::last_os_error());
+    }
+    bytes.truncate(written as usize);
+    Ok(bytes)
+}
+
+/// Standard output, redirected to a temporary file until it is committed to its destination.
+///
+/// Everything written to standard output, including with `print!` and `println!`, goes to a
+/// temporary file beside the destination, because Rust's standard library fetches the handle with
+/// [`GetStdHandle`][gsh] on each write. [`commit()`](Self::commit) then moves or appends it to the
+/// destination in one step, in its `Encoding`. If this is dropped without being committed, as when
+/// making the report fails, standard output is restored and the temporary file is deleted, leaving
+/// the destination as it was.
+///
+/// The destination may also be standard output itself, as it was before, so that output to a file
+/// or pipe it was redirected to by a shell can be written in an encoding other than UTF-8.
+///
+/// [gsh]: https://learn.microsoft.com/en-us/windows/console/getstdhandle
+#[derive(Debug)]
+pub struct RedirectedOutput {
+    destination: Option<PathBuf>,
+    temporary: PathBuf,
+    append: bool,
+    encoding: Encoding,
+    file: Option<File>,
+    previous: HANDLE,
+}
+
+impl RedirectedOutput {
+    /// Redirects standard output to a temporary file, to be committed to `destination`.
+    ///
+    /// If `append` is true, the output is added to the end of `destination` when committed,
+    /// creating it if it does not exist, and is only begun with a byte order mark if
+    /// `destination` is empty. Otherwise, it replaces `destination`.
+    pub fn start(destination: &Path, append: bool, encoding: Encoding) -> Result<Self, Error> {
+        Self::redirect(
+            Some(destination.to_path_buf()),
+            temporary_path(destination),
+            append,
+            encoding,
+        )
+    }
+
+    /// Redirects standard output to a temporary file, to be written back to standard output in
+    /// `encoding` when committed.
+    ///
+    /// This is for when standard output is not a console, which needs no encoding, as described
+    /// on `Encoding`.
+    pub fn capture(encoding: Encoding) -> Result<Self, Error> {
+        let temporary = std::env::temp_dir().join(format!("pfdirs.{}.tmp", process::id()));
+        Self::redirect(None, temporary, false, encoding)
+    }
+
+    /// Redirects standard output to a new file at `temporary`, as `start()` and `capture()` do.
+    fn redirect(
+        destination: Option<PathBuf>,
+        temporary: PathBuf,
+        append: bool,
+        encoding: Encoding,
+    ) -> Result<Self, Error> {
+        let fail = |e| Error::Output(temporary.clone(), e);
+
+        io::stdout().flush().map_err(fail)?;
+        let previous = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) }.map_err(|e| fail(e.into()))?;
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temporary)
+            .map_err(fail)?;
+        let handle = HANDLE(file.as_raw_handle());
+        if let Err(e) = unsafe { SetStdHandle(STD_OUTPUT_HANDLE, handle) } {
+            drop(file);
+            let _ = fs::remove_file(&temporary);
+            return Err(fail(e.into()));
+        }
+
+        Ok(Self {
+            destination,
+            temporary,
+            append,
+            encoding,
+            file: Some(file),
+            previous,
+        })
+    }
+
+    /// Restores standard output, then moves or appends the output to the destination, in its
+    /// encoding.
+    ///
+    /// When replacing, the temporary file is flushed to disk and renamed over the destination,
+    /// which is atomic on the same volume, so readers see either the old report or the whole new
+    /// one. When appending, the destination is locked for the duration, and the output is written
+    /// to it in a single call, so reports appended at the same time, such as by scheduled tasks
+    /// writing to a shared location, do not interleave. When the destination is standard output,
+    /// the output is written to it in a single call, and the temporary file is deleted.
+    pub fn commit(mut self) -> Result<(), Error> {
+        let mut file = self.restore();
+        let fail = |path: &Path, e| Error::Output(path.to_path_buf(), e);
+
+        let Some(destination) = &self.destination else {
+            drop(file);
+            let contents = self
+                .encoded_contents(true)
+                .map_err(|e| fail(&self.temporary, e))?;
+            let mut stdout = io::stdout();
+            stdout
+                .write_all(&contents)
+                .and_then(|()| stdout.flush())
+                .map_err(|e| fail(&self.temporary, e))?;
+            return fs::remove_file(&self.temporary).map_err(|e| fail(&self.temporary, e));
+        };
+
+        if self.append {
+            drop(file);
+            let mut destination_file = OpenOptions::new()
+                .read(true)
+                .append(true)
+                .create(true)
+                .open(destination)
+                .map_err(|e| fail(destination, e))?;
+            destination_file.lock().map_err(|e| fail(destination, e))?;
+            let empty = destination_file
+                .metadata()
+                .map_err(|e| fail(destination, e))?
+                .len()
+                == 0;
+            let contents = self
+                .encoded_contents(empty)
+                .map_err(|e| fail(&self.temporary, e))?;
+            destination_file
+                .write_all(&contents)
+                .and_then(|()| destination_file.sync_all())
+                .map_err(|e| fail(destination, e))?;
+            fs::remove_file(&self.temporary).map_err(|e| fail(&self.temporary, e))
+        } else {
+            if self.encoding != Encoding::Utf8 {
+                let contents = self
+                    .encoded_contents(true)
+                    .map_err(|e| fail(&self.temporary, e))?;
+                file.set_len(0)
+                    .and_then(|()| file.seek(SeekFrom::Start(0)))
+                    .and_then(|_| file.write_all(&contents))
+                    .map_err(|e| fail(&self.temporary, e))?;
+            }
+            file.sync_all().map_err(|e| fail(&self.temporary, e))?;
+            drop(file);
+            fs::rename(&self.temporary, destination).map_err(|e| fail(destination, e))
+        }
+    }
+
+    /// Gives the output written to the temporary file, in the encoding, beginning with its byte
+    /// order mark if `at_start` is true.
+    fn encoded_contents(&self, at_start: bool) -> io::Result<Vec<u8>> {
+        let contents = fs::read(&self.temporary)?;
+        if self.encoding == Encoding::Utf8 {
+            return Ok(contents);
+        }
+
+        let text = String::from_utf8(contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut encoded = Vec::new();
+        if at_start {
+            encoded.extend_from_slice(self.encoding.byte_order_mark());
+        }
+        encoded.extend(self.encoding.encode(&text)?);
+        Ok(encoded)
+    }
+
+    /// Flushes standard output and points it back where it was, giving the temporary file.
+    fn restore(&mut self) -> File {
+        let _ = io::stdout().flush();
+        let _ = unsafe { SetStdHandle(STD_OUTPUT_HANDLE, self.previous) };
+        self.file
+            .take()
+            .expect("output should only be restored once")
+    }
+}
+
+impl Drop for RedirectedOutput {
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            drop(self.restore());
+            let _ = fs::remove_file(&self.temporary);
+        }
+    }
+}
+
+/// The temporary file that output to `destination` is written to before it is committed.
+///
+/// It is in the same directory, so it can be renamed over the destination atomically, and its name
+/// has this process's ID, so two processes writing to the same destination do not collide.
+fn temporary_path(destination: &Path) -> PathBuf {
+    let name = destination
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    destination.with_file_name(format!(".{name}.{}.tmp", process::id()))
+}
@@ -0,0 +1,159 @@
+//! Detecting whether this process runs in an AppContainer or with the identity of an MSIX package,
+//! either of which changes what it sees of the program files directories and known folders.
+
+#[cfg(all(windows, feature = "com"))]
+use std::ffi::OsString;
+#[cfg(all(windows, feature = "com"))]
+use std::fs;
+#[cfg(all(windows, feature = "com"))]
+use std::io;
+#[cfg(all(windows, feature = "com"))]
+use std::os::windows::ffi::OsStringExt;
+
+#[cfg(all(windows, feature = "com"))]
+use windows::core::PWSTR;
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::Foundation::{ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS, WIN32_ERROR};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::Security::TokenIsAppContainer;
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::Storage::Packaging::Appx::{GetCurrentPackageFullName, GetCurrentPackagePath};
+
+#[cfg(all(windows, feature = "com"))]
+use crate::error::EntryError;
+#[cfg(all(windows, feature = "com"))]
+use crate::report::{Lookup, Row, Section};
+#[cfg(all(windows, feature = "com"))]
+use crate::token::current_token_flag;
+
+/// The name of the entry for whether this process's token is for an AppContainer.
+pub const TOKEN_IS_APP_CONTAINER: &str = "TokenIsAppContainer";
+
+/// The value of the `TOKEN_IS_APP_CONTAINER` entry when this process runs in an AppContainer.
+pub const IN_APP_CONTAINER: &str = "in an AppContainer";
+
+/// The value of the `TOKEN_IS_APP_CONTAINER` entry when this process does not run in one.
+pub const NOT_IN_APP_CONTAINER: &str = "not in an AppContainer";
+
+/// The name of the entry for the full name of the package this process has the identity of.
+pub const PACKAGE_FULL_NAME: &str = "GetCurrentPackageFullName";
+
+/// The name of the entry for the directory the package this process belongs to is installed in.
+pub const PACKAGE_PATH: &str = "GetCurrentPackagePath";
+
+/// The names of the entries for the package's virtual file system directories that are merged into
+/// the program files directories, relative to `PACKAGE_PATH`.
+pub const PACKAGE_PROGRAM_FILES: [&str; 2] = [
+    r"GetCurrentPackagePath\VFS\ProgramFilesX64",
+    r"GetCurrentPackagePath\VFS\ProgramFilesX86",
+];
+
+/// Calls a function that gives a string about the current package, such as
+/// `GetCurrentPackageFullName`, and gives the string.
+///
+/// Such a function, given a buffer too small, returns `ERROR_INSUFFICIENT_BUFFER` and sets the
+/// length to the size needed, including the terminating null. For a process with no package
+/// identity, it returns `APPMODEL_ERROR_NO_PACKAGE`, which is given as the error.
+#[cfg(all(windows, feature = "com"))]
+fn package_string(get: impl Fn(&mut u32, PWSTR) -> WIN32_ERROR) -> io::Result<OsString> {
+    let win32_error = |error: WIN32_ERROR| io::Error::from_raw_os_error(error.0 as i32);
+
+    let mut length = 0;
+    match get(&mut length, PWSTR::null()) {
+        ERROR_INSUFFICIENT_BUFFER => {}
+        ERROR_SUCCESS => return Ok(OsString::new()),
+        error => return Err(win32_error(error)),
+    }
+
+    let mut buffer = vec![0_u16; length as usize];
+    match get(&mut length, PWSTR(buffer.as_mut_ptr())) {
+        ERROR_SUCCESS => {
+            let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+            Ok(OsString::from_wide(&buffer[..end]))
+        }
+        error => Err(win32_error(error)),
+    }
+}
+
+/// Finds one of the package's virtual file system directories, as described on
+/// `report_package()`, giving it only if it exists.
+#[cfg(all(windows, feature = "com"))]
+fn package_program_files(name: &str, package_path: Result<OsString, EntryError>) -> Lookup {
+    let relative = name
+        .strip_prefix(PACKAGE_PATH)
+        .expect("package program files are named relative to GetCurrentPackagePath");
+
+    Lookup::new(package_path.and_then(|mut path| {
+        path.push(relative);
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => Ok(path),
+            Ok(_) => Err(io::Error::from(io::ErrorKind::NotADirectory).into()),
+            Err(e) => Err(e.into()),
+        }
+    }))
+}
+
+/// Report whether this process runs in an AppContainer or as part of an MSIX package, and where
+/// the package's files are merged into the program files directories.
+///
+/// Packaged apps see different results from the same lookups, and nothing else in the report says
+/// why. This reports:
+///
+/// - `TOKEN_IS_APP_CONTAINER`, queried from this process's token, as `IN_APP_CONTAINER` or
+///   `NOT_IN_APP_CONTAINER`. A process in an [AppContainer][ac], as Store apps and some sandboxed
+///   processes are, has only the access its capabilities grant, so lookups that read the user's
+///   profile or the registry may be denied, and some per-user known folders are given as the
+///   container's own folders under `%LOCALAPPDATA%\Packages`.
+///
+/// - `PACKAGE_FULL_NAME` and `PACKAGE_PATH`, from [`GetCurrentPackageFullName`][gcpfn] and
+///   [`GetCurrentPackagePath`][gcpp], which fail with `APPMODEL_ERROR_NO_PACKAGE` unless the
+///   process has [package identity][pi]. The package is normally installed under
+///   `WindowsApps` in the 64-bit program files directory.
+///
+/// - Each of `PACKAGE_PROGRAM_FILES`, if it exists. A packaged process that is not in an
+///   AppContainer, such as a packaged desktop app, sees its package's [virtual file system][vfs]
+///   merged into the folders it stands for, so files in `VFS\ProgramFilesX64` appear to this
+///   process, but to no other, to be in the 64-bit program files directory. Writes it makes to
+///   `%APPDATA%` and `%LOCALAPPDATA%` may similarly go to the package's own `LocalCache`.
+///
+/// [ac]: https://learn.microsoft.com/en-us/windows/win32/secauthz/appcontainer-isolation
+/// [gcpfn]: https://learn.microsoft.com/en-us/windows/win32/api/appmodel/nf-appmodel-getcurrentpackagefullname
+/// [gcpp]: https://learn.microsoft.com/en-us/windows/win32/api/appmodel/nf-appmodel-getcurrentpackagepath
+/// [pi]: https://learn.microsoft.com/en-us/windows/apps/desktop/modernize/package-identity-overview
+/// [vfs]: https://learn.microsoft.com/en-us/windows/msix/desktop/desktop-to-uwp-behind-the-scenes
+#[cfg(all(windows, feature = "com"))]
+pub fn report_package() -> Section<Row> {
+    let app_container = current_token_flag(TokenIsAppContainer).map(|flag| {
+        if flag {
+            IN_APP_CONTAINER
+        } else {
+            NOT_IN_APP_CONTAINER
+        }
+    });
+    let full_name =
+        package_string(|length, name| unsafe { GetCurrentPackageFullName(length, name) });
+    let package_path =
+        package_string(|length, path| unsafe { GetCurrentPackagePath(length, path) })
+            .map_err(EntryError::from);
+
+    let mut entries = vec![
+        Row {
+            name: TOKEN_IS_APP_CONTAINER.to_owned(),
+            lookup: Lookup::new(app_container),
+        },
+        Row {
+            name: PACKAGE_FULL_NAME.to_owned(),
+            lookup: Lookup::new(full_name),
+        },
+        Row {
+            name: PACKAGE_PATH.to_owned(),
+            lookup: Lookup::new(package_path.clone()),
+        },
+    ];
+    entries.extend(PACKAGE_PROGRAM_FILES.map(|name| Row {
+        name: name.to_owned(),
+        lookup: package_program_files(name, package_path.clone()),
+    }));
+
+    Section::new("Package and AppContainer", entries)
+}
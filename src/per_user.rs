@@ -0,0 +1,109 @@
+//! Looking up where per-user installs go, other than the per-user program files directory.
+
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{
+    FOLDERID_LocalAppData, FOLDERID_UserProgramFilesCommon, KF_FLAG_DEFAULT,
+};
+#[cfg(windows)]
+use winreg::enums::KEY_WOW64_64KEY;
+
+#[cfg(windows)]
+use crate::known_folder::resolve_guid;
+#[cfg(windows)]
+use crate::registry::{open_current_version, read_string_value};
+#[cfg(windows)]
+use crate::report::{Lookup, Row, Section};
+#[cfg(windows)]
+use crate::shell_folders::EXPLORER_SUBKEY;
+
+/// The name of the known folder for programs' shared components installed per user.
+pub const USER_PROGRAM_FILES_COMMON: &str = "FOLDERID_UserProgramFilesCommon";
+
+/// The name of the entry for the per-user `WindowsApps` directory, which holds the app execution
+/// aliases of MSIX packages, relative to the known folder it is in.
+pub const USER_WINDOWS_APPS: &str = r"FOLDERID_LocalAppData\Microsoft\WindowsApps";
+
+/// The registry value that holds the directory MSIX packages are installed to, in `APPX_SUBKEY`.
+pub const PACKAGE_ROOT_VALUE: &str = "PackageRoot";
+
+/// The registry key, under `HKEY_LOCAL_MACHINE`, that configures MSIX package deployment.
+#[cfg(windows)]
+const APPX_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Appx";
+
+/// The value in the `User Shell Folders` key that redirects `FOLDERID_UserProgramFilesCommon`.
+pub const USER_PROGRAM_FILES_COMMON_SHELL_FOLDER: &str = "{BCBD3057-CA5C-4622-B42D-BC56DB0AE516}";
+
+/// Finds the per-user `WindowsApps` directory, as described on `report_per_user_installs()`.
+#[cfg(windows)]
+fn user_windows_apps() -> Lookup {
+    Lookup::new(
+        resolve_guid(FOLDERID_LocalAppData, KF_FLAG_DEFAULT).map(|mut local_app_data| {
+            local_app_data.push(r"\Microsoft\WindowsApps");
+            local_app_data
+        }),
+    )
+}
+
+/// Reads a string value from a key, as described on `read_string_value()`, or gives the error
+/// opening the key.
+#[cfg(windows)]
+fn read_key_value(subkey: &str, value_name: &str) -> Lookup {
+    match open_current_version(None, subkey, KEY_WOW64_64KEY) {
+        Ok(key) => read_string_value(&key, value_name).2,
+        Err(e) => Lookup::new(Err::<String, _>(e)),
+    }
+}
+
+/// Report where per-user installs go, for installers that do not use the per-user program files
+/// directory.
+///
+/// Installers that install for only the current user, without elevation, cannot write to the
+/// machine-wide program files directories, so they do not use them at all. Some use the
+/// `FOLDERID_UserProgramFiles` known folder, reported by `report_known_folders()`. This reports
+/// the other places such installs go, and the registry values that define them:
+///
+/// - [`FOLDERID_UserProgramFilesCommon`][upfc], for components shared between per-user programs,
+///   normally `%LOCALAPPDATA%\Programs\Common`. Like `FOLDERID_UserProgramFiles`, it is not found
+///   until something creates it.
+///
+/// - `USER_PROGRAM_FILES_COMMON_SHELL_FOLDER` in `HKCU\...\Explorer\User Shell Folders`, which
+///   redirects it, as described on `report_shell_folders()`. It is usually absent.
+///
+/// - The per-user `WindowsApps` directory, `%LOCALAPPDATA%\Microsoft\WindowsApps`, which holds the
+///   app execution aliases by which MSIX packages, even when installed for only one
+///   user, are run from the command line. It is in the user's `PATH`.
+///
+/// - `PACKAGE_ROOT_VALUE` in `HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Appx`, the directory
+///   MSIX packages are really installed to, normally `C:\Program Files\WindowsApps`. MSIX
+///   packages are stored there once per machine, even when installed for only one user, so this
+///   is where the aliases lead. It is read through the 64-bit registry view.
+///
+/// [upfc]: https://learn.microsoft.com/en-us/windows/win32/shell/knownfolderid#FOLDERID_UserProgramFilesCommon
+#[cfg(windows)]
+pub fn report_per_user_installs() -> Section<Row> {
+    let user_shell_folders = format!(r"HKCU\{EXPLORER_SUBKEY}\User Shell Folders");
+
+    let entries = vec![
+        Row {
+            name: USER_PROGRAM_FILES_COMMON.to_owned(),
+            lookup: Lookup::new(resolve_guid(
+                FOLDERID_UserProgramFilesCommon,
+                KF_FLAG_DEFAULT,
+            )),
+        },
+        Row {
+            name: USER_PROGRAM_FILES_COMMON_SHELL_FOLDER.to_owned(),
+            lookup: read_key_value(&user_shell_folders, USER_PROGRAM_FILES_COMMON_SHELL_FOLDER),
+        },
+        Row {
+            name: USER_WINDOWS_APPS.to_owned(),
+            lookup: user_windows_apps(),
+        },
+        Row {
+            name: PACKAGE_ROOT_VALUE.to_owned(),
+            lookup: read_key_value(APPX_SUBKEY, PACKAGE_ROOT_VALUE),
+        },
+    ];
+
+    Section::new("Per-user installs", entries)
+}
@@ -0,0 +1,294 @@
+//! Detecting the architectures of this process and of the system, and the version of Windows.
+
+#[cfg(windows)]
+use windows::core::PWSTR;
+#[cfg(windows)]
+use windows::Win32::System::SystemInformation::{
+    ComputerNameDnsHostname, GetComputerNameExW, IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_AMD64,
+    IMAGE_FILE_MACHINE_ARM64, IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_UNKNOWN,
+};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{GetCurrentProcess, IsWow64Process2};
+#[cfg(windows)]
+use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_QUERY_VALUE};
+
+#[cfg(windows)]
+use crate::registry::open_hive;
+
+/// A processor architecture, as relevant to which program files directories exist and are used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Machine {
+    X86,
+    X64,
+    Arm64,
+    Unknown,
+}
+
+impl Machine {
+    /// The architecture this program was compiled for.
+    ///
+    /// An ARM64EC build is treated as x64, since Windows presents ARM64EC processes to themselves
+    /// and to other programs as x64 processes, and they see the registry and environment as such.
+    pub fn of_this_build() -> Self {
+        if cfg!(target_arch = "x86") {
+            Self::X86
+        } else if cfg!(any(target_arch = "x86_64", target_arch = "arm64ec")) {
+            Self::X64
+        } else if cfg!(target_arch = "aarch64") {
+            Self::Arm64
+        } else {
+            Self::Unknown
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn from_image_file_machine(machine: IMAGE_FILE_MACHINE) -> Self {
+        match machine {
+            IMAGE_FILE_MACHINE_I386 => Self::X86,
+            IMAGE_FILE_MACHINE_AMD64 => Self::X64,
+            IMAGE_FILE_MACHINE_ARM64 => Self::Arm64,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn is_64_bit(self) -> bool {
+        matches!(self, Self::X64 | Self::Arm64)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::X86 => "32-bit x86",
+            Self::X64 => "64-bit x64",
+            Self::Arm64 => "64-bit ARM64",
+            Self::Unknown => "unknown architecture",
+        }
+    }
+}
+
+/// The architectures of this process and of the Windows system it is running on.
+#[derive(Clone, Copy, Debug)]
+pub struct Platform {
+    pub process: Machine,
+    pub native: Machine,
+}
+
+impl Platform {
+    /// Detects the architecture of this process and of the system.
+    ///
+    /// The system architecture is found with [`IsWow64Process2`][iwp2], which reports the native
+    /// architecture of the system to x86 and ARM32 processes under WOW64 as well as to native
+    /// processes. An x86-64 process emulated on ARM64 is not a WOW64 process, but
+    /// `IsWow64Process2` still reports the native architecture. That function is not available
+    /// before Windows 10 version 1511. If it fails, the system is assumed to have the same
+    /// architecture as the process, which is correct unless this is a 32-bit process on an older
+    /// 64-bit system. ARM64 Windows always has `IsWow64Process2`, so this never wrongly reports an
+    /// ARM64 system as some other architecture.
+    ///
+    /// [iwp2]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-iswow64process2
+    #[cfg(windows)]
+    pub fn detect() -> Self {
+        let process = Machine::of_this_build();
+
+        let mut process_machine = IMAGE_FILE_MACHINE::default();
+        let mut native_machine = IMAGE_FILE_MACHINE::default();
+
+        let result = unsafe {
+            IsWow64Process2(
+                GetCurrentProcess(),
+                &mut process_machine,
+                Some(&mut native_machine),
+            )
+        };
+
+        let native = match result {
+            Ok(()) => Machine::from_image_file_machine(native_machine),
+            Err(_) => process,
+        };
+
+        Self { process, native }
+    }
+
+    /// Whether this is a 32-bit process running under WOW64 on 64-bit Windows.
+    ///
+    /// An x86-64 process emulated on ARM64 is not a WOW64 process, so this is `false` for it.
+    pub fn is_wow64(self) -> bool {
+        !self.process.is_64_bit() && self.native.is_64_bit()
+    }
+
+    /// Describes the process and system, for use in explanations.
+    pub fn description(self) -> String {
+        let process = self.process.description();
+        let os = match self.native {
+            Machine::X86 => "32-bit",
+            Machine::X64 => "64-bit x64",
+            Machine::Arm64 => "64-bit ARM64",
+            Machine::Unknown => "unknown",
+        };
+        format!("this is a {process} process on {os} Windows")
+    }
+}
+
+/// What this process is running as, as reported by `IsWow64Process2`, for the header of the output.
+///
+/// This differs from `Platform` in keeping what `IsWow64Process2` says apart from what is known
+/// from how this program was built, so the header can show both, and in noting emulation.
+#[derive(Clone, Copy, Debug)]
+pub struct ProcessArchitecture {
+    /// The architecture this program was compiled for, as given by `Machine::of_this_build()`.
+    pub build: Machine,
+
+    /// The process machine reported by `IsWow64Process2`, or `None` if it reported that the process
+    /// is not running under WOW64 or could not be called.
+    pub wow64_machine: Option<Machine>,
+
+    /// The native machine reported by `IsWow64Process2`, or `None` if it could not be called.
+    pub native: Option<Machine>,
+
+    /// Whether this is an ARM64EC build, whose code is ARM64 but which runs as an x64 process.
+    pub arm64ec: bool,
+}
+
+impl ProcessArchitecture {
+    /// Detects the architectures of this process and of the system.
+    ///
+    /// This calls [`IsWow64Process2`][iwp2], as described on `Platform::detect()`. Its process
+    /// machine is `IMAGE_FILE_MACHINE_UNKNOWN` unless the process runs under WOW64, which only
+    /// 32-bit processes do. An x64 process emulated on ARM64 is not under WOW64, so whether a
+    /// process is emulated is instead found by comparing the architecture it was built for to the
+    /// native one.
+    ///
+    /// [iwp2]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-iswow64process2
+    #[cfg(windows)]
+    pub fn detect() -> Self {
+        let mut process_machine = IMAGE_FILE_MACHINE::default();
+        let mut native_machine = IMAGE_FILE_MACHINE::default();
+
+        let result = unsafe {
+            IsWow64Process2(
+                GetCurrentProcess(),
+                &mut process_machine,
+                Some(&mut native_machine),
+            )
+        };
+
+        let (wow64_machine, native) = match result {
+            Ok(()) if process_machine == IMAGE_FILE_MACHINE_UNKNOWN => {
+                (None, Some(Machine::from_image_file_machine(native_machine)))
+            }
+            Ok(()) => (
+                Some(Machine::from_image_file_machine(process_machine)),
+                Some(Machine::from_image_file_machine(native_machine)),
+            ),
+            Err(_) => (None, None),
+        };
+
+        Self {
+            build: Machine::of_this_build(),
+            wow64_machine,
+            native,
+            arm64ec: cfg!(target_arch = "arm64ec"),
+        }
+    }
+
+    /// Whether WOW64 is active for this process, as reported by `IsWow64Process2`.
+    pub fn is_wow64(self) -> bool {
+        self.wow64_machine.is_some()
+    }
+
+    /// Whether this process's machine code is being translated to run on a different architecture.
+    ///
+    /// This is so for x86 and x64 processes on ARM64, but not for x86 processes on x64, which run
+    /// natively under WOW64, nor for ARM64EC processes, whose own code is ARM64.
+    pub fn is_emulated(self) -> bool {
+        self.native == Some(Machine::Arm64)
+            && matches!(self.build, Machine::X86 | Machine::X64)
+            && !self.arm64ec
+    }
+}
+
+/// The registry key, under `HKEY_LOCAL_MACHINE`, whose values identify the version of Windows.
+#[cfg(windows)]
+const WINDOWS_NT_CURRENT_VERSION_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion";
+
+/// Finds the version of Windows, such as `10.0.22631.4317 (23H2)`, if it can be read.
+///
+/// This reads the version number, build number, and update build revision (UBR) from the registry,
+/// rather than calling `GetVersionEx`, which reports an older version to programs without a
+/// manifest declaring support for newer versions. The display version, such as `23H2`, is
+/// included when present. The `ProductName` value is not used, since it says `Windows 10` even on
+/// Windows 11.
+///
+/// If `computer` is given, this is the version of Windows on that computer, as read from its
+/// registry, as described on `open_hive()`.
+#[cfg(windows)]
+pub fn os_version(computer: Option<&str>) -> Option<String> {
+    let key = open_hive(computer, HKEY_LOCAL_MACHINE)
+        .ok()?
+        .open_subkey_with_flags(WINDOWS_NT_CURRENT_VERSION_SUBKEY, KEY_QUERY_VALUE)
+        .ok()?;
+
+    let major: u32 = key.get_value("CurrentMajorVersionNumber").ok()?;
+    let minor: u32 = key.get_value("CurrentMinorVersionNumber").ok()?;
+    let build: String = key.get_value("CurrentBuildNumber").ok()?;
+    let ubr: u32 = key.get_value("UBR").unwrap_or(0);
+
+    let version = format!("{major}.{minor}.{build}.{ubr}");
+
+    match key.get_value::<String, _>("DisplayVersion") {
+        Ok(display_version) => Some(format!("{version} ({display_version})")),
+        Err(_) => Some(version),
+    }
+}
+
+/// Finds the host name of this computer, such as for labeling saved reports.
+///
+/// This is the DNS host name given by [`GetComputerNameExW`][gcne], which is usually the same as
+/// the NetBIOS name in `COMPUTERNAME` but is not truncated to 15 characters.
+///
+/// [gcne]: https://learn.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-getcomputernameexw
+#[cfg(windows)]
+pub fn computer_name() -> Option<String> {
+    // The first call fails, but gives the size needed, including the terminating null.
+    let mut size = 0;
+    let _ = unsafe { GetComputerNameExW(ComputerNameDnsHostname, PWSTR::null(), &mut size) };
+
+    let mut buffer = vec![0_u16; size as usize];
+    unsafe {
+        GetComputerNameExW(
+            ComputerNameDnsHostname,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        )
+    }
+    .ok()?;
+
+    // On success, the size does not include the terminating null.
+    String::from_utf16(&buffer[..size as usize]).ok()
+}
+
+/// The registry key, under `HKEY_LOCAL_MACHINE`, whose values are the system environment variables.
+#[cfg(windows)]
+const SESSION_MANAGER_ENVIRONMENT_SUBKEY: &str =
+    r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+
+/// Finds the architecture of another computer, if it can be read from that computer's registry.
+///
+/// This reads the `PROCESSOR_ARCHITECTURE` system environment variable as stored in the registry,
+/// which is `x86`, `AMD64`, or `ARM64` and is the same in every registry view. Access is as
+/// described on `open_hive()`.
+#[cfg(windows)]
+pub fn remote_machine(computer: &str) -> Option<Machine> {
+    let architecture: String = open_hive(Some(computer), HKEY_LOCAL_MACHINE)
+        .ok()?
+        .open_subkey_with_flags(SESSION_MANAGER_ENVIRONMENT_SUBKEY, KEY_QUERY_VALUE)
+        .ok()?
+        .get_value("PROCESSOR_ARCHITECTURE")
+        .ok()?;
+
+    Some(match architecture.to_ascii_uppercase().as_str() {
+        "X86" => Machine::X86,
+        "AMD64" => Machine::X64,
+        "ARM64" => Machine::Arm64,
+        _ => Machine::Unknown,
+    })
+}
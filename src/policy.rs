@@ -0,0 +1,198 @@
+//! Looking for Group Policy settings and folder redirection that affect the program files folders.
+
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::UI::Shell::{
+    IKnownFolderManager, KnownFolderManager, KF_REDIRECTION_CAPABILITIES_DENY_ALL,
+    KF_REDIRECTION_CAPABILITIES_DENY_PERMISSIONS, KF_REDIRECTION_CAPABILITIES_DENY_POLICY,
+    KF_REDIRECTION_CAPABILITIES_DENY_POLICY_REDIRECTED, KF_REDIRECTION_CAPABILITIES_REDIRECTABLE,
+};
+use windows_core::GUID;
+#[cfg(all(windows, feature = "com"))]
+use winreg::enums::KEY_QUERY_VALUE;
+#[cfg(all(windows, feature = "com"))]
+use winreg::RegKey;
+
+#[cfg(all(windows, feature = "com"))]
+use crate::error::Error;
+use crate::known_folder::{
+    FOLDERID_ProgramFiles, FOLDERID_ProgramFilesX64, FOLDERID_ProgramFilesX86,
+    FOLDERID_UserProgramFiles,
+};
+#[cfg(all(windows, feature = "com"))]
+use crate::known_folder_definition::ComApartment;
+#[cfg(all(windows, feature = "com"))]
+use crate::registry::split_hive;
+#[cfg(all(windows, feature = "com"))]
+use crate::report::{Lookup, Row, Section};
+
+/// The policy keys that configure Explorer and the Shell, each with a caption and its path, which
+/// starts with its hive, as `split_hive()` takes.
+///
+/// Group Policy writes to the `Policies` keys, which users cannot write to, so a value in one of
+/// them was set by an administrator or a domain.
+pub const POLICY_KEYS: [(&str, &str); 4] = [
+    (
+        r"HKLM\SOFTWARE\Policies\Microsoft\Windows\Explorer",
+        r"HKLM\SOFTWARE\Policies\Microsoft\Windows\Explorer",
+    ),
+    (
+        r"HKCU\Software\Policies\Microsoft\Windows\Explorer",
+        r"HKCU\Software\Policies\Microsoft\Windows\Explorer",
+    ),
+    (
+        r"HKLM\...\CurrentVersion\Policies\Explorer",
+        r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\Explorer",
+    ),
+    (
+        r"HKCU\...\CurrentVersion\Policies\Explorer",
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\Policies\Explorer",
+    ),
+];
+
+/// The value of a policy key's entry when the key exists but has no values.
+pub const NO_VALUES: &str = "no values set";
+
+/// The names of the entries for the redirection capabilities of the *program files* known folders,
+/// with their IDs.
+pub const REDIRECTION_CAPABILITIES: [(&str, GUID); 4] = [
+    ("FOLDERID_ProgramFiles redirection", FOLDERID_ProgramFiles),
+    (
+        "FOLDERID_ProgramFilesX64 redirection",
+        FOLDERID_ProgramFilesX64,
+    ),
+    (
+        "FOLDERID_ProgramFilesX86 redirection",
+        FOLDERID_ProgramFilesX86,
+    ),
+    (
+        "FOLDERID_UserProgramFiles redirection",
+        FOLDERID_UserProgramFiles,
+    ),
+];
+
+/// The value of a redirection capabilities entry when policy has already redirected the folder.
+pub const REDIRECTED_BY_POLICY: &str = "denied, because policy has redirected it";
+
+/// The value of a redirection capabilities entry when policy forbids redirecting the folder.
+pub const DENIED_BY_POLICY: &str = "denied by policy";
+
+/// The value of a redirection capabilities entry when this user may not redirect the folder.
+pub const DENIED_BY_PERMISSIONS: &str = "denied, for lack of permissions";
+
+/// The value of a redirection capabilities entry when the folder cannot be redirected for some
+/// other reason.
+pub const DENIED: &str = "denied";
+
+/// The value of a redirection capabilities entry when the folder can be redirected.
+pub const REDIRECTABLE: &str = "redirectable";
+
+/// The value of a redirection capabilities entry when the folder is not of a kind that can be
+/// redirected, as the fixed program files folders are not.
+pub const NOT_REDIRECTABLE: &str = "not redirectable";
+
+/// Lists the names of the values in a policy key, or gives the error opening it.
+///
+/// A key that does not exist, which is usual, is not configured by policy.
+#[cfg(all(windows, feature = "com"))]
+fn policy_key(path: &str) -> Lookup {
+    let (hive, path) = split_hive(path);
+    let key = match RegKey::predef(hive).open_subkey_with_flags(path, KEY_QUERY_VALUE) {
+        Ok(key) => key,
+        Err(e) => return Lookup::new(Err::<String, _>(e)),
+    };
+
+    let names: Result<Vec<String>, _> = key
+        .enum_values()
+        .map(|value| value.map(|(name, _)| name))
+        .collect();
+    Lookup::new(names.map(|names| {
+        if names.is_empty() {
+            String::from(NO_VALUES)
+        } else {
+            names.join(", ")
+        }
+    }))
+}
+
+/// Describes a known folder's redirection capabilities, by the most specific reason they give.
+#[cfg(all(windows, feature = "com"))]
+fn describe_capabilities(capabilities: u32) -> &'static str {
+    let has = |flag: i32| capabilities & flag as u32 != 0;
+
+    if has(KF_REDIRECTION_CAPABILITIES_DENY_POLICY_REDIRECTED.0) {
+        REDIRECTED_BY_POLICY
+    } else if has(KF_REDIRECTION_CAPABILITIES_DENY_POLICY.0) {
+        DENIED_BY_POLICY
+    } else if has(KF_REDIRECTION_CAPABILITIES_DENY_PERMISSIONS.0) {
+        DENIED_BY_PERMISSIONS
+    } else if has(KF_REDIRECTION_CAPABILITIES_DENY_ALL.0) {
+        DENIED
+    } else if has(KF_REDIRECTION_CAPABILITIES_REDIRECTABLE.0) {
+        REDIRECTABLE
+    } else {
+        NOT_REDIRECTABLE
+    }
+}
+
+/// Gets a known folder's redirection capabilities from the known folder manager.
+#[cfg(all(windows, feature = "com"))]
+fn redirection_capabilities(manager: &IKnownFolderManager, id: GUID) -> Lookup {
+    let capabilities = unsafe { manager.GetFolder(&id) }
+        .and_then(|folder| unsafe { folder.GetRedirectionCapabilities() })
+        .map(describe_capabilities)
+        .map_err(Error::KnownFolder);
+    Lookup::new(capabilities)
+}
+
+/// Report whether Group Policy configures Explorer, and whether the *program files* known folders
+/// are redirected or blocked from redirection by policy.
+///
+/// Folder redirection, usually set up through Group Policy, moves known folders elsewhere, often to
+/// a network share, and policy can also forbid redirecting them. When a folder is not where it is
+/// expected, or a tool fails to move it, policy is the usual reason, and nothing else in the report
+/// says so. This reports:
+///
+/// - Each of `POLICY_KEYS`, giving the names of the values set in it, or `NO_VALUES`. These keys
+///   are absent unless some policy configures Explorer. The values are listed rather than
+///   interpreted, since few of them concern program files, but any of them may explain behavior
+///   that differs from an unmanaged machine.
+///
+/// - Each of `REDIRECTION_CAPABILITIES`, from
+///   [`IKnownFolder::GetRedirectionCapabilities`][ikf-grc], as `REDIRECTED_BY_POLICY`,
+///   `DENIED_BY_POLICY`, `DENIED_BY_PERMISSIONS`, `DENIED`, `REDIRECTABLE`, or
+///   `NOT_REDIRECTABLE`. The machine-wide program files folders are fixed, so they are not
+///   redirectable. `FOLDERID_UserProgramFiles` is per-user, so it usually is, unless policy has
+///   redirected it or forbids it.
+///
+/// Where folder redirection has put a folder is written to the `User Shell Folders` key, as shown
+/// by `report_shell_folders()`, and whether it is at its default location is shown by
+/// `known_folder_redirection()`.
+///
+/// Getting redirection capabilities requires COM, which is initialized on this thread for the
+/// duration. So this is only available with the `com` feature.
+///
+/// [ikf-grc]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-iknownfolder-getredirectioncapabilities
+#[cfg(all(windows, feature = "com"))]
+pub fn report_policy() -> Result<Section<Row>, Error> {
+    let _apartment = ComApartment::initialize()?;
+
+    let manager: IKnownFolderManager =
+        unsafe { CoCreateInstance(&KnownFolderManager, None, CLSCTX_INPROC_SERVER) }
+            .map_err(Error::Com)?;
+
+    let policy_keys = POLICY_KEYS.into_iter().map(|(caption, path)| Row {
+        name: caption.to_owned(),
+        lookup: policy_key(path),
+    });
+    let capabilities = REDIRECTION_CAPABILITIES.into_iter().map(|(name, id)| Row {
+        name: name.to_owned(),
+        lookup: redirection_capabilities(&manager, id),
+    });
+
+    Ok(Section::new(
+        "Policy and folder redirection",
+        policy_keys.chain(capabilities).collect(),
+    ))
+}
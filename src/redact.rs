@@ -0,0 +1,322 @@
+//! Masking user names in reports, so they can be shared publicly.
+
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{FOLDERID_UserProfiles, KF_FLAG_DEFAULT};
+
+use crate::error::EntryError;
+#[cfg(windows)]
+use crate::known_folder::resolve_guid;
+use crate::report::{
+    CsidlEntry, EnvVarEntry, EnvironmentKey, FreshEnvVarEntry, KnownFolderDefinition,
+    KnownFolderDefinitionEntry, KnownFolderEntry, Lookup, RegistryEntry, RegistryView, Report, Row,
+    Section, ShellFolderEntry, ShellFolderKey, TimedOutSection, UserProfile,
+};
+#[cfg(windows)]
+use crate::security::DirectorySecurity;
+
+/// What a user name is replaced with.
+pub const REDACTED_USER: &str = "<user>";
+
+/// Where Windows puts profile directories, unless it is set up otherwise.
+///
+/// This is masked under even if `FOLDERID_UserProfiles` is elsewhere on this computer, since
+/// reports of other computers and offline images most often have their profiles here.
+#[cfg(windows)]
+const DEFAULT_PROFILES_DIRECTORY: &str = r"C:\Users";
+
+/// Directories in the profiles directory that are not named for a user, and so are not masked.
+const SHARED_PROFILES: [&str; 4] = ["All Users", "Default", "Default User", "Public"];
+
+/// The prefix of the SIDs of domain and local accounts, as opposed to well-known accounts such as
+/// `LocalSystem` and service accounts, whose names are the same on every computer.
+const ACCOUNT_SID_PREFIX: &str = "S-1-5-21-";
+
+/// Masks user names in paths and account names.
+///
+/// In a path, the directory just inside a profiles directory, such as `ek` in
+/// `C:\Users\ek\AppData\Local\Programs`, is named for the user whose profile it is, so it is
+/// replaced with `REDACTED_USER`, giving `C:\Users\<user>\AppData\Local\Programs`. Directories
+/// that are not named for a user, such as `Public`, are kept. Profiles directories are matched
+/// without regard to case, anywhere in the text, so paths inside error messages and raw registry
+/// values are masked too.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Redactor {
+    /// The profiles directories, each without a trailing backslash.
+    profiles_directories: Vec<String>,
+}
+
+impl Redactor {
+    /// Makes a redactor for the given profiles directories, such as `C:\Users`, each with or
+    /// without a trailing backslash.
+    pub fn new<S: AsRef<str>>(profiles_directories: impl IntoIterator<Item = S>) -> Self {
+        let mut directories: Vec<String> = Vec::new();
+        for directory in profiles_directories {
+            let directory = directory.as_ref().trim_end_matches('\\');
+            if !directories
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(directory))
+            {
+                directories.push(directory.to_owned());
+            }
+        }
+        Self {
+            profiles_directories: directories,
+        }
+    }
+
+    /// Makes a redactor for this computer's profiles directory, the `FOLDERID_UserProfiles` known
+    /// folder, and for `DEFAULT_PROFILES_DIRECTORY`.
+    #[cfg(windows)]
+    pub fn detect() -> Self {
+        let detected = resolve_guid(FOLDERID_UserProfiles, KF_FLAG_DEFAULT)
+            .map(|directory| directory.to_string_lossy().into_owned());
+        Self::new(
+            [DEFAULT_PROFILES_DIRECTORY.to_owned()]
+                .into_iter()
+                .chain(detected),
+        )
+    }
+
+    /// Gives the text with the user name in each path in it masked.
+    pub fn text(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+        for directory in &self.profiles_directories {
+            text = mask_after(&text, directory);
+        }
+        text
+    }
+
+    /// Gives an account name, as `DOMAIN\name`, with the name masked, keeping the domain.
+    pub fn account(&self, account: &str) -> String {
+        match account.rsplit_once('\\') {
+            Some((domain, _)) => format!(r"{domain}\{REDACTED_USER}"),
+            None => REDACTED_USER.to_owned(),
+        }
+    }
+}
+
+/// Gives the text with the path component following each occurrence of `directory` masked.
+fn mask_after(text: &str, directory: &str) -> String {
+    let prefix = format!("{}\\", directory.to_ascii_lowercase());
+    // ASCII lowercasing keeps every character the same length, so positions carry over.
+    let lowered = text.to_ascii_lowercase();
+
+    let mut masked = String::with_capacity(text.len());
+    let mut rest = 0;
+    while let Some(found) = lowered[rest..].find(&prefix) {
+        let start = rest + found + prefix.len();
+        let length = text[start..]
+            .find(['\\', '/', ';', '"', '\''])
+            .unwrap_or(text.len() - start);
+        let component = &text[start..start + length];
+
+        masked.push_str(&text[rest..start]);
+        let shared = SHARED_PROFILES
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(component));
+        masked.push_str(if component.is_empty() || shared {
+            component
+        } else {
+            REDACTED_USER
+        });
+        rest = start + length;
+    }
+    masked.push_str(&text[rest..]);
+    masked
+}
+
+/// Something in a report that may have user names in it, to be masked by a `Redactor`.
+pub trait Redact {
+    /// Masks the user names in this, as described on `Redactor`.
+    fn redact(&mut self, redactor: &Redactor);
+}
+
+impl Redact for String {
+    fn redact(&mut self, redactor: &Redactor) {
+        *self = redactor.text(self);
+    }
+}
+
+impl<T: Redact> Redact for Option<T> {
+    fn redact(&mut self, redactor: &Redactor) {
+        if let Some(value) = self {
+            value.redact(redactor);
+        }
+    }
+}
+
+impl<T: Redact> Redact for Vec<T> {
+    fn redact(&mut self, redactor: &Redactor) {
+        for value in self {
+            value.redact(redactor);
+        }
+    }
+}
+
+impl Redact for EntryError {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.message.redact(redactor);
+    }
+}
+
+impl Redact for Lookup {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.path.redact(redactor);
+        self.escaped_path.redact(redactor);
+        self.error.redact(redactor);
+    }
+}
+
+impl Redact for EnvVarEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+    }
+}
+
+impl Redact for FreshEnvVarEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+        self.inherited.redact(redactor);
+    }
+}
+
+impl Redact for KnownFolderEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+    }
+}
+
+impl Redact for KnownFolderDefinition {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.parsing_name.redact(redactor);
+    }
+}
+
+impl Redact for KnownFolderDefinitionEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+        self.definition.redact(redactor);
+    }
+}
+
+impl Redact for CsidlEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+        self.default.redact(redactor);
+    }
+}
+
+impl Redact for RegistryEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+        self.raw.redact(redactor);
+    }
+}
+
+impl Redact for ShellFolderEntry {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+        self.raw.redact(redactor);
+    }
+}
+
+impl Redact for Row {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.lookup.redact(redactor);
+    }
+}
+
+impl<E: Redact> Redact for Section<E> {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.heading.redact(redactor);
+        self.entries.redact(redactor);
+    }
+}
+
+impl Redact for RegistryView {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.keys.redact(redactor);
+    }
+}
+
+impl Redact for EnvironmentKey {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.values.redact(redactor);
+    }
+}
+
+impl Redact for ShellFolderKey {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.values.redact(redactor);
+    }
+}
+
+impl Redact for UserProfile {
+    /// Masks the profile's paths and, if it is of a domain or local account rather than a
+    /// well-known one, its account name, including where its headings name it.
+    fn redact(&mut self, redactor: &Redactor) {
+        self.profile_path.redact(redactor);
+        self.environment.redact(redactor);
+        self.shell_folders.redact(redactor);
+
+        if !self.sid.starts_with(ACCOUNT_SID_PREFIX) {
+            return;
+        }
+        if let Some(account) = &self.account {
+            let masked = redactor.account(account);
+            let rename = |heading: &mut String| {
+                *heading = heading.replace(&format!(r"HKU\{account}\"), &format!(r"HKU\{masked}\"));
+            };
+            rename(&mut self.environment.values.heading);
+            for key in &mut self.shell_folders {
+                rename(&mut key.values.heading);
+            }
+            self.account = Some(masked);
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Redact for DirectorySecurity {
+    /// Masks the owner's account name if it is a domain or local account, as for `UserProfile`.
+    /// The trustees whose rights are summarized are all well-known, as listed in
+    /// `SUMMARIZED_TRUSTEES`, so they are kept.
+    fn redact(&mut self, redactor: &Redactor) {
+        if self.owner_sid.starts_with(ACCOUNT_SID_PREFIX) {
+            self.owner = redactor.account(&self.owner);
+            self.owner_sid = REDACTED_USER.to_owned();
+        }
+    }
+}
+
+impl Redact for TimedOutSection {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.section.redact(redactor);
+    }
+}
+
+impl Redact for Report {
+    fn redact(&mut self, redactor: &Redactor) {
+        self.environment_variables.redact(redactor);
+        self.known_folders.redact(redactor);
+        self.known_folder_definitions.redact(redactor);
+        self.shell_monikers.redact(redactor);
+        self.csidls.redact(redactor);
+        self.csidl_locations.redact(redactor);
+        self.registry.redact(redactor);
+        self.shell_folders.redact(redactor);
+        self.environment_registry.redact(redactor);
+        self.fresh_environment.redact(redactor);
+        self.wmi.redact(redactor);
+        self.shells.redact(redactor);
+        self.msi.redact(redactor);
+        self.system_directories.redact(redactor);
+        self.arm_program_files.redact(redactor);
+        self.per_user_installs.redact(redactor);
+        self.virtualization.redact(redactor);
+        self.package.redact(redactor);
+        self.policy.redact(redactor);
+        self.users.redact(redactor);
+        self.timed_out.redact(redactor);
+    }
+}
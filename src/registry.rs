@@ -0,0 +1,660 @@
+//! Looking up program files folder locations in the registry.
+
+#[cfg(windows)]
+use std::ffi::OsString;
+#[cfg(windows)]
+use std::io;
+#[cfg(all(windows, feature = "registry"))]
+use std::mem::ManuallyDrop;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStringExt;
+#[cfg(windows)]
+use std::path::Path;
+
+#[cfg(windows)]
+use windows::core::HSTRING;
+#[cfg(windows)]
+use windows::Wdk::System::Registry::{KeyNameInformation, NtQueryKey};
+#[cfg(windows)]
+use windows::Win32::Foundation::{
+    FILETIME, HANDLE, STATUS_BUFFER_OVERFLOW, STATUS_BUFFER_TOO_SMALL, SYSTEMTIME,
+};
+#[cfg(windows)]
+use windows::Win32::System::Environment::ExpandEnvironmentStringsW;
+#[cfg(windows)]
+use windows::Win32::System::Registry::RegConnectRegistryW;
+#[cfg(windows)]
+use windows::Win32::System::Time::{FileTimeToSystemTime, SystemTimeToTzSpecificLocalTime};
+#[cfg(windows)]
+use winreg::{
+    enums::{RegType, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_QUERY_VALUE, KEY_READ},
+    types::FromRegValue,
+    RegKey, HKEY,
+};
+
+#[cfg(windows)]
+use crate::error::Error;
+#[cfg(windows)]
+use crate::report::{Lookup, RegistryEntry, RegistryView, Section};
+#[cfg(windows)]
+use crate::timing::traced;
+
+/// The registry key, under `HKEY_LOCAL_MACHINE`, whose values hold program files directories.
+pub const CURRENT_VERSION_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion";
+
+/// The registry keys, in `CURRENT_VERSION_SUBKEY`, that may hold program files folder locations.
+pub const PROGRAM_FILES_REGISTRY_VALUES: [&str; 4] = [
+    "ProgramFilesDir",
+    "ProgramFilesDir (Arm)",
+    "ProgramFilesDir (x86)",
+    // "ProgramFilesPath", // Less interesting, usually literal %ProgramFiles% if got this way.
+    "ProgramW6432Dir",
+];
+
+/// The `KEY_WOW64_32KEY` flag, which opens a key through the 32-bit registry view.
+///
+/// This and `KEY_WOW64_64KEY` have the same values as in the `winreg` crate, which has them only
+/// on Windows, so the tables that use them are available everywhere.
+pub const KEY_WOW64_32KEY: u32 = 0x0200;
+
+/// The `KEY_WOW64_64KEY` flag, which opens a key through the 64-bit registry view.
+pub const KEY_WOW64_64KEY: u32 = 0x0100;
+
+/// Registry views to query, each with a caption and the flag, if any, that selects it.
+pub const REGISTRY_VIEWS: [(&str, u32); 3] = [
+    ("default view", 0),
+    ("KEY_WOW64_32KEY", KEY_WOW64_32KEY),
+    ("KEY_WOW64_64KEY", KEY_WOW64_64KEY),
+];
+
+/// The caption of the view that reads `CURRENT_VERSION_SUBKEY`, or the key given instead, by its
+/// literal path, without registry redirection.
+pub const LITERAL_PATH_VIEW: &str = "literal path";
+
+/// The caption of the view that reads the `WOW6432Node` equivalent of `CURRENT_VERSION_SUBKEY`, or
+/// of the key given instead, by its literal path.
+pub const LITERAL_WOW6432NODE_VIEW: &str = "literal WOW6432Node path";
+
+/// Views that read a key by its literal path, each with a caption and the caption of the view in
+/// `REGISTRY_VIEWS` that should read the same key, which it is checked against.
+pub const LITERAL_PATH_VIEWS: [(&str, &str); 2] = [
+    (LITERAL_PATH_VIEW, "KEY_WOW64_64KEY"),
+    (LITERAL_WOW6432NODE_VIEW, "KEY_WOW64_32KEY"),
+];
+
+/// Keys to query in an offline `SOFTWARE` hive, each with a caption.
+///
+/// The `SOFTWARE` hive is what is mounted as `HKLM\SOFTWARE`, so these are `CURRENT_VERSION_SUBKEY`
+/// without its first component. Registry views do not apply to a hive loaded from a file, so the
+/// 32-bit key is reached through `WOW6432Node` directly, as the 32-bit view would redirect to it.
+pub const OFFLINE_HIVE_KEYS: [(&str, &str); 2] = [
+    ("offline hive", r"Microsoft\Windows\CurrentVersion"),
+    (
+        "offline hive, WOW6432Node",
+        r"WOW6432Node\Microsoft\Windows\CurrentVersion",
+    ),
+];
+
+/// Opens a predefined registry key, such as `HKEY_LOCAL_MACHINE`, on this or another computer.
+///
+/// To open the key on another computer, pass its name, with or without leading backslashes. This
+/// connects with [`RegConnectRegistryW`][rcrw], which requires the Remote Registry service to be
+/// running on that computer and the user to have access to it. Only `HKEY_LOCAL_MACHINE` and
+/// `HKEY_USERS` can be opened this way. Opening `HKEY_CURRENT_USER` fails, since it would not be
+/// clear whose it is.
+///
+/// [rcrw]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regconnectregistryw
+#[cfg(windows)]
+pub fn open_hive(computer: Option<&str>, hive: HKEY) -> Result<RegKey, io::Error> {
+    let Some(computer) = computer else {
+        return Ok(RegKey::predef(hive));
+    };
+
+    let name = HSTRING::from(format!(r"\\{}", computer.trim_start_matches('\\')));
+    let mut handle = windows::Win32::System::Registry::HKEY::default();
+
+    let status = unsafe {
+        RegConnectRegistryW(
+            &name,
+            windows::Win32::System::Registry::HKEY(hive as _),
+            &mut handle,
+        )
+    };
+    if status.0 != 0 {
+        return Err(io::Error::from_raw_os_error(status.0 as i32));
+    }
+
+    // The connected key is not predefined, so dropping the RegKey closes it.
+    Ok(RegKey::predef(handle.0 as _))
+}
+
+/// Opens a registry key for querying values, through the specified registry view.
+///
+/// The key is normally `CURRENT_VERSION_SUBKEY`, which is under `HKEY_LOCAL_MACHINE`. For
+/// diagnostics and testing, a different key can be given. It is taken to be under
+/// `HKEY_LOCAL_MACHINE` unless it starts with `HKCU\` or `HKEY_CURRENT_USER\`, which allows a
+/// scratch key that does not require elevation to create. A leading `HKLM\` or
+/// `HKEY_LOCAL_MACHINE\` is also accepted.
+///
+/// If `computer` is given, the key is opened on that computer, as described on `open_hive()`.
+#[cfg(windows)]
+pub fn open_current_version(
+    computer: Option<&str>,
+    subkey: &str,
+    flag_for_view: u32,
+) -> Result<RegKey, io::Error> {
+    let (hive, path) = split_hive(subkey);
+    open_hive(computer, hive)?.open_subkey_with_flags(path, KEY_QUERY_VALUE | flag_for_view)
+}
+
+/// Splits a leading hive name, if any, from a registry key path, for `open_current_version()`.
+#[cfg(windows)]
+pub(crate) fn split_hive(subkey: &str) -> (HKEY, &str) {
+    let hives = [
+        (r"HKCU\", HKEY_CURRENT_USER),
+        (r"HKEY_CURRENT_USER\", HKEY_CURRENT_USER),
+        (r"HKLM\", HKEY_LOCAL_MACHINE),
+        (r"HKEY_LOCAL_MACHINE\", HKEY_LOCAL_MACHINE),
+    ];
+
+    for (prefix, hive) in hives {
+        if let Some(head) = subkey.get(..prefix.len()) {
+            if head.eq_ignore_ascii_case(prefix) {
+                return (hive, &subkey[prefix.len()..]);
+            }
+        }
+    }
+
+    (HKEY_LOCAL_MACHINE, subkey)
+}
+
+/// Finds the literal paths for the views in `LITERAL_PATH_VIEWS`, in the same order, giving them
+/// with the hive they are in.
+///
+/// These are `subkey`, as given to `open_current_version()`, and its `WOW6432Node` equivalent, in
+/// which `WOW6432Node` follows `SOFTWARE`. This is `None` unless `subkey` is in `HKLM\SOFTWARE`,
+/// and not already in `WOW6432Node`, since only then does registry redirection apply to it.
+#[cfg(windows)]
+pub fn literal_paths(subkey: &str) -> Option<(HKEY, [String; 2])> {
+    let (hive, path) = split_hive(subkey);
+    if hive != HKEY_LOCAL_MACHINE {
+        return None;
+    }
+
+    let (software, rest) = path.split_once('\\')?;
+    let redirected = software.eq_ignore_ascii_case("SOFTWARE")
+        && !rest
+            .split('\\')
+            .next()
+            .is_some_and(|component| component.eq_ignore_ascii_case("WOW6432Node"));
+    if !redirected {
+        return None;
+    }
+
+    Some((
+        hive,
+        [path.to_owned(), format!(r"{software}\WOW6432Node\{rest}")],
+    ))
+}
+
+/// Expands environment variable references, such as `%ProgramFiles%`, in a `REG_EXPAND_SZ` value.
+///
+/// This uses [`ExpandEnvironmentStringsW`][eesw], which expands variables from the environment of
+/// this process. This is how the Shell expands these values, so a parent process that customizes
+/// the environment, as described on `report_environment_variables()`, affects the result.
+///
+/// [eesw]: https://learn.microsoft.com/en-us/windows/win32/api/processenv/nf-processenv-expandenvironmentstringsw
+#[cfg(windows)]
+pub(crate) fn expand(raw: &str) -> Result<OsString, Error> {
+    let source = HSTRING::from(raw);
+
+    // The first call finds the size, including the terminating null. The second fills the buffer.
+    let size = unsafe { ExpandEnvironmentStringsW(&source, None) };
+    if size == 0 {
+        return Err(Error::Expand(windows::core::Error::from_win32()));
+    }
+
+    let mut buffer = vec![0u16; size as usize];
+    let written = unsafe { ExpandEnvironmentStringsW(&source, Some(&mut buffer)) };
+    if written == 0 || written > size {
+        return Err(Error::Expand(windows::core::Error::from_win32()));
+    }
+
+    Ok(OsString::from_wide(&buffer[..written as usize - 1]))
+}
+
+/// A library through which to read registry values.
+///
+/// The `winreg` crate is used throughout this crate. Microsoft's `windows-registry` crate is an
+/// alternative, and `CrossCheck` reads each value with both and reports any divergence between
+/// them, as `report_known_folders()` does for its two ways of looking up known folders. Those two
+/// are only available with the `registry` feature, which `windows-registry` is needed for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// The [`winreg`][winreg] crate, which distinguishes `REG_SZ` from `REG_EXPAND_SZ` values.
+    ///
+    /// [winreg]: https://crates.io/crates/winreg
+    Winreg,
+
+    /// The [`windows-registry`][wr] crate.
+    ///
+    /// Its version 0.2 reports `REG_SZ` and `REG_EXPAND_SZ` values as the same type, so values
+    /// read with it are never expanded, and their type is shown as `REG_SZ or REG_EXPAND_SZ`.
+    ///
+    /// [wr]: https://crates.io/crates/windows-registry
+    #[cfg(feature = "registry")]
+    WindowsRegistry,
+
+    /// Both crates. Where they agree, the result is as for `Winreg`. Where they disagree about the
+    /// raw data, or about whether there are any, the entry's error is an [`Error::Discrepancy`].
+    #[cfg(feature = "registry")]
+    CrossCheck,
+}
+
+#[cfg(windows)]
+impl Backend {
+    /// Reads a string value, as described on `read_string_value()`, through this backend.
+    pub(crate) fn read(
+        self,
+        key: &RegKey,
+        value_name: &str,
+    ) -> (Option<String>, Option<String>, Lookup) {
+        match self {
+            Self::Winreg => read_string_value(key, value_name),
+            #[cfg(feature = "registry")]
+            Self::WindowsRegistry => read_string_value_with_windows_registry(key, value_name),
+            #[cfg(feature = "registry")]
+            Self::CrossCheck => {
+                let ours = read_string_value(key, value_name);
+                let theirs = read_string_value_with_windows_registry(key, value_name);
+
+                if ours.1 == theirs.1 {
+                    ours
+                } else {
+                    let error = Error::Discrepancy {
+                        first: ("winreg", describe_raw(&ours.1, &ours.2)),
+                        second: ("windows-registry", describe_raw(&theirs.1, &theirs.2)),
+                    };
+                    (ours.0, ours.1, Lookup::new(Err::<String, _>(error)))
+                }
+            }
+        }
+    }
+}
+
+/// Describes the raw data read from a registry value, or why there are none, for a discrepancy.
+#[cfg(windows)]
+fn describe_raw(raw: &Option<String>, lookup: &Lookup) -> String {
+    match (raw, &lookup.error) {
+        (Some(raw), _) => raw.clone(),
+        (None, Some(error)) => format!("an error ({})", error.message),
+        (None, None) => String::from("nothing"),
+    }
+}
+
+/// Reads a string value from a registry key with the `windows-registry` crate.
+///
+/// The key is opened with `winreg`, so that both backends read through the same handle, and thus
+/// the same registry view. The handle is lent to `windows-registry` without transferring ownership.
+#[cfg(all(windows, feature = "registry"))]
+fn read_string_value_with_windows_registry(
+    key: &RegKey,
+    value_name: &str,
+) -> (Option<String>, Option<String>, Lookup) {
+    // SAFETY: The handle stays open while `key` lives, and ManuallyDrop keeps it from being closed
+    // here, so it is closed only once, when `key` is dropped.
+    let borrowed =
+        ManuallyDrop::new(unsafe { windows_registry::Key::from_raw(key.raw_handle() as _) });
+
+    match borrowed.get_string(value_name) {
+        Ok(raw) => (
+            Some(String::from("REG_SZ or REG_EXPAND_SZ")),
+            Some(raw.clone()),
+            Lookup::new(Ok::<_, Error>(raw)),
+        ),
+        Err(e) => (
+            None,
+            None,
+            Lookup::new(Err::<String, _>(Error::Registry(win32_io_error(e)))),
+        ),
+    }
+}
+
+/// Converts an error from a Win32 registry function, reported as an `HRESULT`, to an `io::Error`,
+/// so it is shown just as the same error from `winreg` would be.
+#[cfg(all(windows, feature = "registry"))]
+fn win32_io_error(e: windows::core::Error) -> io::Error {
+    let code = e.code().0 as u32;
+    if code & 0xFFFF_0000 == 0x8007_0000 {
+        io::Error::from_raw_os_error((code & 0xFFFF) as i32)
+    } else {
+        io::Error::other(e)
+    }
+}
+
+/// Reads a string value from a registry key, giving its type, its raw data, and its path.
+///
+/// The type is the name of the registry value type, such as `REG_SZ`. The raw data are the string
+/// as stored. The path is the raw data with any environment variables expanded, if the type is
+/// `REG_EXPAND_SZ`, or otherwise the raw data unchanged, so that a `REG_SZ` value holding a
+/// literal reference such as `%SystemDrive%\Program Files`, which nothing will expand, is shown as
+/// it would be used. If the value cannot be read, or is not a string, there is no type or raw
+/// data, and the lookup holds the error.
+#[cfg(windows)]
+pub(crate) fn read_string_value(
+    key: &RegKey,
+    value_name: &str,
+) -> (Option<String>, Option<String>, Lookup) {
+    let value = traced("RegQueryValueExW", format_args!("{value_name}"), || {
+        key.get_raw_value(value_name)
+    })
+    .and_then(|value| Ok((String::from_reg_value(&value)?, value.vtype)));
+
+    match value {
+        Ok((raw, vtype)) => {
+            let lookup = if vtype == RegType::REG_EXPAND_SZ {
+                Lookup::new(expand(&raw))
+            } else {
+                Lookup::new(Ok::<_, Error>(raw.clone()))
+            };
+            (Some(format!("{vtype:?}")), Some(raw), lookup)
+        }
+        Err(e) => (None, None, Lookup::new(Err::<String, _>(e))),
+    }
+}
+
+/// Finds the full name the kernel has for an open registry key, with [`NtQueryKey`][ntqk].
+///
+/// This is the key that was really opened, after any registry redirection, so it shows, for
+/// example, that the 32-bit view of `HKLM\SOFTWARE` opened `WOW6432Node`. The name is in the
+/// native form, such as `\REGISTRY\MACHINE\SOFTWARE\WOW6432Node\Microsoft\...`, and a key in an
+/// application hive is under `\REGISTRY\A`. This gives `None` if the key has no such name, as a
+/// key on another computer, which is reached through RPC rather than a kernel handle, does not.
+///
+/// [ntqk]: https://learn.microsoft.com/en-us/windows-hardware/drivers/ddi/wdm/nf-wdm-zwquerykey
+#[cfg(windows)]
+pub(crate) fn key_name(key: &RegKey) -> Option<String> {
+    let handle = HANDLE(key.raw_handle() as _);
+
+    let mut length = 0;
+    let status = unsafe { NtQueryKey(handle, KeyNameInformation, None, 0, &mut length) };
+    if status != STATUS_BUFFER_TOO_SMALL && status != STATUS_BUFFER_OVERFLOW {
+        return None;
+    }
+
+    // KEY_NAME_INFORMATION is a ULONG giving the length of the name in bytes, then the name in
+    // UTF-16, not null-terminated. A buffer of u32 keeps the length aligned.
+    let mut buffer = vec![0_u32; (length as usize).div_ceil(4)];
+    let status = unsafe {
+        NtQueryKey(
+            handle,
+            KeyNameInformation,
+            Some(buffer.as_mut_ptr().cast()),
+            length,
+            &mut length,
+        )
+    };
+    if status.is_err() {
+        return None;
+    }
+
+    let name_length = (buffer[0] as usize / 2).min((buffer.len() - 1) * 2);
+    let name = unsafe { std::slice::from_raw_parts(buffer[1..].as_ptr().cast(), name_length) };
+    Some(String::from_utf16_lossy(name))
+}
+
+/// Finds when an open registry key was last written, with [`RegQueryInfoKeyW`][rqik], as the
+/// local date and time `YYYY-MM-DD HH:MM:SS`.
+///
+/// A key is written when any of its values is set or deleted, so this is when the last of its
+/// values changed, as by an installer. The time is converted to this computer's time zone, even if
+/// the key is on another computer. This gives `None` if the time could not be found.
+///
+/// [rqik]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regqueryinfokeyw
+#[cfg(windows)]
+pub(crate) fn last_write_time(key: &RegKey) -> Option<String> {
+    let info = key.query_info().ok()?;
+    let file_time = FILETIME {
+        dwLowDateTime: info.last_write_time.dwLowDateTime,
+        dwHighDateTime: info.last_write_time.dwHighDateTime,
+    };
+
+    let mut utc = SYSTEMTIME::default();
+    let mut local = SYSTEMTIME::default();
+    unsafe { FileTimeToSystemTime(&file_time, &mut utc) }.ok()?;
+    unsafe { SystemTimeToTzSpecificLocalTime(None, &utc, &mut local) }.ok()?;
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        local.wYear, local.wMonth, local.wDay, local.wHour, local.wMinute, local.wSecond
+    ))
+}
+
+/// Reads each of `PROGRAM_FILES_REGISTRY_VALUES` from a key, through the given backend.
+#[cfg(windows)]
+fn registry_entries(key: &RegKey, backend: Backend) -> Vec<RegistryEntry> {
+    PROGRAM_FILES_REGISTRY_VALUES
+        .into_iter()
+        .map(|key_name| {
+            let (value_type, raw, lookup) = backend.read(key, key_name);
+            RegistryEntry {
+                name: key_name.to_owned(),
+                value_type,
+                raw,
+                lookup,
+            }
+        })
+        .collect()
+}
+
+/// Makes an entry for each of `PROGRAM_FILES_REGISTRY_VALUES`, all with the error opening the key
+/// they are in.
+#[cfg(windows)]
+fn error_entries(e: io::Error) -> Vec<RegistryEntry> {
+    let lookup = Lookup::new(Err::<String, _>(e));
+    PROGRAM_FILES_REGISTRY_VALUES
+        .into_iter()
+        .map(|key_name| RegistryEntry {
+            name: key_name.to_owned(),
+            value_type: None,
+            raw: None,
+            lookup: lookup.clone(),
+        })
+        .collect()
+}
+
+/// Report *program files* folder locations from a single specified view of the registry.
+///
+/// See `report_all_registry_views()` for more information on views.
+///
+/// This accesses subkeys of `HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion` using the `winreg`
+/// crate, which uses [`RegOpenKeyExW`][regokew]. A different key can be passed as `subkey`, as
+/// described on `open_current_version()`. If `computer` is given, the values are read from that
+/// computer's registry instead, and the heading names it.
+///
+/// Each value is read by `read_string_value()`, so its entry has the value's type and raw data as
+/// well as its path, which is the expansion of the data if the value is `REG_EXPAND_SZ`. These
+/// values are normally `REG_SZ` values holding plain paths, but an installer or administrator can
+/// write them otherwise, and the raw data make that visible. The values are read through
+/// `backend`, as described on `Backend`.
+///
+/// The view also has the full name of the key that was opened, as found by `key_name()`, which
+/// shows where registry redirection took the 32-bit view, and when the key was last written, as
+/// found by `last_write_time()`, to help tell whether an installer changed its values.
+///
+/// [regokew]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regopenkeyexw
+#[cfg(windows)]
+pub fn report_registry_view(
+    computer: Option<&str>,
+    backend: Backend,
+    subkey: &str,
+    caption: &'static str,
+    flag_for_view: u32,
+) -> Result<RegistryView, Error> {
+    let heading = match computer {
+        Some(computer) => format!("Relevant registry keys on {computer} - with {caption}"),
+        None => format!("Relevant registry keys - with {caption}"),
+    };
+
+    let cur_ver = open_current_version(computer, subkey, flag_for_view)
+        .map_err(|e| Error::Registry(e).in_section(&heading))?;
+
+    Ok(RegistryView {
+        view: caption,
+        opened_key: key_name(&cur_ver),
+        last_write_time: last_write_time(&cur_ver),
+        keys: Section::new(heading, registry_entries(&cur_ver, backend)),
+    })
+}
+
+/// Report *program files* folder locations from multiple views of the registry.
+///
+/// See also:
+///
+/// - [Accessing an Alternate Registry View][aarv] for details on registry views that can be
+///   accessed.
+///
+/// - `report_registry_view()` for details on how the lookup is performed.
+///
+/// - `report_literal_path_view()` for the views in `LITERAL_PATH_VIEWS`, which follow the views in
+///   `REGISTRY_VIEWS` when `subkey` is in `HKLM\SOFTWARE`, as described on `literal_paths()`.
+///
+/// [aarv]: https://learn.microsoft.com/en-us/windows/win32/winprog64/accessing-an-alternate-registry-view
+#[cfg(windows)]
+pub fn report_all_registry_views(
+    computer: Option<&str>,
+    backend: Backend,
+    subkey: &str,
+) -> Result<Vec<RegistryView>, Error> {
+    let mut views = REGISTRY_VIEWS
+        .into_iter()
+        .map(|(caption, flag_for_view)| {
+            report_registry_view(computer, backend, subkey, caption, flag_for_view)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some((hive, paths)) = literal_paths(subkey) {
+        for ((caption, counterpart), path) in LITERAL_PATH_VIEWS.into_iter().zip(paths) {
+            let counterpart = views.iter().find(|view| view.view == counterpart);
+            let view =
+                report_literal_path_view(computer, backend, hive, &path, caption, counterpart);
+            views.push(view);
+        }
+    }
+
+    Ok(views)
+}
+
+/// Report *program files* folder locations from a key read by its literal path, checking them
+/// against a view that should read the same key.
+///
+/// Some software hardcodes `WOW6432Node` in the paths it reads and writes, rather than passing
+/// `KEY_WOW64_32KEY`, and registry redirection can make the two differ, as can the 32-bit key
+/// having been reflected or shared differently on older versions of Windows. So this opens `path`
+/// in `hive` with `KEY_WOW64_64KEY`, which keeps it from being redirected, so that it is read as
+/// written, even by a 32-bit process. The values are read as `report_registry_view()` reads them.
+///
+/// Each value whose raw data differ from those of the same value in `counterpart`, or that is
+/// present in only one of them, has an [`Error::Discrepancy`] giving both. If the key cannot be
+/// opened, as `WOW6432Node` cannot on 32-bit Windows, which has none, each entry has that error
+/// instead, and is not checked.
+#[cfg(windows)]
+pub fn report_literal_path_view(
+    computer: Option<&str>,
+    backend: Backend,
+    hive: HKEY,
+    path: &str,
+    caption: &'static str,
+    counterpart: Option<&RegistryView>,
+) -> RegistryView {
+    let heading = match computer {
+        Some(computer) => format!("Relevant registry keys on {computer} - with {caption}"),
+        None => format!("Relevant registry keys - with {caption}"),
+    };
+
+    let key = open_hive(computer, hive)
+        .and_then(|root| root.open_subkey_with_flags(path, KEY_QUERY_VALUE | KEY_WOW64_64KEY));
+    let (opened_key, last_write_time, entries) = match key {
+        Ok(key) => {
+            let mut entries = registry_entries(&key, backend);
+            if let Some(counterpart) = counterpart {
+                check_against(&mut entries, caption, counterpart);
+            }
+            (key_name(&key), last_write_time(&key), entries)
+        }
+        Err(e) => (None, None, error_entries(e)),
+    };
+
+    RegistryView {
+        view: caption,
+        opened_key,
+        last_write_time,
+        keys: Section::new(heading, entries),
+    }
+}
+
+/// Marks each entry whose raw data differ from those of the same entry in another view with an
+/// [`Error::Discrepancy`], for `report_literal_path_view()`.
+#[cfg(windows)]
+fn check_against(entries: &mut [RegistryEntry], caption: &'static str, other: &RegistryView) {
+    for entry in entries {
+        let Some(theirs) = other.keys.entries.iter().find(|e| e.name == entry.name) else {
+            continue;
+        };
+        if theirs.raw != entry.raw {
+            let error = Error::Discrepancy {
+                first: (other.view, describe_raw(&theirs.raw, &theirs.lookup)),
+                second: (caption, describe_raw(&entry.raw, &entry.lookup)),
+            };
+            entry.lookup = Lookup::new(Err::<String, _>(error));
+        }
+    }
+}
+
+/// Report *program files* folder locations from an offline `SOFTWARE` registry hive.
+///
+/// This is for servicing a Windows image, such as a mounted WIM or VHD, whose registry is not the
+/// running system's, so none of the other sources can be used. The hive file, normally
+/// `Windows\System32\config\SOFTWARE` in the image, is loaded read-only as an application hive
+/// with [`RegLoadAppKey`][rlak], which does not require elevation and does not make the hive
+/// visible to other processes. Each key in `OFFLINE_HIVE_KEYS` is reported as its own view, in the
+/// same way as `report_registry_view()` reports a view of the running system's registry.
+///
+/// `REG_EXPAND_SZ` values are expanded with this process's environment, which may not match the
+/// image's. The raw data show what the image itself holds.
+///
+/// If a key cannot be opened, as `WOW6432Node` cannot in an image of 32-bit Windows, which has
+/// none, each of its entries has that error. Only failing to load the hive is an error.
+///
+/// Each view has the key's full name and last write time, as `report_registry_view()` gives them.
+/// The name is under `\REGISTRY\A`, where application hives are loaded, rather than where the
+/// image would mount the hive. The last write time shows when the image's values were last set.
+///
+/// [rlak]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regloadappkeyw
+#[cfg(windows)]
+pub fn report_offline_hive(hive: &Path) -> Result<Vec<RegistryView>, Error> {
+    let root = RegKey::load_app_key_with_flags(hive, KEY_READ, 0).map_err(Error::Registry)?;
+
+    let views = OFFLINE_HIVE_KEYS
+        .into_iter()
+        .map(|(caption, path)| {
+            let (opened_key, last_write_time, entries) =
+                match root.open_subkey_with_flags(path, KEY_QUERY_VALUE) {
+                    Ok(cur_ver) => (
+                        key_name(&cur_ver),
+                        last_write_time(&cur_ver),
+                        registry_entries(&cur_ver, Backend::Winreg),
+                    ),
+                    Err(e) => (None, None, error_entries(e)),
+                };
+
+            RegistryView {
+                view: caption,
+                opened_key,
+                last_write_time,
+                keys: Section::new(format!("Relevant registry keys - in {caption}"), entries),
+            }
+        })
+        .collect();
+
+    Ok(views)
+}
@@ -0,0 +1,2456 @@
+//! Rendering reports as text, Markdown, HTML, CSV or TSV, JSON or JSON lines, YAML, and TOML.
+//!
+//! The sources only look things up, giving a `Report`. Everything here only formats what was
+//! looked up, giving it as a string rather than printing it, so each format can be produced, and
+//! checked, without a console. How the text is styled is given by a `Style`.
+//!
+//! The text output is made of many parts, such as the table of resolved directories, and the
+//! findings at the end, each rendered by its own function here. What the parts show that is not in
+//! the report, such as whether each path exists on disk, is looked up by the caller and passed in.
+
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+#[cfg(any(windows, feature = "serde"))]
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use unicode_width::UnicodeWidthChar;
+
+use crate::analysis::{
+    self, entries_for_directory, explain_entry, lookup_status, reconcile_program_w6432,
+    resolve_consensus, summarize, Alias, Anomaly, AppCensus, Change, Check, Expectation,
+    ExpectationOutcome, Finding, LogicalDirectory, Relocation, RelocationSign, ServiceSign,
+    Severity, SummaryCell, ARM64_DIRECTORIES, SUMMARY_COLUMNS,
+};
+#[cfg(windows)]
+use crate::ancestry::Ancestor;
+use crate::apps::InstalledApp;
+#[cfg(windows)]
+use crate::elevation::Elevation;
+#[cfg(feature = "serde")]
+use crate::environment::PROGRAM_FILES_ENVIRONMENT_VARIABLES;
+use crate::error::Error;
+#[cfg(feature = "serde")]
+use crate::experiment::{Matrix, Trial};
+use crate::filesystem::{Verification, Volume, WriteProbe};
+#[cfg(all(windows, feature = "com"))]
+use crate::known_folder::IdListRoundTrip;
+#[cfg(windows)]
+use crate::known_folder::{Redirection, KNOWN_FOLDER_FLAG_VARIATIONS};
+use crate::narrative::Paragraph;
+#[cfg(windows)]
+use crate::platform::Machine;
+use crate::platform::{Platform, ProcessArchitecture};
+use crate::redact::Redactor;
+use crate::report::{
+    escape_non_unicode, Entry, KnownFolderDefinitionEntry, Lookup, Report, Section, SectionRef,
+};
+#[cfg(feature = "serde")]
+use crate::saved::HistoryEntry;
+#[cfg(windows)]
+use crate::security::DirectorySecurity;
+#[cfg(windows)]
+use crate::timing::Timing;
+
+/// A color for highlighting part of the text output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    /// For errors.
+    Red,
+
+    /// For paths that disagree with other sources, and other mismatches.
+    Yellow,
+
+    /// For paths that the sources agree on.
+    Green,
+}
+
+impl Color {
+    /// The ANSI SGR parameter that selects this color.
+    fn code(self) -> &'static str {
+        match self {
+            Self::Red => "31",
+            Self::Yellow => "33",
+            Self::Green => "32",
+        }
+    }
+}
+
+/// How text output is styled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style<'a> {
+    /// Whether to color text with ANSI escape sequences.
+    pub color: bool,
+
+    /// Whether to show paths that are not valid Unicode escaped, as described on
+    /// `escape_non_unicode()`, rather than with U+FFFD REPLACEMENT CHARACTER.
+    pub escape_non_unicode: bool,
+
+    /// What masks user names in paths that were not masked before they were passed in, if they
+    /// are to be masked at all, as described on `masked()`.
+    pub redactor: Option<&'a Redactor>,
+}
+
+impl Style<'_> {
+    /// Gives the text in the specified color, if color is used, or otherwise unchanged.
+    pub fn paint(&self, text: &str, color: Color) -> String {
+        if self.color {
+            format!("\x1b[{}m{text}\x1b[0m", color.code())
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// Gives a path as text, escaped if `escape_non_unicode` is set, or otherwise made valid
+    /// Unicode.
+    pub fn path_text(&self, path: &OsStr) -> String {
+        if self.escape_non_unicode {
+            escape_non_unicode(path)
+        } else {
+            path.to_string_lossy().into_owned()
+        }
+    }
+
+    /// Gives the text with the user names in its paths masked, if there is a `redactor`.
+    ///
+    /// Reports are masked before they are rendered. But some parts of the text output are made
+    /// from real paths, such as what is found on disk at them, since masked paths do not exist, and
+    /// those are masked with this as they are rendered.
+    pub fn masked(&self, text: &str) -> String {
+        match self.redactor {
+            Some(redactor) => redactor.text(text),
+            None => text.to_owned(),
+        }
+    }
+
+    /// Gives the path column for an entry, colored red if it is an error, or yellow if it is a
+    /// `DISCREPANCY`.
+    pub fn path_item(&self, lookup: &Lookup) -> String {
+        let path_item = if self.escape_non_unicode {
+            lookup.escaped_path_item()
+        } else {
+            lookup.path_item()
+        };
+        match &lookup.error {
+            Some(error) if lookup.path.is_none() && error.discrepancy => {
+                self.paint(&path_item, Color::Yellow)
+            }
+            Some(_) if lookup.path.is_none() => self.paint(&path_item, Color::Red),
+            _ => path_item,
+        }
+    }
+}
+
+/// The width of text as shown, in terminal cells, not counting the escape sequences added by
+/// `Style::paint()`.
+///
+/// Characters are not all one cell wide. Most CJK characters take two cells, and combining
+/// characters, such as an accent written after the letter it goes on, take none. So this uses the
+/// widths given by [`unicode-width`][uw], which are what terminals use.
+///
+/// [uw]: https://crates.io/crates/unicode-width
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+    width
+}
+
+/// Gives text followed by enough spaces to make it take up `width` cells, as `visible_width()`
+/// measures them.
+///
+/// This is used instead of padding with a format width, which counts characters, not cells.
+pub fn padded(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(visible_width(text));
+    format!("{text}{:padding$}", "")
+}
+
+/// Finds the width of a column of a table, which is that of its widest cell.
+pub fn column_width<'a, I>(cells: I) -> usize
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    cells.into_iter().map(visible_width).max().unwrap_or(0)
+}
+
+/// Renders rows of cells as a table, with each column as wide as its widest cell, and each row
+/// indented and on its own line.
+///
+/// Cells may be colored with `Style::paint()`, which does not affect how wide they are taken to
+/// be. An empty list of rows gives an empty table.
+pub fn table(rows: &[Vec<String>]) -> String {
+    let Some(first) = rows.first() else {
+        return String::new();
+    };
+    let widths: Vec<usize> = (0..first.len())
+        .map(|column| column_width(rows.iter().map(|row| row[column].as_str())))
+        .collect();
+
+    let mut text = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| padded(cell, width))
+            .collect();
+        text.push_str(&format!("  {}\n", cells.join("  ").trim_end()));
+    }
+    text
+}
+
+/// Renders a section as a heading followed by a table of names and paths, and a blank line.
+pub fn section(heading: &str, entries: &[&dyn Entry], style: &Style<'_>) -> String {
+    let width = column_width(entries.iter().map(|entry| entry.name()));
+
+    let mut text = format!("{heading}:\n\n");
+    for entry in entries {
+        text.push_str(&format!(
+            "  {}  {}\n",
+            padded(entry.name(), width),
+            style.path_item(entry.lookup())
+        ));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders a section as `section()` does, with more columns about each entry.
+///
+/// `columns` has the cells of each entry's extra columns, in order, such as whether its path was
+/// verified on disk. They are found from the entries before any user names were masked, since
+/// masked paths do not exist, so they are masked here, as described on `Style::masked()`.
+pub fn section_with_columns(
+    heading: &str,
+    entries: &[&dyn Entry],
+    columns: &[Vec<String>],
+    style: &Style<'_>,
+) -> String {
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .zip(columns)
+        .map(|(entry, cells)| {
+            let mut row = vec![entry.name().to_owned(), style.path_item(entry.lookup())];
+            row.extend(cells.iter().map(|cell| style.masked(cell)));
+            row
+        })
+        .collect();
+
+    format!("{heading}:\n\n{}\n", table(&rows))
+}
+
+/// Describes what the filesystem says about a reported path, for a verification column.
+///
+/// Problems that mean the path is not a usable directory are red. A reparse point, or a final path
+/// that differs from the reported one, is yellow, since the directory is usable but is not where,
+/// or not written as, it is reported to be.
+pub fn describe_verification(path: &str, verification: &Verification, style: &Style<'_>) -> String {
+    if !verification.exists {
+        return style.paint("[does not exist]", Color::Red);
+    }
+    if !verification.is_directory {
+        return style.paint("[not a directory]", Color::Red);
+    }
+
+    let mut notes = Vec::new();
+    if let Some(reparse) = verification.reparse {
+        notes.push(format!("[{}]", reparse.description()));
+    }
+    match &verification.final_path {
+        Ok(final_path) if verification.differs_from(path) => {
+            notes.push(format!("[final path: {final_path}]"));
+        }
+        Ok(_) => {}
+        Err(e) => notes.push(format!("[final path unavailable: {}]", e.message())),
+    }
+
+    if notes.is_empty() {
+        style.paint("[verified]", Color::Green)
+    } else {
+        style.paint(&notes.join(" "), Color::Yellow)
+    }
+}
+
+/// Describes a path as verified with WOW64 file system redirection disabled, labeled so it is not
+/// mistaken for the column given by `describe_verification()`. If redirection could not be
+/// disabled, that is shown in red instead.
+pub fn describe_unredirected_verification(
+    path: &str,
+    verification: &Result<Verification, windows_core::Error>,
+    style: &Style<'_>,
+) -> String {
+    let item = match verification {
+        Ok(verification) => describe_verification(path, verification, style),
+        Err(e) => style.paint(
+            &format!("[could not disable redirection: {}]", e.message()),
+            Color::Red,
+        ),
+    };
+    format!("unredirected: {item}")
+}
+
+/// Describes another form of a path, such as its 8.3 `short` form, or says why it has none.
+pub fn describe_path_form(form: &str, path: &Result<String, windows_core::Error>) -> String {
+    match path {
+        Ok(path) => format!("{form}: {path}"),
+        Err(e) => format!("[no {form} form: {}]", e.message()),
+    }
+}
+
+/// Renders the profiles whose per-user registry values are in the report, if there are any.
+///
+/// Each is shown with its account name, or `[unknown account]` if its SID no longer names one, its
+/// SID, and its directory, and is marked if its hive had to be loaded from `NTUSER.DAT` because
+/// the user was not logged on.
+pub fn user_profiles(report: &Report, style: &Style<'_>) -> String {
+    if report.users.is_empty() {
+        return String::new();
+    }
+
+    let rows: Vec<Vec<String>> = report
+        .users
+        .iter()
+        .map(|user| {
+            let account = user.account.as_deref().unwrap_or("[unknown account]");
+            let note = if user.hive_was_loaded {
+                String::new()
+            } else {
+                style.paint("[loaded from NTUSER.DAT]", Color::Yellow)
+            };
+            vec![
+                format!("  {account}"),
+                user.sid.clone(),
+                style.path_item(&user.profile_path),
+                note,
+            ]
+        })
+        .collect();
+
+    format!("Local profiles:\n\n{}\n", table(&rows))
+}
+
+/// Renders, for after the section of known folders, the ID of each known folder, next to its
+/// symbol.
+pub fn known_folder_ids(report: &Report) -> String {
+    let Some(section) = &report.known_folders else {
+        return String::new();
+    };
+
+    let width = column_width(section.entries.iter().map(|entry| entry.name.as_str()));
+
+    let mut text = String::from("  IDs:\n\n");
+    for entry in &section.entries {
+        text.push_str(&format!("    {:<width$}  {{{:?}}}\n", entry.name, entry.id));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders, for after a section of known folder definitions, the ID and definition of each known
+/// folder.
+///
+/// Fields a definition does not have, such as a relative path for a folder with no parent, are
+/// shown as `[none]`. A folder whose definition could not be retrieved is shown as such.
+pub fn known_folder_definitions(section: &Section<KnownFolderDefinitionEntry>) -> String {
+    let fields = [
+        "ID",
+        "Canonical name",
+        "Localized name",
+        "Category",
+        "Parsing name",
+        "Relative path",
+        "Parent folder",
+    ];
+    let width = column_width(fields);
+
+    let mut text = String::from("  Definitions:\n\n");
+    for entry in &section.entries {
+        text.push_str(&format!("    {}:\n", entry.name));
+
+        let Some(definition) = &entry.definition else {
+            text.push_str(&format!(
+                "      {:<width$}  {{{:?}}}\n",
+                fields[0], entry.id
+            ));
+            text.push_str("      [definition not available]\n");
+            continue;
+        };
+
+        let none = || String::from("[none]");
+        let values = [
+            format!("{{{:?}}}", entry.id),
+            definition.canonical_name.clone(),
+            match (
+                &definition.localized_name,
+                &definition.localized_name_source,
+            ) {
+                (Some(name), Some(source)) => format!("{name} ({source})"),
+                (None, Some(source)) => format!("[could not be loaded] ({source})"),
+                _ => none(),
+            },
+            definition.category.to_owned(),
+            definition.parsing_name.clone().unwrap_or_else(none),
+            definition.relative_path.clone().unwrap_or_else(none),
+            definition
+                .parent
+                .map(|id| format!("{{{id:?}}}"))
+                .unwrap_or_else(none),
+        ];
+
+        for (field, value) in fields.into_iter().zip(values) {
+            text.push_str(&format!("      {field:<width$}  {value}\n"));
+        }
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders, for after a section of registry values, the type and raw data of each value that was
+/// read.
+///
+/// Each value is given as a name, its type, and its data before expansion. The expansion is the
+/// path shown in the section itself. A `REG_SZ` value with a `%` in it is marked, since it looks
+/// like it was meant to be `REG_EXPAND_SZ`, and nothing will expand it.
+fn raw_values(values: &[(&str, &str, &str)]) -> String {
+    if values.is_empty() {
+        return String::new();
+    }
+
+    let name_width = column_width(values.iter().map(|&(name, _, _)| name));
+    let type_width = column_width(values.iter().map(|&(_, value_type, _)| value_type));
+
+    let mut text = String::from("  Raw values:\n\n");
+    for &(name, value_type, raw) in values {
+        let note = if value_type == "REG_SZ" && raw.contains('%') {
+            "  [not expanded, because the value is REG_SZ]"
+        } else {
+            ""
+        };
+        text.push_str(&format!(
+            "    {name:<name_width$}  {value_type:<type_width$}  {raw}{note}\n"
+        ));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders, for after a section of stored environment variables, the type and raw data of each
+/// value, as `raw_values()` does.
+///
+/// The section is found by its heading, since with `--all-users` there is one for each user with
+/// the same key.
+pub fn environment_raw_values(report: &Report, heading: &str) -> String {
+    let Some(environment_key) = report
+        .environment_registry
+        .iter()
+        .chain(report.users.iter().map(|user| &user.environment))
+        .find(|environment_key| environment_key.values.heading == heading)
+    else {
+        return String::new();
+    };
+
+    let values: Vec<(&str, &str, &str)> = environment_key
+        .values
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            Some((
+                entry.name.as_str(),
+                entry.value_type.as_deref()?,
+                entry.raw.as_deref()?,
+            ))
+        })
+        .collect();
+
+    raw_values(&values)
+}
+
+/// Renders, for after the section of CSIDLs, each CSIDL's current path beside its default path.
+///
+/// The current path is the one shown in the section itself, from `SHGFP_TYPE_CURRENT`, and the
+/// default path is from `SHGFP_TYPE_DEFAULT`. A CSIDL whose paths differ is marked, since its
+/// folder was redirected or otherwise moved from where it would be by default.
+pub fn csidl_defaults(report: &Report, style: &Style<'_>) -> String {
+    let Some(section) = &report.csidls else {
+        return String::new();
+    };
+
+    // The rows are indented by the name column, to line up with other tables after a section.
+    let rows: Vec<Vec<String>> = std::iter::once(vec![
+        String::from("  "),
+        String::from("SHGFP_TYPE_CURRENT"),
+        String::from("SHGFP_TYPE_DEFAULT"),
+        String::new(),
+    ])
+    .chain(section.entries.iter().filter_map(|entry| {
+        let default = entry.default.as_ref()?;
+        let note = if entry.differs_from_default() {
+            style.paint("[differs from default]", Color::Yellow)
+        } else {
+            String::new()
+        };
+        Some(vec![
+            format!("  {}", entry.name),
+            style.path_item(&entry.lookup),
+            style.path_item(default),
+            note,
+        ])
+    }))
+    .collect();
+
+    if rows.len() == 1 {
+        return String::new();
+    }
+
+    format!("  Current and default paths:\n\n{}\n", table(&rows))
+}
+
+/// Renders, for after the section for the fresh environment block, each inherited variable that
+/// differs from it, as found by `report_fresh_environment()`.
+pub fn stale_environment(report: &Report, style: &Style<'_>) -> String {
+    let Some(section) = &report.fresh_environment else {
+        return String::new();
+    };
+
+    let stale: Vec<_> = section.entries.iter().filter(|entry| entry.stale).collect();
+    if stale.is_empty() {
+        return String::new();
+    }
+
+    let width = column_width(stale.iter().map(|entry| entry.name.as_str()));
+
+    let mut text = String::from("  Stale, as inherited:\n\n");
+    for entry in stale {
+        let inherited = entry.inherited.as_deref().unwrap_or("[absent]");
+        text.push_str(&format!(
+            "    {:<width$}  {}\n",
+            entry.name,
+            style.paint(inherited, Color::Yellow)
+        ));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders where each program files environment variable would come from in a new environment,
+/// and whether that matches what this process inherited.
+///
+/// The comparison is made by `environment_origins()`. This is only rendered when both the
+/// inherited and the stored environment variables were looked up, since otherwise it would be
+/// misleading.
+pub fn environment_origins(report: &Report, style: &Style<'_>) -> String {
+    if report.environment_variables.is_none() || report.environment_registry.is_empty() {
+        return String::new();
+    }
+
+    let rows: Vec<Vec<String>> = analysis::environment_origins(report)
+        .into_iter()
+        .map(|origin| {
+            if origin.matches() {
+                vec![origin.variable.to_owned(), origin.description()]
+            } else {
+                vec![
+                    format!("{}*", origin.variable),
+                    style.paint(&origin.description(), Color::Yellow),
+                ]
+            }
+        })
+        .collect();
+
+    format!(
+        "Environment origins:\n\n{}\n  * Differs from what a new environment would get.\n\n",
+        table(&rows)
+    )
+}
+
+/// Renders, for after a section of registry keys, the key that was really opened, when it was last
+/// written, and the type and raw data of each value, as `raw_values()` does.
+///
+/// The key is given by its native name, which shows where registry redirection took the view,
+/// such as to `WOW6432Node`.
+pub fn registry_raw_values(report: &Report, view: &str) -> String {
+    let Some(registry_view) = report.registry.iter().find(|rv| rv.view == view) else {
+        return String::new();
+    };
+
+    let mut text = String::new();
+    if let Some(opened_key) = &registry_view.opened_key {
+        text.push_str(&format!("  Opened key:    {opened_key}\n"));
+    }
+    if let Some(last_write_time) = &registry_view.last_write_time {
+        text.push_str(&format!("  Last written:  {last_write_time}\n"));
+    }
+    if registry_view.opened_key.is_some() || registry_view.last_write_time.is_some() {
+        text.push('\n');
+    }
+
+    let values: Vec<(&str, &str, &str)> = registry_view
+        .keys
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            Some((
+                entry.name.as_str(),
+                entry.value_type.as_deref()?,
+                entry.raw.as_deref()?,
+            ))
+        })
+        .collect();
+
+    text + &raw_values(&values)
+}
+
+/// Renders, for after a section of shell folders registry values, the type and raw data of each
+/// value, as `raw_values()` does.
+///
+/// Values are labeled with the known folders they are for, rather than the GUIDs that name them.
+/// The section is found by its heading, as described on `environment_raw_values()`.
+pub fn shell_folder_raw_values(report: &Report, heading: &str) -> String {
+    let Some(shell_folder_key) = report
+        .shell_folders
+        .iter()
+        .chain(report.users.iter().flat_map(|user| &user.shell_folders))
+        .find(|shell_folder_key| shell_folder_key.values.heading == heading)
+    else {
+        return String::new();
+    };
+
+    let values: Vec<(&str, &str, &str)> = shell_folder_key
+        .values
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            Some((
+                entry.known_folder,
+                entry.value_type.as_deref()?,
+                entry.raw.as_deref()?,
+            ))
+        })
+        .collect();
+
+    raw_values(&values)
+}
+
+/// The columns of the tables of every known folder, as `known_folders()` and the other renderings
+/// of them give them.
+const KNOWN_FOLDER_HEADER: [&str; 4] = ["Known folder ID", "Canonical name", "Category", "Path"];
+
+/// The columns of CSV and TSV output of every known folder, in order, as `known_folders_delimited()`
+/// gives them. Like `DELIMITED_COLUMNS`, these are stable.
+pub const KNOWN_FOLDER_COLUMNS: [&str; 5] = ["id", "name", "category", "status", "path"];
+
+/// The cells of each row of a table of known folders, for the columns in `KNOWN_FOLDER_HEADER`.
+///
+/// Folders whose definitions could not be retrieved have `[unknown]` for their names and
+/// categories.
+fn known_folder_rows(section: &Section<KnownFolderDefinitionEntry>) -> Vec<[String; 4]> {
+    section
+        .entries
+        .iter()
+        .map(|entry| {
+            let (name, category) = match &entry.definition {
+                Some(definition) => (definition.canonical_name.clone(), definition.category),
+                None => (String::from("[unknown]"), "[unknown]"),
+            };
+            [
+                format!("{{{:?}}}", entry.id),
+                name,
+                category.to_owned(),
+                entry.lookup.path_item(),
+            ]
+        })
+        .collect()
+}
+
+/// Renders a section of known folders, with their definitions, as a table of each folder's ID,
+/// canonical name, category, and path, as found by `known_folder_rows()`.
+pub fn known_folders(section: &Section<KnownFolderDefinitionEntry>) -> String {
+    let mut rows = vec![KNOWN_FOLDER_HEADER.map(String::from).to_vec()];
+    rows.extend(
+        known_folder_rows(section)
+            .into_iter()
+            .map(|row| row.to_vec()),
+    );
+
+    format!("{}:\n\n{}\n", section.heading, table(&rows))
+}
+
+/// Renders a section of known folders as a GitHub-flavored Markdown table, with the same columns as
+/// `known_folders()` gives.
+pub fn known_folders_markdown(section: &Section<KnownFolderDefinitionEntry>) -> String {
+    let mut text = format!(
+        "### {}\n\n| {} |\n| --- | --- | --- | --- |\n",
+        section.heading,
+        KNOWN_FOLDER_HEADER.join(" | ")
+    );
+    for row in known_folder_rows(section) {
+        text.push_str(&format!(
+            "| {} |\n",
+            row.map(|cell| markdown_cell(&cell)).join(" | ")
+        ));
+    }
+    text
+}
+
+/// Renders a section of known folders as a standalone HTML page with one table, with the same
+/// columns as `known_folders()` gives.
+pub fn known_folders_html(section: &Section<KnownFolderDefinitionEntry>) -> String {
+    let title = html_escape(&section.heading);
+    let mut text = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n\
+        <head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n\
+        <body>\n<h1>{title}</h1>\n<table>\n<tr><th>{}</th></tr>\n",
+        KNOWN_FOLDER_HEADER.join("</th><th>")
+    );
+    for row in known_folder_rows(section) {
+        text.push_str(&format!(
+            "<tr><td>{}</td></tr>\n",
+            row.map(|cell| html_escape(&cell)).join("</td><td>")
+        ));
+    }
+    text.push_str("</table>\n</body>\n</html>\n");
+    text
+}
+
+/// Renders a section of known folders as CSV or TSV, as `delimited()` renders a report, with the
+/// columns in `KNOWN_FOLDER_COLUMNS`, headed by a line naming them.
+pub fn known_folders_delimited(
+    section: &Section<KnownFolderDefinitionEntry>,
+    separator: char,
+) -> String {
+    let mut text = delimited_line(&KNOWN_FOLDER_COLUMNS, separator) + "\n";
+    for (entry, row) in section.entries.iter().zip(known_folder_rows(section)) {
+        let [id, name, category, _] = row;
+        let path = entry.lookup.path.clone().unwrap_or_default();
+        let status = lookup_status(&entry.lookup).to_owned();
+        let cells = [id, name, category, status, path];
+        text.push_str(&delimited_line(&cells, separator));
+        text.push('\n');
+    }
+    text
+}
+
+/// Renders installed applications as a table, with where each was found and where it is.
+fn apps(apps: &[InstalledApp], style: &Style<'_>) -> String {
+    let rows: Vec<Vec<String>> = apps
+        .iter()
+        .map(|app| {
+            vec![
+                app.name.clone(),
+                format!("({})", app.source),
+                style.masked(app.location.as_deref().unwrap_or_default()),
+            ]
+        })
+        .collect();
+    table(&rows)
+}
+
+/// Renders the installed applications grouped by the program files directory each is in, as
+/// grouped by `app_census()`.
+///
+/// Each directory is shown with its applications, followed by those elsewhere. Applications whose
+/// location is not known are only counted, since there are often many of them and nothing to say
+/// about where they are.
+///
+/// The census is of real paths, so user names are masked here, as described on `Style::masked()`.
+pub fn app_census(census: &AppCensus, style: &Style<'_>) -> String {
+    let mut text = String::new();
+    for (directory, path, found) in &census.directories {
+        text.push_str(&format!(
+            "{} ({}): {} applications\n\n",
+            directory.description(),
+            style.paint(&style.masked(path), Color::Green),
+            found.len()
+        ));
+        if !found.is_empty() {
+            text.push_str(&apps(found, style));
+            text.push('\n');
+        }
+    }
+
+    text.push_str(&format!(
+        "Elsewhere: {} applications\n\n",
+        census.elsewhere.len()
+    ));
+    if !census.elsewhere.is_empty() {
+        text.push_str(&apps(&census.elsewhere, style));
+        text.push('\n');
+    }
+
+    let unknown = format!(
+        "[{} more applications do not say where they are installed]",
+        census.unknown.len()
+    );
+    text.push_str(&style.paint(&unknown, Color::Yellow));
+    text.push('\n');
+    text
+}
+
+/// The width that the text of an explanation from `narrative::explain()` is wrapped to.
+const NARRATIVE_WIDTH: usize = 96;
+
+/// Wraps text to `NARRATIVE_WIDTH`, indenting each line, breaking only between words.
+fn wrap(text: &str, indent: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::from(indent);
+    for word in text.split_whitespace() {
+        if line.len() > indent.len() && line.len() + 1 + word.len() > NARRATIVE_WIDTH {
+            lines.push(std::mem::replace(&mut line, String::from(indent)));
+        }
+        if line.len() > indent.len() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if line.len() > indent.len() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Renders an explanation, as given by `narrative::explain()`, of what the sources in a report show
+/// about the system.
+///
+/// Each paragraph is shown under its heading, wrapped to `NARRATIVE_WIDTH`, followed by the
+/// documentation it cites.
+pub fn narrative(paragraphs: &[Paragraph], style: &Style<'_>) -> String {
+    let mut text = String::new();
+    for paragraph in paragraphs {
+        text.push_str(&style.paint(paragraph.heading, Color::Green));
+        text.push('\n');
+        for line in wrap(&paragraph.text, "  ") {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        for citation in &paragraph.citations {
+            text.push_str(&format!("  See {}: {}\n", citation.title, citation.url));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Renders whether each *program files* known folder appears to have been redirected, as found by
+/// `known_folder_redirection()`.
+///
+/// Each folder is labeled `default` or `redirected`. A redirected folder's default path is shown
+/// too. Folders for which a lookup failed are skipped, with a note giving the error.
+#[cfg(windows)]
+pub fn known_folder_redirection(redirections: &[(&str, Redirection)], style: &Style<'_>) -> String {
+    let width = column_width(redirections.iter().map(|&(symbol, _)| symbol));
+    let path_text = |path: &OsStr| style.masked(&style.path_text(path));
+
+    let mut text = String::from("Known folder redirection:\n\n");
+    for (symbol, redirection) in redirections {
+        let line = match redirection {
+            Redirection::Default(current) => {
+                format!("  {symbol:<width$}  default     {}", path_text(current))
+            }
+            Redirection::Redirected { current, default } => format!(
+                "  {symbol:<width$}  redirected  {} (default: {})",
+                path_text(current),
+                path_text(default),
+            ),
+            Redirection::Unknown(e) => {
+                format!("  {symbol:<width$}  [skipped, because the lookup failed: {e}]")
+            }
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders each *program files* known folder as looked up with each flag, side by side, as looked
+/// up by `known_folder_flag_variations()`.
+///
+/// There is one column for each flag in `KNOWN_FOLDER_FLAG_VARIATIONS`. Failed lookups show the
+/// error in square brackets.
+#[cfg(windows)]
+pub fn known_folder_flag_variations(variations: &[(&str, Vec<Lookup>)]) -> String {
+    let header =
+        std::iter::once("Known folder").chain(KNOWN_FOLDER_FLAG_VARIATIONS.map(|(n, _)| n));
+    let mut rows = vec![header.map(String::from).collect::<Vec<_>>()];
+
+    for (symbol, lookups) in variations {
+        let cells = lookups.iter().map(Lookup::path_item);
+        rows.push(std::iter::once((*symbol).to_owned()).chain(cells).collect());
+    }
+
+    format!("Known folders with each KF_FLAG:\n\n{}\n", table(&rows))
+}
+
+/// Describes whether a known folder's path and item ID list agree, for `known_folder_id_lists()`.
+#[cfg(all(windows, feature = "com"))]
+fn describe_round_trip(round_trip: &IdListRoundTrip) -> String {
+    if round_trip.agrees() {
+        return String::from("agrees");
+    }
+
+    match (
+        &round_trip.path.path,
+        &round_trip.id_list_path.path,
+        &round_trip.same_item,
+    ) {
+        (None, None, _) => String::from("unavailable"),
+        (path, id_list_path, _) if path != id_list_path => String::from("DIFFERENT PATHS"),
+        (_, _, Some(Ok(false))) => String::from("DIFFERENT ITEM"),
+        (_, _, Some(Err(e))) => format!("[path does not parse: {}]", e.message),
+        _ => String::from("[no information]"),
+    }
+}
+
+/// Renders each *program files* known folder as looked up both as a path and as an item ID list,
+/// as looked up by `known_folder_id_list_round_trips()`.
+///
+/// Each row gives the folder's path, the filesystem path and parsing name of its item ID list, and
+/// whether they agree, which is `DIFFERENT PATHS` if the paths differ and `DIFFERENT ITEM` if the
+/// path parses back to another item than the folder's. Failed lookups show the error in square
+/// brackets. If the round trips could not be made at all, that is shown instead.
+#[cfg(all(windows, feature = "com"))]
+pub fn known_folder_id_lists(round_trips: &Result<Vec<(&str, IdListRoundTrip)>, Error>) -> String {
+    let round_trips = match round_trips {
+        Ok(round_trips) => round_trips,
+        Err(e) => return format!("Known folders as item ID lists:\n\n  [skipped: {e}]\n\n"),
+    };
+
+    let header = [
+        "Known folder",
+        "SHGetKnownFolderPath",
+        "SIGDN_FILESYSPATH",
+        "SIGDN_DESKTOPABSOLUTEPARSING",
+        "Round trip",
+    ];
+    let mut rows = vec![header.map(String::from).to_vec()];
+
+    for (symbol, round_trip) in round_trips {
+        rows.push(vec![
+            (*symbol).to_owned(),
+            round_trip.path.path_item(),
+            round_trip.id_list_path.path_item(),
+            round_trip.parsing_name.path_item(),
+            describe_round_trip(round_trip),
+        ]);
+    }
+
+    format!("Known folders as item ID lists:\n\n{}\n", table(&rows))
+}
+
+/// Renders whether the `ProgramW6432` environment variable agrees with the registry.
+///
+/// This shows the comparison made by `reconcile_program_w6432()` as a section, followed by a single
+/// verdict line. It is empty unless both environment variables and the registry were looked up.
+pub fn program_w6432_reconciliation(report: &Report, style: &Style<'_>) -> String {
+    let Some(reconciliation) = reconcile_program_w6432(report) else {
+        return String::new();
+    };
+
+    let entries: Vec<&dyn Entry> = reconciliation
+        .rows
+        .iter()
+        .map(|row| row as &dyn Entry)
+        .collect();
+
+    format!(
+        "{}  {}\n\n",
+        section(&reconciliation.heading, &entries, style),
+        reconciliation.verdict.description()
+    )
+}
+
+/// Renders the chain of ancestors of a process, as found by `ancestors()`, that is missing the
+/// `stripped` environment variables, to help find which ancestor removed them.
+///
+/// Each ancestor is shown with its process ID, executable name, and architecture, from the parent
+/// outward. If the ancestors could not be found, the error is shown instead.
+#[cfg(windows)]
+pub fn ancestors(stripped: &[&str], chain: &Result<Vec<Ancestor>, Error>) -> String {
+    let mut text = format!(
+        "Ancestors of this process ({} missing):\n\n",
+        stripped.join(" and ")
+    );
+
+    match chain {
+        Ok(chain) if chain.is_empty() => text.push_str("  [no running ancestors]\n"),
+        Ok(chain) => {
+            let rows: Vec<Vec<String>> = chain
+                .iter()
+                .map(|ancestor| {
+                    vec![
+                        ancestor.pid.to_string(),
+                        ancestor.name.clone(),
+                        ancestor.machine.map_or_else(
+                            || String::from("[unknown architecture]"),
+                            |machine| machine.description().to_owned(),
+                        ),
+                    ]
+                })
+                .collect();
+            text.push_str(&table(&rows));
+        }
+        Err(e) => text.push_str(&format!("  [{e}]\n")),
+    }
+
+    text.push('\n');
+    text
+}
+
+/// Renders rows of cells as a table, as `table()` does, with the user names in them masked, as
+/// described on `Style::masked()`, for tables made from paths that were not masked.
+fn masked_table(rows: &[Vec<String>], style: &Style<'_>) -> String {
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(|cell| style.masked(cell)).collect())
+        .collect();
+    table(&rows)
+}
+
+/// Renders the program files directories that appear to have been relocated, as found by
+/// `relocations()`, and why. If there are none, which is usual, this is empty.
+pub fn relocations(relocations: &[Relocation], style: &Style<'_>) -> String {
+    if relocations.is_empty() {
+        return String::new();
+    }
+
+    let rows: Vec<Vec<String>> = relocations
+        .iter()
+        .map(|relocation| {
+            let signs: Vec<String> = relocation
+                .signs
+                .iter()
+                .map(RelocationSign::description)
+                .collect();
+            vec![
+                relocation.directory.description().to_owned(),
+                relocation.path.clone(),
+                style.paint(&format!("[{}]", signs.join("; ")), Color::Yellow),
+            ]
+        })
+        .collect();
+
+    format!(
+        "Relocated program files:\n\n{}\n  Many installers assume program files directories are \
+        in their default locations.\n\n",
+        masked_table(&rows, style)
+    )
+}
+
+/// Renders the entries for different program files directories that reach the same directory on
+/// disk, as found by `aliases()`. If there are none, which is usual, this is empty.
+///
+/// Each group of them is shown with the volume serial number and file ID they share.
+pub fn aliases(aliases: &[Alias], style: &Style<'_>) -> String {
+    if aliases.is_empty() {
+        return String::new();
+    }
+
+    let mut text = String::from("Aliased program files:\n\n");
+    for alias in aliases {
+        let id = alias.file_id;
+        let same = format!(
+            "[same directory: volume {:08X}, file ID {:032X}]",
+            id.volume_serial, id.file_id
+        );
+        text.push_str(&format!("  {}\n", style.paint(&same, Color::Yellow)));
+        let rows: Vec<Vec<String>> = alias
+            .entries
+            .iter()
+            .map(|entry| {
+                vec![
+                    entry.directory.description().to_owned(),
+                    entry.name.clone(),
+                    entry.path.clone(),
+                ]
+            })
+            .collect();
+        text.push_str(&masked_table(&rows, style));
+        text.push('\n');
+    }
+
+    text.push_str(
+        "  Programs meant for different program files directories would be installed together.\n\n",
+    );
+    text
+}
+
+/// Formats a size in bytes in gigabytes, as Explorer shows drive sizes, in units of 1024³ bytes.
+fn format_size(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / f64::from(1 << 30))
+}
+
+/// Gives the cells of a row of `volumes()` describing a volume, with any part that could not be
+/// found shown in red.
+fn volume_cells(volume: &Volume, style: &Style<'_>) -> Vec<String> {
+    let error = |e: &windows_core::Error| style.paint(&format!("[{}]", e.message()), Color::Red);
+
+    let dev_drive = match volume.is_dev_drive {
+        Ok(true) => style.paint("Dev Drive", Color::Yellow),
+        Ok(false) => String::from("not a Dev Drive"),
+        Err(ref e) => style.paint(&format!("[Dev Drive unknown: {}]", e.message()), Color::Red),
+    };
+    let file_system = match &volume.file_system {
+        Ok(name) if volume.is_refs() => style.paint(name, Color::Yellow),
+        Ok(name) => name.clone(),
+        Err(e) => error(e),
+    };
+    let space = match volume.space {
+        Ok((free, total)) => format!("{} free of {}", format_size(free), format_size(total)),
+        Err(ref e) => error(e),
+    };
+
+    vec![
+        volume.mount_point.clone(),
+        volume.guid_path.as_ref().map_or_else(error, Clone::clone),
+        file_system,
+        dev_drive,
+        space,
+    ]
+}
+
+/// Renders the volume that each program files directory is on, as found by `volume_of()` for the
+/// path the sources agree on.
+///
+/// ReFS volumes and Dev Drives are highlighted, since installers rarely expect program files
+/// directories on them.
+pub fn volumes(
+    volumes: &[(
+        LogicalDirectory,
+        String,
+        Result<Volume, windows_core::Error>,
+    )],
+    style: &Style<'_>,
+) -> String {
+    let rows: Vec<Vec<String>> = volumes
+        .iter()
+        .map(|(directory, path, volume)| {
+            let mut row = vec![directory.description().to_owned()];
+            match volume {
+                Ok(volume) => row.extend(volume_cells(volume, style)),
+                Err(e) => row.push(style.paint(
+                    &format!("[no volume found for {path}: {}]", e.message()),
+                    Color::Red,
+                )),
+            }
+            row
+        })
+        .collect();
+
+    format!("Volumes:\n\n{}\n", masked_table(&rows, style))
+}
+
+/// Gives the cells of a row of `security()` describing a directory's security, with any broad
+/// trustees that can write to it flagged in red.
+#[cfg(windows)]
+fn security_cells(security: &DirectorySecurity, style: &Style<'_>) -> Vec<String> {
+    let rights: Vec<String> = security
+        .rights
+        .iter()
+        .map(|rights| format!("{}: {}", rights.trustee, rights.level().description()))
+        .collect();
+    let writers = security.world_writers();
+    let flag = if writers.is_empty() {
+        String::new()
+    } else {
+        style.paint(
+            &format!("[WORLD-WRITABLE by {}]", writers.join(", ")),
+            Color::Red,
+        )
+    };
+
+    vec![
+        format!("owner: {}", security.owner),
+        rights.join("; "),
+        flag,
+    ]
+}
+
+/// Renders who owns each program files directory, and who can do what to it, as found by
+/// `directory_security()` for the path the sources agree on.
+///
+/// A directory that broad trustees, such as `Users` or `Everyone`, can write to is flagged, since
+/// any user could then replace the programs in it. Owners and trustees are accounts, not paths, so
+/// they should be masked, with `Redact`, before they are passed in.
+#[cfg(windows)]
+pub fn security(
+    securities: &[(
+        LogicalDirectory,
+        String,
+        Result<DirectorySecurity, windows_core::Error>,
+    )],
+    style: &Style<'_>,
+) -> String {
+    let rows: Vec<Vec<String>> = securities
+        .iter()
+        .map(|(directory, path, security)| {
+            let mut row = vec![directory.description().to_owned()];
+            match security {
+                Ok(security) => row.extend(security_cells(security, style)),
+                Err(e) => row.push(style.paint(
+                    &format!("[could not read the security of {path}: {}]", e.message()),
+                    Color::Red,
+                )),
+            }
+            row
+        })
+        .collect();
+
+    format!("Security:\n\n{}\n", masked_table(&rows, style))
+}
+
+/// Renders whether a process can really write to each program files directory, as found by
+/// `probe_write()` for the path the sources agree on.
+///
+/// A directory a process cannot write to is usual unless it is elevated, so it is shown in yellow,
+/// rather than as an error.
+pub fn write_probes(
+    probes: &[(
+        LogicalDirectory,
+        String,
+        Result<WriteProbe, windows_core::Error>,
+    )],
+    style: &Style<'_>,
+) -> String {
+    let rows: Vec<Vec<String>> = probes
+        .iter()
+        .map(|(directory, path, probe)| {
+            let result = match probe {
+                Ok(WriteProbe::Written) => style.paint("[writable]", Color::Green),
+                Ok(WriteProbe::Redirected(actual)) => style.paint(
+                    &format!("[not writable: redirected to {actual}]"),
+                    Color::Yellow,
+                ),
+                Err(e) => style.paint(&format!("[not writable: {}]", e.message()), Color::Yellow),
+            };
+            vec![directory.description().to_owned(), path.clone(), result]
+        })
+        .collect();
+
+    format!("Write access:\n\n{}\n", masked_table(&rows, style))
+}
+
+/// Renders signs that something changes what a process sees, each on its own line, under a heading
+/// and followed by a note on what they mean. If there are none, this is empty.
+fn signs(heading: &str, descriptions: &[String], note: &str, style: &Style<'_>) -> String {
+    if descriptions.is_empty() {
+        return String::new();
+    }
+
+    let mut text = format!("{heading}:\n\n");
+    for description in descriptions {
+        let sign = style.paint(&format!("[{description}]"), Color::Yellow);
+        text.push_str(&format!("  {sign}\n"));
+    }
+    text.push('\n');
+    text.push_str(note);
+    text.push_str("\n\n");
+    text
+}
+
+/// Renders any signs that UAC virtualization is redirecting writes meant for the program files
+/// directories, as found by `virtualization_signs()`. If there are none, which is usual, this is
+/// empty.
+pub fn virtualization_signs(report: &Report, style: &Style<'_>) -> String {
+    let descriptions: Vec<String> = analysis::virtualization_signs(report)
+        .iter()
+        .map(|sign| sign.description())
+        .collect();
+    signs(
+        "UAC virtualization",
+        &descriptions,
+        "  Files written to program files directories by virtualized programs are seen only by\n  \
+        this user, and only by virtualized programs, so they can seem to vanish.",
+        style,
+    )
+}
+
+/// Renders any ways that running packaged or in an AppContainer changes what a process sees, as
+/// found by `package_effects()`. If there are none, which is usual, this is empty.
+pub fn package_effects(report: &Report, style: &Style<'_>) -> String {
+    let descriptions: Vec<String> = analysis::package_effects(report)
+        .iter()
+        .map(|effect| effect.description())
+        .collect();
+    signs(
+        "Package and AppContainer",
+        &descriptions,
+        "  Run pfdirs outside any package or AppContainer to see what other programs see.",
+        style,
+    )
+}
+
+/// Renders any signs that policy configures Explorer or redirects the program files known folders,
+/// as found by `policy_signs()`. If there are none, which is usual on a machine that is not
+/// managed, this is empty.
+pub fn policy_signs(report: &Report, style: &Style<'_>) -> String {
+    let descriptions: Vec<String> = analysis::policy_signs(report)
+        .iter()
+        .map(|sign| sign.description())
+        .collect();
+    signs(
+        "Policy",
+        &descriptions,
+        "  Folders may be elsewhere, or fail to move, because of how this machine is managed.",
+        style,
+    )
+}
+
+/// Renders why `FOLDERID_UserProgramFiles` is unavailable, as found by
+/// `user_program_files_diagnosis()`, and where it would be in the default user's profile, if that
+/// was looked up. If there are no reasons, which is usual when not running as a service, this is
+/// empty.
+pub fn service_diagnosis(
+    reasons: &[ServiceSign],
+    default_profile: Option<&Result<OsString, Error>>,
+    style: &Style<'_>,
+) -> String {
+    let descriptions: Vec<String> = reasons.iter().map(ServiceSign::description).collect();
+    let mut text = signs(
+        "FOLDERID_UserProgramFiles is unavailable because",
+        &descriptions,
+        "  Per-user folders are found through a loaded user profile, which services usually\n  \
+        lack. This does not mean the per-user program files directory is misconfigured.",
+        style,
+    );
+    if text.is_empty() {
+        return text;
+    }
+
+    let item = match default_profile {
+        None => {
+            text.push_str(
+                "  Pass --retry-default-profile to look it up in the default user's profile.\n\n",
+            );
+            return text;
+        }
+        Some(Ok(path)) => style.paint(&style.masked(&style.path_text(path)), Color::Green),
+        Some(Err(e)) => style.paint(&format!("[{e}]"), Color::Red),
+    };
+    text.push_str(&format!("  In the default user's profile: {item}\n\n"));
+    text
+}
+
+/// Renders a summary table with a row for each logical directory and a column for each source.
+///
+/// The rows are found by `summarize()`. A cell is `[n/a]` if the source has no entry for that
+/// directory, or `[error]` if the lookup failed, whose details are shown in the section for that
+/// source. A path that differs from the path most sources found is marked with `*`.
+pub fn summary(report: &Report, platform: Platform, style: &Style<'_>) -> String {
+    let header = std::iter::once("Directory").chain(SUMMARY_COLUMNS.map(|(header, _)| header));
+    let mut rows = vec![header.map(String::from).collect::<Vec<_>>()];
+
+    for summary_row in summarize(report, platform) {
+        let cells = summary_row.cells.map(|cell| match cell {
+            SummaryCell::NotApplicable => String::from("[n/a]"),
+            SummaryCell::Error => style.paint("[error]", Color::Red),
+            SummaryCell::Path(path) => style.paint(&path, Color::Green),
+            SummaryCell::Outlier(path) => style.paint(&format!("{path} *"), Color::Yellow),
+        });
+
+        let row = std::iter::once(summary_row.directory.description().to_owned()).chain(cells);
+        rows.push(row.collect());
+    }
+
+    format!(
+        "Summary by directory:\n\n{}\n  * Differs from the path most sources found for the same \
+        directory.\n\n",
+        table(&rows)
+    )
+}
+
+/// Describes an architecture reported by `IsWow64Process2`, or that it could not be found.
+#[cfg(windows)]
+fn describe_machine(machine: Option<Machine>) -> &'static str {
+    machine.map_or("[IsWow64Process2 failed]", Machine::description)
+}
+
+/// Whether, for a process, WOW64 is active, the process is emulated, and it is ARM64EC, each with
+/// a name for it.
+pub fn architecture_flags(architecture: ProcessArchitecture) -> [(&'static str, String); 3] {
+    let yes_no = |flag: bool| String::from(if flag { "yes" } else { "no" });
+    let wow64 = match architecture.wow64_machine {
+        Some(machine) => format!("yes, running {}", machine.description()),
+        None => yes_no(false),
+    };
+    [
+        ("WOW64", wow64),
+        ("Emulated", yes_no(architecture.is_emulated())),
+        ("ARM64EC", yes_no(architecture.arm64ec)),
+    ]
+}
+
+/// Whether a process is elevated, how its token was made, its integrity level, and whether UAC is
+/// on, as found by `Elevation::detect()`, each with a name for it.
+#[cfg(windows)]
+pub fn elevation_flags(elevation: Elevation) -> [(&'static str, String); 4] {
+    let unknown = || String::from("[unknown]");
+    let yes_no = |flag: bool| String::from(if flag { "yes" } else { "no" });
+    let on_off = |flag: bool| String::from(if flag { "on" } else { "off" });
+    [
+        ("Elevated", elevation.elevated.map_or_else(unknown, yes_no)),
+        (
+            "Elevation type",
+            elevation
+                .elevation_type
+                .map_or_else(unknown, |elevation_type| {
+                    elevation_type.description().to_owned()
+                }),
+        ),
+        (
+            "Integrity level",
+            elevation
+                .integrity_level
+                .map_or_else(unknown, |level| level.description().to_owned()),
+        ),
+        ("UAC", elevation.uac_enabled.map_or_else(unknown, on_off)),
+    ]
+}
+
+/// Renders the architectures of a process and of the system it is running on, and whether the
+/// process is elevated, as the text output starts with.
+///
+/// Most sources give different paths to processes of different architectures, so the rest of the
+/// output cannot be interpreted without knowing these. Elevated and non-elevated runs can also
+/// legitimately differ, such as in whose per-user folders they see, so the elevation, integrity
+/// level, and UAC status are shown too.
+#[cfg(windows)]
+pub fn architecture(architecture: ProcessArchitecture, elevation: Elevation) -> String {
+    let mut rows = vec![
+        vec![
+            String::from("Process machine"),
+            architecture.build.description().to_owned(),
+        ],
+        vec![
+            String::from("Native machine"),
+            describe_machine(architecture.native).to_owned(),
+        ],
+    ];
+    for (name, value) in architecture_flags(architecture) {
+        rows.push(vec![name.to_owned(), value]);
+    }
+    for (name, value) in elevation_flags(elevation) {
+        rows.push(vec![name.to_owned(), value]);
+    }
+
+    format!("This process:\n\n{}\n", table(&rows))
+}
+
+/// Renders the path of each program files directory that the sources agree on, as the text output
+/// gives it before the sections for each source.
+///
+/// Each directory's consensus is found by `resolve_consensus()`. Any sources that disagree are
+/// listed after it, with the entry and path each one found. A directory no source found is shown
+/// as not applicable, rather than not found, if `platform` has no such directory at all, as
+/// determined by `LogicalDirectory::exists_on()`.
+pub fn resolved(report: &Report, platform: Platform, style: &Style<'_>) -> String {
+    let mut rows = Vec::new();
+
+    for resolution in resolve_consensus(report, platform) {
+        let consensus = match &resolution.consensus {
+            Some(path) => style.paint(path, Color::Green),
+            None if !resolution.directory.exists_on(platform.native) => {
+                String::from("[not applicable on this architecture]")
+            }
+            None => style.paint("[not found]", Color::Red),
+        };
+        let dissents: Vec<String> = resolution
+            .dissents
+            .iter()
+            .map(|dissent| {
+                format!(
+                    "{} ({}) gives {}",
+                    dissent.name, dissent.source, dissent.path
+                )
+            })
+            .collect();
+        let flag = if dissents.is_empty() {
+            String::new()
+        } else {
+            style.paint(
+                &format!("[disagree: {}]", dissents.join("; ")),
+                Color::Yellow,
+            )
+        };
+        rows.push(vec![
+            resolution.directory.description().to_owned(),
+            consensus,
+            flag,
+        ]);
+    }
+
+    format!("Resolved:\n\n{}\n", table(&rows))
+}
+
+/// Renders all three program files directories of an ARM64 system, grouped by architecture.
+///
+/// As noted on `report_environment_variables()`, an ARM64 system has three program files
+/// directories: the 64-bit one shared by ARM64 and x86-64 programs, one for 32-bit x86 programs,
+/// which run under emulation, and one for 32-bit ARM programs. This shows every entry, from every
+/// source, for each of them, as found by `entries_for_directory()`, and notes whether entries for
+/// the 32-bit x86 directory, which exist because of x86 emulation, are present.
+pub fn arm64_directories(report: &Report, platform: Platform, style: &Style<'_>) -> String {
+    let mut text = String::new();
+    for (directory, label) in ARM64_DIRECTORIES {
+        let rows = entries_for_directory(report, directory, platform);
+        let entries: Vec<&dyn Entry> = rows.iter().map(|row| row as &dyn Entry).collect();
+        text.push_str(&section(
+            &format!("ARM64 system - {label} program files"),
+            &entries,
+            style,
+        ));
+
+        if directory == LogicalDirectory::ProgramFiles32 {
+            let present: Vec<&str> = rows
+                .iter()
+                .filter(|row| row.path().is_some())
+                .map(|row| row.name.as_str())
+                .collect();
+
+            if present.is_empty() {
+                text.push_str("  No x86 emulation-related entries are present.\n");
+            } else {
+                text.push_str(&format!(
+                    "  x86 emulation-related entries present: {}\n",
+                    present.join(", ")
+                ));
+            }
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Renders, for after a section, why each of its entries has the value it does, as given by
+/// `explain_entry()`.
+pub fn explanations(section: &SectionRef<'_>, platform: Platform) -> String {
+    let width = column_width(section.entries.iter().map(|entry| entry.name()));
+
+    let mut text = String::from("  Explanations:\n\n");
+    for &entry in &section.entries {
+        let explanation = explain_entry(section.source, entry, platform);
+        text.push_str(&format!("    {:<width$}  {explanation}\n", entry.name()));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders what looking up the sources again found, at `time`, after `trigger` may have changed
+/// them: the entries whose paths or errors changed, as found by `changes()`, or the error if the
+/// sources could not be looked up. If nothing changed, this is empty.
+pub fn watched_changes(time: &str, trigger: &str, changes: Result<&[Change], &Error>) -> String {
+    let changes = match changes {
+        Ok([]) => return String::new(),
+        Ok(changes) => changes,
+        Err(e) => return format!("[{time}] {trigger}: {e}\n\n"),
+    };
+
+    let mut text = format!("[{time}] {trigger}:\n\n");
+    for change in changes {
+        text.push_str(&format!("  {}: {}\n", change.section, change.name));
+        text.push_str(&format!("    was  {}\n", change.before));
+        text.push_str(&format!("    now  {}\n", change.after));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders the entries that differ between two saved reports, as found by `diff()`, each shown as
+/// `watched_changes()` shows it, grouped under the heading of its section.
+pub fn diff(old: &Path, new: &Path, changes: &[Change], style: &Style<'_>) -> String {
+    let mut text = format!("Changes from {} to {}:\n\n", old.display(), new.display());
+
+    if changes.is_empty() {
+        text.push_str("  [no changes]\n\n");
+        return text;
+    }
+
+    let mut section = None;
+    for change in changes {
+        if section != Some(&change.section) {
+            text.push_str(&format!("  {}:\n", change.section));
+            section = Some(&change.section);
+        }
+        text.push_str(&format!("    {}\n", change.name));
+        text.push_str(&format!(
+            "      was  {}\n",
+            style.paint(&change.before, Color::Red)
+        ));
+        text.push_str(&format!(
+            "      now  {}\n",
+            style.paint(&change.after, Color::Green)
+        ));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders the snapshots saved in the history `directory`, as listed by `history()`.
+#[cfg(feature = "serde")]
+pub fn history(directory: &Path, snapshots: &[HistoryEntry]) -> String {
+    let mut text = format!("Snapshots in {}:\n\n", directory.display());
+
+    if snapshots.is_empty() {
+        text.push_str("  [none]\n\n");
+        return text;
+    }
+
+    let unknown = || String::from("[unknown]");
+    let rows: Vec<Vec<String>> = snapshots
+        .iter()
+        .map(|snapshot| match &snapshot.info {
+            Some(info) => vec![
+                snapshot.name.clone(),
+                info.timestamp.clone(),
+                info.hostname.clone().unwrap_or_else(unknown),
+                info.user.clone().unwrap_or_else(unknown),
+                info.os_version.clone().unwrap_or_else(unknown),
+            ],
+            None => vec![
+                snapshot.name.clone(),
+                String::from("[not a snapshot]"),
+                String::new(),
+                String::new(),
+                String::new(),
+            ],
+        })
+        .collect();
+
+    text.push_str(&table(&rows));
+    text.push('\n');
+    text
+}
+
+/// Renders what each child of `inheritance()`, a process of the kind `platform` describes,
+/// received when program files environment variables were removed.
+///
+/// The table has a row for each trial, saying what was removed, and a column for each variable,
+/// saying what the child received. Values that differ from what the child received when nothing
+/// was removed are marked with `*`.
+#[cfg(feature = "serde")]
+pub fn inheritance(platform: Platform, trials: &[Trial]) -> String {
+    let header = std::iter::once("Removed").chain(PROGRAM_FILES_ENVIRONMENT_VARIABLES);
+    let mut rows = vec![header.map(String::from).collect::<Vec<_>>()];
+
+    let baseline = trials.first().map_or(&[][..], |trial| &trial.received);
+
+    for trial in trials {
+        let removed = match trial.removed.as_slice() {
+            [] => String::from("(nothing)"),
+            removed if removed.len() == PROGRAM_FILES_ENVIRONMENT_VARIABLES.len() => {
+                String::from("(all)")
+            }
+            removed => removed.join(", "),
+        };
+
+        let cells = trial
+            .received
+            .iter()
+            .zip(baseline)
+            .map(|((_, value), (_, expected))| {
+                let text = value.clone().unwrap_or_else(|| String::from("[not set]"));
+                if value == expected {
+                    text
+                } else {
+                    format!("{text} *")
+                }
+            });
+
+        rows.push(std::iter::once(removed).chain(cells).collect());
+    }
+
+    format!(
+        "Environment inheritance experiment:\n\n  Each row is a child, a {} process, given this \
+        process's\n  environment without the variables shown as removed.\n\n{}\n  * Differs from \
+        what the child received when nothing was removed.\n\n",
+        platform.process.description(),
+        table(&rows)
+    )
+}
+
+/// Renders how each entry differs between builds of this program for different architectures, as
+/// merged by `matrix()`.
+///
+/// Each section is shown as a table with a column for each build that ran, and entries whose paths
+/// differ between builds are marked with `*`. Builds that could not be run, such as ARM64 builds on
+/// x64 systems, are listed first.
+#[cfg(feature = "serde")]
+pub fn matrix(matrix: &Matrix) -> String {
+    let mut text = String::from("Cross-architecture matrix:\n\n");
+
+    for build in &matrix.builds {
+        let machine = build.machine.description();
+        text.push_str(&format!("  {machine:<12}  {}\n", build.program.display()));
+    }
+    for (build, e) in &matrix.failures {
+        let machine = build.machine.description();
+        text.push_str(&format!(
+            "  {machine:<12}  [could not run {}: {e}]\n",
+            build.program.display()
+        ));
+    }
+    text.push('\n');
+
+    if matrix.builds.is_empty() {
+        return text;
+    }
+
+    let mut sections: Vec<&str> = Vec::new();
+    for row in &matrix.rows {
+        if !sections.contains(&row.section.as_str()) {
+            sections.push(&row.section);
+        }
+    }
+
+    for section in sections {
+        let header = std::iter::once("Name").chain(
+            matrix
+                .builds
+                .iter()
+                .map(|build| build.machine.description()),
+        );
+        let mut rows = vec![header.map(String::from).collect::<Vec<_>>()];
+
+        for row in matrix.rows.iter().filter(|row| row.section == section) {
+            let name = if row.differs() {
+                format!("{} *", row.name)
+            } else {
+                row.name.clone()
+            };
+            let cells = row.cells.iter().map(|cell| match cell {
+                Some(lookup) => lookup.path_item(),
+                None => String::from("[n/a]"),
+            });
+            rows.push(std::iter::once(name).chain(cells).collect());
+        }
+
+        text.push_str(&format!("{section}:\n\n{}\n", table(&rows)));
+    }
+
+    text.push_str("  * Differs between architectures.\n\n");
+    text
+}
+
+/// Renders whether the tables relating sources, entries, and logical directories are consistent.
+///
+/// Each check made by `self_test()` is shown as passing or failing, with any problems it found.
+pub fn self_test(checks: &[Check]) -> String {
+    let width = column_width(checks.iter().map(|check| check.description));
+
+    let mut text = String::from("Self-test of internal tables:\n\n");
+    for check in checks {
+        let description = check.description;
+        if check.passed() {
+            text.push_str(&format!("  {description:<width$}  PASS\n"));
+        } else {
+            text.push_str(&format!("  {description:<width$}  FAIL\n"));
+            for problem in &check.problems {
+                text.push_str(&format!("    {problem}\n"));
+            }
+        }
+    }
+    text.push('\n');
+    text
+}
+
+/// Gives a duration in microseconds, which is the scale most lookups take.
+#[cfg(windows)]
+fn microseconds(duration: Duration) -> String {
+    format!("{:.1} µs", duration.as_secs_f64() * 1_000_000.0)
+}
+
+/// Renders how long each API call to look up each entry took, as measured by `time_calls()` over
+/// `repetitions`.
+///
+/// With one repetition, as for `--timing`, each call's time is shown. With more, as for `bench`,
+/// the minimum, mean, and maximum are shown. Calls that did not find a path are marked, since how
+/// long a call takes to fail does not say how long it takes to succeed.
+#[cfg(windows)]
+pub fn timings(timings: &[Timing], repetitions: u32, style: &Style<'_>) -> String {
+    let header: Vec<String> = if repetitions > 1 {
+        ["API", "Entry", "", "Min", "Mean", "Max"]
+    } else {
+        ["API", "Entry", "", "Time", "", ""]
+    }
+    .map(String::from)
+    .to_vec();
+
+    let rows: Vec<Vec<String>> = std::iter::once(header)
+        .chain(timings.iter().map(|timing| {
+            let status = if timing.found {
+                String::new()
+            } else {
+                style.paint("[not found]", Color::Red)
+            };
+            if repetitions > 1 {
+                vec![
+                    timing.api.to_owned(),
+                    timing.name.to_owned(),
+                    status,
+                    microseconds(timing.min),
+                    microseconds(timing.mean),
+                    microseconds(timing.max),
+                ]
+            } else {
+                vec![
+                    timing.api.to_owned(),
+                    timing.name.to_owned(),
+                    status,
+                    microseconds(timing.mean),
+                    String::new(),
+                    String::new(),
+                ]
+            }
+        }))
+        .collect();
+
+    let heading = if repetitions > 1 {
+        format!("Call timings, over {repetitions} repetitions:")
+    } else {
+        String::from("Call timings:")
+    };
+    format!("{heading}\n\n{}\n", table(&rows))
+}
+
+/// Renders the required entries that did not resolve to a path, each with why, as found by
+/// `missing_requirements()`. If there are none, this is empty.
+pub fn missing_requirements(failures: &[(String, String)]) -> String {
+    if failures.is_empty() {
+        return String::new();
+    }
+
+    let width = column_width(failures.iter().map(|(name, _)| name.as_str()));
+
+    let mut text = String::from("Required entries that did not resolve:\n\n");
+    for (name, reason) in failures {
+        text.push_str(&format!("  {name:<width$}  {reason}\n"));
+    }
+    text.push('\n');
+    text
+}
+
+/// Renders whether each expected entry had its expected path, as found by `check_expectations()`,
+/// and a summary. If there were no expectations, this is empty.
+///
+/// A failed expectation is shown with the path, or error, found in each section that has the entry.
+/// The expectations were checked against real paths, so user names are masked here, as described on
+/// `Style::masked()`.
+pub fn expectations(outcomes: &[ExpectationOutcome], style: &Style<'_>) -> String {
+    if outcomes.is_empty() {
+        return String::new();
+    }
+
+    let failed = outcomes.iter().filter(|outcome| !outcome.passed()).count();
+
+    let mut text = String::from("Expectations:\n\n");
+    for outcome in outcomes {
+        let Expectation { name, value } = &outcome.expectation;
+        let value = style.masked(value);
+        if outcome.passed() {
+            let pass = style.paint("PASS", Color::Green);
+            text.push_str(&format!("  {pass}  {name} = {value}\n"));
+            continue;
+        }
+        let fail = style.paint("FAIL", Color::Red);
+        text.push_str(&format!("  {fail}  {name} = {value}\n"));
+        if outcome.actual.is_empty() {
+            text.push_str("          [no such entry in any section]\n");
+        }
+        for (section, path_item, matched) in &outcome.actual {
+            if !matched {
+                text.push_str(&format!(
+                    "          {section}: {}\n",
+                    style.masked(path_item)
+                ));
+            }
+        }
+    }
+
+    text.push_str(&format!(
+        "\n  {} passed, {failed} failed\n\n",
+        outcomes.len() - failed
+    ));
+    text
+}
+
+/// Renders the conclusions drawn from a report, as found by `findings()`, as the text output ends
+/// with.
+///
+/// Each is shown with its severity and title, and its explanation below it. If there are none,
+/// that is said, so that it is clear nothing was found.
+pub fn findings(found: &[Finding], style: &Style<'_>) -> String {
+    let mut text = String::from("Findings:\n\n");
+
+    if found.is_empty() {
+        text.push_str("  None. The sources reported are consistent.\n\n");
+        return text;
+    }
+
+    for Finding {
+        severity,
+        title,
+        explanation,
+    } in found
+    {
+        let label = format!("[{}]", severity.description());
+        let label = match severity {
+            Severity::Critical => style.paint(&label, Color::Red),
+            Severity::Warning => style.paint(&label, Color::Yellow),
+            Severity::Info => label,
+        };
+        text.push_str(&format!("  {label} {title}\n      {explanation}\n\n"));
+    }
+    text
+}
+
+/// Renders anything wrong with a report that suggests a broken program files configuration, as
+/// found by `anomalies()`. If there is nothing, this is empty.
+pub fn anomalies(found: &[Anomaly]) -> String {
+    if found.is_empty() {
+        return String::new();
+    }
+
+    let kind_width = column_width(found.iter().map(|anomaly| anomaly.kind.description()));
+    let name_width = column_width(found.iter().map(|anomaly| anomaly.name.as_str()));
+
+    let mut text = String::from("Anomalies:\n\n");
+    for Anomaly {
+        kind,
+        section,
+        name,
+        detail,
+    } in found
+    {
+        let kind = kind.description();
+        text.push_str(&format!(
+            "  {kind:<kind_width$}  {name:<name_width$}  {section}: {detail}\n"
+        ));
+    }
+    text.push('\n');
+    text
+}
+
+/// Escapes text for use in a cell of a Markdown table.
+pub fn markdown_cell(text: &str) -> String {
+    text.replace('|', r"\|")
+}
+
+/// Renders the report as GitHub-flavored Markdown, for pasting into issues.
+///
+/// A table of information about the system comes first, with a row for each property in
+/// `system`, and a row saying the report was truncated if it was. The path of each directory that
+/// the sources agree on, as found by `resolve_consensus()`, follows, with any sources that
+/// disagree. Each section then follows as a table, with names and paths in code spans and errors
+/// in square brackets, as in the text output.
+pub fn markdown(report: &Report, platform: Platform, system: &[(String, String)]) -> String {
+    let mut lines = markdown_header(report, platform, system);
+
+    for section in report.sections() {
+        lines.push(String::new());
+        lines.push(format!("### {}", section.heading));
+        lines.push(String::new());
+        lines.push(String::from("| Name | Path |"));
+        lines.push(String::from("| --- | --- |"));
+
+        for entry in &section.entries {
+            let name = markdown_cell(entry.name());
+            let path = match entry.path() {
+                Some(path) => format!("`{}`", markdown_cell(path)),
+                None => markdown_cell(&entry.lookup().path_item()),
+            };
+            lines.push(format!("| `{name}` | {path} |"));
+        }
+    }
+
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// Renders the report as GitHub-flavored Markdown, as `markdown()` does, but with the sections
+/// replaced by one table with a row for each logical directory and a column for each source.
+///
+/// The table is the summary table found by `summarize()`, with paths in code spans. A cell is
+/// `[n/a]` if the source has no entry for that directory, or `[error]` if the lookup failed. A
+/// path that differs from the path most sources found is marked with `*`.
+pub fn pivot_markdown(report: &Report, platform: Platform, system: &[(String, String)]) -> String {
+    let mut lines = markdown_header(report, platform, system);
+
+    let headers: Vec<&str> = SUMMARY_COLUMNS.iter().map(|&(header, _)| header).collect();
+    lines.push(String::new());
+    lines.push(String::from("### By directory"));
+    lines.push(String::new());
+    lines.push(format!("| Directory | {} |", headers.join(" | ")));
+    lines.push(format!("| --- |{}", " --- |".repeat(headers.len())));
+
+    for row in summarize(report, platform) {
+        let cells: Vec<String> = row
+            .cells
+            .iter()
+            .map(|cell| match cell {
+                SummaryCell::NotApplicable => String::from("[n/a]"),
+                SummaryCell::Error => String::from("[error]"),
+                SummaryCell::Path(path) => format!("`{}`", markdown_cell(path)),
+                SummaryCell::Outlier(path) => format!("`{}` \\*", markdown_cell(path)),
+            })
+            .collect();
+        lines.push(format!(
+            "| {} | {} |",
+            row.directory.description(),
+            cells.join(" | ")
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(String::from(
+        "\\* Differs from the path most sources found for the same directory.",
+    ));
+
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// The tables that Markdown output starts with: information about the system, and where each
+/// directory is, as described on `markdown()`.
+fn markdown_header(
+    report: &Report,
+    platform: Platform,
+    system: &[(String, String)],
+) -> Vec<String> {
+    let mut lines = vec![
+        String::from("### System"),
+        String::new(),
+        String::from("| Property | Value |"),
+        String::from("| --- | --- |"),
+    ];
+    for (property, value) in system {
+        lines.push(format!("| {property} | {} |", markdown_cell(value)));
+    }
+    if report.truncated {
+        lines.push(String::from(
+            "| Report truncated | yes, interrupted before every source was looked up |",
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(String::from("### Resolved"));
+    lines.push(String::new());
+    lines.push(String::from("| Directory | Path | Disagreeing sources |"));
+    lines.push(String::from("| --- | --- | --- |"));
+
+    for resolution in resolve_consensus(report, platform) {
+        let path = match &resolution.consensus {
+            Some(path) => format!("`{}`", markdown_cell(path)),
+            None if !resolution.directory.exists_on(platform.native) => {
+                String::from("[not applicable on this architecture]")
+            }
+            None => String::from("[not found]"),
+        };
+        let dissents: Vec<String> = resolution
+            .dissents
+            .iter()
+            .map(|dissent| {
+                let path = markdown_cell(&dissent.path);
+                format!("`{}` ({}): `{path}`", dissent.name, dissent.source)
+            })
+            .collect();
+        let description = resolution.directory.description();
+        lines.push(format!(
+            "| {description} | {path} | {} |",
+            dissents.join("<br>")
+        ));
+    }
+
+    lines
+}
+
+/// Escapes text for use in HTML, as element content or a quoted attribute value.
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The style sheet embedded in HTML output, so the page needs nothing else to be shown.
+const HTML_STYLE: &str = "\
+body { font-family: Segoe UI, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.5em; }
+table { border-collapse: collapse; margin: 0.5em 0 1em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; vertical-align: top; }
+th { background: #f3f3f3; }
+code { font-family: Consolas, monospace; }
+summary { cursor: pointer; font-weight: bold; margin: 0.5em 0; }
+.error { color: #666; font-style: italic; }
+.anomaly td { background: #fde7e7; }
+.critical { color: #a00; font-weight: bold; }
+.warning { color: #a60; font-weight: bold; }
+.info { color: #06a; font-weight: bold; }
+";
+
+/// Renders the report as a standalone HTML page, for attaching to support tickets, whose
+/// recipients may only have a web browser.
+///
+/// The page has everything it needs, including its style sheet, and loads nothing else. A table of
+/// information about the system comes first, as in `markdown()`, with a row for each property in
+/// `system`. The findings, which say what the report means in plain words, follow, so readers who
+/// stop there still learn what matters, or a sentence saying the sources are consistent if there
+/// are none. The directories that the sources agree on follow, as found by `resolve_consensus()`,
+/// and then each section, as a table in a collapsible `<details>` element.
+///
+/// Rows for anomalies, as found by `anomalies()`, and for sources that disagree about a directory,
+/// are highlighted, and a section that has any is expanded, while the others start collapsed.
+pub fn html(
+    report: &Report,
+    platform: Platform,
+    system: &[(String, String)],
+    findings: &[Finding],
+) -> String {
+    let mut lines = vec![
+        String::from("<!DOCTYPE html>"),
+        String::from("<html lang=\"en\">"),
+        String::from("<head>"),
+        String::from("<meta charset=\"utf-8\">"),
+        String::from("<title>Program files directories report</title>"),
+        format!("<style>\n{HTML_STYLE}</style>"),
+        String::from("</head>"),
+        String::from("<body>"),
+        String::from("<h1>Program files directories report</h1>"),
+        String::from("<h2>System</h2>"),
+        String::from("<table>"),
+    ];
+    for (property, value) in system {
+        lines.push(format!(
+            "<tr><th>{}</th><td>{}</td></tr>",
+            html_escape(property),
+            html_escape(value)
+        ));
+    }
+    if report.truncated {
+        lines.push(String::from(
+            "<tr class=\"anomaly\"><th>Report truncated</th>\
+            <td>yes, interrupted before every source was looked up</td></tr>",
+        ));
+    }
+    lines.push(String::from("</table>"));
+
+    lines.push(String::from("<h2>Findings</h2>"));
+    if findings.is_empty() {
+        lines.push(String::from(
+            "<p>None. The sources reported are consistent.</p>",
+        ));
+    } else {
+        lines.push(String::from("<ul>"));
+        for finding in findings {
+            let severity = finding.severity.description();
+            lines.push(format!(
+                "<li><span class=\"{severity}\">{severity}:</span> <strong>{}</strong>. {}</li>",
+                html_escape(&finding.title),
+                html_escape(&finding.explanation)
+            ));
+        }
+        lines.push(String::from("</ul>"));
+    }
+
+    lines.push(String::from("<h2>Resolved</h2>"));
+    lines.push(String::from(
+        "<table>\n<tr><th>Directory</th><th>Path</th><th>Disagreeing sources</th></tr>",
+    ));
+    for resolution in resolve_consensus(report, platform) {
+        let path = match &resolution.consensus {
+            Some(path) => format!("<code>{}</code>", html_escape(path)),
+            None if !resolution.directory.exists_on(platform.native) => {
+                String::from("<span class=\"error\">[not applicable on this architecture]</span>")
+            }
+            None => String::from("<span class=\"error\">[not found]</span>"),
+        };
+        let dissents: Vec<String> = resolution
+            .dissents
+            .iter()
+            .map(|dissent| {
+                format!(
+                    "<code>{}</code> ({}): <code>{}</code>",
+                    html_escape(dissent.name),
+                    html_escape(dissent.source),
+                    html_escape(&dissent.path)
+                )
+            })
+            .collect();
+        let class = if dissents.is_empty() {
+            ""
+        } else {
+            " class=\"anomaly\""
+        };
+        lines.push(format!(
+            "<tr{class}><td>{}</td><td>{path}</td><td>{}</td></tr>",
+            resolution.directory.description(),
+            dissents.join("<br>")
+        ));
+    }
+    lines.push(String::from("</table>"));
+
+    let found = analysis::anomalies(report, platform);
+    lines.push(String::from("<h2>Sections</h2>"));
+    for section in report.sections() {
+        let is_anomalous = |name: &str| {
+            found
+                .iter()
+                .any(|anomaly| anomaly.section == section.heading && anomaly.name == name)
+        };
+        let open = if section
+            .entries
+            .iter()
+            .any(|entry| is_anomalous(entry.name()))
+        {
+            " open"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "<details{open}>\n<summary>{}</summary>",
+            html_escape(section.heading)
+        ));
+        lines.push(String::from("<table>\n<tr><th>Name</th><th>Path</th></tr>"));
+
+        for entry in &section.entries {
+            let name = html_escape(entry.name());
+            let path = match entry.path() {
+                Some(path) => format!("<code>{}</code>", html_escape(path)),
+                None => format!(
+                    "<span class=\"error\">{}</span>",
+                    html_escape(&entry.lookup().path_item())
+                ),
+            };
+            let class = if is_anomalous(entry.name()) {
+                " class=\"anomaly\""
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "<tr{class}><td><code>{name}</code></td><td>{path}</td></tr>"
+            ));
+        }
+        lines.push(String::from("</table>\n</details>"));
+    }
+
+    lines.push(String::from("</body>"));
+    lines.push(String::from("</html>"));
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// The columns of CSV and TSV output, in order. These are stable, so scripts can rely on them.
+pub const DELIMITED_COLUMNS: [&str; 5] = ["source", "view", "symbol", "status", "path"];
+
+/// Formats cells as one line of CSV, if `separator` is a comma, or TSV, if it is a tab.
+///
+/// For CSV, a cell containing a comma, quotation mark, or line break is quoted, with quotation
+/// marks doubled, as in RFC 4180. TSV has no quoting, so tabs and line breaks in a cell are
+/// replaced with spaces.
+pub fn delimited_line(cells: &[impl AsRef<str>], separator: char) -> String {
+    let escape = |cell: &str| {
+        if separator == '\t' {
+            cell.replace(['\t', '\r', '\n'], " ")
+        } else if cell.contains([',', '"', '\r', '\n']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.to_owned()
+        }
+    };
+
+    let escaped: Vec<String> = cells.iter().map(|cell| escape(cell.as_ref())).collect();
+    escaped.join(&separator.to_string())
+}
+
+/// Renders every entry of the report as a line of CSV or TSV, with the columns in
+/// `DELIMITED_COLUMNS`, headed by a line naming them.
+///
+/// Each line gives the kind of source, as given by `SourceKind::label()`, which section of that
+/// kind it is in, if there is more than one, the entry's symbolic name, the outcome of the lookup,
+/// as given by `lookup_status()`, and the path, which is empty if none was found. Error messages
+/// are not included, since they are localized and would make results from different machines
+/// harder to aggregate. The other formats include them.
+pub fn delimited(report: &Report, separator: char) -> String {
+    let mut text = delimited_line(&DELIMITED_COLUMNS, separator) + "\n";
+
+    for section in report.sections() {
+        for entry in &section.entries {
+            let cells = [
+                section.source.label().to_owned(),
+                section.source.view(),
+                entry.name().to_owned(),
+                lookup_status(entry.lookup()).to_owned(),
+                entry.path().unwrap_or_default().to_owned(),
+            ];
+            text.push_str(&delimited_line(&cells, separator));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// The columns of pivoted CSV and TSV output, after `directory`, one for each of
+/// `SUMMARY_COLUMNS`, in the same order. Like `DELIMITED_COLUMNS`, these are stable.
+pub const PIVOT_COLUMNS: [&str; SUMMARY_COLUMNS.len()] = [
+    "environment",
+    "known-folder",
+    "shell-moniker",
+    "csidl",
+    "registry-64",
+    "registry-32",
+    "registry-default",
+];
+
+/// Renders the summary table found by `summarize()` as CSV or TSV, headed by a line naming the
+/// columns: `directory`, then each of `PIVOT_COLUMNS`.
+///
+/// Each line is for one logical directory, given by `LogicalDirectory::label()`, with the path
+/// each source found for it. A cell is empty if the source has no entry for the directory or the
+/// lookup failed. Which sources disagree is left to whoever reads it, by comparing the cells.
+pub fn pivot_delimited(report: &Report, platform: Platform, separator: char) -> String {
+    let header: Vec<&str> = std::iter::once("directory").chain(PIVOT_COLUMNS).collect();
+    let mut text = delimited_line(&header, separator) + "\n";
+
+    for row in summarize(report, platform) {
+        let cells = row.cells.iter().map(|cell| match cell {
+            SummaryCell::Path(path) | SummaryCell::Outlier(path) => path.as_str(),
+            SummaryCell::NotApplicable | SummaryCell::Error => "",
+        });
+        let line: Vec<&str> = std::iter::once(row.directory.label())
+            .chain(cells)
+            .collect();
+        text.push_str(&delimited_line(&line, separator));
+        text.push('\n');
+    }
+    text
+}
+
+/// The version of the schema that JSON and YAML reports follow, given in them as `schema_version`.
+///
+/// This is incremented whenever a property is removed or renamed, or its meaning or type changes,
+/// so consumers can tell whether they understand a report. Adding a property does not change it,
+/// so consumers should ignore properties they do not know. `JSON_SCHEMA` must be updated with it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The [JSON Schema][schema] document describing JSON reports, as `--json-schema` prints it.
+///
+/// It describes the output of `json()`, and of `saved::save()`, which adds a `snapshot`, for
+/// consumers to validate reports against. YAML reports have the same structure. Its
+/// `schema_version` is `SCHEMA_VERSION`.
+///
+/// [schema]: https://json-schema.org/
+pub const JSON_SCHEMA: &str = include_str!("../schema/report.schema.json");
+
+/// A report with the conclusions drawn from it, as `json()` and `yaml()` render it.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct WithFindings<'a> {
+    schema_version: u32,
+
+    #[serde(flatten)]
+    report: &'a Report,
+
+    #[serde(skip_serializing_if = "<[Finding]>::is_empty")]
+    findings: &'a [Finding],
+}
+
+/// Renders the report as pretty-printed JSON, ending in a newline.
+///
+/// The JSON follows `JSON_SCHEMA`, and begins with its version, `SCHEMA_VERSION`, as
+/// `schema_version`.
+///
+/// The findings, as found by `findings()`, follow the sections as a `findings` array, each with
+/// its `severity`, `title`, and `explanation`. If there are none, the array is omitted.
+///
+/// This is only available with the `serde` feature, as are `yaml()` and `toml()`.
+#[cfg(feature = "serde")]
+pub fn json(report: &Report, findings: &[Finding]) -> Result<String, Error> {
+    let with_findings = WithFindings {
+        schema_version: SCHEMA_VERSION,
+        report,
+        findings,
+    };
+    Ok(serde_json::to_string_pretty(&with_findings)? + "\n")
+}
+
+/// One lookup, as `json_lines()` renders it.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct LookupLine<'a> {
+    source: &'static str,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    view: String,
+
+    symbol: &'a str,
+
+    status: &'static str,
+
+    #[serde(flatten)]
+    lookup: &'a Lookup,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+}
+
+/// Renders every entry of the report as a line of JSON, for log shippers and SIEMs.
+///
+/// Each line is an object for one lookup, with the same `source`, `view`, `symbol`, and `status`
+/// as `delimited()` gives, and the `path` or `error` as `json()` gives them. If `duration` is
+/// given, each line also has it as `duration_ms`, in milliseconds. Since a source looks up all its
+/// entries together, this is how long the source the entry is from took, as given by
+/// `Report::collect_observed()`, so each source can be rendered as soon as it is looked up.
+#[cfg(feature = "serde")]
+pub fn json_lines(report: &Report, duration: Option<Duration>) -> Result<String, Error> {
+    let mut text = String::new();
+    for section in report.sections() {
+        for entry in &section.entries {
+            let line = LookupLine {
+                source: section.source.label(),
+                view: section.source.view(),
+                symbol: entry.name(),
+                status: lookup_status(entry.lookup()),
+                lookup: entry.lookup(),
+                duration_ms: duration.map(|duration| duration.as_secs_f64() * 1000.0),
+            };
+            text.push_str(&serde_json::to_string(&line)?);
+            text.push('\n');
+        }
+    }
+    Ok(text)
+}
+
+/// Renders the report as YAML, for configuration management tools that consume it as facts.
+///
+/// This has the same structure as `json()` gives, including the findings.
+#[cfg(feature = "serde")]
+pub fn yaml(report: &Report, findings: &[Finding]) -> Result<String, Error> {
+    let with_findings = WithFindings {
+        schema_version: SCHEMA_VERSION,
+        report,
+        findings,
+    };
+    Ok(serde_yaml::to_string(&with_findings)?)
+}
+
+/// Renders the report as TOML.
+///
+/// This has the same structure as `json()` gives, including `schema_version` and the findings.
+#[cfg(feature = "serde")]
+pub fn toml(report: &Report, findings: &[Finding]) -> Result<String, Error> {
+    let with_findings = WithFindings {
+        schema_version: SCHEMA_VERSION,
+        report,
+        findings,
+    };
+    Ok(toml::to_string(&with_findings)?)
+}
@@ -0,0 +1,1469 @@
+//! Typed results of looking up program files folder locations from each source.
+
+use std::ffi::{OsStr, OsString};
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(windows)]
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+#[cfg(windows)]
+use std::sync::Arc;
+#[cfg(windows)]
+use std::thread;
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+use windows_core::GUID;
+
+use crate::analysis::SourceKind;
+use crate::environment::environment_variables_from;
+use crate::error::EntryError;
+#[cfg(windows)]
+use crate::error::Error;
+use crate::known_folder::known_folders_section;
+#[cfg(windows)]
+use crate::known_folder::report_known_folders_as;
+use crate::os::OsFacade;
+#[cfg(windows)]
+use crate::registry::{report_offline_hive, Backend, CURRENT_VERSION_SUBKEY};
+#[cfg(windows)]
+use crate::source::{Query, SOURCES};
+#[cfg(windows)]
+use crate::token::{Identity, Token};
+#[cfg(windows)]
+use crate::users::report_all_users;
+
+/// Gives a path as text, escaping what is not valid Unicode so that nothing is lost.
+///
+/// Windows paths are sequences of UTF-16 code units that need not be valid UTF-16: they may have
+/// unpaired surrogates, which no Unicode string can hold. Each of those is written as `<U+D800>`,
+/// with its value in hexadecimal, and the rest of the path is kept as it is. Since `<` and `>`
+/// cannot appear in a Windows file name, an escape cannot be mistaken for part of the path, as
+/// something like `\u{D800}` could be after a path separator. A path that is valid Unicode is
+/// returned unchanged.
+pub fn escape_non_unicode(path: &OsStr) -> String {
+    #[cfg(windows)]
+    let units = path.encode_wide();
+    // Elsewhere, such as for a `FakeOs`, paths are not UTF-16, so only what is Unicode is kept.
+    #[cfg(not(windows))]
+    let units = path.to_string_lossy().encode_utf16().collect::<Vec<_>>();
+
+    let mut text = String::new();
+    for unit in char::decode_utf16(units) {
+        match unit {
+            Ok(c) => text.push(c),
+            Err(e) => text.push_str(&format!("<U+{:04X}>", e.unpaired_surrogate())),
+        }
+    }
+    text
+}
+
+/// The outcome of looking up one program files folder location.
+///
+/// Exactly one of `path` and `error` is present.
+///
+/// Paths are found as `OsString`s, which hold whatever Windows gives, even if it is not valid
+/// Unicode. The `path` is then made valid Unicode, with any unpaired surrogates replaced by
+/// U+FFFD REPLACEMENT CHARACTER, so it can be shown and compared. When that loses information,
+/// `escaped_path` also has the path, as given by `escape_non_unicode()`. The path exactly as
+/// Windows gave it is kept in `os_path`, which is what the filesystem is checked with and what
+/// paths are compared by.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Lookup {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub path: Option<String>,
+
+    /// The path with what is not valid Unicode escaped, if it is not valid Unicode.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub escaped_path: Option<String>,
+
+    /// The path as Windows gave it. This is not shown or saved, so `--redact` leaves it alone.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub os_path: Option<OsString>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub error: Option<EntryError>,
+}
+
+impl Lookup {
+    pub fn new<P: Into<OsString>, E: Into<EntryError>>(result: Result<P, E>) -> Self {
+        match result {
+            Ok(path) => {
+                let path = path.into();
+                Self {
+                    path: Some(path.to_string_lossy().into_owned()),
+                    escaped_path: path.to_str().is_none().then(|| escape_non_unicode(&path)),
+                    os_path: Some(path),
+                    error: None,
+                }
+            }
+            Err(e) => Self {
+                path: None,
+                escaped_path: None,
+                os_path: None,
+                error: Some(e.into()),
+            },
+        }
+    }
+
+    /// Text for the path column: the path, or the error message in square brackets.
+    pub fn path_item(&self) -> String {
+        match (&self.path, &self.error) {
+            (Some(path), _) => path.clone(),
+            (None, Some(error)) => format!("[{}]", error.message),
+            (None, None) => String::from("[no information]"),
+        }
+    }
+
+    /// Like `path_item()`, but with the path escaped, if it is not valid Unicode.
+    pub fn escaped_path_item(&self) -> String {
+        match &self.escaped_path {
+            Some(escaped_path) => escaped_path.clone(),
+            None => self.path_item(),
+        }
+    }
+}
+
+/// An entry in a section of the report: a symbolic name and what looking it up produced.
+///
+/// Each source has its own entry type, which may carry more information about how the entry was
+/// looked up. This trait gives access to what they have in common.
+pub trait Entry {
+    /// The symbolic name of the entry, such as `ProgramW6432` or `FOLDERID_ProgramFilesX86`.
+    fn name(&self) -> &str;
+
+    /// The path found, or why none was found.
+    fn lookup(&self) -> &Lookup;
+
+    /// The path found, if any.
+    fn path(&self) -> Option<&str> {
+        self.lookup().path.as_deref()
+    }
+
+    /// The path found, if any, exactly as Windows gave it.
+    fn os_path(&self) -> Option<&OsStr> {
+        self.lookup().os_path.as_deref()
+    }
+}
+
+/// A program files folder location from an environment variable.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct EnvVarEntry {
+    /// The name of the environment variable.
+    pub name: String,
+
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+}
+
+/// A program files folder location from a freshly created environment block, with the value of the
+/// same variable this process inherited.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FreshEnvVarEntry {
+    /// The name of the environment variable.
+    pub name: String,
+
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+
+    /// The value this process inherited, if it has the variable.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub inherited: Option<String>,
+
+    /// Whether the inherited value differs from the fresh one, as described on
+    /// `report_fresh_environment()`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "std::ops::Not::not"))]
+    pub stale: bool,
+}
+
+/// A program files folder location from a known folder.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct KnownFolderEntry {
+    /// The symbolic name of the known folder ID, such as `FOLDERID_ProgramFiles`.
+    pub name: String,
+
+    /// The known folder ID.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_guid"))]
+    pub id: GUID,
+
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+}
+
+/// A program files folder location from a known folder, looked up through `IKnownFolder`, along
+/// with the folder's definition.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct KnownFolderDefinitionEntry {
+    /// The symbolic name of the known folder ID, such as `FOLDERID_ProgramFiles`.
+    pub name: String,
+
+    /// The known folder ID.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_guid"))]
+    pub id: GUID,
+
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+
+    /// How the folder is defined, if its definition could be retrieved.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub definition: Option<KnownFolderDefinition>,
+}
+
+/// The parts of a known folder's [`KNOWNFOLDER_DEFINITION`][kfd] that say where it is.
+///
+/// [kfd]: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/ns-shobjidl_core-knownfolder_definition
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct KnownFolderDefinition {
+    /// The canonical name, which is not localized, such as `ProgramFilesX86`.
+    pub canonical_name: String,
+
+    /// The name Explorer shows for the folder, in the user's display language, if it has one and
+    /// it could be loaded.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub localized_name: Option<String>,
+
+    /// The indirect string the localized name is loaded from, such as
+    /// `@%SystemRoot%\system32\shell32.dll,-21781`, if the folder has one.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub localized_name_source: Option<String>,
+
+    /// The category, which is `virtual`, `fixed`, `common`, or `per-user`.
+    pub category: &'static str,
+
+    /// The Shell namespace parsing name, if the folder has one.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub parsing_name: Option<String>,
+
+    /// The path relative to the parent folder, if the folder is defined that way.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub relative_path: Option<String>,
+
+    /// The ID of the parent folder, if the folder has one.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "serialize_optional_guid",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub parent: Option<GUID>,
+}
+
+/// A program files folder location from a CSIDL.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CsidlEntry {
+    /// The symbolic name of the CSIDL, such as `CSIDL_PROGRAM_FILES`.
+    pub name: String,
+
+    /// The numeric value of the CSIDL.
+    pub csidl: u32,
+
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+
+    /// The folder's default path, rather than its current one, if it was looked up.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub default: Option<Lookup>,
+}
+
+impl CsidlEntry {
+    /// Whether the default path was looked up and differs from the current path.
+    ///
+    /// This is only the case if both were found, since a failure to find one of them is already
+    /// shown as such.
+    pub fn differs_from_default(&self) -> bool {
+        match (
+            &self.lookup.path,
+            self.default.as_ref().and_then(|d| d.path.as_ref()),
+        ) {
+            (Some(current), Some(default)) => current != default,
+            _ => false,
+        }
+    }
+}
+
+/// A program files folder location from a value of a registry key.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RegistryEntry {
+    /// The name of the registry value, such as `ProgramFilesDir`.
+    pub name: String,
+
+    /// The type of the registry value, such as `REG_SZ`, if it was read.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub value_type: Option<String>,
+
+    /// The data of the registry value, before any environment variables in it are expanded.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub raw: Option<String>,
+
+    /// The path, which is the data with any environment variables expanded, or why there is none.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+}
+
+/// A program files folder location from a value of a legacy shell folders registry key.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ShellFolderEntry {
+    /// The name of the registry value, which is a known folder ID in registry format.
+    pub name: String,
+
+    /// The symbolic name of the known folder the value is for, such as `FOLDERID_ProgramFiles`.
+    pub known_folder: &'static str,
+
+    /// The type of the registry value, such as `REG_EXPAND_SZ`, if it was read.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub value_type: Option<String>,
+
+    /// The data of the registry value, before any environment variables in it are expanded.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub raw: Option<String>,
+
+    /// The path, which is the data with any environment variables expanded, or why there is none.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+}
+
+/// An entry from some other section, shown again under a different name.
+///
+/// This is for tables, such as the reconciliation of `ProgramW6432`, that bring together entries
+/// from several sections and need to say where each came from.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Row {
+    pub name: String,
+
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lookup: Lookup,
+}
+
+impl Row {
+    /// Copies the lookup result of an entry, giving it a new name.
+    pub fn relabel(entry: &dyn Entry, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            lookup: entry.lookup().clone(),
+        }
+    }
+}
+
+macro_rules! impl_entry {
+    ($($type:ty),*) => {
+        $(
+            impl Entry for $type {
+                fn name(&self) -> &str {
+                    &self.name
+                }
+
+                fn lookup(&self) -> &Lookup {
+                    &self.lookup
+                }
+            }
+        )*
+    };
+}
+
+impl_entry!(
+    EnvVarEntry,
+    FreshEnvVarEntry,
+    KnownFolderEntry,
+    KnownFolderDefinitionEntry,
+    CsidlEntry,
+    RegistryEntry,
+    ShellFolderEntry,
+    Row
+);
+
+/// Serializes a GUID in registry format, such as `{905E63B6-C1BF-494E-B29C-65B732D3D21A}`.
+#[cfg(feature = "serde")]
+fn serialize_guid<S: Serializer>(id: &GUID, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{{{id:?}}}"))
+}
+
+/// Serializes a GUID, if present, as `serialize_guid()` does.
+#[cfg(feature = "serde")]
+fn serialize_optional_guid<S: Serializer>(
+    id: &Option<GUID>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match id {
+        Some(id) => serialize_guid(id, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// How to order the entries within each section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Alphabetically by symbolic name.
+    Name,
+
+    /// By path, with entries that have errors last.
+    Path,
+
+    /// In the order they are looked up, which groups related entries.
+    None,
+}
+
+/// The entries looked up from one source, and the heading to show them under.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Section<E> {
+    pub heading: String,
+    pub entries: Vec<E>,
+}
+
+impl<E: Entry> Section<E> {
+    pub fn new(heading: impl Into<String>, entries: Vec<E>) -> Self {
+        Self {
+            heading: heading.into(),
+            entries,
+        }
+    }
+
+    /// Reorders the entries as specified.
+    ///
+    /// Sorting by path puts entries with errors after all entries with paths, and sorts each of
+    /// those groups by name, so that the order is fully determined by the results.
+    pub fn sort(&mut self, order: SortOrder) {
+        match order {
+            SortOrder::None => {}
+            SortOrder::Name => self.entries.sort_by(|a, b| a.name().cmp(b.name())),
+            SortOrder::Path => self.entries.sort_by(|a, b| {
+                let key = |entry: &E| (entry.path().is_none(), entry.path().map(str::to_owned));
+                key(a).cmp(&key(b)).then_with(|| a.name().cmp(b.name()))
+            }),
+        }
+    }
+
+    /// Keeps only the entries for which `keep` gives `true`, in the same order.
+    pub fn retain(&mut self, mut keep: impl FnMut(&E) -> bool) {
+        self.entries.retain(|entry| keep(entry));
+    }
+
+    /// Finds the entry with the given symbolic name, if this section has one.
+    pub fn find(&self, name: &str) -> Option<&E> {
+        self.entries.iter().find(|entry| entry.name() == name)
+    }
+
+    /// Borrows this section without its entry type, as coming from the given kind of source.
+    pub fn as_section_ref(&self, source: SourceKind) -> SectionRef<'_> {
+        SectionRef {
+            source,
+            heading: &self.heading,
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| entry as &dyn Entry)
+                .collect(),
+        }
+    }
+}
+
+/// A section of any source, borrowed, for code that treats all sections alike.
+#[derive(Clone)]
+pub struct SectionRef<'a> {
+    pub source: SourceKind,
+    pub heading: &'a str,
+    pub entries: Vec<&'a dyn Entry>,
+}
+
+impl<'a> SectionRef<'a> {
+    /// Finds the entry with the given symbolic name, if this section has one.
+    pub fn find(&self, name: &str) -> Option<&'a dyn Entry> {
+        self.entries
+            .iter()
+            .copied()
+            .find(|entry| entry.name() == name)
+    }
+}
+
+/// The entries looked up from one view of the registry.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RegistryView {
+    pub view: &'static str,
+
+    /// The full name of the key that was opened, as described on `key_name()`, or `None` if it
+    /// could not be found.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub opened_key: Option<String>,
+
+    /// When the key was last written, as described on `last_write_time()`, or `None` if it could
+    /// not be found.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub last_write_time: Option<String>,
+
+    pub keys: Section<RegistryEntry>,
+}
+
+/// The values stored for the program files environment variables in one registry key.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct EnvironmentKey {
+    pub key: &'static str,
+    pub values: Section<RegistryEntry>,
+}
+
+/// The entries looked up from one legacy shell folders key, through one view of the registry.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ShellFolderKey {
+    pub key: &'static str,
+    pub view: &'static str,
+    pub values: Section<ShellFolderEntry>,
+}
+
+/// The per-user registry values of one local user profile, as described on
+/// `report_all_users()`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct UserProfile {
+    /// The SID of the profile's user, in its string form, as it is named in `ProfileList`.
+    pub sid: String,
+
+    /// The account name of the profile's user, as `DOMAIN\name`, or `None` if it could not be
+    /// found, as for an account that was deleted but whose profile remains.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub account: Option<String>,
+
+    /// The profile directory, from the `ProfileImagePath` value, expanded.
+    pub profile_path: Lookup,
+
+    /// Whether the user's hive was already loaded in `HKEY_USERS`, as it is while they are logged
+    /// on, rather than loaded from the profile's `NTUSER.DAT` by this process.
+    pub hive_was_loaded: bool,
+
+    pub environment: EnvironmentKey,
+    pub shell_folders: Vec<ShellFolderKey>,
+}
+
+/// Which sources to look up.
+#[derive(Clone, Copy, Debug)]
+pub struct Selection {
+    pub environment_variables: bool,
+    pub known_folders: bool,
+    pub known_folder_definitions: bool,
+    pub shell_monikers: bool,
+    pub csidls: bool,
+    pub registry: bool,
+    pub shell_folders: bool,
+    pub environment_registry: bool,
+    pub fresh_environment: bool,
+    pub wmi: bool,
+    pub system_directories: bool,
+
+    /// Whether to run `cmd` and PowerShell, as described on `report_shells()`.
+    pub shells: bool,
+
+    /// Whether to open a Windows Installer session, as described on `report_msi()`.
+    pub msi: bool,
+}
+
+impl Selection {
+    /// Selects every source, except running other shells and opening a Windows Installer session,
+    /// which are only done when asked for, since they start other programs or services.
+    pub const ALL: Self = Self {
+        environment_variables: true,
+        known_folders: true,
+        known_folder_definitions: true,
+        shell_monikers: true,
+        csidls: true,
+        registry: true,
+        shell_folders: true,
+        environment_registry: true,
+        fresh_environment: true,
+        wmi: true,
+        system_directories: true,
+        shells: false,
+        msi: false,
+    };
+
+    /// Selects the sources selected by both `self` and `other`.
+    pub fn intersect(self, other: Self) -> Self {
+        Self {
+            environment_variables: self.environment_variables && other.environment_variables,
+            known_folders: self.known_folders && other.known_folders,
+            known_folder_definitions: self.known_folder_definitions
+                && other.known_folder_definitions,
+            shell_monikers: self.shell_monikers && other.shell_monikers,
+            csidls: self.csidls && other.csidls,
+            registry: self.registry && other.registry,
+            shell_folders: self.shell_folders && other.shell_folders,
+            environment_registry: self.environment_registry && other.environment_registry,
+            fresh_environment: self.fresh_environment && other.fresh_environment,
+            wmi: self.wmi && other.wmi,
+            system_directories: self.system_directories && other.system_directories,
+            shells: self.shells && other.shells,
+            msi: self.msi && other.msi,
+        }
+    }
+}
+
+/// Everything looked up from the selected sources.
+///
+/// Each field is for one of the sources in `SOURCES`, and has the name that source gives.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Report {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub environment_variables: Option<Section<EnvVarEntry>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub known_folders: Option<Section<KnownFolderEntry>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub known_folder_definitions: Option<Section<KnownFolderDefinitionEntry>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub shell_monikers: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub csidls: Option<Section<CsidlEntry>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub csidl_locations: Option<Section<CsidlEntry>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub registry: Vec<RegistryView>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub shell_folders: Vec<ShellFolderKey>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub environment_registry: Vec<EnvironmentKey>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub fresh_environment: Option<Section<FreshEnvVarEntry>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub wmi: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub shells: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub msi: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub system_directories: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub arm_program_files: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub per_user_installs: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub virtualization: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub package: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub policy: Option<Section<Row>>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub users: Vec<UserProfile>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub timed_out: Vec<TimedOutSection>,
+
+    /// Whether collecting the report was interrupted, so that sources not yet looked up by then
+    /// are missing from it, as described on `interrupt_collection()`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "std::ops::Not::not"))]
+    pub truncated: bool,
+}
+
+/// A section standing in for one whose source was not looked up in time, or could not be looked up.
+///
+/// It has the heading the section would have had, and an entry for each name the source looks up,
+/// with an `Error::TimedOut`, an `Error::Panicked`, or the error the source failed with as its
+/// error. It is shown in place of the section, as described on `Report::collect_from()`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TimedOutSection {
+    /// The kind of source that timed out, given by its label in structured output.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_source"))]
+    pub source: SourceKind,
+
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub section: Section<Row>,
+}
+
+impl TimedOutSection {
+    #[cfg(windows)]
+    fn new(source: SourceKind, heading: impl Into<String>, error: &EntryError) -> Self {
+        let entries = source
+            .names()
+            .into_iter()
+            .map(|name| Row {
+                name: name.to_owned(),
+                lookup: Lookup::new(Err::<String, _>(error.clone())),
+            })
+            .collect();
+
+        Self {
+            source,
+            section: Section::new(heading, entries),
+        }
+    }
+}
+
+/// Serializes a kind of source as its label, as given by `SourceKind::label()`.
+#[cfg(feature = "serde")]
+fn serialize_source<S: Serializer>(source: &SourceKind, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(source.label())
+}
+
+/// Starts looking up a source on its own thread, giving where to receive it.
+#[cfg(windows)]
+fn spawn<T, F>(look_up: F) -> Receiver<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // If the result is no longer wanted, because it timed out, there is nothing to do.
+        let _ = sender.send(look_up());
+    });
+    receiver
+}
+
+/// Whether a collection by `Report::collect_from()` is in progress, to be interrupted.
+static COLLECTING: AtomicBool = AtomicBool::new(false);
+
+/// Whether the collection in progress has been interrupted by `interrupt_collection()`.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// How often a collection checks whether it has been interrupted, while waiting for a source.
+#[cfg(windows)]
+const INTERRUPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Stops waiting for the sources of the collection in progress, if there is one, and says whether
+/// there was.
+///
+/// This is meant to be called from a console control handler, when the user presses Ctrl+C, so
+/// the sources already looked up can be shown instead of being lost. `Report::collect_from()`
+/// then returns with those sources, and with `truncated` set. If no collection is in progress,
+/// this does nothing and returns `false`, so that the handler can let the interruption end the
+/// process as usual.
+pub fn interrupt_collection() -> bool {
+    let collecting = COLLECTING.load(Ordering::SeqCst);
+    if collecting {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+    collecting
+}
+
+/// Marks a collection as in progress, as `interrupt_collection()` checks, until this is dropped.
+#[cfg(windows)]
+struct Collecting;
+
+#[cfg(windows)]
+impl Collecting {
+    fn start() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        COLLECTING.store(true, Ordering::SeqCst);
+        Self
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Collecting {
+    fn drop(&mut self) {
+        COLLECTING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Waits for the sources started with `spawn()`, and keeps track of those not waited for.
+#[cfg(windows)]
+struct Waiter {
+    /// When to stop waiting, and the timeout it was computed from, if there is a timeout.
+    deadline: Option<(Instant, Duration)>,
+
+    /// Stand-ins for the sections of sources that timed out.
+    timed_out: Vec<TimedOutSection>,
+
+    /// Whether the collection was interrupted, so sources not yet done were left out.
+    truncated: bool,
+}
+
+#[cfg(windows)]
+impl Waiter {
+    fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            deadline: timeout.map(|timeout| (Instant::now() + timeout, timeout)),
+            timed_out: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// Waits for a source until the deadline, if there is one, or until interrupted.
+    ///
+    /// If the deadline passes first, this gives `None`, and adds the sections the source would
+    /// have had, as described by `stand_ins`, to `timed_out`. If the thread panicked, this does the
+    /// same, but with errors saying so, and the rest of the sources are still waited for. If the
+    /// collection is interrupted, this and each later source are given only if already done, and
+    /// `truncated` is set. Either way, the thread is left to finish, or not, on its own.
+    fn receive<T>(
+        &mut self,
+        receiver: Receiver<T>,
+        stand_ins: impl FnOnce() -> Vec<(SourceKind, String)>,
+    ) -> Option<T> {
+        loop {
+            if self.truncated || INTERRUPTED.load(Ordering::SeqCst) {
+                self.truncated = true;
+                return receiver.try_recv().ok();
+            }
+
+            let wait = match self.deadline {
+                Some((deadline, _)) => deadline
+                    .saturating_duration_since(Instant::now())
+                    .min(INTERRUPT_POLL_INTERVAL),
+                None => INTERRUPT_POLL_INTERVAL,
+            };
+
+            match receiver.recv_timeout(wait) {
+                Ok(value) => return Some(value),
+                Err(RecvTimeoutError::Timeout) => {}
+                // The thread panicked, which the panic hook has already reported.
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.stand_in(stand_ins, Error::Panicked);
+                    return None;
+                }
+            }
+
+            if let Some((deadline, timeout)) = self.deadline {
+                if Instant::now() >= deadline {
+                    self.stand_in(stand_ins, Error::TimedOut(timeout));
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Adds the sections a source would have had, as described by `stand_ins`, to `timed_out`,
+    /// with `error` as the error of each of their entries.
+    fn stand_in(&mut self, stand_ins: impl FnOnce() -> Vec<(SourceKind, String)>, error: Error) {
+        let error = EntryError::from(error);
+        self.timed_out.extend(
+            stand_ins()
+                .into_iter()
+                .map(|(source, heading)| TimedOutSection::new(source, heading, &error)),
+        );
+    }
+}
+
+impl Report {
+    /// Looks up program files folder locations from every source.
+    #[cfg(windows)]
+    pub fn collect() -> Result<Self, Error> {
+        Self::collect_from(
+            Selection::ALL,
+            CURRENT_VERSION_SUBKEY,
+            None,
+            Backend::Winreg,
+            None,
+        )
+    }
+
+    /// Looks up program files folder locations from the selected sources.
+    ///
+    /// Registry values are read from `registry_subkey`, which is normally `CURRENT_VERSION_SUBKEY`.
+    ///
+    /// If `computer` is given, the registry sources are read from that computer, as described on
+    /// `open_hive()`. The other sources can only be looked up on this computer, so they should not
+    /// be selected along with another computer.
+    ///
+    /// The registry values in `registry_subkey` are read through `backend`, as described on
+    /// `Backend`.
+    ///
+    /// On ARM64 systems, selecting known folders or CSIDLs also reports the 32-bit ARM program
+    /// files directory, for which there is neither, as described on `report_arm_program_files()`.
+    /// Selecting known folders also reports the other places per-user installs go, as described on
+    /// `report_per_user_installs()`, whether UAC virtualization may redirect writes to the
+    /// program files directories, as described on `report_virtualization()`, whether this
+    /// process is packaged or in an AppContainer, as described on `report_package()`, and whether
+    /// policy configures Explorer or redirects the known folders, as described on
+    /// `report_policy()`.
+    ///
+    /// Shell monikers, as described on `report_shell_monikers()`, are looked up only on this
+    /// computer. Selecting CSIDLs reports them both ways old software looks them up, as described
+    /// on `report_csidl_locations()`.
+    ///
+    /// The sources are those in `SOURCES`, each looked up through the `Source` trait if it is
+    /// selected, as `Source::is_selected()` says.
+    ///
+    /// Each source is looked up on its own thread, so they are looked up in parallel. Shell APIs
+    /// can hang when the Shell infrastructure is broken, such as by a corrupt COM registration or a
+    /// folder redirected to a server that is not responding, and reading another computer's
+    /// registry can hang on the network. So if `timeout` is given, the sources that are not done by
+    /// then are not waited for. Each of their sections is replaced with a `TimedOutSection`, in
+    /// `timed_out`, and the rest of the report is kept. The threads looking up those sources are
+    /// left running, since they cannot be stopped, until the process exits. A source that fails as
+    /// a whole, such as when COM cannot be initialized, or whose thread panics, is replaced the same
+    /// way, with entries giving its error, and the rest are still collected.
+    ///
+    /// While this runs, `interrupt_collection()` stops it waiting, as described there.
+    ///
+    /// How long each source took, and whether it failed or timed out, is logged as `tracing`
+    /// events at the `DEBUG` level. The raw API calls sources make are logged at the `TRACE`
+    /// level, as described on `traced()`.
+    #[cfg(windows)]
+    pub fn collect_from(
+        selection: Selection,
+        registry_subkey: &str,
+        computer: Option<&str>,
+        backend: Backend,
+        timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
+        Self::collect_observed(
+            selection,
+            registry_subkey,
+            computer,
+            backend,
+            timeout,
+            |_, _| {},
+        )
+    }
+
+    /// Like `collect_from()`, but calls `observe` with each source as soon as it is looked up,
+    /// so it can be shown without waiting for the whole report.
+    ///
+    /// Each source is given as a report with only that source's sections, along with how long the
+    /// source took to look up. A source that times out is given as a report with only the
+    /// `TimedOutSection`s standing in for it, along with the timeout, and a source that failed or
+    /// panicked is given the same way, along with how long it took or was waited for. The sources
+    /// are given in the order of `SOURCES`, each as soon as it and those before it are done, since
+    /// they are looked up in parallel. A source not looked up because the collection was
+    /// interrupted is not given.
+    #[cfg(windows)]
+    pub fn collect_observed(
+        selection: Selection,
+        registry_subkey: &str,
+        computer: Option<&str>,
+        backend: Backend,
+        timeout: Option<Duration>,
+        mut observe: impl FnMut(&Report, Duration),
+    ) -> Result<Self, Error> {
+        let _collecting = Collecting::start();
+        let started = Instant::now();
+        let query = Arc::new(Query {
+            registry_subkey: registry_subkey.to_owned(),
+            computer: computer.map(String::from),
+            backend,
+        });
+
+        let pending: Vec<_> = SOURCES
+            .iter()
+            .filter(|source| source.is_selected(selection, &query))
+            .map(|source| {
+                let query = Arc::clone(&query);
+                (
+                    source,
+                    spawn(move || {
+                        let start = Instant::now();
+                        let collected = source.collect(&query);
+                        let elapsed = start.elapsed();
+                        match &collected {
+                            Ok(_) => tracing::debug!(source = source.name(), ?elapsed, "collected"),
+                            Err(error) => {
+                                tracing::debug!(source = source.name(), %error, ?elapsed, "failed");
+                            }
+                        }
+                        (collected, elapsed)
+                    }),
+                )
+            })
+            .collect();
+
+        let mut waiter = Waiter::new(timeout);
+        let mut report = Self::default();
+        for (source, receiver) in pending {
+            let already_timed_out = waiter.timed_out.len();
+            let waited = match waiter.receive(receiver, || source.sections(&query)) {
+                Some((Ok(collected), elapsed)) => {
+                    let mut part = Self::default();
+                    collected.add_to(&mut part);
+                    observe(&part, elapsed);
+                    report.update(part);
+                    continue;
+                }
+                Some((Err(error), elapsed)) => {
+                    waiter.stand_in(|| source.sections(&query), error);
+                    elapsed
+                }
+                None => {
+                    tracing::debug!(
+                        source = source.name(),
+                        "not collected, since it timed out, panicked, or was interrupted"
+                    );
+                    match waiter.deadline {
+                        Some((deadline, timeout)) if Instant::now() >= deadline => timeout,
+                        _ => started.elapsed(),
+                    }
+                }
+            };
+            let stand_ins = Self {
+                timed_out: waiter.timed_out[already_timed_out..].to_vec(),
+                ..Self::default()
+            };
+            if !stand_ins.timed_out.is_empty() {
+                observe(&stand_ins, waited);
+            }
+        }
+        report.timed_out = waiter.timed_out;
+        report.truncated = waiter.truncated;
+
+        Ok(report)
+    }
+
+    /// Looks up the environment variables and known folders sections through `os`, which may be
+    /// a simulated system, such as a `FakeOs`.
+    ///
+    /// The environment variables are looked up as `report_environment_variables()` looks them up
+    /// through `RealOs`, and the known folders section is made as `report_known_folders()` makes
+    /// it, but with each folder looked up by `OsFacade::known_folder()`, so there is no
+    /// cross-check. So reports of simulated systems, with the sections that collection fills in,
+    /// can be analyzed and rendered on any platform. The other sections are absent.
+    pub fn collect_with(os: &impl OsFacade) -> Self {
+        Self {
+            environment_variables: Some(environment_variables_from(os)),
+            known_folders: Some(known_folders_section(
+                String::from("Relevant known folders"),
+                |id| os.known_folder(id),
+            )),
+            ..Self::default()
+        }
+    }
+
+    /// Looks up program files folder locations in an offline `SOFTWARE` registry hive.
+    ///
+    /// This has only registry sections, as described on `report_offline_hive()`, since the other
+    /// sources can only look up locations on the running system.
+    #[cfg(windows)]
+    pub fn collect_offline(hive: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            registry: report_offline_hive(hive)?,
+            ..Self::default()
+        })
+    }
+
+    /// Looks up the per-user registry values relevant to program files for every local profile.
+    ///
+    /// This has only the per-user environment and shell folders sections, as described on
+    /// `report_all_users()`, since the other sources would only show this process's own view.
+    #[cfg(windows)]
+    pub fn collect_all_users() -> Result<Self, Error> {
+        Ok(Self {
+            users: report_all_users()?,
+            ..Self::default()
+        })
+    }
+
+    /// Looks up the program files known folders as another security context would.
+    ///
+    /// This has only the known folders section, looked up with a token for `identity`, as
+    /// described on `report_known_folders_as()`. The other sources do not take a token, so they
+    /// would only show this process's own view, which could be mistaken for the other user's.
+    #[cfg(windows)]
+    pub fn collect_as(identity: &Identity) -> Result<Self, Error> {
+        let token = Token::open(identity)?;
+        Ok(Self {
+            known_folders: Some(report_known_folders_as(&token, &identity.description())?),
+            ..Self::default()
+        })
+    }
+
+    /// Reorders the entries in every section, as described on `Section::sort()`.
+    pub fn sort(&mut self, order: SortOrder) {
+        if let Some(section) = &mut self.environment_variables {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.known_folders {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.known_folder_definitions {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.shell_monikers {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.csidls {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.csidl_locations {
+            section.sort(order);
+        }
+        for view in &mut self.registry {
+            view.keys.sort(order);
+        }
+        for key in &mut self.shell_folders {
+            key.values.sort(order);
+        }
+        for key in &mut self.environment_registry {
+            key.values.sort(order);
+        }
+        if let Some(section) = &mut self.fresh_environment {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.wmi {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.shells {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.msi {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.system_directories {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.arm_program_files {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.per_user_installs {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.virtualization {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.package {
+            section.sort(order);
+        }
+        if let Some(section) = &mut self.policy {
+            section.sort(order);
+        }
+        for user in &mut self.users {
+            user.environment.values.sort(order);
+            for key in &mut user.shell_folders {
+                key.values.sort(order);
+            }
+        }
+        for stand_in in &mut self.timed_out {
+            stand_in.section.sort(order);
+        }
+    }
+
+    /// Keeps only the entries for which `keep` gives `true`, given the kind of source each is from
+    /// and its symbolic name, dropping sections that are left with none.
+    ///
+    /// The sections of each user's profile are kept even if they are left empty, since a profile
+    /// has exactly one of each.
+    pub fn retain(&mut self, keep: impl Fn(SourceKind, &str) -> bool) {
+        fn retain_section<E: Entry>(
+            section: &mut Option<Section<E>>,
+            source: SourceKind,
+            keep: &impl Fn(SourceKind, &str) -> bool,
+        ) {
+            if let Some(inner) = section {
+                inner.retain(|entry| keep(source, entry.name()));
+                if inner.entries.is_empty() {
+                    *section = None;
+                }
+            }
+        }
+
+        retain_section(
+            &mut self.environment_variables,
+            SourceKind::EnvironmentVariable,
+            &keep,
+        );
+        retain_section(&mut self.known_folders, SourceKind::KnownFolder, &keep);
+        retain_section(
+            &mut self.known_folder_definitions,
+            SourceKind::KnownFolderDefinition,
+            &keep,
+        );
+        retain_section(&mut self.shell_monikers, SourceKind::ShellMoniker, &keep);
+        retain_section(&mut self.csidls, SourceKind::Csidl, &keep);
+        retain_section(&mut self.csidl_locations, SourceKind::CsidlLocation, &keep);
+        retain_section(
+            &mut self.fresh_environment,
+            SourceKind::FreshEnvironment,
+            &keep,
+        );
+        retain_section(&mut self.wmi, SourceKind::Wmi, &keep);
+        retain_section(&mut self.shells, SourceKind::Shells, &keep);
+        retain_section(&mut self.msi, SourceKind::Msi, &keep);
+        retain_section(
+            &mut self.system_directories,
+            SourceKind::SystemDirectories,
+            &keep,
+        );
+        retain_section(
+            &mut self.arm_program_files,
+            SourceKind::ArmProgramFiles,
+            &keep,
+        );
+        retain_section(
+            &mut self.per_user_installs,
+            SourceKind::PerUserInstalls,
+            &keep,
+        );
+        retain_section(&mut self.virtualization, SourceKind::Virtualization, &keep);
+        retain_section(&mut self.package, SourceKind::Package, &keep);
+        retain_section(&mut self.policy, SourceKind::Policy, &keep);
+
+        self.registry.retain_mut(|view| {
+            let source = SourceKind::Registry(view.view);
+            view.keys.retain(|entry| keep(source, entry.name()));
+            !view.keys.entries.is_empty()
+        });
+        self.shell_folders.retain_mut(|key| {
+            let source = SourceKind::ShellFolders {
+                key: key.key,
+                view: key.view,
+            };
+            key.values.retain(|entry| keep(source, entry.name()));
+            !key.values.entries.is_empty()
+        });
+        self.environment_registry.retain_mut(|key| {
+            let source = SourceKind::EnvironmentRegistry(key.key);
+            key.values.retain(|entry| keep(source, entry.name()));
+            !key.values.entries.is_empty()
+        });
+        for user in &mut self.users {
+            let source = SourceKind::EnvironmentRegistry(user.environment.key);
+            user.environment
+                .values
+                .retain(|entry| keep(source, entry.name()));
+            for key in &mut user.shell_folders {
+                let source = SourceKind::ShellFolders {
+                    key: key.key,
+                    view: key.view,
+                };
+                key.values.retain(|entry| keep(source, entry.name()));
+            }
+        }
+        self.timed_out.retain_mut(|stand_in| {
+            let source = stand_in.source;
+            stand_in.section.retain(|entry| keep(source, entry.name()));
+            !stand_in.section.entries.is_empty()
+        });
+    }
+
+    /// Replaces the sections of this report with those `newer` has, keeping the others.
+    ///
+    /// A section that timed out in `newer` replaces the section this report has for that source,
+    /// and one that timed out here is dropped once `newer` has that source.
+    pub fn update(&mut self, newer: Report) {
+        let timed_out: Vec<SourceKind> = newer
+            .timed_out
+            .iter()
+            .map(|stand_in| stand_in.source)
+            .collect();
+        self.retain(|source, _| !timed_out.contains(&source));
+        let looked_up: Vec<SourceKind> = newer
+            .sections()
+            .into_iter()
+            .map(|section| section.source)
+            .collect();
+        self.timed_out
+            .retain(|stand_in| !looked_up.contains(&stand_in.source));
+        if newer.environment_variables.is_some() {
+            self.environment_variables = newer.environment_variables;
+        }
+        if newer.known_folders.is_some() {
+            self.known_folders = newer.known_folders;
+        }
+        if newer.known_folder_definitions.is_some() {
+            self.known_folder_definitions = newer.known_folder_definitions;
+        }
+        if newer.shell_monikers.is_some() {
+            self.shell_monikers = newer.shell_monikers;
+        }
+        if newer.csidls.is_some() {
+            self.csidls = newer.csidls;
+        }
+        if newer.csidl_locations.is_some() {
+            self.csidl_locations = newer.csidl_locations;
+        }
+        if !newer.registry.is_empty() {
+            self.registry = newer.registry;
+        }
+        if !newer.shell_folders.is_empty() {
+            self.shell_folders = newer.shell_folders;
+        }
+        if !newer.environment_registry.is_empty() {
+            self.environment_registry = newer.environment_registry;
+        }
+        if newer.fresh_environment.is_some() {
+            self.fresh_environment = newer.fresh_environment;
+        }
+        if newer.wmi.is_some() {
+            self.wmi = newer.wmi;
+        }
+        if newer.shells.is_some() {
+            self.shells = newer.shells;
+        }
+        if newer.msi.is_some() {
+            self.msi = newer.msi;
+        }
+        if newer.system_directories.is_some() {
+            self.system_directories = newer.system_directories;
+        }
+        if newer.arm_program_files.is_some() {
+            self.arm_program_files = newer.arm_program_files;
+        }
+        if newer.per_user_installs.is_some() {
+            self.per_user_installs = newer.per_user_installs;
+        }
+        if newer.virtualization.is_some() {
+            self.virtualization = newer.virtualization;
+        }
+        if newer.package.is_some() {
+            self.package = newer.package;
+        }
+        if newer.policy.is_some() {
+            self.policy = newer.policy;
+        }
+        if !newer.users.is_empty() {
+            self.users = newer.users;
+        }
+        self.timed_out.extend(newer.timed_out);
+    }
+
+    /// Borrows all sections, with the kind of source of each, in the order they are shown.
+    pub fn sections(&self) -> Vec<SectionRef<'_>> {
+        let environment_variables = self
+            .environment_variables
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::EnvironmentVariable));
+        let known_folders = self
+            .known_folders
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::KnownFolder));
+        let known_folder_definitions = self
+            .known_folder_definitions
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::KnownFolderDefinition));
+        let shell_monikers = self
+            .shell_monikers
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::ShellMoniker));
+        let csidls = self
+            .csidls
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::Csidl));
+        let csidl_locations = self
+            .csidl_locations
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::CsidlLocation));
+        let arm_program_files = self
+            .arm_program_files
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::ArmProgramFiles));
+        let per_user_installs = self
+            .per_user_installs
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::PerUserInstalls));
+        let virtualization = self
+            .virtualization
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::Virtualization));
+        let package = self
+            .package
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::Package));
+        let policy = self
+            .policy
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::Policy));
+        let registry = self
+            .registry
+            .iter()
+            .map(|view| view.keys.as_section_ref(SourceKind::Registry(view.view)));
+        let environment_registry = self.environment_registry.iter().map(|key| {
+            key.values
+                .as_section_ref(SourceKind::EnvironmentRegistry(key.key))
+        });
+        let fresh_environment = self
+            .fresh_environment
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::FreshEnvironment));
+        let wmi = self
+            .wmi
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::Wmi));
+        let shells = self
+            .shells
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::Shells));
+        let msi = self
+            .msi
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::Msi));
+        let system_directories = self
+            .system_directories
+            .iter()
+            .map(|section| section.as_section_ref(SourceKind::SystemDirectories));
+        let shell_folders = self.shell_folders.iter().map(|key| {
+            key.values.as_section_ref(SourceKind::ShellFolders {
+                key: key.key,
+                view: key.view,
+            })
+        });
+        let users = self.users.iter().flat_map(|user| {
+            let environment = user
+                .environment
+                .values
+                .as_section_ref(SourceKind::EnvironmentRegistry(user.environment.key));
+            let shell_folders = user.shell_folders.iter().map(|key| {
+                key.values.as_section_ref(SourceKind::ShellFolders {
+                    key: key.key,
+                    view: key.view,
+                })
+            });
+            std::iter::once(environment).chain(shell_folders)
+        });
+        let timed_out = self
+            .timed_out
+            .iter()
+            .map(|stand_in| stand_in.section.as_section_ref(stand_in.source));
+
+        environment_variables
+            .chain(environment_registry)
+            .chain(fresh_environment)
+            .chain(wmi)
+            .chain(shells)
+            .chain(msi)
+            .chain(system_directories)
+            .chain(known_folders)
+            .chain(known_folder_definitions)
+            .chain(shell_monikers)
+            .chain(csidls)
+            .chain(csidl_locations)
+            .chain(arm_program_files)
+            .chain(per_user_installs)
+            .chain(virtualization)
+            .chain(package)
+            .chain(policy)
+            .chain(users)
+            .chain(registry)
+            .chain(shell_folders)
+            .chain(timed_out)
+            .collect()
+    }
+
+    /// Borrows the section holding the entries of the given kind of source, if it is present.
+    pub fn section(&self, source: SourceKind) -> Option<SectionRef<'_>> {
+        self.sections()
+            .into_iter()
+            .find(|section| section.source == source)
+    }
+}
@@ -0,0 +1,118 @@
+//! Finding a program files directory by trying each source in turn.
+
+use std::env::VarError;
+use std::path::PathBuf;
+
+use windows_core::GUID;
+
+use crate::error::{EntryError, ResolveError};
+use crate::known_folder::{FOLDERID_ProgramFilesX64, FOLDERID_ProgramFilesX86};
+use crate::os::OsFacade;
+#[cfg(windows)]
+use crate::os::RealOs;
+
+/// An architecture whose program files directory can be resolved by `resolve_program_files()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arch {
+    /// The 64-bit directory, which is used by both x86-64 and ARM64 programs.
+    X64,
+
+    /// The 32-bit x86 directory.
+    X86,
+
+    /// The 32-bit ARM directory, present only on ARM64 systems.
+    Arm,
+}
+
+impl Arch {
+    fn description(self) -> &'static str {
+        match self {
+            Self::X64 => "64-bit program files directory",
+            Self::X86 => "32-bit x86 program files directory",
+            Self::Arm => "32-bit ARM program files directory",
+        }
+    }
+
+    /// The known folder for this architecture's directory. There is none for 32-bit ARM.
+    fn known_folder(self) -> Option<(&'static str, GUID)> {
+        match self {
+            Self::X64 => Some(("FOLDERID_ProgramFilesX64", FOLDERID_ProgramFilesX64)),
+            Self::X86 => Some(("FOLDERID_ProgramFilesX86", FOLDERID_ProgramFilesX86)),
+            Self::Arm => None,
+        }
+    }
+
+    fn environment_variable(self) -> &'static str {
+        match self {
+            Self::X64 => "ProgramW6432",
+            Self::X86 => "ProgramFiles(x86)",
+            Self::Arm => "ProgramFiles(Arm)",
+        }
+    }
+
+    fn registry_value(self) -> &'static str {
+        match self {
+            Self::X64 => "ProgramW6432Dir",
+            Self::X86 => "ProgramFilesDir (x86)",
+            Self::Arm => "ProgramFilesDir (Arm)",
+        }
+    }
+}
+
+/// Finds the program files directory for an architecture, trying each source until one works.
+///
+/// Unlike `Report::collect()`, which shows what every source says, this gives a single answer. The
+/// sources are tried in this order, which is the order of preference described in the crate
+/// documentation:
+///
+/// 1. The known folder, such as `FOLDERID_ProgramFilesX64`, through `SHGetKnownFolderPath`. This
+///    is what Microsoft recommends, but a 32-bit process does not see `FOLDERID_ProgramFilesX64`,
+///    and there is no known folder for the 32-bit ARM directory.
+///
+/// 2. The environment variable, such as `ProgramW6432`. This is usually available, but an ancestor
+///    process may have removed it from the environment.
+///
+/// 3. The registry value, such as `ProgramW6432Dir`, in `CURRENT_VERSION_SUBKEY`, through the
+///    default registry view. This is not affected by the environment.
+///
+/// Only sources whose meaning does not depend on the architecture of the calling process are
+/// used, so the result is the same for 32-bit and 64-bit callers. If every source fails, the error
+/// says why each one did. That is expected on a 32-bit system, which has no 64-bit directory, and
+/// on systems other than ARM64 for the 32-bit ARM directory.
+#[cfg(windows)]
+pub fn resolve_program_files(arch: Arch) -> Result<PathBuf, ResolveError> {
+    resolve_program_files_with(&RealOs, arch)
+}
+
+/// Like `resolve_program_files()`, but looks up each source through `os`, which may be a
+/// simulated system, such as a `FakeOs`.
+pub fn resolve_program_files_with(os: &impl OsFacade, arch: Arch) -> Result<PathBuf, ResolveError> {
+    let mut attempts = Vec::new();
+
+    if let Some((symbol, id)) = arch.known_folder() {
+        match os.known_folder(id) {
+            Ok(path) => return Ok(path.into()),
+            Err(e) => attempts.push((format!("known folder {symbol}"), e.into())),
+        }
+    }
+
+    let name = arch.environment_variable();
+    match os.env_var(name) {
+        Some(path) => return Ok(path.into()),
+        None => attempts.push((
+            format!("environment variable {name}"),
+            VarError::NotPresent.into(),
+        )),
+    }
+
+    let name = arch.registry_value();
+    match os.registry_value(name) {
+        Ok(path) => return Ok(path.into()),
+        Err(e) => attempts.push((format!("registry value {name}"), EntryError::from(e))),
+    }
+
+    Err(ResolveError {
+        directory: arch.description(),
+        attempts,
+    })
+}
@@ -0,0 +1,336 @@
+//! Saving reports as JSON, reading them back, and comparing them.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use windows::Win32::System::SystemInformation::GetLocalTime;
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{FOLDERID_LocalAppData, KF_FLAG_DEFAULT};
+
+use crate::analysis::{Change, Expectation};
+use crate::error::{CodeKind, EntryError, Error};
+#[cfg(windows)]
+use crate::known_folder::resolve_guid;
+#[cfg(windows)]
+use crate::platform::{computer_name, os_version};
+#[cfg(windows)]
+use crate::render::SCHEMA_VERSION;
+use crate::report::Lookup;
+#[cfg(windows)]
+use crate::report::Report;
+#[cfg(windows)]
+use crate::token::current_account_name;
+
+/// Where and when a saved report was made, stored with it by `save()`.
+///
+/// Each field but the timestamp is `None` if it could not be found.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// The host name of the computer, as given by `computer_name()`.
+    pub hostname: Option<String>,
+
+    /// The user this process ran as, as `DOMAIN\name`.
+    pub user: Option<String>,
+
+    /// The version and build of Windows, as given by `os_version()`.
+    pub os_version: Option<String>,
+
+    /// The local date and time the report was saved, as `YYYY-MM-DD HH:MM:SS`.
+    pub timestamp: String,
+}
+
+impl SnapshotInfo {
+    /// Finds out where and when this is, to save with a report.
+    #[cfg(windows)]
+    pub fn capture() -> Self {
+        let now = unsafe { GetLocalTime() };
+        Self {
+            hostname: computer_name(),
+            user: current_account_name(),
+            os_version: os_version(None),
+            timestamp: format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond
+            ),
+        }
+    }
+}
+
+/// A report as saved by `save()`: the report's sections, and a `snapshot` object describing it.
+#[cfg(windows)]
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    schema_version: u32,
+
+    snapshot: &'a SnapshotInfo,
+
+    #[serde(flatten)]
+    report: &'a Report,
+}
+
+/// Saves a report as JSON, with where and when it was made, as found by `SnapshotInfo::capture()`.
+///
+/// The report is saved as `--format json` would output it, with its `schema_version`, and with a
+/// `snapshot` object added, so it can be compared by `diff()` to reports saved either way.
+#[cfg(windows)]
+pub fn save(report: &Report, path: &Path) -> Result<SnapshotInfo, Error> {
+    let info = SnapshotInfo::capture();
+    let snapshot = Snapshot {
+        schema_version: SCHEMA_VERSION,
+        snapshot: &info,
+        report,
+    };
+    let json = serde_json::to_string_pretty(&snapshot)?;
+    fs::write(path, json + "\n").map_err(|e| Error::SavedReport(path.to_path_buf(), e))?;
+    Ok(info)
+}
+
+/// The directory snapshots are kept in: `pfdirs` in the `FOLDERID_LocalAppData` known folder.
+#[cfg(windows)]
+pub fn history_directory() -> Result<PathBuf, Error> {
+    let local_app_data = resolve_guid(FOLDERID_LocalAppData, KF_FLAG_DEFAULT)?;
+    Ok(Path::new(&local_app_data).join("pfdirs"))
+}
+
+/// Saves a report as a snapshot in `history_directory()`, which is created if needed, giving the
+/// path it was saved to.
+///
+/// Snapshots are named by the local date and time, to the millisecond, such as
+/// `20261016-140322-123.json`, so they sort in the order they were taken.
+#[cfg(windows)]
+pub fn save_to_history(report: &Report) -> Result<PathBuf, Error> {
+    let directory = history_directory()?;
+    fs::create_dir_all(&directory).map_err(|e| Error::SavedReport(directory.clone(), e))?;
+
+    let now = unsafe { GetLocalTime() };
+    let name = format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}.json",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond, now.wMilliseconds
+    );
+    let path = directory.join(name);
+    save(report, &path)?;
+    Ok(path)
+}
+
+/// A snapshot in `history_directory()`, as listed by `history()`.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    /// The name of the snapshot, which is its file name without `.json`.
+    pub name: String,
+
+    pub path: PathBuf,
+
+    /// Where and when the snapshot was made, or `None` if the file could not be read as a
+    /// snapshot, such as if it was saved without `save()`.
+    pub info: Option<SnapshotInfo>,
+}
+
+/// Reads where and when a saved report was made, if it was saved by `save()`.
+#[cfg(windows)]
+fn read_info(path: &Path) -> Option<SnapshotInfo> {
+    let text = fs::read_to_string(path).ok()?;
+    let report: serde_json::Value = serde_json::from_str(&text).ok()?;
+    serde_json::from_value(report.get("snapshot")?.clone()).ok()
+}
+
+/// Lists the snapshots in `history_directory()`, oldest first.
+///
+/// If no snapshot has been saved, the directory may not exist, and the list is empty.
+#[cfg(windows)]
+pub fn history() -> Result<Vec<HistoryEntry>, Error> {
+    let directory = history_directory()?;
+    let entries = match fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::SavedReport(directory, e)),
+    };
+
+    let mut snapshots: Vec<HistoryEntry> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_owned();
+            let info = read_info(&path);
+            Some(HistoryEntry { name, path, info })
+        })
+        .collect();
+    snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(snapshots)
+}
+
+/// Reads expected values from a baseline file, for `check_expectations()`.
+///
+/// The file is TOML, with a key for each entry's name and its expected path as a string, such as
+/// `ProgramW6432 = 'C:\Program Files'`. Names with spaces, such as registry value names, must be
+/// quoted, as in `"ProgramFilesDir (x86)" = 'C:\Program Files (x86)'`. Literal strings, in single
+/// quotes, are easiest for paths, since backslashes are not escapes in them. The expectations are
+/// given in order by name.
+pub fn load_expectations(path: &Path) -> Result<Vec<Expectation>, Error> {
+    let text =
+        fs::read_to_string(path).map_err(|e| Error::Baseline(path.to_path_buf(), e.to_string()))?;
+    let table: BTreeMap<String, String> =
+        toml::from_str(&text).map_err(|e| Error::Baseline(path.to_path_buf(), e.to_string()))?;
+
+    Ok(table
+        .into_iter()
+        .map(|(name, value)| Expectation { name, value })
+        .collect())
+}
+
+/// Finds a saved report by the name of a snapshot in `history_directory()`, or else as a path.
+#[cfg(windows)]
+pub fn find_snapshot(name_or_path: &str) -> Result<PathBuf, Error> {
+    let in_history = history_directory()?.join(format!("{name_or_path}.json"));
+    if in_history.is_file() {
+        Ok(in_history)
+    } else {
+        Ok(PathBuf::from(name_or_path))
+    }
+}
+
+/// The entries of a section, by heading, as read from a JSON report by `sections_from_json()`.
+pub type JsonSection = (String, Vec<(String, Lookup)>);
+
+/// Gets the entries of each section of a report output as JSON by this program.
+///
+/// The report is read as JSON values rather than as a `Report`, so this works with output from
+/// builds or versions of this program that do not report exactly the same sections. Sections that
+/// are lists of registry views or keys, each with its own section, or of sections that timed out,
+/// are read as those sections. The `schema_version`, the `snapshot` object added by `save()`, and
+/// the `findings` array added by `render::json()`, are not sections, and are skipped.
+pub fn sections_from_json(report: &serde_json::Value) -> Vec<JsonSection> {
+    let mut sections = Vec::new();
+    for (key, value) in report.as_object().into_iter().flatten() {
+        if key == "schema_version" || key == "snapshot" || key == "findings" {
+            continue;
+        }
+        let values = match value.as_array() {
+            Some(views) => views
+                .iter()
+                .map(|view| {
+                    ["keys", "values"]
+                        .into_iter()
+                        .map(|field| &view[field])
+                        .find(|section| section.is_object())
+                        .unwrap_or(view)
+                })
+                .collect(),
+            None => vec![value],
+        };
+        for section in values {
+            let heading = section["heading"].as_str().unwrap_or_default().to_owned();
+            let entries = section["entries"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|entry| {
+                    let name = entry["name"].as_str().unwrap_or_default().to_owned();
+                    let lookup = Lookup {
+                        path: entry["path"].as_str().map(String::from),
+                        escaped_path: entry["escaped_path"].as_str().map(String::from),
+                        // A saved report has the path only as text.
+                        os_path: entry["path"].as_str().map(OsString::from),
+                        error: entry["error"]["message"].as_str().map(|message| {
+                            let error = &entry["error"];
+                            // Reports saved before the kind of code was recorded only have
+                            // HRESULTs as negative codes, so take anything else as Win32.
+                            let code = error["code"].as_i64().map(|code| {
+                                let kind =
+                                    error["code_kind"].as_str().and_then(CodeKind::from_name);
+                                let kind = kind.unwrap_or(if code < 0 {
+                                    CodeKind::Hresult
+                                } else {
+                                    CodeKind::Win32
+                                });
+                                (code, kind)
+                            });
+                            EntryError::new(message.to_owned(), code, error["discrepancy"] == true)
+                        }),
+                    };
+                    (name, lookup)
+                })
+                .collect();
+            sections.push((heading, entries));
+        }
+    }
+    sections
+}
+
+/// Reads a report saved as JSON, such as with `--format json`, as described on
+/// `sections_from_json()`.
+pub fn load_sections(path: &Path) -> Result<Vec<JsonSection>, Error> {
+    let text = fs::read_to_string(path).map_err(|e| Error::SavedReport(path.to_path_buf(), e))?;
+    let report: serde_json::Value = serde_json::from_str(&text)?;
+    Ok(sections_from_json(&report))
+}
+
+/// Finds the entries whose path or error differs between two reports read by `load_sections()`.
+///
+/// This is like `changes()`, but compares in both directions, since neither report is a partial
+/// update of the other. An entry, or a whole section, present in only one report is a change,
+/// shown as `[absent]` in the report that lacks it. Entries are matched by the heading of their
+/// section and their name, so their order does not matter. Changes are given in the order of the
+/// sections and entries of `after`, followed by those only in `before`.
+pub fn diff_sections(before: &[JsonSection], after: &[JsonSection]) -> Vec<Change> {
+    let absent = || String::from("[absent]");
+    let find = |sections: &[JsonSection], heading: &str, name: &str| {
+        sections
+            .iter()
+            .filter(|(other_heading, _)| other_heading == heading)
+            .flat_map(|(_, entries)| entries)
+            .find(|(other_name, _)| other_name == name)
+            .map(|(_, lookup)| lookup.path_item())
+    };
+
+    let mut changes = Vec::new();
+
+    for (heading, entries) in after {
+        for (name, lookup) in entries {
+            let old = find(before, heading, name).unwrap_or_else(absent);
+            let new = lookup.path_item();
+            if old != new {
+                changes.push(Change {
+                    section: heading.clone(),
+                    name: name.clone(),
+                    before: old,
+                    after: new,
+                });
+            }
+        }
+    }
+
+    for (heading, entries) in before {
+        for (name, lookup) in entries {
+            if find(after, heading, name).is_none() {
+                changes.push(Change {
+                    section: heading.clone(),
+                    name: name.clone(),
+                    before: lookup.path_item(),
+                    after: absent(),
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+/// Compares two reports saved as JSON, as described on `diff_sections()`.
+///
+/// The reports may be of the same machine at different times, such as before and after an upgrade
+/// of Windows or a change of policy, or of two different machines.
+pub fn diff(before: &Path, after: &Path) -> Result<Vec<Change>, Error> {
+    Ok(diff_sections(
+        &load_sections(before)?,
+        &load_sections(after)?,
+    ))
+}
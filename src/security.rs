@@ -0,0 +1,274 @@
+//! Summarizing who owns, and who can modify, the program files directories.
+
+use core::ffi::c_void;
+use std::ffi::OsStr;
+
+use windows::core::{HSTRING, PWSTR};
+use windows::Win32::Foundation::{
+    LocalFree, ERROR_SUCCESS, GENERIC_ALL, GENERIC_EXECUTE, GENERIC_READ, GENERIC_WRITE, HLOCAL,
+};
+use windows::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, GetNamedSecurityInfoW, SE_FILE_OBJECT,
+};
+use windows::Win32::Security::{
+    GetAce, ACCESS_ALLOWED_ACE, ACE_HEADER, ACL, DACL_SECURITY_INFORMATION, INHERIT_ONLY_ACE,
+    OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID,
+};
+use windows::Win32::Storage::FileSystem::{
+    DELETE, FILE_ALL_ACCESS, FILE_APPEND_DATA, FILE_GENERIC_EXECUTE, FILE_GENERIC_READ,
+    FILE_GENERIC_WRITE, FILE_WRITE_DATA, WRITE_DAC, WRITE_OWNER,
+};
+use windows::Win32::System::SystemServices::{ACCESS_ALLOWED_ACE_TYPE, ACCESS_DENIED_ACE_TYPE};
+
+use crate::token::sid_account_name;
+
+/// The trustees whose rights are summarized, with their SIDs, and whether each of them stands for
+/// all, or nearly all, users of the system.
+///
+/// A program files directory that any of the broad trustees can write to lets any user replace
+/// the programs installed in it, which other users, including administrators, then run.
+pub const SUMMARIZED_TRUSTEES: [(&str, &str, bool); 6] = [
+    (
+        "TrustedInstaller",
+        "S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464",
+        false,
+    ),
+    ("SYSTEM", "S-1-5-18", false),
+    ("Administrators", "S-1-5-32-544", false),
+    ("Users", "S-1-5-32-545", true),
+    ("Authenticated Users", "S-1-5-11", true),
+    ("Everyone", "S-1-1-0", true),
+];
+
+/// The rights that let a trustee change what is in a directory, or who may: adding files or
+/// subdirectories, deleting the directory, or changing its permissions or owner.
+const WRITE_RIGHTS: u32 =
+    FILE_WRITE_DATA.0 | FILE_APPEND_DATA.0 | DELETE.0 | WRITE_DAC.0 | WRITE_OWNER.0;
+
+/// How much access a trustee has to a directory, summarized as the Security tab in Explorer does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessLevel {
+    /// No access is granted.
+    None,
+
+    /// The directory can be listed and its attributes and permissions read.
+    Read,
+
+    /// As with `Read`, and the directory can also be traversed.
+    ReadAndExecute,
+
+    /// As with `ReadAndExecute`, and the directory can also be written to and deleted.
+    Modify,
+
+    /// All access, including changing the directory's permissions and owner.
+    FullControl,
+
+    /// Some other combination of rights, with this access mask.
+    Special(u32),
+}
+
+impl AccessLevel {
+    /// Summarizes an access mask, whose generic rights have been mapped to file rights.
+    fn from_mask(mask: u32) -> Self {
+        let read = FILE_GENERIC_READ.0;
+        let read_and_execute = read | FILE_GENERIC_EXECUTE.0;
+        let modify = read_and_execute | FILE_GENERIC_WRITE.0 | DELETE.0;
+
+        if mask & FILE_ALL_ACCESS.0 == FILE_ALL_ACCESS.0 {
+            Self::FullControl
+        } else if mask == modify {
+            Self::Modify
+        } else if mask == read_and_execute {
+            Self::ReadAndExecute
+        } else if mask == read {
+            Self::Read
+        } else if mask == 0 {
+            Self::None
+        } else {
+            Self::Special(mask)
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Self::None => String::from("none"),
+            Self::Read => String::from("read"),
+            Self::ReadAndExecute => String::from("read & execute"),
+            Self::Modify => String::from("modify"),
+            Self::FullControl => String::from("full control"),
+            Self::Special(mask) => format!("special ({mask:#010X})"),
+        }
+    }
+}
+
+/// A trustee's access to a directory, found by `directory_security()`.
+#[derive(Clone, Copy, Debug)]
+pub struct TrusteeRights {
+    /// The trustee's name, from `SUMMARIZED_TRUSTEES`.
+    pub trustee: &'static str,
+
+    /// Whether the trustee stands for all, or nearly all, users.
+    pub broad: bool,
+
+    /// The rights the trustee is granted and not denied, as an access mask of file rights.
+    pub mask: u32,
+}
+
+impl TrusteeRights {
+    pub fn level(&self) -> AccessLevel {
+        AccessLevel::from_mask(self.mask)
+    }
+
+    /// Whether the trustee can change what is in the directory, or who may.
+    pub fn can_write(&self) -> bool {
+        self.mask & WRITE_RIGHTS != 0
+    }
+}
+
+/// Who owns a directory, and a summary of who can do what to it, found by `directory_security()`.
+#[derive(Clone, Debug)]
+pub struct DirectorySecurity {
+    /// The owner, as `DOMAIN\name`, or as a SID string if its account name could not be found.
+    pub owner: String,
+
+    /// The owner's SID, in its string form, or an empty string if it could not be converted.
+    pub owner_sid: String,
+
+    /// The access of each trustee in `SUMMARIZED_TRUSTEES` that has any.
+    pub rights: Vec<TrusteeRights>,
+}
+
+impl DirectorySecurity {
+    /// The broad trustees that can write to the directory, such as `Users` or `Everyone`.
+    ///
+    /// If there are any, the directory is world-writable, which is a security misconfiguration.
+    pub fn world_writers(&self) -> Vec<&'static str> {
+        self.rights
+            .iter()
+            .filter(|rights| rights.broad && rights.can_write())
+            .map(|rights| rights.trustee)
+            .collect()
+    }
+}
+
+/// Gives the string form of a SID, such as `S-1-5-32-544`.
+fn sid_string(sid: PSID) -> Option<String> {
+    let mut string = PWSTR::null();
+    unsafe { ConvertSidToStringSidW(sid, &mut string) }.ok()?;
+    let result = unsafe { string.to_string() }.ok();
+    let _ = unsafe { LocalFree(HLOCAL(string.0.cast())) };
+    result
+}
+
+/// Maps the generic rights in an access mask to the file rights they stand for.
+fn map_generic_rights(mask: u32) -> u32 {
+    [
+        (GENERIC_ALL.0, FILE_ALL_ACCESS.0),
+        (GENERIC_READ.0, FILE_GENERIC_READ.0),
+        (GENERIC_WRITE.0, FILE_GENERIC_WRITE.0),
+        (GENERIC_EXECUTE.0, FILE_GENERIC_EXECUTE.0),
+    ]
+    .into_iter()
+    .filter(|&(generic, _)| mask & generic != 0)
+    .fold(
+        mask & !(GENERIC_ALL.0 | GENERIC_READ.0 | GENERIC_WRITE.0 | GENERIC_EXECUTE.0),
+        |mask, (_, specific)| mask | specific,
+    )
+}
+
+/// Summarizes the rights each of `SUMMARIZED_TRUSTEES` has from a DACL.
+///
+/// Only access allowed and access denied entries that apply to the directory itself are counted,
+/// not those that are only inherited by its contents. Each trustee's rights are those some entry
+/// for it allows and no entry for it denies. This does not account for rights a trustee gets as a
+/// member of another group, or for the order of the entries, so it is a summary, not an access
+/// check. A null DACL grants everyone full control.
+fn summarize_dacl(dacl: *const ACL) -> Vec<TrusteeRights> {
+    let mut allowed = [0_u32; SUMMARIZED_TRUSTEES.len()];
+    let mut denied = [0_u32; SUMMARIZED_TRUSTEES.len()];
+
+    if dacl.is_null() {
+        allowed = [FILE_ALL_ACCESS.0; SUMMARIZED_TRUSTEES.len()];
+    } else {
+        for index in 0..u32::from(unsafe { (*dacl).AceCount }) {
+            let mut ace: *mut c_void = std::ptr::null_mut();
+            if unsafe { GetAce(dacl, index, &mut ace) }.is_err() {
+                continue;
+            }
+            let header = unsafe { *ace.cast::<ACE_HEADER>() };
+            if u32::from(header.AceFlags) & INHERIT_ONLY_ACE.0 != 0 {
+                continue;
+            }
+            let totals = match u32::from(header.AceType) {
+                ACCESS_ALLOWED_ACE_TYPE => &mut allowed,
+                ACCESS_DENIED_ACE_TYPE => &mut denied,
+                _ => continue,
+            };
+
+            // Access denied entries have the same layout as access allowed entries.
+            let ace = ace.cast::<ACCESS_ALLOWED_ACE>();
+            let mask = map_generic_rights(unsafe { (*ace).Mask });
+            let sid = PSID(unsafe { std::ptr::addr_of_mut!((*ace).SidStart) }.cast());
+            let Some(sid) = sid_string(sid) else {
+                continue;
+            };
+            if let Some(position) = SUMMARIZED_TRUSTEES
+                .iter()
+                .position(|&(_, trustee_sid, _)| trustee_sid == sid)
+            {
+                totals[position] |= mask;
+            }
+        }
+    }
+
+    SUMMARIZED_TRUSTEES
+        .into_iter()
+        .zip(allowed.into_iter().zip(denied))
+        .map(|((trustee, _, broad), (allowed, denied))| TrusteeRights {
+            trustee,
+            broad,
+            mask: allowed & !denied,
+        })
+        .filter(|rights| rights.mask != 0)
+        .collect()
+}
+
+/// Finds who owns the directory at `path`, and summarizes who can do what to it.
+///
+/// The owner and DACL are read with [`GetNamedSecurityInfoW`][gnsi], and the DACL is summarized as
+/// described on `summarize_dacl()`. The program files directories are usually owned by
+/// `TrustedInstaller`, which has full control, with `SYSTEM` and `Administrators` able to modify
+/// them and `Users` only able to read and execute. If any broad trustee, such as `Users` or
+/// `Everyone`, can write to one, as `DirectorySecurity::world_writers()` finds, any user can
+/// replace the programs in it, which is a real misconfiguration on some machines.
+///
+/// [gnsi]: https://learn.microsoft.com/en-us/windows/win32/api/aclapi/nf-aclapi-getnamedsecurityinfow
+pub fn directory_security(path: &OsStr) -> Result<DirectorySecurity, windows::core::Error> {
+    let mut owner = PSID::default();
+    let mut dacl: *mut ACL = std::ptr::null_mut();
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+    let status = unsafe {
+        GetNamedSecurityInfoW(
+            &HSTRING::from(path),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION,
+            Some(&mut owner),
+            None,
+            Some(&mut dacl),
+            None,
+            &mut descriptor,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return Err(status.to_hresult().into());
+    }
+
+    let owner_sid = sid_string(owner).unwrap_or_default();
+    let security = DirectorySecurity {
+        owner: sid_account_name(owner).unwrap_or_else(|| owner_sid.clone()),
+        owner_sid,
+        rights: summarize_dacl(dacl),
+    };
+    let _ = unsafe { LocalFree(HLOCAL(descriptor.0)) };
+    Ok(security)
+}
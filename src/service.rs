@@ -0,0 +1,160 @@
+//! Detecting whether this process runs as a service, without a loaded user profile, which is why
+//! per-user known folders such as `FOLDERID_UserProgramFiles` may be unavailable to it.
+
+use std::ffi::OsString;
+use std::fs;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStringExt;
+
+#[cfg(windows)]
+use windows::core::PWSTR;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+#[cfg(windows)]
+use windows::Win32::Security::{
+    WinLocalServiceSid, WinLocalSystemSid, WinNetworkServiceSid, TOKEN_QUERY,
+};
+#[cfg(windows)]
+use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+#[cfg(windows)]
+use windows::Win32::System::Threading::{GetCurrentProcess, GetCurrentProcessId, OpenProcessToken};
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{
+    FOLDERID_UserProgramFiles, GetUserProfileDirectoryW, KF_FLAG_DONT_VERIFY,
+};
+
+#[cfg(windows)]
+use crate::error::Error;
+#[cfg(windows)]
+use crate::known_folder::resolve_guid_as;
+#[cfg(windows)]
+use crate::token::{current_well_known_user, Token};
+
+/// A built-in account that services run as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceAccount {
+    LocalSystem,
+    LocalService,
+    NetworkService,
+}
+
+impl ServiceAccount {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::LocalSystem => "LocalSystem",
+            Self::LocalService => "LocalService",
+            Self::NetworkService => "NetworkService",
+        }
+    }
+}
+
+/// The context of this process with respect to services and user profiles.
+///
+/// Per-user known folders are found through the user's profile, so a process without one, as is
+/// common for services, cannot look them up. `FOLDERID_UserProgramFiles` then fails with an error
+/// that does not say why. This gathers what is needed to say why.
+#[derive(Clone, Debug)]
+pub struct ServiceContext {
+    /// The Remote Desktop Services session this process is in, or `None` if it could not be
+    /// found. Services run in session 0, which no interactive user logs on to.
+    pub session_id: Option<u32>,
+
+    /// The built-in service account this process runs as, or `None` if it runs as another
+    /// account or that could not be determined.
+    pub account: Option<ServiceAccount>,
+
+    /// The root directory of this process's user profile, as given by `GetUserProfileDirectoryW`,
+    /// or `None` if it could not be found.
+    pub profile_directory: Option<OsString>,
+}
+
+/// Finds the session this process is in.
+#[cfg(windows)]
+fn session_id() -> Option<u32> {
+    let mut session_id = 0;
+    unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session_id) }.ok()?;
+    Some(session_id)
+}
+
+/// Finds the root directory of the profile of the user this process runs as.
+#[cfg(windows)]
+fn profile_directory() -> Option<OsString> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }.ok()?;
+
+    let mut length = 0;
+    let _ = unsafe { GetUserProfileDirectoryW(token, PWSTR::null(), &mut length) };
+    let mut buffer = vec![0_u16; length as usize];
+    let result =
+        unsafe { GetUserProfileDirectoryW(token, PWSTR(buffer.as_mut_ptr()), &mut length) };
+    let _ = unsafe { CloseHandle(token) };
+    result.ok()?;
+
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Some(OsString::from_wide(&buffer[..end]))
+}
+
+impl ServiceContext {
+    /// Detects the session, service account, and profile directory of this process.
+    ///
+    /// The session is found with [`ProcessIdToSessionId`][pitsi]. The account is compared to the
+    /// well-known SIDs of `LocalSystem`, `LocalService`, and `NetworkService`, rather than by name,
+    /// since their names are localized. The profile directory is found with
+    /// [`GetUserProfileDirectoryW`][gupd].
+    ///
+    /// [pitsi]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-processidtosessionid
+    /// [gupd]: https://learn.microsoft.com/en-us/windows/win32/api/userenv/nf-userenv-getuserprofiledirectoryw
+    #[cfg(windows)]
+    pub fn detect() -> Self {
+        Self {
+            session_id: session_id(),
+            account: current_well_known_user(&[
+                (WinLocalSystemSid, ServiceAccount::LocalSystem),
+                (WinLocalServiceSid, ServiceAccount::LocalService),
+                (WinNetworkServiceSid, ServiceAccount::NetworkService),
+            ]),
+            profile_directory: profile_directory(),
+        }
+    }
+
+    /// Whether this process is in session 0, where services run.
+    pub fn is_session_zero(&self) -> bool {
+        self.session_id == Some(0)
+    }
+
+    /// Whether this process has a user profile, which is taken to be so if its profile directory
+    /// is known and exists.
+    ///
+    /// A service account's profile, such as `LocalSystem`'s under `System32\config`, exists but
+    /// is not a full profile, so this is true for it. That it runs as such an account is given
+    /// separately.
+    pub fn has_profile(&self) -> bool {
+        self.profile_directory
+            .as_ref()
+            .is_some_and(|path| fs::metadata(path).is_ok_and(|metadata| metadata.is_dir()))
+    }
+
+    /// Whether anything about this context would explain per-user known folders being unavailable.
+    pub fn is_service_like(&self) -> bool {
+        self.is_session_zero() || self.account.is_some() || !self.has_profile()
+    }
+}
+
+/// Looks up `FOLDERID_UserProgramFiles` in the default user's profile, as a retry when it is
+/// unavailable to this process.
+///
+/// This passes `Token::DEFAULT_USER`, so the folder is looked up in the profile that new users'
+/// profiles are copied from, which is always present. That profile usually has no
+/// `Programs` directory, so this also passes [`KF_FLAG_DONT_VERIFY`][kff], giving the path the
+/// folder would have without checking that it exists. The result is where a user would get their
+/// per-user program files directory, not where any service has one.
+///
+/// [kff]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/ne-shlobj_core-known_folder_flag
+#[cfg(windows)]
+pub fn user_program_files_as_default_user() -> Result<OsString, Error> {
+    resolve_guid_as(
+        FOLDERID_UserProgramFiles,
+        KF_FLAG_DONT_VERIFY,
+        &Token::DEFAULT_USER,
+    )
+}
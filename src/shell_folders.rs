@@ -0,0 +1,181 @@
+//! Looking up program files folder locations in the legacy shell folders registry keys.
+
+#[cfg(windows)]
+use std::io;
+
+#[cfg(windows)]
+use winreg::{
+    enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_QUERY_VALUE},
+    RegKey, HKEY,
+};
+
+#[cfg(windows)]
+use crate::registry::{open_hive, read_string_value, REGISTRY_VIEWS};
+#[cfg(windows)]
+use crate::report::{Lookup, Section, ShellFolderEntry, ShellFolderKey};
+
+/// The registry key, under both `HKEY_LOCAL_MACHINE` and `HKEY_CURRENT_USER`, that holds the
+/// shell folders keys.
+pub const EXPLORER_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Explorer";
+
+/// The shell folders keys to query, each with a caption, its hive, and its name in
+/// `EXPLORER_SUBKEY`.
+#[cfg(windows)]
+pub const SHELL_FOLDERS_KEYS: [(&str, HKEY, &str); 4] = [
+    (
+        r"HKLM\...\Shell Folders",
+        HKEY_LOCAL_MACHINE,
+        "Shell Folders",
+    ),
+    (
+        r"HKLM\...\User Shell Folders",
+        HKEY_LOCAL_MACHINE,
+        "User Shell Folders",
+    ),
+    (
+        r"HKCU\...\Shell Folders",
+        HKEY_CURRENT_USER,
+        "Shell Folders",
+    ),
+    (
+        r"HKCU\...\User Shell Folders",
+        HKEY_CURRENT_USER,
+        "User Shell Folders",
+    ),
+];
+
+/// The values, in each shell folders key, that may hold program files folder locations, with the
+/// symbolic names of the known folders they are for.
+///
+/// Folders that have no legacy name, which includes all the program files folders, are listed in
+/// these keys under their known folder IDs, in registry format.
+pub const PROGRAM_FILES_SHELL_FOLDER_VALUES: [(&str, &str); 4] = [
+    (
+        "{905E63B6-C1BF-494E-B29C-65B732D3D21A}",
+        "FOLDERID_ProgramFiles",
+    ),
+    (
+        "{6D809377-6AF0-444B-8957-A3773F02200E}",
+        "FOLDERID_ProgramFilesX64",
+    ),
+    (
+        "{7C5A40EF-A0FB-4BFC-874A-C0F2E0B9FA8E}",
+        "FOLDERID_ProgramFilesX86",
+    ),
+    (
+        "{5CD7AEE2-2219-4A67-B85D-6C9CE15660CB}",
+        "FOLDERID_UserProgramFiles",
+    ),
+];
+
+/// Reads a value from a shell folders key as an entry, as described on `read_string_value()`.
+#[cfg(windows)]
+fn read_value(key: &RegKey, value_name: &str, known_folder: &'static str) -> ShellFolderEntry {
+    let (value_type, raw, lookup) = read_string_value(key, value_name);
+
+    ShellFolderEntry {
+        name: value_name.to_owned(),
+        known_folder,
+        value_type,
+        raw,
+        lookup,
+    }
+}
+
+/// Report *program files* folder locations from one shell folders key, through one registry view.
+///
+/// If the key cannot be opened, every entry has that error, rather than the whole report failing,
+/// because a user may legitimately lack one of these keys. If `computer` is given, the key is read
+/// from that computer's registry, as described on `open_hive()`. There, the `HKEY_CURRENT_USER`
+/// keys cannot be opened.
+#[cfg(windows)]
+pub fn report_shell_folders_key(
+    computer: Option<&str>,
+    caption: &'static str,
+    hive: HKEY,
+    key_name: &str,
+    view: &'static str,
+    flag_for_view: u32,
+) -> ShellFolderKey {
+    let heading = match computer {
+        Some(computer) => {
+            format!("Shell folders registry values on {computer} - {caption} - with {view}")
+        }
+        None => format!("Shell folders registry values - {caption} - with {view}"),
+    };
+    let path = format!(r"{EXPLORER_SUBKEY}\{key_name}");
+
+    let key = open_hive(computer, hive)
+        .and_then(|hive| hive.open_subkey_with_flags(path, KEY_QUERY_VALUE | flag_for_view));
+
+    shell_folders_key(caption, view, heading, key)
+}
+
+/// Reads the *program files* values from a shell folders key that has been opened, or gives the
+/// error opening it for each, as described on `report_shell_folders_key()`.
+#[cfg(windows)]
+pub(crate) fn shell_folders_key(
+    caption: &'static str,
+    view: &'static str,
+    heading: String,
+    key: io::Result<RegKey>,
+) -> ShellFolderKey {
+    let entries = match key {
+        Ok(key) => PROGRAM_FILES_SHELL_FOLDER_VALUES
+            .into_iter()
+            .map(|(value_name, known_folder)| read_value(&key, value_name, known_folder))
+            .collect(),
+        Err(e) => {
+            let lookup = Lookup::new(Err::<String, _>(e));
+            PROGRAM_FILES_SHELL_FOLDER_VALUES
+                .into_iter()
+                .map(|(value_name, known_folder)| ShellFolderEntry {
+                    name: value_name.to_owned(),
+                    known_folder,
+                    value_type: None,
+                    raw: None,
+                    lookup: lookup.clone(),
+                })
+                .collect()
+        }
+    };
+
+    ShellFolderKey {
+        key: caption,
+        view,
+        values: Section::new(heading, entries),
+    }
+}
+
+/// Report *program files* folder locations from the legacy shell folders registry keys.
+///
+/// Before known folders, the Shell recorded the locations of special folders in the `Shell Folders`
+/// and `User Shell Folders` subkeys of `EXPLORER_SUBKEY`, in both `HKEY_LOCAL_MACHINE` and
+/// `HKEY_CURRENT_USER`. `User Shell Folders` holds the locations as set, often as `REG_EXPAND_SZ`
+/// values that refer to environment variables, while `Shell Folders` holds expanded copies kept
+/// for compatibility. Microsoft documents neither as a supported way to find folders, but folder
+/// redirection and policy write to them, and old software still reads them, so a value here that
+/// disagrees with the known folders is worth knowing about.
+///
+/// The program files folders are not usually listed in these keys. Each value that is present is
+/// shown with its type, its raw data, and, as its path, its expansion, as found by
+/// `read_string_value()`.
+///
+/// This queries every key through every view in `REGISTRY_VIEWS`. `HKEY_CURRENT_USER` is shared
+/// between views on current versions of Windows, so its keys usually look the same in every view.
+///
+/// If `computer` is given, the keys are read from that computer, as described on
+/// `report_shell_folders_key()`.
+#[cfg(windows)]
+pub fn report_shell_folders(computer: Option<&str>) -> Vec<ShellFolderKey> {
+    SHELL_FOLDERS_KEYS
+        .into_iter()
+        .flat_map(|(caption, hive, key_name)| {
+            REGISTRY_VIEWS
+                .into_iter()
+                .map(move |(view, flag_for_view)| {
+                    report_shell_folders_key(computer, caption, hive, key_name, view, flag_for_view)
+                })
+        })
+        .collect()
+}
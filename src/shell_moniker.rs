@@ -0,0 +1,137 @@
+//! Looking up program files folder locations through `shell:` monikers in the Shell namespace.
+
+#[cfg(windows)]
+use std::ffi::OsString;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStringExt;
+
+#[cfg(all(windows, feature = "com"))]
+use windows::core::HSTRING;
+#[cfg(windows)]
+use windows::Win32::Foundation::{E_FAIL, MAX_PATH};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::System::Com::IBindCtx;
+#[cfg(windows)]
+use windows::Win32::UI::Shell::Common::ITEMIDLIST;
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::UI::Shell::SHParseDisplayName;
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{ILFree, SHGetPathFromIDListW};
+use windows_core::GUID;
+
+#[cfg(windows)]
+use crate::error::Error;
+use crate::known_folder::{
+    FOLDERID_ProgramFiles, FOLDERID_ProgramFilesX64, FOLDERID_ProgramFilesX86,
+    FOLDERID_UserProgramFiles,
+};
+#[cfg(all(windows, feature = "com"))]
+use crate::known_folder_definition::ComApartment;
+#[cfg(all(windows, feature = "com"))]
+use crate::report::{Lookup, Row, Section};
+
+/// The *program files* `shell:` monikers, with the names and IDs of the known folders they name.
+///
+/// A moniker's name after `shell:` is the canonical name of a known folder, as registered in its
+/// definition, which is not always its `FOLDERID_` name without the prefix, though it is for these.
+pub const PROGRAM_FILES_SHELL_MONIKERS: [(&str, &str, GUID); 4] = [
+    (
+        "shell:ProgramFiles",
+        "FOLDERID_ProgramFiles",
+        FOLDERID_ProgramFiles,
+    ),
+    (
+        "shell:ProgramFilesX64",
+        "FOLDERID_ProgramFilesX64",
+        FOLDERID_ProgramFilesX64,
+    ),
+    (
+        "shell:ProgramFilesX86",
+        "FOLDERID_ProgramFilesX86",
+        FOLDERID_ProgramFilesX86,
+    ),
+    (
+        "shell:UserProgramFiles",
+        "FOLDERID_UserProgramFiles",
+        FOLDERID_UserProgramFiles,
+    ),
+];
+
+/// Finds the name of the known folder a `shell:` moniker in `PROGRAM_FILES_SHELL_MONIKERS` names.
+pub fn known_folder_of_moniker(moniker: &str) -> Option<&'static str> {
+    PROGRAM_FILES_SHELL_MONIKERS
+        .into_iter()
+        .find_map(|(name, known_folder, _)| (name == moniker).then_some(known_folder))
+}
+
+/// Gets the filesystem path of an item in the Shell namespace, and frees its ID list.
+///
+/// This calls [`SHGetPathFromIDListW`][shgpfidlw], which, like `SHGetFolderPathW`, gives at most
+/// `MAX_PATH` characters. It fails for items that are not in the filesystem, without saying why,
+/// so such failures are reported as `E_FAIL`.
+///
+/// [shgpfidlw]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetpathfromidlistw
+#[cfg(windows)]
+pub(crate) fn path_from_id_list(pidl: *mut ITEMIDLIST) -> Result<OsString, Error> {
+    let mut buffer = [0u16; MAX_PATH as usize];
+    let succeeded = unsafe { SHGetPathFromIDListW(pidl, &mut buffer) }.as_bool();
+    unsafe { ILFree(Some(pidl)) };
+
+    if !succeeded {
+        return Err(Error::ShellNamespace(E_FAIL.into()));
+    }
+    let length = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Ok(OsString::from_wide(&buffer[..length]))
+}
+
+/// Parses a `shell:` moniker to an item in the Shell namespace and gets its path.
+#[cfg(all(windows, feature = "com"))]
+fn resolve_moniker(moniker: &str) -> Result<OsString, Error> {
+    let mut pidl = std::ptr::null_mut();
+    unsafe {
+        SHParseDisplayName(
+            &HSTRING::from(moniker),
+            None::<&IBindCtx>,
+            &mut pidl,
+            0,
+            None,
+        )
+    }
+    .map_err(Error::ShellNamespace)?;
+
+    path_from_id_list(pidl)
+}
+
+/// Report *program files* folder locations by resolving `shell:` monikers.
+///
+/// A `shell:` moniker, such as `shell:ProgramFiles`, names a known folder by its canonical name. It
+/// can be typed into the File Explorer address bar or the Run dialog, and is accepted by Shell
+/// functions that parse display names, so scripts and some software use it to find folders. This
+/// parses each moniker with [`SHParseDisplayName`][shpdn] into an item ID list, which is how the
+/// Shell identifies an item in its namespace, and then gets the item's filesystem path as
+/// described on `path_from_id_list()`.
+///
+/// This goes through the Shell namespace rather than asking for a path directly, so it can in
+/// principle differ from `SHGetKnownFolderPath`, such as if the Shell namespace has been extended
+/// or a moniker is taken by a folder registered with the same canonical name. It is expected to
+/// agree with the known folders. Like them, `shell:ProgramFilesX64` is unavailable in 32-bit
+/// processes.
+///
+/// Parsing display names requires COM, which is initialized on this thread for the duration. So
+/// this is only available with the `com` feature.
+///
+/// [shpdn]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shparsedisplayname
+#[cfg(all(windows, feature = "com"))]
+pub fn report_shell_monikers() -> Result<Section<Row>, Error> {
+    let _apartment = ComApartment::initialize()?;
+
+    let entries = PROGRAM_FILES_SHELL_MONIKERS
+        .into_iter()
+        .map(|(moniker, _, _)| Row {
+            name: moniker.to_owned(),
+            lookup: Lookup::new(resolve_moniker(moniker)),
+        })
+        .collect();
+
+    Ok(Section::new("Shell monikers", entries))
+}
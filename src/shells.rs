@@ -0,0 +1,132 @@
+//! Looking up program files folder locations as other shells report them, by running `cmd` and
+//! PowerShell.
+
+use std::env::VarError;
+use std::io;
+use std::process::Command;
+
+use crate::error::{EntryError, Error};
+use crate::report::{Lookup, Row, Section};
+
+/// The environment variable references that `cmd` is asked to expand, with `echo`.
+pub const CMD_REFERENCES: [&str; 4] = [
+    "%ProgramFiles%",
+    "%ProgramFiles(Arm)%",
+    "%ProgramFiles(x86)%",
+    "%ProgramW6432%",
+];
+
+/// The PowerShell expressions that PowerShell is asked to evaluate, each giving a path.
+pub const POWERSHELL_EXPRESSIONS: [&str; 3] = [
+    "[Environment]::GetFolderPath('ProgramFiles')",
+    "[Environment]::GetFolderPath('ProgramFilesX86')",
+    "$env:ProgramW6432",
+];
+
+/// Runs a command and gives its standard output, or why it could not be run or did not succeed.
+///
+/// The output is decoded as UTF-8, with anything that is not valid UTF-8 replaced.
+fn run(command: &mut Command) -> Result<String, Error> {
+    let output = command.output().map_err(Error::Child)?;
+
+    if !output.status.success() {
+        let message = format!(
+            "{} failed with {}",
+            command.get_program().to_string_lossy(),
+            output.status
+        );
+        return Err(Error::Child(io::Error::other(message)));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Asks `cmd` to expand a reference, treating it as not found if it is left unexpanded, which is
+/// what `echo` does with a reference to a variable that is not set.
+fn cmd_entry(reference: &str) -> Row {
+    let lookup = match run(Command::new("cmd").args(["/d", "/c", "echo", reference])) {
+        Ok(output) if output.trim_end() == reference => {
+            Lookup::new(Err::<String, _>(VarError::NotPresent))
+        }
+        Ok(output) => Lookup::new(Ok::<_, Error>(output.trim_end().to_owned())),
+        Err(e) => Lookup::new(Err::<String, _>(e)),
+    };
+
+    Row {
+        name: reference.to_owned(),
+        lookup,
+    }
+}
+
+/// Gets the path on a line of PowerShell's output, treating an empty line as not found.
+fn nth_path(output: &str, line: usize) -> Result<String, EntryError> {
+    match output.lines().nth(line).map(str::trim_end) {
+        Some(path) if !path.is_empty() => Ok(path.to_owned()),
+        _ => Err(io::Error::other("PowerShell gave nothing").into()),
+    }
+}
+
+/// Asks PowerShell to evaluate each of `POWERSHELL_EXPRESSIONS`, all in one run, since PowerShell
+/// is slow to start. An expression that gives an empty string is treated as not found.
+fn powershell_entries() -> Vec<Row> {
+    // Converting each result to a string outputs a line even for $null, so the lines line up.
+    let script = POWERSHELL_EXPRESSIONS
+        .map(|expression| format!("[string]({expression})"))
+        .join(";");
+    let output = run(Command::new("powershell").args([
+        "-NoLogo",
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        &script,
+    ]))
+    .map_err(EntryError::from);
+
+    POWERSHELL_EXPRESSIONS
+        .into_iter()
+        .enumerate()
+        .map(|(i, expression)| {
+            let value = output.clone().and_then(|output| nth_path(&output, i));
+            Row {
+                name: expression.to_owned(),
+                lookup: Lookup::new(value),
+            }
+        })
+        .collect()
+}
+
+/// Report *program files* folder locations as `cmd` and PowerShell report them, by running them.
+///
+/// When someone says a path is different in PowerShell, the usual reason is that the shell does
+/// not see what this process sees. This runs each shell as a child process and shows what it
+/// gives, so that can be checked directly:
+///
+/// - `cmd /d /c echo` is run for each of `CMD_REFERENCES`, the way a batch file would expand them.
+///   `/d` keeps `AutoRun` commands, which could change the environment, from running.
+///
+/// - `powershell -NoProfile` is run once to evaluate all of `POWERSHELL_EXPRESSIONS`. These are
+///   the [`Environment.GetFolderPath`][gfp] calls that .NET programs use, which look up the
+///   corresponding CSIDLs, and the environment variable that PowerShell scripts usually read for
+///   the 64-bit directory. The profile is skipped, so that it cannot change the results.
+///
+/// The shells are started with this process's environment, so they report differently than this
+/// process does only if they look something up in a different way. They are started by name, so
+/// under WOW64 a 32-bit process starts the 32-bit `cmd` and PowerShell, because `System32` is
+/// redirected, and they see the 32-bit directory as `ProgramFiles` just as this process does. A
+/// shell the user starts from the Start menu on 64-bit Windows is 64-bit, so it sees the 64-bit
+/// directory instead. That difference is the commonest cause of such reports.
+///
+/// Output is decoded as UTF-8, so characters outside ASCII in paths may be shown incorrectly, as
+/// the shells write in the console code page. If a shell cannot be run, or fails, each of its
+/// entries has that error.
+///
+/// [gfp]: https://learn.microsoft.com/en-us/dotnet/api/system.environment.getfolderpath
+pub fn report_shells() -> Section<Row> {
+    let entries = CMD_REFERENCES
+        .into_iter()
+        .map(cmd_entry)
+        .chain(powershell_entries())
+        .collect();
+
+    Section::new("Other shells (cmd and PowerShell)", entries)
+}
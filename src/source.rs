@@ -0,0 +1,678 @@
+//! The sources the report is collected from, each looked up in the same way through `Source`.
+//!
+//! `Report::collect_from()` looks up every source in `SOURCES` that is selected, each on its own
+//! thread, and adds what each finds to the report. So a new source, such as one that queries WMI,
+//! is added by implementing `Source` for it, listing it in `SOURCES`, and giving it a field of
+//! `Report` and a `SourceKind` for its sections, without changing how the report is collected,
+//! shown, filtered, or serialized.
+
+use crate::analysis::SourceKind;
+use crate::arm::report_arm_program_files;
+use crate::csidl::{report_csidl, report_csidl_locations};
+use crate::environment::{
+    report_environment_registry, report_environment_variables, report_fresh_environment,
+    ENVIRONMENT_REGISTRY_KEYS,
+};
+use crate::error::Error;
+use crate::known_folder::report_known_folders;
+#[cfg(feature = "com")]
+use crate::known_folder_definition::report_known_folder_definitions;
+#[cfg(feature = "com")]
+use crate::msi::report_msi;
+#[cfg(feature = "com")]
+use crate::package::report_package;
+use crate::per_user::report_per_user_installs;
+use crate::platform::{Machine, Platform};
+#[cfg(feature = "com")]
+use crate::policy::report_policy;
+use crate::registry::{
+    literal_paths, report_all_registry_views, Backend, LITERAL_PATH_VIEWS, REGISTRY_VIEWS,
+};
+use crate::report::{Report, Selection};
+use crate::shell_folders::{report_shell_folders, SHELL_FOLDERS_KEYS};
+#[cfg(feature = "com")]
+use crate::shell_moniker::report_shell_monikers;
+use crate::shells::report_shells;
+use crate::system_directories::report_system_directories;
+use crate::virtualization::report_virtualization;
+#[cfg(feature = "com")]
+use crate::wmi::report_wmi;
+
+/// What to look up, which is the same for every source, as given to `Report::collect_from()`.
+#[derive(Clone, Debug)]
+pub struct Query {
+    /// The subkey of `HKEY_LOCAL_MACHINE` the registry sources read, as described on
+    /// `report_all_registry_views()`.
+    pub registry_subkey: String,
+
+    /// The computer whose registry is read, or `None` for this computer.
+    pub computer: Option<String>,
+
+    /// Which library registry values are read with.
+    pub backend: Backend,
+}
+
+impl Query {
+    /// Whether the sources are to be looked up on this computer, which all sources can do.
+    pub fn is_local(&self) -> bool {
+        self.computer.is_none()
+    }
+
+    /// Gives a heading, saying which computer it is for if it is not this one, in the way the
+    /// sources that read the registry remotely do.
+    fn heading(&self, title: &str, details: &str) -> String {
+        match &self.computer {
+            Some(computer) => format!("{title} on {computer}{details}"),
+            None => format!("{title}{details}"),
+        }
+    }
+}
+
+/// What a source found, ready to be added to a report.
+///
+/// This holds what was found together with how to add it to the report, so that sources whose
+/// results are of different types can all be collected in the same way.
+pub struct Collected(Box<dyn FnOnce(&mut Report) + Send>);
+
+impl Collected {
+    /// Makes what a source found from how to add it to a report.
+    pub fn new(add: impl FnOnce(&mut Report) + Send + 'static) -> Self {
+        Self(Box::new(add))
+    }
+
+    /// Adds what was found to the report.
+    pub fn add_to(self, report: &mut Report) {
+        (self.0)(report)
+    }
+}
+
+/// A source of information about program files folder locations, giving one or more sections of
+/// the report.
+pub trait Source: Sync {
+    /// The name of the source, which is the name of its field in `Report` and thus its key in
+    /// structured output.
+    fn name(&self) -> &'static str;
+
+    /// Whether to look up this source, given the sources selected and what is to be looked up.
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool;
+
+    /// The kind and heading of each section this source gives, such as to stand in for them if
+    /// the source takes too long.
+    fn sections(&self, query: &Query) -> Vec<(SourceKind, String)>;
+
+    /// Looks up the source.
+    ///
+    /// An error means no section could be given at all. Errors looking up particular entries are
+    /// instead kept in those entries.
+    fn collect(&self, query: &Query) -> Result<Collected, Error>;
+}
+
+/// The sources the report is collected from, in the order they are collected.
+///
+/// Known folder definitions, shell monikers, WMI, and policy are looked up through COM objects, and
+/// Windows Installer and the package APIs through components the resolver has no need of, so they
+/// are only listed with the `com` feature. Without it, their sections are always absent.
+pub static SOURCES: &[&dyn Source] = &[
+    &EnvironmentVariables,
+    &KnownFolders,
+    #[cfg(feature = "com")]
+    &KnownFolderDefinitions,
+    #[cfg(feature = "com")]
+    &ShellMonikers,
+    &Csidls,
+    &CsidlLocations,
+    &RegistryViews,
+    &ShellFolders,
+    &EnvironmentRegistry,
+    &FreshEnvironment,
+    #[cfg(feature = "com")]
+    &Wmi,
+    &Shells,
+    #[cfg(feature = "com")]
+    &Msi,
+    &SystemDirectories,
+    &ArmProgramFiles,
+    &PerUserInstalls,
+    &Virtualization,
+    #[cfg(feature = "com")]
+    &Package,
+    #[cfg(feature = "com")]
+    &Policy,
+];
+
+/// Environment variables, as described on `report_environment_variables()`.
+pub struct EnvironmentVariables;
+
+impl Source for EnvironmentVariables {
+    fn name(&self) -> &'static str {
+        "environment_variables"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.environment_variables
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::EnvironmentVariable,
+            String::from("Relevant environment variables"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_environment_variables();
+        Ok(Collected::new(|report| {
+            report.environment_variables = Some(section);
+        }))
+    }
+}
+
+/// Known folders, as described on `report_known_folders()`.
+pub struct KnownFolders;
+
+impl Source for KnownFolders {
+    fn name(&self) -> &'static str {
+        "known_folders"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.known_folders
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::KnownFolder,
+            String::from("Relevant known folders"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_known_folders()?;
+        Ok(Collected::new(|report| {
+            report.known_folders = Some(section)
+        }))
+    }
+}
+
+/// Known folder definitions, as described on `report_known_folder_definitions()`.
+#[cfg(feature = "com")]
+pub struct KnownFolderDefinitions;
+
+#[cfg(feature = "com")]
+impl Source for KnownFolderDefinitions {
+    fn name(&self) -> &'static str {
+        "known_folder_definitions"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.known_folder_definitions
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::KnownFolderDefinition,
+            String::from("Relevant known folder definitions"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_known_folder_definitions()?;
+        Ok(Collected::new(|report| {
+            report.known_folder_definitions = Some(section);
+        }))
+    }
+}
+
+/// Shell monikers, as described on `report_shell_monikers()`, which are looked up only on this
+/// computer.
+#[cfg(feature = "com")]
+pub struct ShellMonikers;
+
+#[cfg(feature = "com")]
+impl Source for ShellMonikers {
+    fn name(&self) -> &'static str {
+        "shell_monikers"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.shell_monikers && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(SourceKind::ShellMoniker, String::from("Shell monikers"))]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_shell_monikers()?;
+        Ok(Collected::new(|report| {
+            report.shell_monikers = Some(section)
+        }))
+    }
+}
+
+/// CSIDLs, as described on `report_csidl()`.
+pub struct Csidls;
+
+impl Source for Csidls {
+    fn name(&self) -> &'static str {
+        "csidls"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.csidls
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(SourceKind::Csidl, String::from("Relevant CSIDLs"))]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_csidl()?;
+        Ok(Collected::new(|report| report.csidls = Some(section)))
+    }
+}
+
+/// CSIDLs through `SHGetFolderLocation`, as described on `report_csidl_locations()`, which are
+/// selected along with CSIDLs.
+pub struct CsidlLocations;
+
+impl Source for CsidlLocations {
+    fn name(&self) -> &'static str {
+        "csidl_locations"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.csidls
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::CsidlLocation,
+            String::from("Relevant CSIDLs - through SHGetFolderLocation"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_csidl_locations()?;
+        Ok(Collected::new(|report| {
+            report.csidl_locations = Some(section)
+        }))
+    }
+}
+
+/// The registry key with the `ProgramFilesDir` values, in each registry view, as described on
+/// `report_all_registry_views()`.
+pub struct RegistryViews;
+
+impl Source for RegistryViews {
+    fn name(&self) -> &'static str {
+        "registry"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.registry
+    }
+
+    fn sections(&self, query: &Query) -> Vec<(SourceKind, String)> {
+        let literal_views = literal_paths(&query.registry_subkey)
+            .map_or(&[][..], |_| &LITERAL_PATH_VIEWS[..])
+            .iter()
+            .map(|&(view, _)| view);
+
+        REGISTRY_VIEWS
+            .into_iter()
+            .map(|(view, _)| view)
+            .chain(literal_views)
+            .map(|view| {
+                let heading = query.heading("Relevant registry keys", &format!(" - with {view}"));
+                (SourceKind::Registry(view), heading)
+            })
+            .collect()
+    }
+
+    fn collect(&self, query: &Query) -> Result<Collected, Error> {
+        let views = report_all_registry_views(
+            query.computer.as_deref(),
+            query.backend,
+            &query.registry_subkey,
+        )?;
+        Ok(Collected::new(|report| report.registry = views))
+    }
+}
+
+/// The Shell Folders and User Shell Folders registry keys, as described on
+/// `report_shell_folders()`.
+pub struct ShellFolders;
+
+impl Source for ShellFolders {
+    fn name(&self) -> &'static str {
+        "shell_folders"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.shell_folders
+    }
+
+    fn sections(&self, query: &Query) -> Vec<(SourceKind, String)> {
+        SHELL_FOLDERS_KEYS
+            .into_iter()
+            .flat_map(|(key, _, _)| {
+                REGISTRY_VIEWS.into_iter().map(move |(view, _)| {
+                    let details = format!(" - {key} - with {view}");
+                    let heading = query.heading("Shell folders registry values", &details);
+                    (SourceKind::ShellFolders { key, view }, heading)
+                })
+            })
+            .collect()
+    }
+
+    fn collect(&self, query: &Query) -> Result<Collected, Error> {
+        let keys = report_shell_folders(query.computer.as_deref());
+        Ok(Collected::new(|report| report.shell_folders = keys))
+    }
+}
+
+/// The registry keys new environments are built from, as described on
+/// `report_environment_registry()`.
+pub struct EnvironmentRegistry;
+
+impl Source for EnvironmentRegistry {
+    fn name(&self) -> &'static str {
+        "environment_registry"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.environment_registry
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        ENVIRONMENT_REGISTRY_KEYS
+            .into_iter()
+            .map(|(key, _, _)| {
+                (
+                    SourceKind::EnvironmentRegistry(key),
+                    format!("Stored environment variables - {key}"),
+                )
+            })
+            .collect()
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let keys = report_environment_registry();
+        Ok(Collected::new(|report| report.environment_registry = keys))
+    }
+}
+
+/// A freshly created environment block, as described on `report_fresh_environment()`.
+pub struct FreshEnvironment;
+
+impl Source for FreshEnvironment {
+    fn name(&self) -> &'static str {
+        "fresh_environment"
+    }
+
+    fn is_selected(&self, selection: Selection, _query: &Query) -> bool {
+        selection.fresh_environment
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::FreshEnvironment,
+            String::from("Fresh environment block (CreateEnvironmentBlock)"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_fresh_environment();
+        Ok(Collected::new(|report| {
+            report.fresh_environment = Some(section);
+        }))
+    }
+}
+
+/// WMI, as described on `report_wmi()`, which is looked up only on this computer.
+#[cfg(feature = "com")]
+pub struct Wmi;
+
+#[cfg(feature = "com")]
+impl Source for Wmi {
+    fn name(&self) -> &'static str {
+        "wmi"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.wmi && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(SourceKind::Wmi, String::from("Relevant WMI properties"))]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_wmi()?;
+        Ok(Collected::new(|report| report.wmi = Some(section)))
+    }
+}
+
+/// `cmd` and PowerShell, as described on `report_shells()`, which are run only on this computer.
+pub struct Shells;
+
+impl Source for Shells {
+    fn name(&self) -> &'static str {
+        "shells"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.shells && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::Shells,
+            String::from("Other shells (cmd and PowerShell)"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_shells();
+        Ok(Collected::new(|report| report.shells = Some(section)))
+    }
+}
+
+/// Windows Installer folder properties, as described on `report_msi()`, which are looked up only
+/// on this computer.
+#[cfg(feature = "com")]
+pub struct Msi;
+
+#[cfg(feature = "com")]
+impl Source for Msi {
+    fn name(&self) -> &'static str {
+        "msi"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.msi && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::Msi,
+            String::from("Windows Installer folder properties"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_msi();
+        Ok(Collected::new(|report| report.msi = Some(section)))
+    }
+}
+
+/// The Windows and system directories, as described on `report_system_directories()`, which are
+/// looked up only on this computer.
+pub struct SystemDirectories;
+
+impl Source for SystemDirectories {
+    fn name(&self) -> &'static str {
+        "system_directories"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.system_directories && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::SystemDirectories,
+            String::from("System directories"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_system_directories();
+        Ok(Collected::new(|report| {
+            report.system_directories = Some(section);
+        }))
+    }
+}
+
+/// The 32-bit ARM program files directory, as described on `report_arm_program_files()`, which is
+/// selected along with known folders or CSIDLs, only on this computer, and only on ARM64 systems.
+pub struct ArmProgramFiles;
+
+impl Source for ArmProgramFiles {
+    fn name(&self) -> &'static str {
+        "arm_program_files"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        (selection.known_folders || selection.csidls)
+            && query.is_local()
+            && Platform::detect().native == Machine::Arm64
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::ArmProgramFiles,
+            String::from("ARM program files, which has no known folder or CSIDL"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_arm_program_files();
+        Ok(Collected::new(|report| {
+            report.arm_program_files = Some(section);
+        }))
+    }
+}
+
+/// The other places per-user installs go, as described on `report_per_user_installs()`, which are
+/// selected along with known folders, only on this computer.
+pub struct PerUserInstalls;
+
+impl Source for PerUserInstalls {
+    fn name(&self) -> &'static str {
+        "per_user_installs"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.known_folders && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::PerUserInstalls,
+            String::from("Per-user installs"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_per_user_installs();
+        Ok(Collected::new(|report| {
+            report.per_user_installs = Some(section);
+        }))
+    }
+}
+
+/// Whether UAC virtualization may redirect writes, as described on `report_virtualization()`,
+/// which is selected along with known folders, only on this computer.
+pub struct Virtualization;
+
+impl Source for Virtualization {
+    fn name(&self) -> &'static str {
+        "virtualization"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.known_folders && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::Virtualization,
+            String::from("UAC virtualization"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_virtualization();
+        Ok(Collected::new(|report| {
+            report.virtualization = Some(section);
+        }))
+    }
+}
+
+/// Whether this process is packaged or in an AppContainer, as described on `report_package()`,
+/// which is selected along with known folders, only on this computer.
+#[cfg(feature = "com")]
+pub struct Package;
+
+#[cfg(feature = "com")]
+impl Source for Package {
+    fn name(&self) -> &'static str {
+        "package"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.known_folders && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::Package,
+            String::from("Package and AppContainer"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_package();
+        Ok(Collected::new(|report| report.package = Some(section)))
+    }
+}
+
+/// Policy and folder redirection, as described on `report_policy()`, which is selected along with
+/// known folders, only on this computer.
+#[cfg(feature = "com")]
+pub struct Policy;
+
+#[cfg(feature = "com")]
+impl Source for Policy {
+    fn name(&self) -> &'static str {
+        "policy"
+    }
+
+    fn is_selected(&self, selection: Selection, query: &Query) -> bool {
+        selection.known_folders && query.is_local()
+    }
+
+    fn sections(&self, _query: &Query) -> Vec<(SourceKind, String)> {
+        vec![(
+            SourceKind::Policy,
+            String::from("Policy and folder redirection"),
+        )]
+    }
+
+    fn collect(&self, _query: &Query) -> Result<Collected, Error> {
+        let section = report_policy()?;
+        Ok(Collected::new(|report| report.policy = Some(section)))
+    }
+}
@@ -0,0 +1,135 @@
+//! Looking up the Windows and system directories, which show the context of WOW64 file system
+//! redirection.
+
+#[cfg(windows)]
+use std::ffi::OsString;
+#[cfg(windows)]
+use std::fs;
+#[cfg(windows)]
+use std::io;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStringExt;
+
+#[cfg(windows)]
+use windows::Win32::System::SystemInformation::{
+    GetSystemDirectoryW, GetSystemWow64Directory2W, GetWindowsDirectoryW, IMAGE_FILE_MACHINE,
+    IMAGE_FILE_MACHINE_ARMNT, IMAGE_FILE_MACHINE_I386,
+};
+
+#[cfg(windows)]
+use crate::registry::expand;
+#[cfg(windows)]
+use crate::report::{Lookup, Row, Section};
+
+/// The name of the entry for the Windows directory.
+pub const WINDOWS_DIRECTORY: &str = "GetWindowsDirectoryW";
+
+/// The name of the entry for the system directory.
+pub const SYSTEM_DIRECTORY: &str = "GetSystemDirectoryW";
+
+/// The name of the entry for the system directory of 32-bit x86 programs on 64-bit Windows.
+pub const SYSTEM_WOW64_DIRECTORY: &str = "GetSystemWow64Directory2W (x86)";
+
+/// The name of the entry for the system directory of 32-bit ARM programs on ARM64 Windows.
+pub const SYSTEM_WOW64_ARM_DIRECTORY: &str = "GetSystemWow64Directory2W (ARM)";
+
+/// The path through which a 32-bit process under WOW64 reaches the real system directory.
+pub const SYSNATIVE: &str = r"%windir%\Sysnative";
+
+/// Calls a function that copies a path into a buffer, as `GetSystemDirectoryW` does, and gives the
+/// path.
+///
+/// Such a function, given a buffer too small, returns the size needed, including the terminating
+/// null. Given a big enough buffer, it returns the length of the path, not including the null. It
+/// returns 0 on failure, with the error available from `GetLastError`.
+#[cfg(windows)]
+fn path_from(get: impl Fn(Option<&mut [u16]>) -> u32) -> io::Result<OsString> {
+    let size = get(None);
+    if size == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut buffer = vec![0u16; size as usize];
+    let length = get(Some(&mut buffer));
+    if length == 0 || length >= size {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(OsString::from_wide(&buffer[..length as usize]))
+}
+
+/// Gets the system directory used by WOW64 for 32-bit programs of the given architecture.
+#[cfg(windows)]
+fn wow64_directory(machine: IMAGE_FILE_MACHINE) -> io::Result<OsString> {
+    path_from(|buffer| unsafe { GetSystemWow64Directory2W(buffer, machine) })
+}
+
+/// Expands `SYSNATIVE` and checks that it is a directory, as it is only to a 32-bit process under
+/// WOW64.
+#[cfg(windows)]
+fn sysnative() -> Lookup {
+    let path = match expand(SYSNATIVE) {
+        Ok(path) => path,
+        Err(e) => return Lookup::new(Err::<String, _>(e)),
+    };
+
+    match fs::metadata(&path) {
+        Ok(metadata) if metadata.is_dir() => Lookup::new(Ok::<_, io::Error>(path)),
+        Ok(_) => Lookup::new(Err::<String, _>(io::Error::from(
+            io::ErrorKind::NotADirectory,
+        ))),
+        Err(e) => Lookup::new(Err::<String, _>(e)),
+    }
+}
+
+/// Report the Windows and system directories, to show what WOW64 file system redirection does to
+/// this process.
+///
+/// A 32-bit process on 64-bit Windows runs under [WOW64][wow64], which redirects its access to the
+/// system directory, normally `C:\Windows\System32`, to the directory for 32-bit programs, normally
+/// `C:\Windows\SysWOW64`. This is the file system counterpart of the registry views and of the
+/// program files directory a 32-bit process sees as `ProgramFiles`, so it helps in interpreting
+/// what a 32-bit process reports. This section shows:
+///
+/// - [`GetWindowsDirectoryW`][gwd] and [`GetSystemDirectoryW`][gsd]. The system directory is
+///   given as `System32` even under WOW64, since redirection happens when it is accessed.
+///
+/// - [`GetSystemWow64Directory2W`][gswd2], for 32-bit x86 and for 32-bit ARM programs. These are
+///   what `System32` is redirected to, and are available to 64-bit processes too. On 32-bit
+///   Windows, there is no such directory. The one for ARM is only on ARM64 systems, and newer
+///   versions of Windows no longer have it, since they do not run 32-bit ARM programs.
+///
+/// - Whether `SYSNATIVE` is a directory. `Sysnative` is an alias, seen only by 32-bit processes
+///   under WOW64, through which they reach the real `System32` without redirection. So it is found
+///   exactly when this process is being redirected.
+///
+/// [wow64]: https://learn.microsoft.com/en-us/windows/win32/winprog64/file-system-redirector
+/// [gwd]: https://learn.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-getwindowsdirectoryw
+/// [gsd]: https://learn.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-getsystemdirectoryw
+/// [gswd2]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-getsystemwow64directory2w
+#[cfg(windows)]
+pub fn report_system_directories() -> Section<Row> {
+    let entries = vec![
+        Row {
+            name: WINDOWS_DIRECTORY.to_owned(),
+            lookup: Lookup::new(path_from(|buffer| unsafe { GetWindowsDirectoryW(buffer) })),
+        },
+        Row {
+            name: SYSTEM_DIRECTORY.to_owned(),
+            lookup: Lookup::new(path_from(|buffer| unsafe { GetSystemDirectoryW(buffer) })),
+        },
+        Row {
+            name: SYSTEM_WOW64_DIRECTORY.to_owned(),
+            lookup: Lookup::new(wow64_directory(IMAGE_FILE_MACHINE_I386)),
+        },
+        Row {
+            name: SYSTEM_WOW64_ARM_DIRECTORY.to_owned(),
+            lookup: Lookup::new(wow64_directory(IMAGE_FILE_MACHINE_ARMNT)),
+        },
+        Row {
+            name: SYSNATIVE.to_owned(),
+            lookup: sysnative(),
+        },
+    ];
+
+    Section::new("System directories", entries)
+}
@@ -0,0 +1,155 @@
+//! Measuring how long each way of looking up a program files folder location takes.
+
+use std::fmt::{self, Debug};
+use std::time::{Duration, Instant};
+
+use windows::Win32::UI::Shell::{KF_FLAG_DEFAULT, SHGFP_TYPE_CURRENT};
+
+use crate::csidl::{try_get_path_from_csidl, PROGRAM_FILES_CSIDLS};
+use crate::environment::PROGRAM_FILES_ENVIRONMENT_VARIABLES;
+use crate::known_folder::{get_known_folder_path_or_detailed_error, PROGRAM_FILES_KNOWN_FOLDERS};
+use crate::registry::{
+    open_current_version, CURRENT_VERSION_SUBKEY, PROGRAM_FILES_REGISTRY_VALUES,
+};
+use crate::token::Token;
+
+/// Makes a raw API call, logging it, with its arguments, its result or error, and how long it
+/// took, as a `tracing` event at the `TRACE` level.
+///
+/// This is for debugging how lookups behave on a particular system, where it shows exactly what
+/// was asked of Windows and what it answered, including the `HRESULT` or Win32 error code of any
+/// failure, as the `Debug` form of the error gives it. The arguments are only formatted if the
+/// event is enabled, so this costs little otherwise.
+pub(crate) fn traced<T: Debug, E: Debug>(
+    api: &'static str,
+    arguments: fmt::Arguments<'_>,
+    call: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let start = Instant::now();
+    let result = call();
+    let elapsed = start.elapsed();
+    match &result {
+        Ok(value) => tracing::trace!(api, %arguments, ?value, ?elapsed, "API call succeeded"),
+        Err(error) => tracing::trace!(api, %arguments, ?error, ?elapsed, "API call failed"),
+    }
+    result
+}
+
+/// One call that looks up one entry, as measured by `time_calls()`.
+struct Call {
+    /// The API the call is made through, such as `SHGetKnownFolderPath`.
+    api: &'static str,
+
+    /// The name of the entry looked up, such as `FOLDERID_ProgramFilesX64`.
+    name: &'static str,
+
+    /// Makes the call, giving whether it found a path.
+    run: Box<dyn Fn() -> bool>,
+}
+
+/// Every call `time_calls()` measures, one per entry of each source it covers.
+///
+/// Each call is made the way the source's `report_*` function makes it, but without the extra work
+/// those functions do, such as cross-checking known folders with the `known-folders` crate, so
+/// that only the API call itself, and converting its result, is measured. Registry calls open the
+/// key each time, since a program that looks up one value usually does.
+fn calls() -> Vec<Call> {
+    let environment = PROGRAM_FILES_ENVIRONMENT_VARIABLES.map(|name| Call {
+        api: "GetEnvironmentVariableW",
+        name,
+        run: Box::new(move || std::env::var_os(name).is_some()),
+    });
+
+    let known_folders = PROGRAM_FILES_KNOWN_FOLDERS.map(|(name, id)| Call {
+        api: "SHGetKnownFolderPath",
+        name,
+        run: Box::new(move || {
+            get_known_folder_path_or_detailed_error(id, KF_FLAG_DEFAULT, &Token::CURRENT).is_ok()
+        }),
+    });
+
+    let csidls = PROGRAM_FILES_CSIDLS.map(|(name, csidl, _)| Call {
+        api: "SHGetFolderPathW",
+        name,
+        run: Box::new(move || try_get_path_from_csidl(csidl, SHGFP_TYPE_CURRENT).is_ok()),
+    });
+
+    let registry = PROGRAM_FILES_REGISTRY_VALUES.map(|name| Call {
+        api: "RegOpenKeyExW + RegQueryValueExW",
+        name,
+        run: Box::new(move || {
+            open_current_version(None, CURRENT_VERSION_SUBKEY, 0)
+                .and_then(|key| key.get_raw_value(name))
+                .is_ok()
+        }),
+    });
+
+    environment
+        .into_iter()
+        .chain(known_folders)
+        .chain(csidls)
+        .chain(registry)
+        .collect()
+}
+
+/// How long one call took, over some number of repetitions, found by `time_calls()`.
+#[derive(Clone, Debug)]
+pub struct Timing {
+    /// The API the call is made through, such as `SHGetKnownFolderPath`.
+    pub api: &'static str,
+
+    /// The name of the entry looked up, such as `FOLDERID_ProgramFilesX64`.
+    pub name: &'static str,
+
+    /// Whether the call found a path, the last time it was made.
+    pub found: bool,
+
+    pub min: Duration,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+/// Measures how long each way of looking up each entry takes, repeating each call `repetitions`
+/// times.
+///
+/// The calls are the environment variable lookups, `SHGetKnownFolderPath`, `SHGetFolderPathW`, and
+/// opening the registry key and reading a value from it, for each of the entries those sources
+/// look up. This helps choose the cheapest source that is reliable enough. A call that fails is
+/// timed too, since failing can be slower or faster than succeeding, and a failure is reported by
+/// `Timing::found`.
+///
+/// Each call is repeated before the next is made, so the first repetition may include one-time
+/// costs, such as loading a DLL or filling a cache, which `Timing::max` then shows. With one
+/// repetition, the minimum, mean, and maximum are all the same.
+pub fn time_calls(repetitions: u32) -> Vec<Timing> {
+    let repetitions = repetitions.max(1);
+
+    calls()
+        .into_iter()
+        .map(|call| {
+            let mut found = false;
+            let mut total = Duration::ZERO;
+            let mut min = Duration::MAX;
+            let mut max = Duration::ZERO;
+
+            for _ in 0..repetitions {
+                let start = Instant::now();
+                found = (call.run)();
+                let elapsed = start.elapsed();
+
+                total += elapsed;
+                min = min.min(elapsed);
+                max = max.max(elapsed);
+            }
+
+            Timing {
+                api: call.api,
+                name: call.name,
+                found,
+                min,
+                mean: total / repetitions,
+                max,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,260 @@
+//! Access tokens for looking up known folders as other security contexts would.
+
+use core::ffi::c_void;
+
+use windows::core::{HSTRING, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, LocalFree, HANDLE, HLOCAL};
+use windows::Win32::Security::Authorization::ConvertStringSidToSidW;
+use windows::Win32::Security::{
+    GetTokenInformation, IsWellKnownSid, LookupAccountSidW, TokenUser, PSID, SID_NAME_USE,
+    TOKEN_IMPERSONATE, TOKEN_INFORMATION_CLASS, TOKEN_QUERY, TOKEN_USER, WELL_KNOWN_SID_TYPE,
+};
+use windows::Win32::System::Threading::{
+    GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+use crate::ancestry::snapshot_processes;
+use crate::error::Error;
+
+/// Whose known folders to look up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Identity {
+    /// The user this process runs as. This is the usual case.
+    Current,
+
+    /// The default user, whose profile is copied to make the profiles of new users.
+    DefaultUser,
+
+    /// The user with this account name, either alone or as `DOMAIN\name`.
+    User(String),
+}
+
+impl Identity {
+    /// Describes whose known folders these are, for use in headings.
+    pub fn description(&self) -> String {
+        match self {
+            Self::Current => String::from("the current user"),
+            Self::DefaultUser => String::from("the default user"),
+            Self::User(name) => name.clone(),
+        }
+    }
+}
+
+/// An access token to pass to `SHGetKnownFolderPath`, which is closed when dropped if it was
+/// opened.
+#[derive(Debug)]
+pub struct Token {
+    handle: HANDLE,
+    owned: bool,
+}
+
+impl Token {
+    /// The null token, which means the user this process runs as.
+    pub const CURRENT: Self = Self {
+        handle: HANDLE(std::ptr::null_mut()),
+        owned: false,
+    };
+
+    /// The pseudo-token `-1`, which means the default user.
+    pub const DEFAULT_USER: Self = Self {
+        handle: HANDLE(-1_isize as *mut c_void),
+        owned: false,
+    };
+
+    /// Gets a token for the given identity, as described on `Token::for_user()` for a named user.
+    pub fn open(identity: &Identity) -> Result<Self, Error> {
+        match identity {
+            Identity::Current => Ok(Self::CURRENT),
+            Identity::DefaultUser => Ok(Self::DEFAULT_USER),
+            Identity::User(name) => Self::for_user(name),
+        }
+    }
+
+    /// Gets a token for the named user, by borrowing it from a process that user is running.
+    ///
+    /// Getting a token for a user directly, with `LogonUserW`, requires their password. Instead,
+    /// this looks for a running process, such as that user's `explorer.exe`, whose token is for a
+    /// user with that name, and opens its token with the `TOKEN_QUERY` and `TOKEN_IMPERSONATE`
+    /// access that [`SHGetKnownFolderPath`][shgkfp] requires. The name is compared without regard
+    /// to case, and may be given with or without its domain, as in `DOMAIN\name`.
+    ///
+    /// Opening another user's processes usually requires running as an administrator. If the user
+    /// is not logged on, or none of their processes can be opened, this fails with
+    /// [`Error::NoSuchUser`].
+    ///
+    /// [shgkfp]: https://learn.microsoft.com/en-us/windows/win32/api/shlobj_core/nf-shlobj_core-shgetknownfolderpath
+    pub fn for_user(name: &str) -> Result<Self, Error> {
+        let processes = snapshot_processes()?;
+
+        for &pid in processes.keys() {
+            let Some(handle) = open_process_token(pid) else {
+                continue;
+            };
+            let token = Self {
+                handle,
+                owned: true,
+            };
+            if token_account_name(handle).is_some_and(|account| names_match(&account, name)) {
+                return Ok(token);
+            }
+        }
+
+        Err(Error::NoSuchUser(name.to_owned()))
+    }
+
+    /// Whether this is the token of the user this process runs as.
+    pub fn is_current(&self) -> bool {
+        self.handle.0.is_null()
+    }
+
+    pub(crate) fn handle(&self) -> HANDLE {
+        self.handle
+    }
+}
+
+impl Drop for Token {
+    fn drop(&mut self) {
+        if self.owned {
+            let _ = unsafe { CloseHandle(self.handle) };
+        }
+    }
+}
+
+/// Opens the token of the process with the given ID for use with `SHGetKnownFolderPath`, if
+/// allowed.
+fn open_process_token(pid: u32) -> Option<HANDLE> {
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+
+    let mut token = HANDLE::default();
+    let result = unsafe { OpenProcessToken(process, TOKEN_QUERY | TOKEN_IMPERSONATE, &mut token) };
+    let _ = unsafe { CloseHandle(process) };
+    result.ok()?;
+
+    Some(token)
+}
+
+/// Queries the `TOKEN_USER` of a token, which holds the SID of the user it is for.
+///
+/// The SID follows the `TOKEN_USER` in the buffer, so the buffer must be kept as long as it is
+/// used.
+fn token_user(token: HANDLE) -> Option<Vec<u64>> {
+    let mut length = 0;
+    let _ = unsafe { GetTokenInformation(token, TokenUser, None, 0, &mut length) };
+
+    // Use u64 elements so the buffer is aligned enough to hold a TOKEN_USER.
+    let mut buffer = vec![0_u64; (length as usize).div_ceil(8)];
+    unsafe {
+        GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr().cast()),
+            length,
+            &mut length,
+        )
+    }
+    .ok()?;
+    Some(buffer)
+}
+
+/// Finds the account name of the user a token is for, as `DOMAIN\name`.
+fn token_account_name(token: HANDLE) -> Option<String> {
+    let buffer = token_user(token)?;
+    let sid = unsafe { &*buffer.as_ptr().cast::<TOKEN_USER>() }.User.Sid;
+    sid_account_name(sid)
+}
+
+/// Finds the account name for a SID, given in its string form, such as `S-1-5-18`, as
+/// `DOMAIN\name`.
+pub(crate) fn string_sid_account_name(string_sid: &str) -> Option<String> {
+    let mut sid = PSID::default();
+    unsafe { ConvertStringSidToSidW(&HSTRING::from(string_sid), &mut sid) }.ok()?;
+    let name = sid_account_name(sid);
+    let _ = unsafe { LocalFree(HLOCAL(sid.0)) };
+    name
+}
+
+/// Finds the account name for a SID, as `DOMAIN\name`.
+pub(crate) fn sid_account_name(sid: PSID) -> Option<String> {
+    let mut name = [0_u16; 256];
+    let mut domain = [0_u16; 256];
+    let mut name_length = name.len() as u32;
+    let mut domain_length = domain.len() as u32;
+    let mut sid_type = SID_NAME_USE::default();
+    unsafe {
+        LookupAccountSidW(
+            None,
+            sid,
+            PWSTR(name.as_mut_ptr()),
+            &mut name_length,
+            PWSTR(domain.as_mut_ptr()),
+            &mut domain_length,
+            &mut sid_type,
+        )
+    }
+    .ok()?;
+
+    let name = String::from_utf16(&name[..name_length as usize]).ok()?;
+    let domain = String::from_utf16(&domain[..domain_length as usize]).ok()?;
+    Some(format!(r"{domain}\{name}"))
+}
+
+/// Finds the account name of the user this process runs as, as `DOMAIN\name`.
+pub fn current_account_name() -> Option<String> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }.ok()?;
+    let name = token_account_name(token);
+    let _ = unsafe { CloseHandle(token) };
+    name
+}
+
+/// Finds which of the given well-known SIDs, if any, is that of the user this process runs as.
+pub(crate) fn current_well_known_user<T: Copy>(
+    candidates: &[(WELL_KNOWN_SID_TYPE, T)],
+) -> Option<T> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }.ok()?;
+    let buffer = token_user(token);
+    let _ = unsafe { CloseHandle(token) };
+    let buffer = buffer?;
+
+    let sid = unsafe { &*buffer.as_ptr().cast::<TOKEN_USER>() }.User.Sid;
+    candidates.iter().find_map(|&(kind, value)| {
+        unsafe { IsWellKnownSid(sid, kind) }
+            .as_bool()
+            .then_some(value)
+    })
+}
+
+/// Queries a flag, held in a `DWORD`, of this process's token.
+pub(crate) fn current_token_flag(class: TOKEN_INFORMATION_CLASS) -> Result<bool, Error> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }
+        .map_err(Error::Token)?;
+
+    let mut flag = 0_u32;
+    let mut length = 0;
+    let result = unsafe {
+        GetTokenInformation(
+            token,
+            class,
+            Some((&mut flag as *mut u32).cast()),
+            size_of::<u32>() as u32,
+            &mut length,
+        )
+    };
+    let _ = unsafe { CloseHandle(token) };
+    result.map_err(Error::Token)?;
+
+    Ok(flag != 0)
+}
+
+/// Whether an account name, as `DOMAIN\name`, is the one asked for, which may omit the domain.
+fn names_match(account: &str, wanted: &str) -> bool {
+    if wanted.contains('\\') {
+        account.eq_ignore_ascii_case(wanted)
+    } else {
+        account
+            .rsplit_once('\\')
+            .is_some_and(|(_, name)| name.eq_ignore_ascii_case(wanted))
+    }
+}
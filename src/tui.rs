@@ -0,0 +1,637 @@
+//! Exploring a report interactively in the terminal, for troubleshooting sessions.
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::analysis::{changes, explain_entry, SourceKind};
+use crate::clipboard::copy_to_clipboard;
+use crate::error::{CodeKind, EntryError, Error};
+use crate::platform::Platform;
+use crate::redact::{Redact, Redactor};
+use crate::report::{Entry, Report, Section, SectionRef, Selection};
+use crate::watch::{refresh_environment, timestamp, Trigger, Watcher};
+
+/// How long to wait for a key before checking whether the watcher has seen a change.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How many lines Page Up and Page Down move the selection.
+const PAGE: usize = 10;
+
+/// The keys, shown at the bottom of the screen.
+const HELP: &str = "\u{2191}\u{2193} move  Enter expand  +/- all  c copy path  r refresh  q quit";
+
+/// Shows the report in the terminal, with each section expandable to its entries, until the user
+/// quits.
+///
+/// The selected entry's details are shown beside the list: its path, and if it has none, its
+/// error's code, what kind of code it is, and the code's facility, then what the entry's source
+/// gives beyond the path, such as a known folder's ID and definition or a registry value's type and
+/// raw data, and last the explanation `explain_entry()` gives. The `c` key copies the selected
+/// entry's path to the clipboard.
+///
+/// `collect` looks up the sources it is given, which are the ones in `selection` at first and
+/// when the `r` key refreshes them all. While this runs, a `Watcher` on `registry_subkey` runs on
+/// its own thread, and each time it sees something that may have changed program files folder
+/// locations, the sources it may have affected, of those selected, are looked up again with
+/// `collect`, as in `pfdirs --watch`, so the report stays current. How many entries changed is
+/// shown with the time and the trigger in the status line.
+///
+/// If `redactor` is given, user names are masked in what is shown, as `--redact` does. `collect`
+/// should give reports without them masked, since the `c` key copies the real path.
+///
+/// The report is looked up once before the terminal is taken over, so if that fails, the error
+/// is returned without drawing anything.
+pub fn run(
+    selection: Selection,
+    registry_subkey: &str,
+    platform: Platform,
+    redactor: Option<&Redactor>,
+    mut collect: impl FnMut(Selection) -> Result<Report, Error>,
+) -> Result<(), Error> {
+    let real = collect(selection)?;
+    let triggers = watch_in_background(registry_subkey.to_owned());
+
+    let mut terminal = ratatui::try_init().map_err(Error::Terminal)?;
+    let mut app = App {
+        selection,
+        collect,
+        platform,
+        redactor: redactor.cloned(),
+        report: masked(redactor, &real),
+        real,
+        expanded: HashSet::new(),
+        list: ListState::default().with_selected(Some(0)),
+        status: String::from("Watching for changes."),
+    };
+    let result = app.run(&mut terminal, &triggers);
+    ratatui::restore();
+    result
+}
+
+/// Watches for changes on another thread, sending each trigger, or the error that stopped it.
+///
+/// The `Watcher` is made on that thread, since only the thread that makes it receives the
+/// broadcasts it watches for, and waiting for them blocks.
+fn watch_in_background(registry_subkey: String) -> Receiver<Result<Trigger, Error>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut watcher = match Watcher::new(&registry_subkey) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                let _ = sender.send(Err(e));
+                return;
+            }
+        };
+        loop {
+            let trigger = watcher.wait();
+            let failed = trigger.is_err();
+            if sender.send(trigger).is_err() || failed {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+/// Gives a copy of the report with user names masked by `redactor`, if there is one.
+fn masked(redactor: Option<&Redactor>, report: &Report) -> Report {
+    let mut report = report.clone();
+    if let Some(redactor) = redactor {
+        report.redact(redactor);
+    }
+    report
+}
+
+/// A line in the list: a section's heading or, if the section is expanded, one of its entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Item {
+    /// The section with this index in `Report::sections()`.
+    Section(usize),
+
+    /// The entry with the second index, in the section with the first.
+    Entry(usize, usize),
+}
+
+/// The state of the interactive view.
+struct App<C> {
+    selection: Selection,
+    collect: C,
+    platform: Platform,
+
+    /// What masks user names in what is shown, if `--redact` was passed.
+    redactor: Option<Redactor>,
+
+    /// The report as shown, with user names masked if there is a `redactor`.
+    report: Report,
+
+    /// The report before any user names were masked, which paths are copied from.
+    real: Report,
+
+    /// The headings of the expanded sections, which are kept when the report is looked up again.
+    expanded: HashSet<String>,
+
+    list: ListState,
+
+    /// What happened last, such as a change the watcher saw or a path that was copied.
+    status: String,
+}
+
+impl<C: FnMut(Selection) -> Result<Report, Error>> App<C> {
+    /// Draws and handles keys and changes, until the user quits.
+    fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        triggers: &Receiver<Result<Trigger, Error>>,
+    ) -> Result<(), Error> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(Error::Terminal)?;
+
+            if event::poll(POLL_INTERVAL).map_err(Error::Terminal)? {
+                if let Event::Key(key) = event::read().map_err(Error::Terminal)? {
+                    if key.kind == KeyEventKind::Press && !self.handle(key) {
+                        return Ok(());
+                    }
+                }
+            }
+
+            while let Ok(trigger) = triggers.try_recv() {
+                match trigger {
+                    Ok(trigger) => self.refresh(Some(&trigger)),
+                    Err(e) => self.status = format!("Stopped watching for changes: {e}"),
+                }
+            }
+        }
+    }
+
+    /// The lines of the list, in order.
+    fn items(&self) -> Vec<Item> {
+        let mut items = Vec::new();
+        for (index, section) in self.report.sections().iter().enumerate() {
+            items.push(Item::Section(index));
+            if self.expanded.contains(section.heading) {
+                items.extend((0..section.entries.len()).map(|entry| Item::Entry(index, entry)));
+            }
+        }
+        items
+    }
+
+    /// The selected line, if there are any lines.
+    fn selected(&self) -> Option<Item> {
+        self.items().get(self.list.selected()?).copied()
+    }
+
+    /// Handles a key, giving whether to keep going.
+    fn handle(&mut self, key: KeyEvent) -> bool {
+        let last = self.items().len().saturating_sub(1);
+        let current = self.list.selected().unwrap_or(0);
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.list.select(Some(current.saturating_sub(1))),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select(Some((current + 1).min(last))),
+            KeyCode::PageUp => self.list.select(Some(current.saturating_sub(PAGE))),
+            KeyCode::PageDown => self.list.select(Some((current + PAGE).min(last))),
+            KeyCode::Home | KeyCode::Char('g') => self.list.select(Some(0)),
+            KeyCode::End | KeyCode::Char('G') => self.list.select(Some(last)),
+            KeyCode::Enter | KeyCode::Char(' ') => self.toggle(None),
+            KeyCode::Right | KeyCode::Char('l') => self.toggle(Some(true)),
+            KeyCode::Left | KeyCode::Char('h') => self.toggle(Some(false)),
+            KeyCode::Char('+') => {
+                let headings = self
+                    .report
+                    .sections()
+                    .iter()
+                    .map(|s| s.heading.to_owned())
+                    .collect();
+                self.reselect(|app| app.expanded = headings);
+            }
+            KeyCode::Char('-') => self.reselect(|app| app.expanded.clear()),
+            KeyCode::Char('c') => self.copy(),
+            KeyCode::Char('r') => self.refresh(None),
+            _ => {}
+        }
+        true
+    }
+
+    /// Expands or collapses the selected section, or the section of the selected entry, keeping
+    /// that section selected. With `None`, a section is expanded if it was collapsed, and collapsed
+    /// if it was expanded.
+    fn toggle(&mut self, expand: Option<bool>) {
+        let index = match self.selected() {
+            Some(Item::Section(index) | Item::Entry(index, _)) => index,
+            None => return,
+        };
+        let heading = self.report.sections()[index].heading.to_owned();
+        let expand = expand.unwrap_or(!self.expanded.contains(&heading));
+
+        if expand {
+            self.expanded.insert(heading);
+        } else {
+            self.expanded.remove(&heading);
+        }
+        let line = self
+            .items()
+            .iter()
+            .position(|&item| item == Item::Section(index));
+        self.list.select(line);
+    }
+
+    /// Changes what is shown with `change`, keeping the same line selected, or if it is no longer
+    /// shown, the heading of its section.
+    fn reselect(&mut self, change: impl FnOnce(&mut Self)) {
+        let key = self.selected_key();
+        change(self);
+
+        let sections = self.report.sections();
+        let line = key.and_then(|(heading, name)| {
+            let index = sections.iter().position(|s| s.heading == heading)?;
+            let entry = name.and_then(|name| {
+                let entries = &sections[index].entries;
+                entries.iter().position(|entry| entry.name() == name)
+            });
+            let items = self.items();
+            let item = entry
+                .map(|entry| Item::Entry(index, entry))
+                .filter(|item| items.contains(item))
+                .unwrap_or(Item::Section(index));
+            items.iter().position(|&other| other == item)
+        });
+        self.list.select(Some(line.unwrap_or(0)));
+    }
+
+    /// The heading of the selected section, or of the selected entry's section with the entry's
+    /// name, which identify the line even when the report is looked up again.
+    fn selected_key(&self) -> Option<(String, Option<String>)> {
+        let sections = self.report.sections();
+        match self.selected()? {
+            Item::Section(index) => Some((sections[index].heading.to_owned(), None)),
+            Item::Entry(index, entry) => Some((
+                sections[index].heading.to_owned(),
+                Some(sections[index].entries[entry].name().to_owned()),
+            )),
+        }
+    }
+
+    /// Copies the selected entry's path to the clipboard, saying in the status line how it went.
+    ///
+    /// The real path is copied, as Windows gave it, even if user names are masked in what is shown.
+    fn copy(&mut self) {
+        let (sections, real_sections) = (self.report.sections(), self.real.sections());
+        let Some(Item::Entry(index, entry)) = self.selected() else {
+            self.status = String::from("No path to copy.");
+            return;
+        };
+        let (shown, real) = (
+            sections[index].entries[entry],
+            real_sections[index].entries[entry],
+        );
+        self.status = match shown.path().zip(real.os_path()) {
+            Some((shown, path)) => match copy_to_clipboard(path) {
+                Ok(()) => format!("Copied {shown}"),
+                Err(e) => format!("Could not copy to the clipboard: {e}"),
+            },
+            None => String::from("No path to copy."),
+        };
+    }
+
+    /// Looks up the sources again, describing what changed in the status line.
+    ///
+    /// With a trigger from the watcher, only the sources it may have affected are looked up, and
+    /// the report is updated with them. Otherwise, all the selected sources are, and the report is
+    /// replaced.
+    fn refresh(&mut self, trigger: Option<&Trigger>) {
+        let (selection, cause) = match trigger {
+            Some(trigger) => (
+                self.selection.intersect(trigger.affected()),
+                trigger.description(),
+            ),
+            None => (self.selection, String::from("Refreshed")),
+        };
+
+        let newer = if selection.environment_variables && trigger.is_some() {
+            refresh_environment().and_then(|()| (self.collect)(selection))
+        } else {
+            (self.collect)(selection)
+        };
+        let newer = match newer {
+            Ok(newer) => newer,
+            Err(e) => {
+                self.status = format!("[{}] {cause}: {e}", timestamp());
+                return;
+            }
+        };
+
+        let changed = changes(&self.real, &newer).len();
+        let shown = masked(self.redactor.as_ref(), &newer);
+        self.reselect(|app| match trigger {
+            Some(_) => {
+                app.real.update(newer);
+                app.report.update(shown);
+            }
+            None => {
+                app.real = newer;
+                app.report = shown;
+            }
+        });
+        let entries = if changed == 1 { "entry" } else { "entries" };
+        self.status = format!("[{}] {cause}: {changed} {entries} changed", timestamp());
+    }
+
+    /// Draws the list, the details of the selected line, and the status line.
+    fn draw(&mut self, frame: &mut Frame) {
+        let [body, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(body);
+
+        let sections = self.report.sections();
+        let selected = self.selected();
+
+        let lines: Vec<ListItem> = self
+            .items()
+            .into_iter()
+            .map(|item| match item {
+                Item::Section(index) => section_line(&sections[index], &self.expanded),
+                Item::Entry(index, entry) => entry_line(sections[index].entries[entry]),
+            })
+            .collect();
+        let list = List::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(" pfdirs "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let (title, details) = match selected {
+            Some(Item::Section(index)) => (
+                sections[index].heading.to_owned(),
+                section_details(&sections[index]),
+            ),
+            Some(Item::Entry(index, entry)) => {
+                let section = &sections[index];
+                let entry = section.entries[entry];
+                let details = entry_details(&self.report, section, entry, self.platform);
+                (entry.name().to_owned(), details)
+            }
+            None => (String::new(), Vec::new()),
+        };
+        let details: Vec<Line> = details
+            .into_iter()
+            .map(|(field, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{field}: "), Style::default().fg(Color::Cyan)),
+                    Span::raw(value),
+                ])
+            })
+            .collect();
+        let pane = Paragraph::new(details)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {title} ")),
+            )
+            .wrap(Wrap { trim: false });
+        frame.render_widget(pane, right);
+
+        let status = Line::from(vec![
+            Span::styled(HELP, Style::default().add_modifier(Modifier::DIM)),
+            Span::raw("  "),
+            Span::raw(self.status.as_str()),
+        ]);
+        frame.render_widget(Paragraph::new(status), footer);
+    }
+}
+
+/// The line for a section's heading, with how many entries it has and how many of them are
+/// errors.
+fn section_line<'a>(section: &SectionRef<'_>, expanded: &HashSet<String>) -> ListItem<'a> {
+    let marker = if expanded.contains(section.heading) {
+        '\u{25BE}'
+    } else {
+        '\u{25B8}'
+    };
+    let errors = section
+        .entries
+        .iter()
+        .filter(|e| e.path().is_none())
+        .count();
+    let style = if errors == 0 {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().add_modifier(Modifier::BOLD).fg(Color::Red)
+    };
+    let count = match errors {
+        0 => format!("{}", section.entries.len()),
+        _ => format!("{}, {errors} without a path", section.entries.len()),
+    };
+    ListItem::new(Line::styled(
+        format!("{marker} {} ({count})", section.heading),
+        style,
+    ))
+}
+
+/// The line for an entry, with its path or, in red, its error.
+fn entry_line<'a>(entry: &dyn Entry) -> ListItem<'a> {
+    let style = match entry.path() {
+        Some(_) => Style::default(),
+        None => Style::default().fg(Color::Red),
+    };
+    ListItem::new(Line::from(vec![
+        Span::raw(format!("    {}  ", entry.name())),
+        Span::styled(entry.lookup().escaped_path_item(), style),
+    ]))
+}
+
+/// The details shown for a section: its kind of source and how many entries it has.
+fn section_details(section: &SectionRef<'_>) -> Vec<(&'static str, String)> {
+    let found = section
+        .entries
+        .iter()
+        .filter(|e| e.path().is_some())
+        .count();
+    let mut details = vec![("Source", section.source.label().to_owned())];
+    let view = section.source.view();
+    if !view.is_empty() {
+        details.push(("View", view));
+    }
+    details.push(("Entries", section.entries.len().to_string()));
+    details.push(("With a path", found.to_string()));
+    details.push((
+        "Without a path",
+        (section.entries.len() - found).to_string(),
+    ));
+    details
+}
+
+/// The details shown for an entry, as described on `run()`.
+///
+/// What the source gives beyond the path is found in the report's typed sections, by the heading
+/// of the entry's section and the entry's name.
+fn entry_details(
+    report: &Report,
+    section: &SectionRef<'_>,
+    entry: &dyn Entry,
+    platform: Platform,
+) -> Vec<(&'static str, String)> {
+    let lookup = entry.lookup();
+    let name = entry.name();
+    let mut details = vec![("Source", section.source.label().to_owned())];
+
+    match &lookup.path {
+        Some(path) => details.push(("Path", path.clone())),
+        None => details.push(("Path", String::from("[none]"))),
+    }
+    if let Some(escaped_path) = &lookup.escaped_path {
+        details.push(("Escaped path", escaped_path.clone()));
+    }
+    if let Some(error) = &lookup.error {
+        details.extend(error_details(error));
+    }
+
+    let heading = section.heading;
+    match section.source {
+        SourceKind::KnownFolder => {
+            if let Some(entry) = find(&report.known_folders, heading, name) {
+                details.push(("Known folder ID", format!("{{{:?}}}", entry.id)));
+            }
+        }
+        SourceKind::KnownFolderDefinition => {
+            if let Some(entry) = find(&report.known_folder_definitions, heading, name) {
+                details.push(("Known folder ID", format!("{{{:?}}}", entry.id)));
+                if let Some(definition) = &entry.definition {
+                    details.push(("Canonical name", definition.canonical_name.clone()));
+                    if let Some(localized_name) = &definition.localized_name {
+                        details.push(("Localized name", localized_name.clone()));
+                    }
+                    details.push(("Category", definition.category.to_owned()));
+                    if let Some(parsing_name) = &definition.parsing_name {
+                        details.push(("Parsing name", parsing_name.clone()));
+                    }
+                    if let Some(relative_path) = &definition.relative_path {
+                        details.push(("Relative path", relative_path.clone()));
+                    }
+                    if let Some(parent) = definition.parent {
+                        details.push(("Parent ID", format!("{{{parent:?}}}")));
+                    }
+                }
+            }
+        }
+        SourceKind::Csidl | SourceKind::CsidlLocation => {
+            let sections = report.csidls.iter().chain(&report.csidl_locations);
+            if let Some(entry) = find(sections, heading, name) {
+                details.push(("CSIDL", format!("{0} (0x{0:04X})", entry.csidl)));
+                if let Some(default) = &entry.default {
+                    details.push(("Default path", default.escaped_path_item()));
+                }
+            }
+        }
+        SourceKind::Registry(_) => {
+            let view = report
+                .registry
+                .iter()
+                .find(|view| view.keys.heading == heading);
+            if let Some(view) = view {
+                if let Some(opened_key) = &view.opened_key {
+                    details.push(("Key", opened_key.clone()));
+                }
+                if let Some(last_write_time) = &view.last_write_time {
+                    details.push(("Key last written", last_write_time.clone()));
+                }
+                if let Some(entry) = view.keys.find(name) {
+                    details.extend(value_details(&entry.value_type, &entry.raw));
+                }
+            }
+        }
+        SourceKind::EnvironmentRegistry(_) => {
+            let keys = report.environment_registry.iter();
+            let users = report.users.iter().map(|user| &user.environment);
+            let sections = keys.chain(users).map(|key| &key.values);
+            if let Some(entry) = find(sections, heading, name) {
+                details.extend(value_details(&entry.value_type, &entry.raw));
+            }
+        }
+        SourceKind::ShellFolders { .. } => {
+            let keys = report.shell_folders.iter();
+            let users = report.users.iter().flat_map(|user| &user.shell_folders);
+            let sections = keys.chain(users).map(|key| &key.values);
+            if let Some(entry) = find(sections, heading, name) {
+                details.push(("Known folder", entry.known_folder.to_owned()));
+                details.extend(value_details(&entry.value_type, &entry.raw));
+            }
+        }
+        SourceKind::FreshEnvironment => {
+            if let Some(entry) = find(&report.fresh_environment, heading, name) {
+                let inherited = entry.inherited.as_deref().unwrap_or("[not inherited]");
+                details.push(("Inherited", inherited.to_owned()));
+                details.push(("Stale", yes_no(entry.stale)));
+            }
+        }
+        _ => {}
+    }
+
+    details.push((
+        "Explanation",
+        explain_entry(section.source, entry, platform),
+    ));
+    details
+}
+
+/// Finds the entry with the given name in the section with the given heading, of those given.
+fn find<'a, E: Entry>(
+    sections: impl IntoIterator<Item = &'a Section<E>>,
+    heading: &str,
+    name: &str,
+) -> Option<&'a E> {
+    sections
+        .into_iter()
+        .find(|section| section.heading == heading)
+        .and_then(|section| section.find(name))
+}
+
+/// The details of why an entry has no path, with its code in the form usual for its kind.
+fn error_details(error: &EntryError) -> Vec<(&'static str, String)> {
+    let mut details = vec![("Error", error.message.clone())];
+    if let Some(code) = error.code {
+        let code = match error.code_kind {
+            Some(CodeKind::Hresult) => format!("0x{:08X}", code as u32),
+            _ => format!("{code} (0x{code:X})"),
+        };
+        details.push(("Code", code));
+    }
+    if let Some(code_kind) = error.code_kind {
+        let code_kind = match code_kind {
+            CodeKind::Hresult => "HRESULT",
+            CodeKind::Win32 => "Win32 error code",
+        };
+        details.push(("Code kind", code_kind.to_owned()));
+    }
+    if let Some(facility) = error.facility {
+        details.push(("Facility", facility.to_string()));
+    }
+    details.push(("Discrepancy", yes_no(error.discrepancy)));
+    details
+}
+
+/// The details of a registry value: its type and its data before expansion.
+fn value_details(value_type: &Option<String>, raw: &Option<String>) -> Vec<(&'static str, String)> {
+    let unread = || String::from("[not read]");
+    vec![
+        ("Value type", value_type.clone().unwrap_or_else(unread)),
+        ("Raw data", raw.clone().unwrap_or_else(unread)),
+    ]
+}
+
+fn yes_no(value: bool) -> String {
+    String::from(if value { "yes" } else { "no" })
+}
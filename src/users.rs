@@ -0,0 +1,130 @@
+//! Looking up the per-user registry values relevant to program files for every local profile.
+
+use std::io;
+use std::path::Path;
+
+use winreg::enums::{HKEY_LOCAL_MACHINE, HKEY_USERS, KEY_READ};
+use winreg::RegKey;
+
+use crate::environment::environment_key;
+use crate::error::Error;
+use crate::registry::read_string_value;
+use crate::report::{Lookup, UserProfile};
+use crate::shell_folders::{shell_folders_key, EXPLORER_SUBKEY};
+use crate::token::string_sid_account_name;
+
+/// The registry key, under `HKEY_LOCAL_MACHINE`, that lists the profiles on this computer, with a
+/// subkey for each, named by its user's SID.
+pub const PROFILE_LIST_SUBKEY: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\ProfileList";
+
+/// The value, in each profile's subkey of `PROFILE_LIST_SUBKEY`, that holds its directory.
+const PROFILE_IMAGE_PATH_VALUE: &str = "ProfileImagePath";
+
+/// The caption of the `Environment` key in each user's hive.
+pub const USER_ENVIRONMENT_KEY: &str = r"HKU\...\Environment";
+
+/// The shell folders keys to query in each user's hive, each with a caption and its name in
+/// `EXPLORER_SUBKEY`.
+pub const USER_SHELL_FOLDERS_KEYS: [(&str, &str); 2] = [
+    (r"HKU\...\Shell Folders", "Shell Folders"),
+    (r"HKU\...\User Shell Folders", "User Shell Folders"),
+];
+
+/// Opens a user's hive, giving it and whether it was already loaded.
+///
+/// If the hive is not in `HKEY_USERS`, it is loaded from the profile's `NTUSER.DAT` as an
+/// application hive, which is unloaded when the key is dropped.
+fn open_user_hive(sid: &str, profile_path: &Lookup) -> io::Result<(RegKey, bool)> {
+    if let Ok(hive) = RegKey::predef(HKEY_USERS).open_subkey_with_flags(sid, KEY_READ) {
+        return Ok((hive, true));
+    }
+
+    let Some(profile_path) = &profile_path.path else {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
+    };
+    let hive =
+        RegKey::load_app_key_with_flags(Path::new(profile_path).join("NTUSER.DAT"), KEY_READ, 0)?;
+    Ok((hive, false))
+}
+
+/// Report the per-user registry values relevant to program files for one profile.
+fn report_user(profile_list: &RegKey, sid: String) -> UserProfile {
+    let profile_path = match profile_list.open_subkey_with_flags(&sid, KEY_READ) {
+        Ok(profile) => read_string_value(&profile, PROFILE_IMAGE_PATH_VALUE).2,
+        Err(e) => Lookup::new(Err::<String, _>(e)),
+    };
+    let account = string_sid_account_name(&sid);
+    let whose = account.as_deref().unwrap_or(&sid);
+
+    let hive = open_user_hive(&sid, &profile_path);
+    let hive_was_loaded = hive.as_ref().is_ok_and(|&(_, loaded)| loaded);
+    let open = |path: &str| match &hive {
+        Ok((hive, _)) => hive.open_subkey_with_flags(path, KEY_READ),
+        Err(e) => Err(e.raw_os_error().map_or_else(
+            || io::Error::new(e.kind(), e.to_string()),
+            io::Error::from_raw_os_error,
+        )),
+    };
+
+    let environment = environment_key(
+        USER_ENVIRONMENT_KEY,
+        format!("Stored environment variables - HKU\\{whose}\\Environment"),
+        open("Environment"),
+    );
+    let shell_folders = USER_SHELL_FOLDERS_KEYS
+        .into_iter()
+        .map(|(caption, key_name)| {
+            shell_folders_key(
+                caption,
+                "default view",
+                format!("Shell folders registry values - HKU\\{whose}\\...\\{key_name}"),
+                open(&format!(r"{EXPLORER_SUBKEY}\{key_name}")),
+            )
+        })
+        .collect();
+
+    UserProfile {
+        sid,
+        account,
+        profile_path,
+        hive_was_loaded,
+        environment,
+        shell_folders,
+    }
+}
+
+/// Report the per-user environment and shell folders registry values relevant to program files,
+/// for every profile on this computer.
+///
+/// `report_environment_registry()` and `report_shell_folders()` read `HKEY_CURRENT_USER`, which is
+/// only the hive of the user this process runs as. To audit every account on a shared machine,
+/// this enumerates the profiles in `PROFILE_LIST_SUBKEY`, including those of service accounts, and
+/// for each reads its `Environment` key and each of `USER_SHELL_FOLDERS_KEYS`, as described on
+/// those functions.
+///
+/// The hive of a user who is logged on, or whose profile a service is using, is already loaded in
+/// `HKEY_USERS` under their SID. Otherwise, the hive is loaded read-only from the profile's
+/// `NTUSER.DAT`, as an application hive, with [`RegLoadAppKey`][rlak], as `report_offline_hive()`
+/// loads a `SOFTWARE` hive, and unloaded afterwards. Reading another user's `NTUSER.DAT` usually
+/// requires running as an administrator. If a hive can be neither opened nor loaded, each of that
+/// profile's entries has the error. Only failing to open `PROFILE_LIST_SUBKEY` is an error.
+///
+/// `HKEY_USERS` is shared between registry views, so each key is read only through the default
+/// view.
+///
+/// [rlak]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regloadappkeyw
+pub fn report_all_users() -> Result<Vec<UserProfile>, Error> {
+    let profile_list = RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey_with_flags(PROFILE_LIST_SUBKEY, KEY_READ)
+        .map_err(Error::Registry)?;
+
+    let sids: Vec<String> = profile_list
+        .enum_keys()
+        .collect::<io::Result<_>>()
+        .map_err(Error::Registry)?;
+
+    Ok(sids
+        .into_iter()
+        .map(|sid| report_user(&profile_list, sid))
+        .collect())
+}
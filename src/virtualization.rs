@@ -0,0 +1,120 @@
+//! Looking for signs that UAC virtualization redirects writes meant for the program files
+//! directories.
+
+#[cfg(windows)]
+use std::fs;
+#[cfg(windows)]
+use std::io;
+
+#[cfg(windows)]
+use windows::Win32::Security::{
+    TokenVirtualizationAllowed, TokenVirtualizationEnabled, TOKEN_INFORMATION_CLASS,
+};
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{FOLDERID_LocalAppData, KF_FLAG_DEFAULT};
+
+#[cfg(windows)]
+use crate::known_folder::resolve_guid;
+#[cfg(windows)]
+use crate::report::{Lookup, Row, Section};
+#[cfg(windows)]
+use crate::token::current_token_flag;
+
+/// The name of the entry for whether UAC virtualization is allowed for this process's token.
+pub const TOKEN_VIRTUALIZATION_ALLOWED: &str = "TokenVirtualizationAllowed";
+
+/// The name of the entry for whether UAC virtualization is enabled for this process's token.
+pub const TOKEN_VIRTUALIZATION_ENABLED: &str = "TokenVirtualizationEnabled";
+
+/// The value of the `TOKEN_VIRTUALIZATION_ENABLED` entry when virtualization is enabled.
+pub const ENABLED: &str = "enabled";
+
+/// The value of the `TOKEN_VIRTUALIZATION_ENABLED` entry when virtualization is not enabled.
+pub const DISABLED: &str = "disabled";
+
+/// The value of the `TOKEN_VIRTUALIZATION_ALLOWED` entry when virtualization is allowed.
+pub const ALLOWED: &str = "allowed";
+
+/// The value of the `TOKEN_VIRTUALIZATION_ALLOWED` entry when virtualization is not allowed.
+pub const NOT_ALLOWED: &str = "not allowed";
+
+/// The names of the entries for the directories of the virtual store that writes to the program
+/// files directories are redirected to, relative to the known folder they are in.
+pub const VIRTUAL_STORES: [&str; 3] = [
+    r"FOLDERID_LocalAppData\VirtualStore\Program Files",
+    r"FOLDERID_LocalAppData\VirtualStore\Program Files (x86)",
+    r"FOLDERID_LocalAppData\VirtualStore\Program Files (Arm)",
+];
+
+/// Gives a token flag as one of two values, or the error querying it.
+#[cfg(windows)]
+fn token_flag_lookup(class: TOKEN_INFORMATION_CLASS, yes: &str, no: &str) -> Lookup {
+    Lookup::new(current_token_flag(class).map(|flag| if flag { yes } else { no }))
+}
+
+/// Finds a directory of the virtual store, as described on `report_virtualization()`, giving it
+/// only if it exists.
+#[cfg(windows)]
+fn virtual_store(name: &str) -> Lookup {
+    let relative = name
+        .strip_prefix("FOLDERID_LocalAppData")
+        .expect("virtual stores are named relative to FOLDERID_LocalAppData");
+
+    let path = match resolve_guid(FOLDERID_LocalAppData, KF_FLAG_DEFAULT) {
+        Ok(mut path) => {
+            path.push(relative);
+            path
+        }
+        Err(e) => return Lookup::new(Err::<String, _>(e)),
+    };
+
+    match fs::metadata(&path) {
+        Ok(metadata) if metadata.is_dir() => Lookup::new(Ok::<_, io::Error>(path)),
+        Ok(_) => Lookup::new(Err::<String, _>(io::Error::from(
+            io::ErrorKind::NotADirectory,
+        ))),
+        Err(e) => Lookup::new(Err::<String, _>(e)),
+    }
+}
+
+/// Report whether UAC virtualization could be redirecting this user's writes to the program files
+/// directories, and where such writes have gone before.
+///
+/// When a program that is not elevated, and is not marked as aware of UAC, writes to a directory
+/// only administrators can write to, such as a program files directory, [UAC virtualization][uacv]
+/// may silently redirect the write to the user's virtual store, normally
+/// `%LOCALAPPDATA%\VirtualStore`, and reads by that user then see the redirected file. Other users,
+/// elevated processes, and programs with a manifest do not see it, so a program appears to write
+/// files that vanish. This reports:
+///
+/// - `TOKEN_VIRTUALIZATION_ALLOWED` and `TOKEN_VIRTUALIZATION_ENABLED`, queried from this process's
+///   token with [`GetTokenInformation`][gti], as `ALLOWED` or `NOT_ALLOWED`, and `ENABLED` or
+///   `DISABLED`. Virtualization is enabled only for 32-bit processes without a manifest that run
+///   unelevated, so it is normally disabled for this program. A program that is affected is
+///   started the same way by the same user, so if it is allowed here, it may be enabled there.
+///
+/// - Each of `VIRTUAL_STORES`, if it exists. The virtual store mirrors the paths redirected to it
+///   without their drive letters, so files here were written by some program, not necessarily this
+///   one, that meant to write them to the program files directory of the same name.
+///
+/// [uacv]: https://learn.microsoft.com/en-us/windows/security/application-security/application-control/user-account-control/how-it-works#virtualization
+/// [gti]: https://learn.microsoft.com/en-us/windows/win32/api/securitybaseapi/nf-securitybaseapi-gettokeninformation
+#[cfg(windows)]
+pub fn report_virtualization() -> Section<Row> {
+    let mut entries = vec![
+        Row {
+            name: TOKEN_VIRTUALIZATION_ALLOWED.to_owned(),
+            lookup: token_flag_lookup(TokenVirtualizationAllowed, ALLOWED, NOT_ALLOWED),
+        },
+        Row {
+            name: TOKEN_VIRTUALIZATION_ENABLED.to_owned(),
+            lookup: token_flag_lookup(TokenVirtualizationEnabled, ENABLED, DISABLED),
+        },
+    ];
+    entries.extend(VIRTUAL_STORES.map(|name| Row {
+        name: name.to_owned(),
+        lookup: virtual_store(name),
+    }));
+
+    Section::new("UAC virtualization", entries)
+}
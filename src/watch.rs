@@ -0,0 +1,284 @@
+//! Waiting for changes that may affect program files folder locations.
+
+use core::ffi::c_void;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{
+    CloseHandle, HANDLE, HWND, LPARAM, LRESULT, WAIT_OBJECT_0, WPARAM,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Registry::{RegNotifyChangeKeyValue, HKEY, REG_NOTIFY_CHANGE_LAST_SET};
+use windows::Win32::System::SystemInformation::GetLocalTime;
+use windows::Win32::System::Threading::{CreateEventW, INFINITE};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, MsgWaitForMultipleObjects,
+    PeekMessageW, RegisterClassW, MSG, PM_REMOVE, QS_ALLINPUT, WINDOW_EX_STYLE, WINDOW_STYLE,
+    WM_SETTINGCHANGE, WNDCLASSW,
+};
+use winreg::enums::{KEY_NOTIFY, KEY_WOW64_32KEY, KEY_WOW64_64KEY};
+use winreg::RegKey;
+
+use crate::environment::{create_environment_block, PROGRAM_FILES_ENVIRONMENT_VARIABLES};
+use crate::error::Error;
+use crate::registry::split_hive;
+use crate::report::Selection;
+
+/// Something that happened that may have changed program files folder locations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// A value was set or deleted in the watched registry key, in the view with this caption.
+    Registry(&'static str),
+
+    /// A `WM_SETTINGCHANGE` message was broadcast, naming this area, if it named one.
+    Setting(Option<String>),
+}
+
+impl Trigger {
+    /// The sources whose entries may have changed.
+    ///
+    /// A registry change affects only the registry. A setting change for `Environment` affects the
+    /// environment variables, once `refresh_environment()` is called, the registry keys they
+    /// are stored in, the fresh environment block, and WMI. Other setting changes, such
+    /// as for policy, may affect how the Shell resolves folders, so they affect the known folders,
+    /// shell monikers, CSIDLs, and shell folders keys.
+    pub fn affected(&self) -> Selection {
+        let none = Selection {
+            environment_variables: false,
+            known_folders: false,
+            known_folder_definitions: false,
+            shell_monikers: false,
+            csidls: false,
+            registry: false,
+            shell_folders: false,
+            environment_registry: false,
+            fresh_environment: false,
+            wmi: false,
+            system_directories: false,
+            shells: false,
+            msi: false,
+        };
+
+        match self {
+            Self::Registry(_) => Selection {
+                registry: true,
+                ..none
+            },
+            Self::Setting(Some(area)) if area == "Environment" => Selection {
+                environment_variables: true,
+                environment_registry: true,
+                fresh_environment: true,
+                wmi: true,
+                ..none
+            },
+            Self::Setting(_) => Selection {
+                known_folders: true,
+                known_folder_definitions: true,
+                shell_monikers: true,
+                csidls: true,
+                shell_folders: true,
+                ..none
+            },
+        }
+    }
+
+    /// Describes what happened.
+    pub fn description(&self) -> String {
+        match self {
+            Self::Registry(caption) => format!("Registry key changed, seen through {caption}"),
+            Self::Setting(Some(area)) => format!("WM_SETTINGCHANGE broadcast for {area}"),
+            Self::Setting(None) => String::from("WM_SETTINGCHANGE broadcast"),
+        }
+    }
+}
+
+thread_local! {
+    /// Areas named by `WM_SETTINGCHANGE` messages received but not yet returned by `wait()`.
+    static SETTING_CHANGES: RefCell<VecDeque<Option<String>>> = const {
+        RefCell::new(VecDeque::new())
+    };
+}
+
+/// Window procedure of the hidden window that receives `WM_SETTINGCHANGE` broadcasts.
+extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if msg != WM_SETTINGCHANGE {
+        return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
+    }
+
+    let area = match lparam.0 {
+        0 => None,
+        address => unsafe { PCWSTR::from_raw(address as *const u16).to_string() }.ok(),
+    };
+    SETTING_CHANGES.with_borrow_mut(|changes| changes.push_back(area));
+    LRESULT(0)
+}
+
+/// Watches for changes to the registry key that holds program files directories, and for
+/// broadcasts announcing changes to the environment and other settings.
+///
+/// The registry key is watched with [`RegNotifyChangeKeyValue`][rnckv], separately through the
+/// 32-bit and 64-bit views, since on 64-bit Windows those are different keys. Changes to the
+/// environment and other settings are announced by broadcasting [`WM_SETTINGCHANGE`][wmsc] to
+/// top-level windows, so this creates a hidden top-level window to receive them. Only the thread
+/// that created the watcher receives the broadcasts.
+///
+/// [rnckv]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regnotifychangekeyvalue
+/// [wmsc]: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-settingchange
+pub struct Watcher {
+    keys: Vec<(&'static str, RegKey)>,
+    events: Vec<HANDLE>,
+    window: HWND,
+}
+
+impl Watcher {
+    /// Starts watching `subkey`, interpreted as described on `open_current_version()`.
+    pub fn new(subkey: &str) -> Result<Self, Error> {
+        let (hive, path) = split_hive(subkey);
+
+        let mut watcher = Self {
+            keys: Vec::new(),
+            events: Vec::new(),
+            window: HWND::default(),
+        };
+
+        for (caption, flag_for_view) in [
+            ("KEY_WOW64_32KEY", KEY_WOW64_32KEY),
+            ("KEY_WOW64_64KEY", KEY_WOW64_64KEY),
+        ] {
+            let key = RegKey::predef(hive)
+                .open_subkey_with_flags(path, KEY_NOTIFY | flag_for_view)
+                .map_err(Error::Registry)?;
+            let event = unsafe { CreateEventW(None, false, false, None) }.map_err(Error::Watch)?;
+            watcher.events.push(event);
+            arm(&key, event)?;
+            watcher.keys.push((caption, key));
+        }
+
+        watcher.window = create_window()?;
+        Ok(watcher)
+    }
+
+    /// Waits until something happens that may have changed program files folder locations.
+    pub fn wait(&mut self) -> Result<Trigger, Error> {
+        loop {
+            if let Some(area) = SETTING_CHANGES.with_borrow_mut(VecDeque::pop_front) {
+                return Ok(Trigger::Setting(area));
+            }
+
+            let result = unsafe {
+                MsgWaitForMultipleObjects(Some(&self.events), false, INFINITE, QS_ALLINPUT)
+            };
+            let index = result.0.wrapping_sub(WAIT_OBJECT_0.0) as usize;
+
+            if index < self.events.len() {
+                let (caption, key) = &self.keys[index];
+                arm(key, self.events[index])?;
+                return Ok(Trigger::Registry(caption));
+            } else if index == self.events.len() {
+                pump_messages();
+            } else {
+                return Err(Error::Watch(windows::core::Error::from_win32()));
+            }
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyWindow(self.window);
+            for &event in &self.events {
+                let _ = CloseHandle(event);
+            }
+        }
+    }
+}
+
+/// Requests that `event` be signaled the next time a value in `key` is set or deleted.
+///
+/// Each request is for a single notification, so this is called again after each one.
+fn arm(key: &RegKey, event: HANDLE) -> Result<(), Error> {
+    let hkey = HKEY(key.raw_handle() as *mut c_void);
+    unsafe { RegNotifyChangeKeyValue(hkey, false, REG_NOTIFY_CHANGE_LAST_SET, event, true) }
+        .ok()
+        .map_err(Error::Watch)
+}
+
+/// Creates the hidden top-level window that receives `WM_SETTINGCHANGE` broadcasts.
+fn create_window() -> Result<HWND, Error> {
+    let class_name = w!("pfdirs watcher");
+
+    unsafe {
+        let instance = GetModuleHandleW(None).map_err(Error::Watch)?;
+
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            lpszClassName: class_name,
+            ..Default::default()
+        };
+        if RegisterClassW(&class) == 0 {
+            return Err(Error::Watch(windows::core::Error::from_win32()));
+        }
+
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class_name,
+            class_name,
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            instance,
+            None,
+        )
+        .map_err(Error::Watch)
+    }
+}
+
+/// Dispatches all messages waiting for this thread's windows.
+fn pump_messages() {
+    let mut message = MSG::default();
+    while unsafe { PeekMessageW(&mut message, None, 0, 0, PM_REMOVE) }.as_bool() {
+        unsafe { DispatchMessageW(&message) };
+    }
+}
+
+/// Updates this process's program files environment variables to what a new process would get.
+///
+/// A running process's environment does not change when the system's environment does, so after a
+/// `WM_SETTINGCHANGE` broadcast for `Environment`, the environment variables in the report would
+/// still show what this process inherited. This builds a fresh environment block from the system
+/// environment with [`CreateEnvironmentBlock`][ceb], which is where program files related
+/// variables are defined, and copies those variables from it into this process's environment,
+/// removing any that are absent from it.
+///
+/// [ceb]: https://learn.microsoft.com/en-us/windows/win32/api/userenv/nf-userenv-createenvironmentblock
+pub fn refresh_environment() -> Result<(), Error> {
+    let variables = create_environment_block(HANDLE::default()).map_err(Error::Watch)?;
+
+    for name in PROGRAM_FILES_ENVIRONMENT_VARIABLES {
+        let value = variables
+            .iter()
+            .find_map(|(key, value)| key.eq_ignore_ascii_case(name).then_some(value));
+        match value {
+            Some(value) => std::env::set_var(name, value),
+            None => std::env::remove_var(name),
+        }
+    }
+
+    Ok(())
+}
+
+/// The current local date and time, for labeling changes.
+pub fn timestamp() -> String {
+    let now = unsafe { GetLocalTime() };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond
+    )
+}
@@ -0,0 +1,186 @@
+//! Looking up the program files environment variables, and the OS architecture, through WMI.
+//!
+//! WMI is queried through COM objects, so `report_wmi()` is only available with the `com` feature.
+
+#[cfg(all(windows, feature = "com"))]
+use std::env::VarError;
+
+#[cfg(all(windows, feature = "com"))]
+use windows::core::{w, BSTR, PCWSTR, VARIANT};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::Foundation::E_FAIL;
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoSetProxyBlanket, CLSCTX_INPROC_SERVER, EOAC_NONE, RPC_C_AUTHN_LEVEL_CALL,
+    RPC_C_IMP_LEVEL_IMPERSONATE,
+};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::System::Rpc::{RPC_C_AUTHN_WINNT, RPC_C_AUTHZ_NONE};
+#[cfg(all(windows, feature = "com"))]
+use windows::Win32::System::Wmi::{
+    IWbemClassObject, IWbemLocator, IWbemServices, WbemLocator, WBEM_FLAG_FORWARD_ONLY,
+    WBEM_FLAG_RETURN_IMMEDIATELY, WBEM_INFINITE,
+};
+
+#[cfg(all(windows, feature = "com"))]
+use crate::environment::PROGRAM_FILES_ENVIRONMENT_VARIABLES;
+#[cfg(all(windows, feature = "com"))]
+use crate::error::{EntryError, Error};
+#[cfg(all(windows, feature = "com"))]
+use crate::known_folder_definition::ComApartment;
+#[cfg(all(windows, feature = "com"))]
+use crate::report::{Lookup, Row, Section};
+
+/// The name of the entry for the `OSArchitecture` property of `Win32_OperatingSystem`.
+pub const OS_ARCHITECTURE: &str = "OSArchitecture";
+
+/// The WMI namespace holding the classes queried here.
+#[cfg(all(windows, feature = "com"))]
+const NAMESPACE: &str = r"ROOT\CIMV2";
+
+/// Connects to the WMI service on this computer, in `NAMESPACE`, as the user this thread runs as.
+#[cfg(all(windows, feature = "com"))]
+fn connect() -> windows::core::Result<IWbemServices> {
+    unsafe {
+        let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)?;
+        let services = locator.ConnectServer(
+            &BSTR::from(NAMESPACE),
+            &BSTR::new(),
+            &BSTR::new(),
+            &BSTR::new(),
+            0,
+            &BSTR::new(),
+            None,
+        )?;
+
+        // Without this, calls through the proxy may use a level of impersonation too low for WMI.
+        CoSetProxyBlanket(
+            &services,
+            RPC_C_AUTHN_WINNT,
+            RPC_C_AUTHZ_NONE,
+            PCWSTR::null(),
+            RPC_C_AUTHN_LEVEL_CALL,
+            RPC_C_IMP_LEVEL_IMPERSONATE,
+            None,
+            EOAC_NONE,
+        )?;
+
+        Ok(services)
+    }
+}
+
+/// Runs a WQL query and gives each object it returns.
+#[cfg(all(windows, feature = "com"))]
+fn query(services: &IWbemServices, wql: &str) -> windows::core::Result<Vec<IWbemClassObject>> {
+    let enumerator = unsafe {
+        services.ExecQuery(
+            &BSTR::from("WQL"),
+            &BSTR::from(wql),
+            WBEM_FLAG_FORWARD_ONLY | WBEM_FLAG_RETURN_IMMEDIATELY,
+            None,
+        )
+    }?;
+
+    let mut objects = Vec::new();
+    loop {
+        let mut row = [None];
+        let mut returned = 0;
+        unsafe { enumerator.Next(WBEM_INFINITE, &mut row, &mut returned) }.ok()?;
+        match row {
+            [Some(object)] if returned != 0 => objects.push(object),
+            _ => return Ok(objects),
+        }
+    }
+}
+
+/// Gets a string property of a WMI object.
+#[cfg(all(windows, feature = "com"))]
+fn string_property(object: &IWbemClassObject, name: PCWSTR) -> windows::core::Result<String> {
+    let mut value = VARIANT::new();
+    unsafe { object.Get(name, 0, &mut value, None, None) }?;
+    Ok(BSTR::try_from(&value)?.to_string())
+}
+
+/// Finds the system environment variables stored for WMI's `Win32_Environment` class, by name.
+#[cfg(all(windows, feature = "com"))]
+fn system_variables(services: &IWbemServices) -> windows::core::Result<Vec<(String, String)>> {
+    let wql = "SELECT Name, VariableValue FROM Win32_Environment WHERE SystemVariable = TRUE";
+    query(services, wql)?
+        .iter()
+        .map(|object| {
+            Ok((
+                string_property(object, w!("Name"))?,
+                string_property(object, w!("VariableValue"))?,
+            ))
+        })
+        .collect()
+}
+
+/// Gets the `OSArchitecture` property of the one `Win32_OperatingSystem` object.
+#[cfg(all(windows, feature = "com"))]
+fn os_architecture(services: &IWbemServices) -> windows::core::Result<String> {
+    let wql = "SELECT OSArchitecture FROM Win32_OperatingSystem";
+    match query(services, wql)?.first() {
+        Some(object) => string_property(object, w!("OSArchitecture")),
+        None => Err(E_FAIL.into()),
+    }
+}
+
+/// Report the *program files* environment variables, and the architecture of the OS, as WMI gives
+/// them.
+///
+/// Configuration management agents and inventory tools often read system information through
+/// [WMI][wmi] rather than the Win32 APIs, so this shows whether that view matches the others. It
+/// queries two classes in the `ROOT\CIMV2` namespace:
+///
+/// - [`Win32_Environment`][w32env], for system variables. WMI reads these from the registry key
+///   that `report_environment_registry()` reads as `System`, not from any process's environment.
+///   Windows does not normally store the program files variables there, but computes them when it
+///   builds an environment block, so each is usually not found, even though every process sees it.
+///   A tool that reads them through WMI thus gets nothing, or gets a value stored to override what
+///   Windows computes.
+///
+/// - [`Win32_OperatingSystem`][w32os], for its `OSArchitecture` property, shown as the entry named
+///   `OS_ARCHITECTURE`. This is a description such as `64-bit`, not a path, and may be localized.
+///   Unlike `ProgramFiles`, it does not depend on the architecture of the calling process.
+///
+/// WMI is queried through COM, which is initialized on this thread for the duration. If WMI cannot
+/// be reached, every entry has that error.
+///
+/// [wmi]: https://learn.microsoft.com/en-us/windows/win32/wmisdk/wmi-start-page
+/// [w32env]: https://learn.microsoft.com/en-us/windows/win32/cimwin32prov/win32-environment
+/// [w32os]: https://learn.microsoft.com/en-us/windows/win32/cimwin32prov/win32-operatingsystem
+#[cfg(all(windows, feature = "com"))]
+pub fn report_wmi() -> Result<Section<Row>, Error> {
+    let _apartment = ComApartment::initialize()?;
+
+    let wmi_error = |e| EntryError::from(Error::Com(e));
+    let services = connect().map_err(wmi_error);
+    let variables = services
+        .clone()
+        .and_then(|services| system_variables(&services).map_err(wmi_error));
+    let architecture = services.and_then(|services| os_architecture(&services).map_err(wmi_error));
+
+    let mut entries: Vec<Row> = PROGRAM_FILES_ENVIRONMENT_VARIABLES
+        .into_iter()
+        .map(|name| {
+            let value = variables.clone().and_then(|variables| {
+                variables
+                    .into_iter()
+                    .find(|(other, _)| other.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value)
+                    .ok_or_else(|| VarError::NotPresent.into())
+            });
+            Row {
+                name: name.to_owned(),
+                lookup: Lookup::new(value),
+            }
+        })
+        .collect();
+    entries.push(Row {
+        name: OS_ARCHITECTURE.to_owned(),
+        lookup: Lookup::new(architecture),
+    });
+
+    Ok(Section::new("Relevant WMI properties", entries))
+}
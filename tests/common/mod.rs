@@ -0,0 +1,46 @@
+//! What the tests share: the platforms they simulate, and reports collected from simulated systems.
+
+// Each test crate uses only some of these.
+#![allow(dead_code)]
+
+use pfdirs::os::FakeOs;
+use pfdirs::{Machine, Platform, Report};
+
+/// 32-bit Windows, with a 32-bit process.
+pub const X86: Platform = Platform {
+    process: Machine::X86,
+    native: Machine::X86,
+};
+
+/// x64 Windows, with a 32-bit process running under WOW64.
+pub const WOW64: Platform = Platform {
+    process: Machine::X86,
+    native: Machine::X64,
+};
+
+/// x64 Windows, with a 64-bit process.
+pub const X64: Platform = Platform {
+    process: Machine::X64,
+    native: Machine::X64,
+};
+
+/// ARM64 Windows, with an ARM64 process.
+pub const ARM64: Platform = Platform {
+    process: Machine::Arm64,
+    native: Machine::Arm64,
+};
+
+/// Simulates a system as `FakeOs::simulate()` does, with the named environment variables removed,
+/// as an ancestor process that sanitizes its children's environments might remove them.
+pub fn simulate(platform: Platform, stripped: &[&str]) -> FakeOs {
+    stripped
+        .iter()
+        .fold(FakeOs::simulate(platform), |os, name| {
+            os.without_env_var(name)
+        })
+}
+
+/// Collects a report of a system simulated by `simulate()`, through `Report::collect_with()`.
+pub fn report_of(platform: Platform, stripped: &[&str]) -> Report {
+    Report::collect_with(&simulate(platform, stripped))
+}
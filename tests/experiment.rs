@@ -0,0 +1,71 @@
+//! Tests of how the experiments read the reports of the child processes they run.
+//!
+//! Each report is collected from a `FakeOs` and rendered as the child would output it, so these do
+//! not run any child process.
+
+#![cfg(feature = "serde")]
+
+mod common;
+
+use std::ffi::OsStr;
+
+use pfdirs::analysis::{anomalies, findings, AnomalyKind};
+use pfdirs::experiment::read_child_report;
+use pfdirs::render;
+
+use common::report_of;
+
+/// The platform of a child of `inheritance()` on x64 Windows.
+const PLATFORM: pfdirs::Platform = common::X64;
+
+/// Renders a report of a system simulated for `PLATFORM`, with the `stripped` environment
+/// variables removed, as the JSON report a child would output, with the exit status it would exit
+/// with.
+fn child_output(stripped: &[&str]) -> (Vec<u8>, i32) {
+    let report = report_of(PLATFORM, stripped);
+
+    let json = render::json(&report, &findings(&report, PLATFORM)).expect("the report renders");
+    let code = anomalies(&report, PLATFORM)
+        .into_iter()
+        .map(|anomaly| anomaly.kind)
+        .max()
+        .map_or(0, AnomalyKind::exit_code);
+    (json.into_bytes(), code.into())
+}
+
+#[test]
+fn stripped_environment_child_is_read() {
+    let (stdout, code) = child_output(&["ProgramW6432"]);
+    assert_eq!(code, i32::from(AnomalyKind::Missing.exit_code()));
+
+    let sections = read_child_report(OsStr::new("pfdirs"), Some(code), &stdout)
+        .expect("a child reporting a missing variable is read");
+    let (_, entries) = sections
+        .iter()
+        .find(|(heading, _)| heading == "Relevant environment variables")
+        .expect("the child reports environment variables");
+    let received = |name| {
+        entries
+            .iter()
+            .find(|(entry_name, _)| entry_name == name)
+            .and_then(|(_, lookup)| lookup.path.as_deref())
+    };
+
+    assert_eq!(received("ProgramW6432"), None);
+    assert_eq!(
+        received("ProgramFiles(x86)"),
+        Some(r"C:\Program Files (x86)")
+    );
+}
+
+#[test]
+fn child_with_api_failure_is_an_error() {
+    let (stdout, _) = child_output(&[]);
+    let code = AnomalyKind::ApiFailure.exit_code().into();
+    assert!(read_child_report(OsStr::new("pfdirs"), Some(code), &stdout).is_err());
+}
+
+#[test]
+fn child_without_output_is_an_error() {
+    assert!(read_child_report(OsStr::new("pfdirs"), Some(0), b"").is_err());
+}
@@ -0,0 +1,64 @@
+//! Tests of looking up known folders by GUIDs given as text, as for OEM and application folders.
+//!
+//! Parsing runs on any platform. Resolving a known folder needs Windows, so those tests run only
+//! there.
+
+use pfdirs::known_folder::{parse_guid, FOLDERID_ProgramFiles};
+use pfdirs::Error;
+
+/// `FOLDERID_ProgramFiles`, written as its GUID, as it would be given on the command line.
+const PROGRAM_FILES_GUID: &str = "{905e63b6-c1bf-494e-b29c-65b732d3d21a}";
+
+#[test]
+fn guid_parses_with_or_without_braces() {
+    assert_eq!(
+        parse_guid(PROGRAM_FILES_GUID).ok(),
+        Some(FOLDERID_ProgramFiles)
+    );
+    assert_eq!(
+        parse_guid("905E63B6-C1BF-494E-B29C-65B732D3D21A").ok(),
+        Some(FOLDERID_ProgramFiles)
+    );
+}
+
+#[test]
+fn malformed_guid_is_a_parse_error() {
+    for text in [
+        "ProgramFiles",
+        "{905e63b6-c1bf-494e-b29c}",
+        "{905e63b6-c1bf-494e-b29c-65b732d3d21g}",
+        "{905e63b6c1bf494eb29c65b732d3d21a}",
+        "{905e63b6-c1bf-494e-b29c-65b732d3d21a",
+    ] {
+        match parse_guid(text) {
+            Err(Error::InvalidGuid(given)) => assert_eq!(given, text),
+            other => panic!("{text} should not parse, but gave {other:?}"),
+        }
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn raw_guid_resolves_as_folderid_program_files() {
+    use pfdirs::known_folder::resolve_guid;
+    use windows::Win32::UI::Shell::{self, KF_FLAG_DEFAULT};
+
+    let id = parse_guid(PROGRAM_FILES_GUID).expect("the GUID is well formed");
+    let by_raw_guid = resolve_guid(id, KF_FLAG_DEFAULT).expect("every system has the folder");
+    let by_folder_id = resolve_guid(Shell::FOLDERID_ProgramFiles, KF_FLAG_DEFAULT)
+        .expect("every system has the folder");
+    assert_eq!(by_raw_guid, by_folder_id);
+}
+
+#[cfg(all(windows, feature = "known-folders-crosscheck"))]
+#[test]
+fn raw_guid_resolves_as_the_known_folders_enum() {
+    use known_folders::{get_known_folder_path, KnownFolder};
+    use pfdirs::known_folder::resolve_guid;
+    use windows::Win32::UI::Shell::KF_FLAG_DEFAULT;
+
+    let id = parse_guid(PROGRAM_FILES_GUID).expect("the GUID is well formed");
+    let by_raw_guid = resolve_guid(id, KF_FLAG_DEFAULT).expect("every system has the folder");
+    let by_enum = get_known_folder_path(KnownFolder::ProgramFiles);
+    assert_eq!(Some(by_raw_guid.into()), by_enum);
+}
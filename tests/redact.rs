@@ -0,0 +1,85 @@
+//! Tests of masking user names in paths, as `--redact` does, including paths inside longer text
+//! such as error messages and `PATH`-like values.
+
+use pfdirs::redact::Redactor;
+
+/// A redactor for the usual profiles directory, given with a trailing backslash, as
+/// `FOLDERID_UserProfiles` may be.
+fn redactor() -> Redactor {
+    Redactor::new([r"C:\Users\"])
+}
+
+#[test]
+fn user_name_is_masked() {
+    assert_eq!(
+        redactor().text(r"C:\Users\ek\AppData\Local\Programs"),
+        r"C:\Users\<user>\AppData\Local\Programs",
+    );
+}
+
+#[test]
+fn profiles_directory_matches_in_any_case() {
+    assert_eq!(
+        redactor().text(r"C:\USERS\Name\AppData"),
+        r"C:\USERS\<user>\AppData",
+    );
+    assert_eq!(
+        redactor().text(r"c:\users\Name\AppData"),
+        r"c:\users\<user>\AppData",
+    );
+}
+
+#[test]
+fn shared_profiles_are_kept() {
+    let redactor = redactor();
+
+    assert_eq!(
+        redactor.text(r"C:\Users\Public\Documents"),
+        r"C:\Users\Public\Documents",
+    );
+    assert_eq!(redactor.text(r"C:\Users\PUBLIC"), r"C:\Users\PUBLIC");
+    assert_eq!(
+        redactor.text(r"C:\Users\Default User\NTUSER.DAT"),
+        r"C:\Users\Default User\NTUSER.DAT",
+    );
+}
+
+#[test]
+fn path_ending_at_profiles_directory_is_kept() {
+    let redactor = redactor();
+
+    assert_eq!(redactor.text(r"C:\Users\"), r"C:\Users\");
+    assert_eq!(redactor.text(r"C:\Users"), r"C:\Users");
+    assert_eq!(redactor.text(r"C:\Users\;D:\"), r"C:\Users\;D:\");
+}
+
+#[test]
+fn every_occurrence_is_masked() {
+    assert_eq!(
+        redactor().text(r"could not move C:\Users\alice\a to C:\Users\bob\b or C:\Users\carol"),
+        r"could not move C:\Users\<user>\a to C:\Users\<user>\b or C:\Users\<user>",
+    );
+}
+
+#[test]
+fn name_ends_at_separator_or_quote() {
+    let redactor = redactor();
+
+    assert_eq!(
+        redactor.text(r"C:\Users\alice;C:\Users\bob\bin;C:\Windows"),
+        r"C:\Users\<user>;C:\Users\<user>\bin;C:\Windows",
+    );
+    assert_eq!(
+        redactor.text(r#"the system cannot find "C:\Users\alice" (2)"#),
+        r#"the system cannot find "C:\Users\<user>" (2)"#,
+    );
+    assert_eq!(redactor.text(r"'C:\Users\alice'"), r"'C:\Users\<user>'");
+}
+
+#[test]
+fn account_keeps_domain() {
+    let redactor = redactor();
+
+    assert_eq!(redactor.account(r"CONTOSO\alice"), r"CONTOSO\<user>");
+    assert_eq!(redactor.account("alice"), "<user>");
+}
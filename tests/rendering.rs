@@ -0,0 +1,91 @@
+//! Snapshot tests of how reports of simulated systems are rendered, and of the anomalies and
+//! findings found in them.
+//!
+//! Each report is collected from a `FakeOs`, so these do not depend on the system they run on. Error
+//! messages that Windows localizes, such as for a missing known folder, are only rendered where the
+//! format leaves them out, as CSV does. Run `cargo insta review` to review changes to the
+//! snapshots in `tests/snapshots`.
+
+mod common;
+
+use pfdirs::analysis::{anomalies, findings};
+use pfdirs::render::{self, Style};
+use pfdirs::{Platform, SourceKind};
+
+use common::{report_of, ARM64, WOW64, X64, X86};
+
+/// Renders a report of a system simulated for `platform`, with the `stripped` environment
+/// variables removed, as text, as CSV, and as the anomalies and findings found in it.
+///
+/// Only the environment variables section is rendered as text, since its error messages are not
+/// localized.
+fn render(platform: Platform, stripped: &[&str]) -> String {
+    let report = report_of(platform, stripped);
+
+    let section = report
+        .section(SourceKind::EnvironmentVariable)
+        .expect("the report has environment variables");
+    let text = render::section(section.heading, &section.entries, &Style::default());
+
+    let csv = render::delimited(&report, ',');
+
+    let found: Vec<String> = anomalies(&report, platform)
+        .into_iter()
+        .map(|anomaly| {
+            format!(
+                "{:?}: {}: {}: {}",
+                anomaly.kind, anomaly.section, anomaly.name, anomaly.detail
+            )
+        })
+        .collect();
+    let found = if found.is_empty() {
+        String::from("[none]")
+    } else {
+        found.join("\n")
+    };
+
+    let concluded: Vec<String> = findings(&report, platform)
+        .into_iter()
+        .map(|finding| {
+            format!(
+                "{:?}: {}: {}",
+                finding.severity, finding.title, finding.explanation
+            )
+        })
+        .collect();
+    let concluded = if concluded.is_empty() {
+        String::from("[none]")
+    } else {
+        concluded.join("\n")
+    };
+
+    format!(
+        "{}\n\n--- CSV ---\n\n{}\n\n--- Anomalies ---\n\n{found}\n\n\
+        --- Findings ---\n\n{concluded}",
+        text.trim_end(),
+        csv.trim_end()
+    )
+}
+
+#[test]
+fn x86_os() {
+    insta::assert_snapshot!("x86_os", render(X86, &[]));
+}
+
+#[test]
+fn x64_os_with_32_bit_process() {
+    insta::assert_snapshot!("x64_os_with_32_bit_process", render(WOW64, &[]));
+}
+
+#[test]
+fn arm64() {
+    insta::assert_snapshot!("arm64", render(ARM64, &[]));
+}
+
+#[test]
+fn stripped_environment() {
+    insta::assert_snapshot!(
+        "stripped_environment",
+        render(X64, &["ProgramFiles(x86)", "ProgramW6432"])
+    );
+}
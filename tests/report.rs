@@ -0,0 +1,81 @@
+//! Tests of updating a report with a newer one, as `--watch` and `pfdirs tui` do when they look
+//! the sources up again.
+
+use pfdirs::report::TimedOutSection;
+use pfdirs::{Lookup, Report, Row, Section, SourceKind};
+
+/// A section with one entry, giving `path`.
+fn section(name: &str, path: &str) -> Section<Row> {
+    Section::new(
+        "Relevant shell monikers",
+        vec![Row {
+            name: name.to_owned(),
+            lookup: Lookup::new(Ok::<_, std::io::Error>(path)),
+        }],
+    )
+}
+
+/// A report in which the shell monikers source timed out.
+fn timed_out() -> Report {
+    let error = Lookup::new(Err::<String, _>(std::io::Error::other("timed out")));
+    Report {
+        timed_out: vec![TimedOutSection {
+            source: SourceKind::ShellMoniker,
+            section: Section::new(
+                "Relevant shell monikers",
+                vec![Row {
+                    name: String::from("shell:ProgramFiles"),
+                    lookup: error,
+                }],
+            ),
+        }],
+        ..Report::default()
+    }
+}
+
+#[test]
+fn timed_out_source_replaces_its_section() {
+    let mut report = Report {
+        shell_monikers: Some(section("shell:ProgramFiles", r"C:\Program Files")),
+        ..Report::default()
+    };
+
+    report.update(timed_out());
+
+    assert!(report.shell_monikers.is_none());
+    assert_eq!(report.timed_out.len(), 1);
+    let sections = report.sections();
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].source, SourceKind::ShellMoniker);
+    assert!(sections[0].entries[0].path().is_none());
+}
+
+#[test]
+fn looked_up_source_replaces_its_stand_in() {
+    let mut report = timed_out();
+
+    report.update(Report {
+        shell_monikers: Some(section("shell:ProgramFiles", r"C:\Program Files")),
+        ..Report::default()
+    });
+
+    assert!(report.timed_out.is_empty());
+    let section = report
+        .section(SourceKind::ShellMoniker)
+        .expect("the source was looked up");
+    assert_eq!(section.entries[0].path(), Some(r"C:\Program Files"));
+}
+
+#[test]
+fn other_sections_are_kept() {
+    let mut report = Report {
+        shell_monikers: Some(section("shell:ProgramFiles", r"C:\Program Files")),
+        wmi: Some(section("ProgramFiles", r"C:\Program Files")),
+        ..Report::default()
+    };
+
+    report.update(timed_out());
+
+    assert!(report.wmi.is_some());
+    assert_eq!(report.sections().len(), 2);
+}
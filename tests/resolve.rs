@@ -0,0 +1,96 @@
+//! Tests of how `resolve_program_files_with()` chooses among sources on simulated systems.
+//!
+//! Each system is a `FakeOs`, so these run on any platform, not only on Windows.
+
+mod common;
+
+use std::path::Path;
+
+use pfdirs::known_folder::FOLDERID_ProgramFilesX86;
+use pfdirs::os::FakeOs;
+use pfdirs::resolve::{resolve_program_files_with, Arch};
+
+use common::{simulate, ARM64, WOW64, X64, X86};
+
+/// Resolves each architecture's program files directory on `os`, giving the path or, if there is
+/// none, the sources that were tried.
+fn resolve_all(os: &FakeOs) -> [Result<String, Vec<String>>; 3] {
+    [Arch::X64, Arch::X86, Arch::Arm].map(|arch| {
+        resolve_program_files_with(os, arch)
+            .map(|path| path.display().to_string())
+            .map_err(|e| e.attempts.into_iter().map(|(source, _)| source).collect())
+    })
+}
+
+#[test]
+fn x86_os() {
+    let os = simulate(X86, &[]);
+    let [x64, x86, arm] = resolve_all(&os);
+
+    assert_eq!(
+        x64,
+        Err(vec![
+            String::from("known folder FOLDERID_ProgramFilesX64"),
+            String::from("environment variable ProgramW6432"),
+            String::from("registry value ProgramW6432Dir"),
+        ])
+    );
+    assert_eq!(x86, Ok(String::from(r"C:\Program Files")));
+    assert_eq!(
+        arm,
+        Err(vec![
+            String::from("environment variable ProgramFiles(Arm)"),
+            String::from("registry value ProgramFilesDir (Arm)"),
+        ])
+    );
+}
+
+#[test]
+fn x64_os_with_64_bit_process() {
+    let os = simulate(X64, &[]);
+    let [x64, x86, arm] = resolve_all(&os);
+
+    assert_eq!(x64, Ok(String::from(r"C:\Program Files")));
+    assert_eq!(x86, Ok(String::from(r"C:\Program Files (x86)")));
+    assert!(arm.is_err());
+}
+
+#[test]
+fn x64_os_with_32_bit_process() {
+    let os = simulate(WOW64, &[]);
+    let [x64, x86, arm] = resolve_all(&os);
+
+    // A 32-bit process does not see FOLDERID_ProgramFilesX64, so this comes from ProgramW6432.
+    assert_eq!(x64, Ok(String::from(r"C:\Program Files")));
+    assert_eq!(x86, Ok(String::from(r"C:\Program Files (x86)")));
+    assert!(arm.is_err());
+}
+
+#[test]
+fn arm64() {
+    let os = simulate(ARM64, &[]);
+    let [x64, x86, arm] = resolve_all(&os);
+
+    assert_eq!(x64, Ok(String::from(r"C:\Program Files")));
+    assert_eq!(x86, Ok(String::from(r"C:\Program Files (x86)")));
+    assert_eq!(arm, Ok(String::from(r"C:\Program Files (Arm)")));
+}
+
+#[test]
+fn stripped_environment_falls_back_to_registry() {
+    let os = simulate(WOW64, &["ProgramW6432"]);
+
+    let path = resolve_program_files_with(&os, Arch::X64)
+        .expect("the 64-bit directory is in the registry");
+    assert_eq!(path, Path::new(r"C:\Program Files"));
+}
+
+#[test]
+fn every_failed_source_is_given() {
+    let os = FakeOs::new().with_known_folder(FOLDERID_ProgramFilesX86, r"C:\Program Files (x86)");
+
+    let error = resolve_program_files_with(&os, Arch::X64).expect_err("nothing has the directory");
+    assert_eq!(error.directory, "64-bit program files directory");
+    assert_eq!(error.attempts.len(), 3);
+    assert!(resolve_program_files_with(&os, Arch::X86).is_ok());
+}
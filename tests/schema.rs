@@ -0,0 +1,212 @@
+//! Tests that reports rendered as JSON, YAML, and TOML follow the JSON Schema that `--json-schema`
+//! prints, which is `schema/report.schema.json`, on simulated systems.
+
+#![cfg(feature = "serde")]
+
+mod common;
+
+use pfdirs::analysis::findings;
+use pfdirs::render::{self, JSON_SCHEMA, SCHEMA_VERSION};
+use pfdirs::report::TimedOutSection;
+use pfdirs::{Lookup, Platform, Report, Row, Section, SourceKind};
+use regex::Regex;
+use serde_json::Value;
+
+use common::{report_of, ARM64, WOW64, X64, X86};
+
+/// Finds each way `value`, at `at`, does not follow `schema`, which is part of `root`.
+///
+/// Only the keywords `schema/report.schema.json` uses are understood, and any other is reported,
+/// so that the schema cannot come to use one these tests silently skip.
+fn violations(root: &Value, schema: &Value, value: &Value, at: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    let Some(keywords) = schema.as_object() else {
+        return vec![format!("{at}: the schema is not an object")];
+    };
+
+    for (keyword, expected) in keywords {
+        match keyword.as_str() {
+            "$schema" | "$defs" | "title" | "description" => {}
+            "$ref" => {
+                let pointer = expected
+                    .as_str()
+                    .unwrap_or_default()
+                    .trim_start_matches('#');
+                match root.pointer(pointer) {
+                    Some(schema) => found.extend(violations(root, schema, value, at)),
+                    None => found.push(format!("{at}: no such definition as {expected}")),
+                }
+            }
+            "type" => {
+                let types: Vec<&str> = match expected {
+                    Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                    _ => expected.as_str().into_iter().collect(),
+                };
+                if !types.iter().any(|&name| has_type(value, name)) {
+                    found.push(format!("{at}: {value} is not of type {expected}"));
+                }
+            }
+            "const" if value != expected => {
+                found.push(format!("{at}: {value} is not {expected}"));
+            }
+            "enum"
+                if !expected
+                    .as_array()
+                    .is_some_and(|values| values.contains(value)) =>
+            {
+                found.push(format!("{at}: {value} is not one of {expected}"));
+            }
+            "minimum"
+                if value
+                    .as_f64()
+                    .zip(expected.as_f64())
+                    .is_some_and(|(number, minimum)| number < minimum) =>
+            {
+                found.push(format!("{at}: {value} is less than {expected}"));
+            }
+            "pattern" => {
+                let pattern = Regex::new(expected.as_str().unwrap_or_default())
+                    .expect("the schema's patterns are valid");
+                if value.as_str().is_some_and(|text| !pattern.is_match(text)) {
+                    found.push(format!("{at}: {value} does not match {expected}"));
+                }
+            }
+            "required" => {
+                if let Some(object) = value.as_object() {
+                    for name in expected.as_array().into_iter().flatten() {
+                        if !object.contains_key(name.as_str().unwrap_or_default()) {
+                            found.push(format!("{at}: {name} is missing"));
+                        }
+                    }
+                }
+            }
+            "properties" => {
+                if let (Some(object), Some(properties)) = (value.as_object(), expected.as_object())
+                {
+                    for (name, schema) in properties {
+                        if let Some(property) = object.get(name) {
+                            let at = format!("{at}/{name}");
+                            found.extend(violations(root, schema, property, &at));
+                        }
+                    }
+                }
+            }
+            "items" => {
+                for (i, item) in value.as_array().into_iter().flatten().enumerate() {
+                    found.extend(violations(root, expected, item, &format!("{at}/{i}")));
+                }
+            }
+            "allOf" => {
+                for schema in expected.as_array().into_iter().flatten() {
+                    found.extend(violations(root, schema, value, at));
+                }
+            }
+            "oneOf" => {
+                let matched = expected
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|schema| violations(root, schema, value, at).is_empty())
+                    .count();
+                if matched != 1 {
+                    found.push(format!("{at}: {matched} of the oneOf schemas match, not 1"));
+                }
+            }
+            "not" if violations(root, expected, value, at).is_empty() => {
+                found.push(format!(
+                    "{at}: {value} matches {expected}, which it must not"
+                ));
+            }
+            "const" | "enum" | "minimum" | "not" => {}
+            _ => found.push(format!("{at}: the keyword {keyword} is not understood")),
+        }
+    }
+    found
+}
+
+/// Whether `value` is of the JSON Schema type `name`.
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+/// Renders a report as JSON, YAML, and TOML, with its findings for `platform`, and reads each
+/// back, checking that it follows the schema.
+fn check(report: &Report, platform: Platform) {
+    let schema: Value = serde_json::from_str(JSON_SCHEMA).expect("the schema is valid JSON");
+    let findings = findings(report, platform);
+
+    let rendered: [(&str, Value); 3] = [
+        (
+            "JSON",
+            serde_json::from_str(&render::json(report, &findings).expect("renders as JSON"))
+                .expect("the JSON can be read back"),
+        ),
+        (
+            "YAML",
+            serde_yaml::from_str(&render::yaml(report, &findings).expect("renders as YAML"))
+                .expect("the YAML can be read back"),
+        ),
+        (
+            "TOML",
+            toml::from_str(&render::toml(report, &findings).expect("renders as TOML"))
+                .expect("the TOML can be read back"),
+        ),
+    ];
+
+    for (format, value) in rendered {
+        let found = violations(&schema, &schema, &value, "");
+        assert!(
+            found.is_empty(),
+            "{format} does not follow the schema: {found:#?}"
+        );
+        assert_eq!(value["schema_version"], SCHEMA_VERSION, "{format}");
+        assert_eq!(
+            value["findings"].as_array().map_or(0, Vec::len),
+            findings.len(),
+            "{format} should have every finding",
+        );
+    }
+}
+
+#[test]
+fn simulated_reports_follow_schema() {
+    for platform in [X86, WOW64, X64, ARM64] {
+        check(&report_of(platform, &[]), platform);
+    }
+}
+
+#[test]
+fn report_with_findings_follows_schema() {
+    let report = report_of(X64, &["ProgramFiles(x86)", "ProgramW6432"]);
+    assert!(!findings(&report, X64).is_empty());
+
+    check(&report, X64);
+}
+
+#[test]
+fn truncated_report_with_stand_in_follows_schema() {
+    let error = Lookup::new(Err::<String, _>(std::io::Error::other("timed out")));
+    let mut report = report_of(X64, &[]);
+    report.timed_out.push(TimedOutSection {
+        source: SourceKind::ShellMoniker,
+        section: Section::new(
+            "Relevant shell monikers",
+            vec![Row {
+                name: String::from("shell:ProgramFiles"),
+                lookup: error,
+            }],
+        ),
+    });
+    report.truncated = true;
+
+    check(&report, X64);
+}
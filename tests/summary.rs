@@ -0,0 +1,185 @@
+//! Tests of the summary table: which entry each of its cells is about, and which cells are flagged
+//! as outliers, on simulated systems.
+
+mod common;
+
+use pfdirs::analysis::{
+    directory_of_entry, summarize, summary_entry_name, LogicalDirectory, SummaryCell,
+    SUMMARY_COLUMNS,
+};
+use pfdirs::known_folder::FOLDERID_ProgramFilesX64;
+use pfdirs::{EnvVarEntry, KnownFolderEntry, Lookup, Report, Row, Section, SourceKind};
+
+use common::{simulate, ARM64, WOW64, X64};
+
+#[test]
+fn every_summary_entry_is_about_its_directory() {
+    for platform in [X64, WOW64] {
+        for directory in LogicalDirectory::ALL {
+            for (column, source) in SUMMARY_COLUMNS {
+                if let Some(name) = summary_entry_name(directory, source, platform) {
+                    assert_eq!(
+                        directory_of_entry(source, name, platform),
+                        Some(directory),
+                        "{name}, in the {column} column, should be about the {}",
+                        directory.description(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn summary_entries_have_the_expected_names() {
+    use LogicalDirectory::*;
+
+    let names =
+        |directory| SUMMARY_COLUMNS.map(|(_, source)| summary_entry_name(directory, source, X64));
+
+    assert_eq!(
+        names(ProgramFiles64),
+        [
+            Some("ProgramW6432"),
+            Some("FOLDERID_ProgramFilesX64"),
+            Some("shell:ProgramFilesX64"),
+            Some("CSIDL_PROGRAM_FILES"),
+            Some("ProgramFilesDir"),
+            Some("ProgramW6432Dir"),
+            Some("ProgramW6432Dir"),
+        ]
+    );
+    assert_eq!(
+        names(ProgramFiles32),
+        [
+            Some("ProgramFiles(x86)"),
+            Some("FOLDERID_ProgramFilesX86"),
+            Some("shell:ProgramFilesX86"),
+            Some("CSIDL_PROGRAM_FILESX86"),
+            Some("ProgramFilesDir (x86)"),
+            Some("ProgramFilesDir"),
+            Some("ProgramFilesDir (x86)"),
+        ]
+    );
+    assert_eq!(
+        names(ProgramFilesArm),
+        [
+            Some("ProgramFiles(Arm)"),
+            None,
+            None,
+            None,
+            Some("ProgramFilesDir (Arm)"),
+            Some("ProgramFilesDir (Arm)"),
+            Some("ProgramFilesDir (Arm)"),
+        ]
+    );
+    assert_eq!(
+        names(UserProgramFiles),
+        [
+            None,
+            Some("FOLDERID_UserProgramFiles"),
+            Some("shell:UserProgramFiles"),
+            None,
+            None,
+            None,
+            None,
+        ]
+    );
+}
+
+#[test]
+fn csidl_for_64_bit_directory_is_only_read_by_64_bit_process() {
+    let csidl = |platform| {
+        summary_entry_name(
+            LogicalDirectory::ProgramFiles64,
+            SourceKind::Csidl,
+            platform,
+        )
+    };
+
+    assert_eq!(csidl(X64), Some("CSIDL_PROGRAM_FILES"));
+    assert_eq!(csidl(ARM64), Some("CSIDL_PROGRAM_FILES"));
+    assert_eq!(csidl(WOW64), None);
+}
+
+#[test]
+fn summary_rows_are_in_directory_order() {
+    let rows = summarize(&Report::collect_with(&simulate(X64, &[])), X64);
+
+    let directories: Vec<_> = rows.iter().map(|row| row.directory).collect();
+    assert_eq!(directories, LogicalDirectory::ALL);
+
+    let native = SummaryCell::Path(String::from(r"C:\Program Files"));
+    assert_eq!(rows[0].cells[0], native);
+    assert_eq!(rows[0].cells[1], native);
+}
+
+#[test]
+fn differing_value_is_an_outlier() {
+    const MOVED: &str = r"D:\Program Files";
+
+    let os = simulate(X64, &[]).with_known_folder(FOLDERID_ProgramFilesX64, MOVED);
+    let mut report = Report::collect_with(&os);
+
+    // Give the environment's value a second source, so it is the consensus by more than its order.
+    let program_w6432 = report
+        .section(SourceKind::EnvironmentVariable)
+        .and_then(|section| section.find("ProgramW6432"))
+        .expect("the simulated environment has ProgramW6432");
+    report.shell_monikers = Some(Section::new(
+        "Shell monikers",
+        vec![Row::relabel(program_w6432, "shell:ProgramFilesX64")],
+    ));
+
+    let rows = summarize(&report, X64);
+    let row = &rows[0];
+    assert_eq!(row.directory, LogicalDirectory::ProgramFiles64);
+
+    let native = SummaryCell::Path(String::from(r"C:\Program Files"));
+    assert_eq!(row.cells[0], native);
+    assert_eq!(row.cells[1], SummaryCell::Outlier(String::from(MOVED)));
+    assert_eq!(row.cells[2], native);
+}
+
+#[cfg(unix)]
+#[test]
+fn paths_are_compared_as_given() {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+
+    // Paths that differ only in what cannot be shown, so they look the same once it is replaced.
+    let lookup = |last: u8| {
+        let path = [br"C:\Program Files".as_slice(), &[last]].concat();
+        Lookup::new(Ok::<_, std::io::Error>(OsString::from_vec(path)))
+    };
+
+    let mut report = Report::collect_with(&simulate(X64, &[]));
+    report.environment_variables = Some(Section::new(
+        "Environment variables",
+        vec![EnvVarEntry {
+            name: String::from("ProgramW6432"),
+            lookup: lookup(0xFF),
+        }],
+    ));
+    report.known_folders = Some(Section::new(
+        "Known folders",
+        vec![KnownFolderEntry {
+            name: String::from("FOLDERID_ProgramFilesX64"),
+            id: FOLDERID_ProgramFilesX64,
+            lookup: lookup(0xFE),
+        }],
+    ));
+    report.shell_monikers = Some(Section::new(
+        "Shell monikers",
+        vec![Row {
+            name: String::from("shell:ProgramFilesX64"),
+            lookup: lookup(0xFE),
+        }],
+    ));
+
+    let rows = summarize(&report, X64);
+    let shown = String::from("C:\\Program Files\u{FFFD}");
+    assert_eq!(rows[0].cells[0], SummaryCell::Outlier(shown.clone()));
+    assert_eq!(rows[0].cells[1], SummaryCell::Path(shown.clone()));
+    assert_eq!(rows[0].cells[2], SummaryCell::Path(shown));
+}
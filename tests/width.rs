@@ -0,0 +1,63 @@
+//! Tests of how wide text is taken to be when it is lined up in columns, which is in terminal
+//! cells, not characters.
+
+use pfdirs::render::{padded, table, visible_width, Color, Style};
+
+/// `プログラム` ("program"), as in a Japanese folder name. Each of its five characters is two
+/// cells wide.
+const KATAKANA: &str = "\u{30d7}\u{30ed}\u{30b0}\u{30e9}\u{30e0}";
+
+/// `Programmé`, with the accent written as U+0301 COMBINING ACUTE ACCENT after the `e`, so it is
+/// ten characters but nine cells wide.
+const DECOMPOSED: &str = "Programme\u{301}";
+
+#[test]
+fn cjk_characters_are_two_cells_wide() {
+    assert_eq!(KATAKANA.chars().count(), 5);
+    assert_eq!(visible_width(KATAKANA), 10);
+    assert_eq!(visible_width("\u{7a0b}\u{5e8f}"), 4);
+}
+
+#[test]
+fn combining_accent_takes_no_cells() {
+    assert_eq!(DECOMPOSED.chars().count(), 10);
+    assert_eq!(visible_width(DECOMPOSED), 9);
+    assert_eq!(visible_width("e\u{301}"), 1);
+}
+
+#[test]
+fn color_takes_no_cells() {
+    let style = Style {
+        color: true,
+        ..Style::default()
+    };
+    let painted = style.paint(KATAKANA, Color::Green);
+
+    assert_ne!(painted, KATAKANA);
+    assert_eq!(visible_width(&painted), visible_width(KATAKANA));
+}
+
+#[test]
+fn padding_fills_cells() {
+    assert_eq!(padded(KATAKANA, 12), format!("{KATAKANA}  "));
+    assert_eq!(padded(DECOMPOSED, 12), format!("{DECOMPOSED}   "));
+    assert_eq!(padded("Program Files", 12), "Program Files");
+}
+
+#[test]
+fn padded_columns_line_up() {
+    let rows = [KATAKANA, DECOMPOSED, "Program Files"]
+        .map(|name| vec![name.to_owned(), String::from(r"C:\Program Files")]);
+    let text = table(&rows);
+
+    let starts: Vec<usize> = text
+        .lines()
+        .map(|line| {
+            let (name, _) = line
+                .split_once(r"C:\")
+                .expect("each line has the second column");
+            visible_width(name)
+        })
+        .collect();
+    assert_eq!(starts, [17, 17, 17]);
+}